@@ -0,0 +1,76 @@
+//! The file structures of STAT & GETDENTS
+
+use core::str;
+
+/// The maximum length of a single path component
+pub const MAX_NAME_LENGTH: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u64)]
+pub enum FileType {
+    Regular = 0,
+    Directory = 1,
+    CharDevice = 2,
+}
+
+/// File metadata, returned by STAT
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct Stat {
+    pub inode: u64,
+    pub file_type: FileType,
+    /// The file size in bytes
+    pub size: u64,
+}
+
+impl Stat {
+    pub const fn empty() -> Self {
+        Stat {
+            inode: 0,
+            file_type: FileType::Regular,
+            size: 0,
+        }
+    }
+}
+
+/// A single directory entry, returned by GETDENTS
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct DirectoryEntry {
+    pub inode: u64,
+    pub file_type: FileType,
+    pub name_length: usize,
+    pub name: [u8; MAX_NAME_LENGTH],
+}
+
+impl DirectoryEntry {
+    pub const fn empty() -> Self {
+        DirectoryEntry {
+            inode: 0,
+            file_type: FileType::Regular,
+            name_length: 0,
+            name: [0; MAX_NAME_LENGTH],
+        }
+    }
+
+    /// Creates a new entry, `None` if the name is longer than `MAX_NAME_LENGTH`
+    pub fn new(inode: u64, file_type: FileType, name: &str) -> Option<Self> {
+        if name.len() > MAX_NAME_LENGTH {
+            return None;
+        }
+
+        let mut entry = DirectoryEntry {
+            inode,
+            file_type,
+            name_length: name.len(),
+            ..DirectoryEntry::empty()
+        };
+        entry.name[..name.len()].copy_from_slice(name.as_bytes());
+        Some(entry)
+    }
+
+    /// Returns the entry name
+    pub fn name(&self) -> &str {
+        str::from_utf8(&self.name[..self.name_length]).unwrap_or("")
+    }
+}
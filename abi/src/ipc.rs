@@ -0,0 +1,19 @@
+//! The message buffers of SEND & RECV
+
+/// The largest message a channel passes
+pub const MAX_MESSAGE_SIZE: usize = 4096;
+/// The most files a message passes
+pub const MAX_MESSAGE_FILES: usize = 8;
+
+/// The addresses & lengths of a message's buffers, SEND reads them and RECV fills them
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct MessageBuffers {
+    pub data: u64,
+    /// The length of the data, RECV's capacity
+    pub length: u64,
+    /// An array of file descriptors
+    pub files: u64,
+    /// The amount of file descriptors, RECV's capacity and the amount of files it received
+    pub file_count: u64,
+}
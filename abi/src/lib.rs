@@ -0,0 +1,131 @@
+//! The syscall ABI of CrabOS: the syscall numbers, statuses & flags, and the `#[repr(C)]` structures
+//! the syscalls exchange with userland.
+//!
+//! Both the kernel and libcrab depend on this crate, so a change of the ABI changes both of them.
+
+#![no_std]
+
+pub mod fs;
+pub mod ipc;
+pub mod memory;
+pub mod process;
+pub mod time;
+pub mod tty;
+
+pub const STDIN: usize = 0;
+pub const STDOUT: usize = 1;
+pub const STDERR: usize = 2;
+
+/// The nice value of the highest priority
+pub const MIN_NICE: i64 = -20;
+/// The nice value of the lowest priority
+pub const MAX_NICE: i64 = 19;
+
+/// Syscalls numbers
+pub mod number {
+    pub const DISPLAY_PROCESS_INFO: u64 = 0;
+    pub const CREATE: u64 = 1;
+    pub const EXECUTE: u64 = 2;
+    pub const KILL: u64 = 3;
+    pub const GET_PID: u64 = 4;
+    pub const READ: u64 = 5;
+    pub const WRITE: u64 = 6;
+    pub const OPEN: u64 = 7;
+    pub const CLOSE: u64 = 8;
+    pub const DUP: u64 = 9;
+    pub const DUP2: u64 = 10;
+    pub const STAT: u64 = 11;
+    pub const GETDENTS: u64 = 12;
+    pub const MKDIR: u64 = 13;
+    pub const UNLINK: u64 = 14;
+    pub const CHDIR: u64 = 15;
+    pub const GETCWD: u64 = 16;
+    pub const CLOCK_GETTIME: u64 = 17;
+    pub const NANOSLEEP: u64 = 18;
+    pub const IOCTL: u64 = 19;
+    pub const EXIT: u64 = 20;
+    pub const PROCESSES: u64 = 21;
+    pub const MEMINFO: u64 = 22;
+    pub const START: u64 = 23;
+    pub const WAIT: u64 = 24;
+    pub const PIPE: u64 = 25;
+    pub const SPAWN: u64 = 26;
+    pub const READ_LOG: u64 = 27;
+    pub const BRK: u64 = 28;
+    pub const MMAP: u64 = 29;
+    pub const MUNMAP: u64 = 30;
+    pub const SIGACTION: u64 = 31;
+    pub const SIGRETURN: u64 = 32;
+    pub const SIGPROCMASK: u64 = 33;
+    pub const ALARM: u64 = 34;
+    pub const SETPGID: u64 = 35;
+    pub const GETPGID: u64 = 36;
+    pub const SETSID: u64 = 37;
+    pub const FUTEX: u64 = 38;
+    pub const CHANNEL_CREATE: u64 = 39;
+    pub const SEND: u64 = 40;
+    pub const RECV: u64 = 41;
+    pub const SELECT: u64 = 42;
+    pub const SHM_CREATE: u64 = 43;
+    pub const SHM_MAP: u64 = 44;
+    pub const SHM_UNMAP: u64 = 45;
+    pub const SETPRIORITY: u64 = 46;
+}
+
+/// Syscalls exit statuses
+pub mod status {
+    pub const SUCCESS: i64 = 0;
+    pub const FAILURE: i64 = -1;
+    /// A syscall interrupted by a signal is issued again after the signal is handled, never returned to userland
+    pub const RESTART: i64 = -2;
+    /// A syscall that waits on a wait queue is issued again once the caller is woken, never returned to userland
+    pub const BLOCKED: i64 = -3;
+    /// A non blocking syscall that would have waited
+    pub const WOULD_BLOCK: i64 = -4;
+}
+
+/// OPEN flags, the access mode of an opened file
+pub mod open_flags {
+    pub const READ: u64 = 1;
+    pub const WRITE: u64 = 1 << 1;
+    /// Creates a regular file if the path doesn't exist
+    pub const CREATE: u64 = 1 << 2;
+    /// Truncates a regular file to zero bytes
+    pub const TRUNCATE: u64 = 1 << 3;
+    /// Every write of a regular file is done at it's end
+    pub const APPEND: u64 = 1 << 4;
+}
+
+/// FUTEX operations
+pub mod futex {
+    /// Waits while the word holds the value, until a wake of the word or until the timeout (a `Timespec`) passes
+    pub const WAIT: u64 = 0;
+    /// Wakes up to the value waiters of the word, returns the number of waiters woken
+    pub const WAKE: u64 = 1;
+}
+
+/// SEND, RECV & SELECT flags, the messages are described by a `MessageBuffers`
+pub mod message {
+    /// The syscall returns `status::WOULD_BLOCK` instead of waiting
+    pub const NON_BLOCKING: u64 = 1;
+}
+
+/// IOCTL requests, the argument is a pointer to a `Termios` or a process group id
+pub mod ioctl {
+    pub const GET_SETTINGS: u64 = 0;
+    pub const SET_SETTINGS: u64 = 1;
+    pub const GET_FOREGROUND: u64 = 2;
+    pub const SET_FOREGROUND: u64 = 3;
+    /// The `SET_FOREGROUND` argument that leaves the terminal without a foreground process group
+    pub const NO_FOREGROUND: u64 = u64::MAX;
+}
+
+/// WAIT flags, and the statuses WAIT returns besides exit statuses
+pub mod wait {
+    /// WAIT also returns when the child stops, with `STOPPED` and the signal that stopped it
+    pub const REPORT_STOPPED: u64 = 1;
+    /// WAIT returns `RUNNING` instead of waiting for a child that didn't exit (or stop)
+    pub const NO_HANG: u64 = 1 << 1;
+    pub const STOPPED: i64 = 0x100;
+    pub const RUNNING: i64 = 0x200;
+}
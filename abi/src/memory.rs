@@ -0,0 +1,12 @@
+//! The memory usage MEMINFO returns
+
+/// Memory usage in bytes, returned by MEMINFO
+#[derive(Clone, Copy, Debug, Default)]
+#[repr(C)]
+pub struct MemoryInfo {
+    /// The physical memory managed by the kernel allocator
+    pub total: usize,
+    pub free: usize,
+    pub heap_size: usize,
+    pub heap_used: usize,
+}
@@ -0,0 +1,74 @@
+//! The process information PROCESSES returns
+
+/// Process States
+/// 
+/// # Active
+/// 
+/// when the current process is running he is `Active`
+/// 
+/// # Waiting
+/// 
+/// If a process has never been executed, or was preempted, then he is `Waiting` for execution
+/// 
+/// # Paused
+/// 
+/// If a process waits for one of it's children (EXECUTE or WAIT) he becomes `Paused` until the child exits,
+/// or until a signal is sent to him
+/// 
+/// # Blocked
+/// 
+/// If a process waits for an event (e.g. a sleep deadline or a wait queue) he is `Blocked` until the event
+/// or a signal wakes him
+/// 
+/// # Stopped
+/// 
+/// If a process receives a stop signal (SIGSTOP or SIGTSTP) he is `Stopped` until SIGCONT continues him
+/// 
+/// # Terminated
+/// 
+/// If a process was killed while it was `Active` on another processor,
+/// it's resources are released when that processor switches to the next process
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u64)]
+pub enum ProcessState {
+    Active,
+    Waiting,
+    Paused,
+    Blocked,
+    Terminated,
+    Stopped,
+}
+
+/// The `ppid` of a process without a parent
+pub const NO_PARENT: usize = usize::MAX;
+
+/// Process information, returned by PROCESSES
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct ProcessInfo {
+    pub pid: usize,
+    /// The pid of the parent, `NO_PARENT` if there is none
+    pub ppid: usize,
+    pub state: ProcessState,
+    /// The process group
+    pub pgid: usize,
+    /// The session
+    pub sid: usize,
+}
+
+impl ProcessInfo {
+    pub const fn empty() -> Self {
+        ProcessInfo {
+            pid: 0,
+            ppid: NO_PARENT,
+            state: ProcessState::Terminated,
+            pgid: 0,
+            sid: 0,
+        }
+    }
+
+    /// The pid of the parent
+    pub fn parent(&self) -> Option<usize> {
+        (self.ppid != NO_PARENT).then_some(self.ppid)
+    }
+}
@@ -0,0 +1,35 @@
+//! The clocks of CLOCK_GETTIME, and the times the time syscalls exchange
+
+pub const NANOSECONDS_PER_SECOND: u64 = 1_000_000_000;
+
+/// The clocks of `CLOCK_GETTIME`
+pub const CLOCK_REALTIME: u64 = 0;
+pub const CLOCK_MONOTONIC: u64 = 1;
+
+/// A point in time or a duration
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Timespec {
+    pub seconds: u64,
+    /// Below `NANOSECONDS_PER_SECOND`
+    pub nanoseconds: u64,
+}
+
+impl Timespec {
+    pub const fn from_nanoseconds(nanoseconds: u64) -> Self {
+        Timespec {
+            seconds: nanoseconds / NANOSECONDS_PER_SECOND,
+            nanoseconds: nanoseconds % NANOSECONDS_PER_SECOND,
+        }
+    }
+
+    /// Returns the total nanoseconds, `None` if the nanoseconds field is invalid or the total overflows
+    pub fn as_nanoseconds(&self) -> Option<u64> {
+        if self.nanoseconds >= NANOSECONDS_PER_SECOND {
+            return None;
+        }
+        self.seconds
+            .checked_mul(NANOSECONDS_PER_SECOND)
+            .and_then(|nanoseconds| nanoseconds.checked_add(self.nanoseconds))
+    }
+}
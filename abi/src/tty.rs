@@ -0,0 +1,52 @@
+//! The terminal settings of IOCTL
+
+/// The `Termios` modes, a terminal without any mode is in raw mode
+pub mod mode {
+    /// Reads return whole lines, which are edited with the erase, kill & word erase characters
+    pub const CANONICAL: u64 = 1;
+    /// The typed characters are written back to the terminal
+    pub const ECHO: u64 = 1 << 1;
+    /// The interrupt & suspend characters signal the foreground process group
+    pub const SIGNALS: u64 = 1 << 2;
+}
+
+/// The settings of a terminal, exchanged by IOCTL
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Termios {
+    /// The `mode` bits
+    pub mode: u64,
+    /// Erases the last character (backspace)
+    pub erase: u8,
+    /// Erases the line (Ctrl + U)
+    pub kill: u8,
+    /// Erases the last word (Ctrl + W)
+    pub word_erase: u8,
+    /// Ends the line without a new line (Ctrl + D), on an empty line the read returns end of file
+    pub end_of_file: u8,
+    /// Interrupts the foreground process group (Ctrl + C)
+    pub interrupt: u8,
+    /// Suspends the foreground process group (Ctrl + Z)
+    pub suspend: u8,
+}
+
+impl Termios {
+    /// The settings terminals start with, canonical mode with echo and signals
+    pub const fn canonical() -> Self {
+        Termios {
+            mode: mode::CANONICAL | mode::ECHO | mode::SIGNALS,
+            erase: 0x08,
+            kill: 0x15,
+            word_erase: 0x17,
+            end_of_file: 0x04,
+            interrupt: 0x03,
+            suspend: 0x1A,
+        }
+    }
+}
+
+impl Default for Termios {
+    fn default() -> Self {
+        Termios::canonical()
+    }
+}
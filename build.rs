@@ -0,0 +1,126 @@
+//! Packs the `initramfs` directory to a cpio archive (new ASCII format) which is embedded in the kernel image.
+//!
+//! The userland programs of the `user` workspace are built first and packed to "/bin".
+
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const INITRAMFS_DIRECTORY: &str = "initramfs";
+const USER_DIRECTORY: &str = "user";
+/// The syscall ABI, the userland programs depend on it
+const ABI_DIRECTORY: &str = "abi";
+/// Every source file of this directory is a program
+const PROGRAMS_DIRECTORY: &str = "user/coreutils/src/bin";
+const USER_TARGET: &str = "x86_64-crabos";
+const BIN_DIRECTORY: &str = "bin";
+const DIRECTORY_MODE: u32 = 0o040755;
+const REGULAR_MODE: u32 = 0o100644;
+const EXECUTABLE_MODE: u32 = 0o100755;
+
+fn main() -> io::Result<()> {
+    println!("cargo:rerun-if-changed={}", INITRAMFS_DIRECTORY);
+    println!("cargo:rerun-if-changed={}", USER_DIRECTORY);
+    println!("cargo:rerun-if-changed={}", ABI_DIRECTORY);
+
+    let out_directory = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let programs = build_programs(&out_directory.join("user"))?;
+
+    let mut archive = Vec::new();
+    let mut inode = 1;
+    pack_directory(Path::new(INITRAMFS_DIRECTORY), Path::new(""), &mut archive, &mut inode)?;
+    pack_programs(&programs, &mut archive, &mut inode)?;
+    write_entry(&mut archive, "TRAILER!!!", 0, 0, &[])?;
+
+    fs::write(out_directory.join("initramfs.cpio"), archive)
+}
+
+/// Builds the `user` workspace to `target_directory`, returns the names & paths of the programs
+fn build_programs(target_directory: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    // the kernel's build has it's own flags and target, they mustn't leak to the userland build
+    let status = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".into()))
+        .current_dir(USER_DIRECTORY)
+        .args(["build", "--release", "--target", &format!("{}.json", USER_TARGET)])
+        .arg("--target-dir")
+        .arg(target_directory)
+        .env_remove("RUSTFLAGS")
+        .env_remove("CARGO_ENCODED_RUSTFLAGS")
+        .env_remove("RUSTC_WORKSPACE_WRAPPER")
+        .status()?;
+    if !status.success() {
+        return Err(io::Error::new(io::ErrorKind::Other, "the userland programs failed to build"));
+    }
+
+    let mut programs = Vec::new();
+    for entry in fs::read_dir(PROGRAMS_DIRECTORY)? {
+        let path = entry?.path();
+        if let Some(name) = path.file_stem().and_then(|name| name.to_str()) {
+            let binary = target_directory.join(USER_TARGET).join("release").join(name);
+            programs.push((name.to_string(), binary));
+        }
+    }
+    programs.sort();
+    Ok(programs)
+}
+
+/// Packs the programs to the bin directory
+fn pack_programs(programs: &[(String, PathBuf)], archive: &mut Vec<u8>, inode: &mut u32) -> io::Result<()> {
+    *inode += 1;
+    write_entry(archive, BIN_DIRECTORY, *inode, DIRECTORY_MODE, &[])?;
+    for (name, binary) in programs {
+        *inode += 1;
+        let name = format!("{}/{}", BIN_DIRECTORY, name);
+        write_entry(archive, &name, *inode, EXECUTABLE_MODE, &fs::read(binary)?)?;
+    }
+    Ok(())
+}
+
+/// Packs every entry of `directory` recursively, a directory is always packed before it's content.
+fn pack_directory(directory: &Path, prefix: &Path, archive: &mut Vec<u8>, inode: &mut u32) -> io::Result<()> {
+    if !directory.exists() {
+        return Ok(());
+    }
+
+    let mut entries = fs::read_dir(directory)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = prefix.join(entry.file_name());
+        let name = name.to_str().expect("initramfs paths must be valid UTF-8");
+        *inode += 1;
+
+        if entry.file_type()?.is_dir() {
+            write_entry(archive, name, *inode, DIRECTORY_MODE, &[])?;
+            pack_directory(&entry.path(), Path::new(name), archive, inode)?;
+        } else {
+            write_entry(archive, name, *inode, REGULAR_MODE, &fs::read(entry.path())?)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_entry(archive: &mut Vec<u8>, name: &str, inode: u32, mode: u32, data: &[u8]) -> io::Result<()> {
+    // magic, inode, mode, uid, gid, nlink, mtime, file size, device major / minor,
+    // rdevice major / minor, name size (with the null byte) and a checksum
+    write!(archive, "070701")?;
+    for field in [inode, mode, 0, 0, 1, 0, data.len() as u32, 0, 0, 0, 0, name.len() as u32 + 1, 0] {
+        write!(archive, "{:08x}", field)?;
+    }
+
+    archive.extend_from_slice(name.as_bytes());
+    archive.push(0);
+    pad(archive);
+    archive.extend_from_slice(data);
+    pad(archive);
+    Ok(())
+}
+
+/// Aligns the archive to 4 bytes
+fn pad(archive: &mut Vec<u8>) {
+    while archive.len() % 4 != 0 {
+        archive.push(0);
+    }
+}
@@ -0,0 +1,22 @@
+#! /bin/bash
+# Creates the disk images that QEMU attaches to the integration tests (see `test-args` in Cargo.toml)
+SCRIPT_DIR=$(cd -- $(dirname ${BASH_SOURCE[0]}) &> /dev/null && pwd)
+DISKS_DIR=$SCRIPT_DIR/../target/disks
+ATA_SECTOR_CONTENT="CrabOS ATA test sector"
+
+mkdir -p $DISKS_DIR
+
+# 1MiB raw disk, sector 1 holds a known string
+dd if=/dev/zero of=$DISKS_DIR/ata.img bs=512 count=2048 status=none
+printf "$ATA_SECTOR_CONTENT" | dd of=$DISKS_DIR/ata.img bs=512 seek=1 conv=notrunc status=none
+
+# 40MiB FAT32 volume (one sector per cluster, FAT32 requires at least 65525 clusters), requires dosfstools & mtools
+FAT32_IMAGE=$DISKS_DIR/fat32.img
+rm -f $FAT32_IMAGE
+mkfs.fat -F 32 -s 1 -n CRABOS -C $FAT32_IMAGE 40960 > /dev/null
+printf "hello from the host" > $DISKS_DIR/hello.txt
+printf "a file with a long name" > "$DISKS_DIR/A long file name.text"
+mmd -i $FAT32_IMAGE ::/docs
+mcopy -i $FAT32_IMAGE $DISKS_DIR/hello.txt ::/hello.txt
+mcopy -i $FAT32_IMAGE "$DISKS_DIR/A long file name.text" "::/docs/A long file name.text"
+rm $DISKS_DIR/hello.txt "$DISKS_DIR/A long file name.text"
@@ -0,0 +1,127 @@
+//! The Multiple APIC Description Table, describes the local APICs, the IOAPICs and the ISA IRQs routing
+
+use alloc::vec::Vec;
+
+use super::{read_u32, read_u64, SDT_HEADER_LENGTH};
+
+pub const SIGNATURE: &[u8; 4] = b"APIC";
+
+const LOCAL_APIC_ADDRESS_OFFSET: usize = SDT_HEADER_LENGTH;
+const FLAGS_OFFSET: usize = SDT_HEADER_LENGTH + 4;
+const ENTRIES_OFFSET: usize = SDT_HEADER_LENGTH + 8;
+/// The system has 8259 PICs which must be masked before using the APICs
+const FLAG_PCAT_COMPATIBLE: u32 = 1;
+
+const LOCAL_APIC_ENTRY: u8 = 0;
+const IO_APIC_ENTRY: u8 = 1;
+const INTERRUPT_OVERRIDE_ENTRY: u8 = 2;
+const LOCAL_APIC_ADDRESS_OVERRIDE_ENTRY: u8 = 5;
+
+const LOCAL_APIC_ENABLED: u32 = 1;
+
+// interrupt override flags (MPS INTI flags)
+const POLARITY_MASK: u16 = 0b11;
+const POLARITY_ACTIVE_LOW: u16 = 0b11;
+const TRIGGER_MASK: u16 = 0b11 << 2;
+const TRIGGER_LEVEL: u16 = 0b11 << 2;
+
+/// A processor and it's local APIC
+#[derive(Clone, Copy, Debug)]
+pub struct Processor {
+    pub processor_id: u8,
+    pub apic_id: u8,
+    /// Disabled processors must not be started
+    pub enabled: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct IoApic {
+    pub id: u8,
+    /// The physical address of the IOAPIC registers
+    pub address: u64,
+    /// The first Global System Interrupt this IOAPIC handles
+    pub gsi_base: u32,
+}
+
+/// An ISA IRQ that isn't connected to the IOAPIC input with it's number
+#[derive(Clone, Copy, Debug)]
+pub struct InterruptOverride {
+    pub irq: u8,
+    pub gsi: u32,
+    pub active_low: bool,
+    pub level_triggered: bool,
+}
+
+#[derive(Debug)]
+pub struct Madt {
+    /// The physical address of the local APIC registers
+    pub local_apic_address: u64,
+    /// Whether the legacy PICs exist
+    pub has_pics: bool,
+    pub processors: Vec<Processor>,
+    pub io_apics: Vec<IoApic>,
+    pub overrides: Vec<InterruptOverride>,
+}
+
+impl Madt {
+    /// Parses the table content, including it's header
+    pub fn parse(table: &[u8]) -> Result<Self, ()> {
+        if table.len() < ENTRIES_OFFSET {
+            return Err(());
+        }
+
+        let mut madt = Madt {
+            local_apic_address: read_u32(table, LOCAL_APIC_ADDRESS_OFFSET) as u64,
+            has_pics: read_u32(table, FLAGS_OFFSET) & FLAG_PCAT_COMPATIBLE != 0,
+            processors: Vec::new(),
+            io_apics: Vec::new(),
+            overrides: Vec::new(),
+        };
+
+        // every entry starts with it's type and length
+        let mut offset = ENTRIES_OFFSET;
+        while offset + 2 <= table.len() {
+            let length = table[offset + 1] as usize;
+            if length < 2 || offset + length > table.len() {
+                return Err(());
+            }
+            let entry = &table[offset..offset + length];
+
+            match entry[0] {
+                LOCAL_APIC_ENTRY if length >= 8 => {
+                    let flags = read_u32(entry, 4);
+                    madt.processors.push(Processor {
+                        processor_id: entry[2],
+                        apic_id: entry[3],
+                        enabled: flags & LOCAL_APIC_ENABLED != 0,
+                    });
+                }
+                IO_APIC_ENTRY if length >= 12 => madt.io_apics.push(IoApic {
+                    id: entry[2],
+                    address: read_u32(entry, 4) as u64,
+                    gsi_base: read_u32(entry, 8),
+                }),
+                INTERRUPT_OVERRIDE_ENTRY if length >= 10 => {
+                    let flags = u16::from_le_bytes([entry[8], entry[9]]);
+                    madt.overrides.push(InterruptOverride {
+                        irq: entry[3],
+                        gsi: read_u32(entry, 4),
+                        active_low: flags & POLARITY_MASK == POLARITY_ACTIVE_LOW,
+                        level_triggered: flags & TRIGGER_MASK == TRIGGER_LEVEL,
+                    });
+                }
+                LOCAL_APIC_ADDRESS_OVERRIDE_ENTRY if length >= 12 => {
+                    madt.local_apic_address = read_u64(entry, 4);
+                }
+                // NMIs and x2APIC entries are not used
+                _ => {}
+            }
+            offset += length;
+        }
+
+        if madt.io_apics.is_empty() {
+            return Err(());
+        }
+        Ok(madt)
+    }
+}
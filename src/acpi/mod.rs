@@ -0,0 +1,135 @@
+//! The acpi module goal is to find the ACPI tables the firmware left in physical memory.
+//!
+//! The tables are read through the physical memory mapping (`get_linear_addr`),
+//! the RSDP points to the XSDT (or the RSDT on ACPI 1.0) which points to every other table.
+
+pub mod madt;
+
+use core::slice;
+
+use crate::memory::get_linear_addr;
+
+pub use self::madt::Madt;
+
+const RSDP_SIGNATURE: &[u8; 8] = b"RSD PTR ";
+const RSDP_V1_LENGTH: usize = 20;
+const RSDP_V2_LENGTH: usize = 36;
+/// The RSDP is aligned to 16 bytes
+const RSDP_ALIGNMENT: usize = 16;
+/// The real mode segment of the Extended BIOS Data Area is saved at this address
+const EBDA_SEGMENT_POINTER: u64 = 0x40E;
+const EBDA_SEARCH_LENGTH: usize = 1024;
+const BIOS_AREA_START: u64 = 0xE0000;
+const BIOS_AREA_END: u64 = 0x100000;
+
+const SDT_HEADER_LENGTH: usize = 36;
+const XSDT_SIGNATURE: &[u8; 4] = b"XSDT";
+const RSDT_SIGNATURE: &[u8; 4] = b"RSDT";
+
+/// Returns `length` bytes of physical memory through the physical memory mapping
+fn physical_bytes<'a>(physical_addr: u64, length: usize) -> &'a [u8] {
+    unsafe { slice::from_raw_parts(get_linear_addr(physical_addr) as *const u8, length) }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+/// ACPI structures are valid if all of their bytes sum to zero
+fn is_checksum_valid(bytes: &[u8]) -> bool {
+    bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)) == 0
+}
+
+/// The root pointer, points to the system descriptor table
+struct Rsdp {
+    revision: u8,
+    rsdt_address: u32,
+    xsdt_address: u64,
+}
+
+impl Rsdp {
+    /// Searches the first KiB of the EBDA and then the BIOS read only area
+    fn find() -> Result<Self, ()> {
+        let ebda = (u16::from_le_bytes(physical_bytes(EBDA_SEGMENT_POINTER, 2).try_into().unwrap()) as u64) << 4;
+
+        Rsdp::search(ebda, ebda + EBDA_SEARCH_LENGTH as u64).or_else(|_| Rsdp::search(BIOS_AREA_START, BIOS_AREA_END))
+    }
+
+    fn search(start: u64, end: u64) -> Result<Self, ()> {
+        for address in (start..end).step_by(RSDP_ALIGNMENT) {
+            let bytes = physical_bytes(address, RSDP_V1_LENGTH);
+            if &bytes[..8] != RSDP_SIGNATURE || !is_checksum_valid(bytes) {
+                continue;
+            }
+
+            let revision = bytes[15];
+            let rsdt_address = read_u32(bytes, 16);
+            // ACPI 2.0 extends the structure with the XSDT address and a second checksum
+            if revision >= 2 {
+                let bytes = physical_bytes(address, RSDP_V2_LENGTH);
+                if !is_checksum_valid(bytes) {
+                    continue;
+                }
+                return Ok(Rsdp {
+                    revision,
+                    rsdt_address,
+                    xsdt_address: read_u64(bytes, 24),
+                });
+            }
+            return Ok(Rsdp {
+                revision,
+                rsdt_address,
+                xsdt_address: 0,
+            });
+        }
+        Err(())
+    }
+}
+
+/// Returns the content of the table at `physical_addr` including it's header, after validating it.
+fn read_table<'a>(physical_addr: u64, signature: &[u8; 4]) -> Result<&'a [u8], ()> {
+    let header = physical_bytes(physical_addr, SDT_HEADER_LENGTH);
+    if &header[..4] != signature {
+        return Err(());
+    }
+
+    let table = physical_bytes(physical_addr, read_u32(header, 4) as usize);
+    if table.len() < SDT_HEADER_LENGTH || !is_checksum_valid(table) {
+        return Err(());
+    }
+    Ok(table)
+}
+
+/// Returns the physical addresses of all the tables the XSDT (or the RSDT) points to
+fn table_addresses() -> Result<impl Iterator<Item = u64>, ()> {
+    let rsdp = Rsdp::find()?;
+
+    let (root, pointer_size) = if rsdp.revision >= 2 && rsdp.xsdt_address != 0 {
+        (read_table(rsdp.xsdt_address, XSDT_SIGNATURE)?, 8)
+    } else {
+        (read_table(rsdp.rsdt_address as u64, RSDT_SIGNATURE)?, 4)
+    };
+
+    Ok(root[SDT_HEADER_LENGTH..]
+        .chunks_exact(pointer_size)
+        .map(move |pointer| match pointer_size {
+            8 => read_u64(pointer, 0),
+            _ => read_u32(pointer, 0) as u64,
+        }))
+}
+
+/// Finds the table with `signature`, and returns it's content including it's header
+pub fn find_table<'a>(signature: &[u8; 4]) -> Result<&'a [u8], ()> {
+    table_addresses()?
+        .find_map(|address| read_table(address, signature).ok())
+        .ok_or(())
+}
+
+/// Parses the Multiple APIC Description Table, fails if the firmware doesn't provide one
+pub fn madt() -> Result<Madt, ()> {
+    Madt::parse(find_table(madt::SIGNATURE)?)
+}
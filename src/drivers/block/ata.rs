@@ -0,0 +1,268 @@
+//! An ATA PIO driver for the legacy IDE channels, sectors are addressed with 28 bit LBA.
+//!
+//! The driver polls the drive status instead of waiting for it's interrupts.
+
+use alloc::vec::Vec;
+use spin::Mutex;
+use x86_64::instructions::port::Port;
+
+use super::BlockDevice;
+
+const SECTOR_SIZE: usize = 512;
+const WORDS_PER_SECTOR: usize = SECTOR_SIZE / 2;
+/// The maximum sectors a single command can transfer
+const MAX_SECTORS_PER_COMMAND: usize = 256;
+const MAX_LBA28_SECTORS: u64 = 1 << 28;
+/// Status polls before giving up on the drive
+const POLL_LIMIT: usize = 1_000_000;
+
+// registers offsets from the channel io base
+const DATA_REGISTER: u16 = 0;
+const SECTOR_COUNT_REGISTER: u16 = 2;
+const LBA_LOW_REGISTER: u16 = 3;
+const LBA_MID_REGISTER: u16 = 4;
+const LBA_HIGH_REGISTER: u16 = 5;
+const DRIVE_REGISTER: u16 = 6;
+/// Status on read, command on write
+const STATUS_REGISTER: u16 = 7;
+const COMMAND_REGISTER: u16 = 7;
+
+const READ_SECTORS_COMMAND: u8 = 0x20;
+const WRITE_SECTORS_COMMAND: u8 = 0x30;
+const CACHE_FLUSH_COMMAND: u8 = 0xE7;
+const IDENTIFY_COMMAND: u8 = 0xEC;
+
+const STATUS_ERROR: u8 = 1;
+const STATUS_DATA_REQUEST: u8 = 1 << 3;
+const STATUS_DRIVE_FAULT: u8 = 1 << 5;
+const STATUS_BUSY: u8 = 1 << 7;
+
+/// Disables the channel interrupts (nIEN)
+const CONTROL_DISABLE_INTERRUPTS: u8 = 1 << 1;
+const DRIVE_LBA_MODE: u8 = 0xE0;
+const DRIVE_SLAVE: u8 = 1 << 4;
+
+// identify words
+const IDENTIFY_LBA28_SECTORS: usize = 60;
+
+static PRIMARY_CHANNEL: Mutex<Channel> = Mutex::new(Channel::new(0x1F0, 0x3F6));
+static SECONDARY_CHANNEL: Mutex<Channel> = Mutex::new(Channel::new(0x170, 0x376));
+
+/// An IDE channel, every channel connects up to two drives (master & slave)
+struct Channel {
+    io_base: u16,
+    control_base: u16,
+}
+
+impl Channel {
+    const fn new(io_base: u16, control_base: u16) -> Self {
+        Channel { io_base, control_base }
+    }
+
+    fn read_register(&self, register: u16) -> u8 {
+        unsafe { Port::<u8>::new(self.io_base + register).read() }
+    }
+
+    fn write_register(&self, register: u16, value: u8) {
+        unsafe { Port::<u8>::new(self.io_base + register).write(value) }
+    }
+
+    fn read_data(&self) -> u16 {
+        unsafe { Port::<u16>::new(self.io_base + DATA_REGISTER).read() }
+    }
+
+    fn write_data(&self, value: u16) {
+        unsafe { Port::<u16>::new(self.io_base + DATA_REGISTER).write(value) }
+    }
+
+    fn write_control(&self, value: u8) {
+        unsafe { Port::<u8>::new(self.control_base).write(value) }
+    }
+
+    /// Reads the alternate status register, which doesn't acknowledge interrupts
+    fn alternate_status(&self) -> u8 {
+        unsafe { Port::<u8>::new(self.control_base).read() }
+    }
+
+    /// Waits ~400ns for the drive to update it's status after selection or a command
+    fn delay(&self) {
+        for _ in 0..4 {
+            self.alternate_status();
+        }
+    }
+
+    /// Selects a drive and the highest 4 bits of an LBA28 address
+    fn select(&self, slave: bool, lba: u64) {
+        let drive = if slave { DRIVE_SLAVE } else { 0 };
+        self.write_register(DRIVE_REGISTER, DRIVE_LBA_MODE | drive | ((lba >> 24) as u8 & 0xF));
+        self.delay();
+    }
+
+    /// Waits until the drive is not busy
+    fn wait_ready(&self) -> Result<u8, ()> {
+        for _ in 0..POLL_LIMIT {
+            let status = self.read_register(STATUS_REGISTER);
+            if status & STATUS_BUSY == 0 {
+                return Ok(status);
+            }
+        }
+        Err(())
+    }
+
+    /// Waits until the drive is ready to transfer a sector
+    fn wait_data_request(&self) -> Result<(), ()> {
+        for _ in 0..POLL_LIMIT {
+            let status = self.wait_ready()?;
+            if status & (STATUS_ERROR | STATUS_DRIVE_FAULT) != 0 {
+                return Err(());
+            }
+            if status & STATUS_DATA_REQUEST != 0 {
+                return Ok(());
+            }
+        }
+        Err(())
+    }
+
+    /// Sends a read / write command for `count` sectors (0 means 256 sectors)
+    fn send_command(&self, slave: bool, lba: u64, count: u8, command: u8) -> Result<(), ()> {
+        self.wait_ready()?;
+        self.select(slave, lba);
+        self.write_register(SECTOR_COUNT_REGISTER, count);
+        self.write_register(LBA_LOW_REGISTER, lba as u8);
+        self.write_register(LBA_MID_REGISTER, (lba >> 8) as u8);
+        self.write_register(LBA_HIGH_REGISTER, (lba >> 16) as u8);
+        self.write_register(COMMAND_REGISTER, command);
+        self.delay();
+        Ok(())
+    }
+
+    /// Sends the IDENTIFY command, returns the number of sectors of an ATA drive.
+    ///
+    /// Returns `None` if there is no drive or if it is not an ATA drive (e.g ATAPI).
+    fn identify(&self, slave: bool) -> Option<u64> {
+        self.write_control(CONTROL_DISABLE_INTERRUPTS);
+        self.select(slave, 0);
+        self.write_register(SECTOR_COUNT_REGISTER, 0);
+        self.write_register(LBA_LOW_REGISTER, 0);
+        self.write_register(LBA_MID_REGISTER, 0);
+        self.write_register(LBA_HIGH_REGISTER, 0);
+        self.write_register(COMMAND_REGISTER, IDENTIFY_COMMAND);
+        self.delay();
+
+        // a floating bus (0xFF) or a zero status means that there is no drive
+        let status = self.read_register(STATUS_REGISTER);
+        if status == 0 || status == 0xFF {
+            return None;
+        }
+        self.wait_ready().ok()?;
+
+        // ATAPI and SATA drives set the lba mid & high registers
+        if self.read_register(LBA_MID_REGISTER) != 0 || self.read_register(LBA_HIGH_REGISTER) != 0 {
+            return None;
+        }
+        self.wait_data_request().ok()?;
+
+        let mut identify = [0u16; WORDS_PER_SECTOR];
+        for word in identify.iter_mut() {
+            *word = self.read_data();
+        }
+
+        let sectors = (identify[IDENTIFY_LBA28_SECTORS + 1] as u64) << 16 | identify[IDENTIFY_LBA28_SECTORS] as u64;
+        Some(sectors)
+    }
+}
+
+/// A drive connected to one of the IDE channels
+pub struct AtaDrive {
+    channel: &'static Mutex<Channel>,
+    slave: bool,
+    sectors: u64,
+}
+
+impl AtaDrive {
+    /// Validates that the sectors range is inside the drive, and returns the number of sectors
+    fn sectors_range(&self, first_sector: u64, length: usize) -> Result<usize, ()> {
+        if length % SECTOR_SIZE != 0 {
+            return Err(());
+        }
+
+        let count = length / SECTOR_SIZE;
+        let end = first_sector.checked_add(count as u64).ok_or(())?;
+        if end > self.sectors || end > MAX_LBA28_SECTORS {
+            return Err(());
+        }
+        Ok(count)
+    }
+}
+
+impl BlockDevice for AtaDrive {
+    fn sector_size(&self) -> usize {
+        SECTOR_SIZE
+    }
+
+    fn capacity(&self) -> u64 {
+        self.sectors
+    }
+
+    fn read_sectors(&self, first_sector: u64, buffer: &mut [u8]) -> Result<(), ()> {
+        self.sectors_range(first_sector, buffer.len())?;
+        let channel = self.channel.lock();
+
+        for (index, chunk) in buffer.chunks_mut(SECTOR_SIZE * MAX_SECTORS_PER_COMMAND).enumerate() {
+            let lba = first_sector + (index * MAX_SECTORS_PER_COMMAND) as u64;
+            let count = chunk.len() / SECTOR_SIZE;
+            // a sector count of 0 means 256 sectors
+            channel.send_command(self.slave, lba, count as u8, READ_SECTORS_COMMAND)?;
+
+            for sector in chunk.chunks_mut(SECTOR_SIZE) {
+                channel.wait_data_request()?;
+                for bytes in sector.chunks_mut(2) {
+                    bytes.copy_from_slice(&channel.read_data().to_le_bytes());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_sectors(&self, first_sector: u64, buffer: &[u8]) -> Result<(), ()> {
+        self.sectors_range(first_sector, buffer.len())?;
+        let channel = self.channel.lock();
+
+        for (index, chunk) in buffer.chunks(SECTOR_SIZE * MAX_SECTORS_PER_COMMAND).enumerate() {
+            let lba = first_sector + (index * MAX_SECTORS_PER_COMMAND) as u64;
+            let count = chunk.len() / SECTOR_SIZE;
+            channel.send_command(self.slave, lba, count as u8, WRITE_SECTORS_COMMAND)?;
+
+            for sector in chunk.chunks(SECTOR_SIZE) {
+                channel.wait_data_request()?;
+                for bytes in sector.chunks(2) {
+                    channel.write_data(u16::from_le_bytes([bytes[0], bytes[1]]));
+                }
+            }
+        }
+
+        channel.write_register(COMMAND_REGISTER, CACHE_FLUSH_COMMAND);
+        channel.delay();
+        channel.wait_ready().map(|_| ())
+    }
+}
+
+/// Detects the ATA drives of both IDE channels.
+///
+/// The drives are named by their position: hda & hdb on the primary channel, hdc & hdd on the secondary.
+pub fn probe() -> Vec<(&'static str, AtaDrive)> {
+    let positions = [
+        ("hda", &PRIMARY_CHANNEL, false),
+        ("hdb", &PRIMARY_CHANNEL, true),
+        ("hdc", &SECONDARY_CHANNEL, false),
+        ("hdd", &SECONDARY_CHANNEL, true),
+    ];
+
+    let mut drives = Vec::new();
+    for (name, channel, slave) in positions {
+        if let Some(sectors) = channel.lock().identify(slave) {
+            drives.push((name, AtaDrive { channel, slave, sectors }));
+        }
+    }
+    drives
+}
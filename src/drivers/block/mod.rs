@@ -0,0 +1,55 @@
+//! The block module goal is to manage storage devices which are accessed in fixed size sectors
+
+pub mod ata;
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+use lazy_static::lazy_static;
+use log::info;
+use spin::Mutex;
+
+lazy_static! {
+    /// The detected block devices and their names
+    pub static ref BLOCK_DEVICES: Mutex<Vec<(String, Arc<dyn BlockDevice>)>> = Mutex::new(Vec::new());
+}
+
+/// A storage device that is read and written in whole sectors
+pub trait BlockDevice: Send + Sync {
+    /// The size of a single sector in bytes
+    fn sector_size(&self) -> usize;
+
+    /// The number of sectors the device holds
+    fn capacity(&self) -> u64;
+
+    /// Reads sectors starting at `first_sector` into `buffer`.
+    ///
+    /// The buffer size must be a multiple of the sector size.
+    fn read_sectors(&self, first_sector: u64, buffer: &mut [u8]) -> Result<(), ()>;
+
+    /// Writes `buffer` to the sectors starting at `first_sector`.
+    ///
+    /// The buffer size must be a multiple of the sector size.
+    fn write_sectors(&self, first_sector: u64, buffer: &[u8]) -> Result<(), ()>;
+}
+
+/// Detects the storage devices and registers them
+pub fn init() {
+    for (name, drive) in ata::probe() {
+        info!("found ATA drive {} with {:#x} sectors", name, drive.capacity());
+        register(name, Arc::new(drive));
+    }
+    info!("block devices initialized");
+}
+
+/// Saves a block device under `name`
+pub fn register(name: &str, device: Arc<dyn BlockDevice>) {
+    BLOCK_DEVICES.lock().push((String::from(name), device));
+}
+
+/// Returns the block device named `name`
+pub fn get_device(name: &str) -> Option<Arc<dyn BlockDevice>> {
+    BLOCK_DEVICES
+        .lock()
+        .iter()
+        .find(|(device_name, _)| device_name == name)
+        .map(|(_, device)| device.clone())
+}
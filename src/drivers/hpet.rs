@@ -0,0 +1,80 @@
+//! The High Precision Event Timer, a memory mapped counter that runs at a fixed frequency.
+//!
+//! Only the main counter is used (to measure the other clocks), the comparators stay disabled.
+
+use core::{
+    ptr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use log::info;
+
+use crate::{acpi, memory::map_device_memory};
+
+pub const SIGNATURE: &[u8; 4] = b"HPET";
+/// The address field of the table's generic address structure
+const ADDRESS_OFFSET: usize = 44;
+
+// registers offsets from the HPET base
+const CAPABILITIES_REGISTER: u64 = 0x00;
+const CONFIGURATION_REGISTER: u64 = 0x10;
+const MAIN_COUNTER_REGISTER: u64 = 0xF0;
+
+const ENABLE: u64 = 1;
+/// The counter period is in bits 63:32 of the capabilities, at most 100ns
+const PERIOD_SHIFT: u64 = 32;
+const MAX_PERIOD_FEMTOSECONDS: u64 = 100_000_000;
+const FEMTOSECONDS_PER_MICROSECOND: u64 = 1_000_000_000;
+
+/// The linear address of the HPET registers, zero while the HPET is disabled
+static BASE: AtomicU64 = AtomicU64::new(0);
+static PERIOD_FEMTOSECONDS: AtomicU64 = AtomicU64::new(0);
+
+fn read_register(register: u64) -> u64 {
+    unsafe { ptr::read_volatile((BASE.load(Ordering::Relaxed) + register) as *const u64) }
+}
+
+fn write_register(register: u64, value: u64) {
+    unsafe { ptr::write_volatile((BASE.load(Ordering::Relaxed) + register) as *mut u64, value) }
+}
+
+/// Finds the HPET in the ACPI tables and starts it's main counter, fails if the firmware doesn't provide one
+pub fn init() -> Result<(), ()> {
+    let table = acpi::find_table(SIGNATURE)?;
+    let address = table.get(ADDRESS_OFFSET..ADDRESS_OFFSET + 8).ok_or(())?;
+    let address = u64::from_le_bytes(address.try_into().unwrap());
+    BASE.store(map_device_memory(address)?, Ordering::Relaxed);
+
+    let period = read_register(CAPABILITIES_REGISTER) >> PERIOD_SHIFT;
+    if period == 0 || period > MAX_PERIOD_FEMTOSECONDS {
+        BASE.store(0, Ordering::Relaxed);
+        return Err(());
+    }
+    PERIOD_FEMTOSECONDS.store(period, Ordering::Relaxed);
+
+    write_register(CONFIGURATION_REGISTER, read_register(CONFIGURATION_REGISTER) | ENABLE);
+    info!("HPET counts every {} fs", period);
+    Ok(())
+}
+
+/// Whether the HPET was initialized
+pub fn is_enabled() -> bool {
+    BASE.load(Ordering::Relaxed) != 0
+}
+
+/// Returns the main counter, it increments every `period_femtoseconds`
+pub fn counter() -> u64 {
+    read_register(MAIN_COUNTER_REGISTER)
+}
+
+pub fn period_femtoseconds() -> u64 {
+    PERIOD_FEMTOSECONDS.load(Ordering::Relaxed)
+}
+
+/// Busy waits `microseconds` using the main counter, the HPET must be initialized
+pub fn wait_microseconds(microseconds: u64) {
+    let counts = microseconds * FEMTOSECONDS_PER_MICROSECOND / period_femtoseconds();
+    let start = counter();
+    while counter().wrapping_sub(start) < counts {
+        core::hint::spin_loop();
+    }
+}
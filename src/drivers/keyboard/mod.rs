@@ -0,0 +1,174 @@
+//! A PS/2 keyboard driver, the 8042 controller raises IRQ 1 for every scancode it receives.
+//!
+//! The interrupt handler decodes the scancodes to key events and queues them without taking any lock,
+//! each virtual terminal has it's own queue which receives the events typed while it's active.
+//! Alt + F1 - F4 switch the virtual terminals, and Shift + Page Up / Page Down scroll the active one.
+
+pub mod scancodes;
+
+use log::info;
+use spin::Mutex;
+use x86_64::instructions::{interrupts, port::Port};
+
+use crate::interrupts::register_irq;
+
+use self::scancodes::{Decoder, Key, KeyEvent, Modifiers};
+use super::{
+    ring_buffer::RingBuffer,
+    vga::{self, TERMINAL_COUNT},
+};
+
+pub const KEYBOARD_IRQ: u8 = 1;
+const EVENTS_CAPACITY: usize = 128;
+
+const DATA_PORT: u16 = 0x60;
+/// Status on read, command on write
+const STATUS_PORT: u16 = 0x64;
+const COMMAND_PORT: u16 = 0x64;
+
+const STATUS_OUTPUT_FULL: u8 = 1;
+const STATUS_INPUT_FULL: u8 = 1 << 1;
+
+const READ_CONFIGURATION_COMMAND: u8 = 0x20;
+const WRITE_CONFIGURATION_COMMAND: u8 = 0x60;
+/// Makes the controller send the next data byte as if it came from the keyboard
+const WRITE_KEYBOARD_OUTPUT_COMMAND: u8 = 0xD2;
+
+const CONFIGURATION_KEYBOARD_INTERRUPT: u8 = 1;
+/// Translates the keyboard scancodes to scancode set 1
+const CONFIGURATION_TRANSLATION: u8 = 1 << 6;
+
+/// Status polls before giving up on the controller
+const POLL_LIMIT: usize = 100_000;
+/// The lines Shift + Page Up / Page Down scroll
+const SCROLL_LINES: isize = 12;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_EVENTS: RingBuffer<KeyEvent, EVENTS_CAPACITY> = RingBuffer::new(KeyEvent::empty());
+/// The key events of every virtual terminal
+static EVENTS: [RingBuffer<KeyEvent, EVENTS_CAPACITY>; TERMINAL_COUNT] = [EMPTY_EVENTS; TERMINAL_COUNT];
+/// Used only by the interrupt handler
+static DECODER: Mutex<Decoder> = Mutex::new(Decoder::new());
+
+fn status() -> u8 {
+    unsafe { Port::<u8>::new(STATUS_PORT).read() }
+}
+
+/// Waits until the controller can receive a command or data
+fn wait_input_empty() -> Result<(), ()> {
+    for _ in 0..POLL_LIMIT {
+        if status() & STATUS_INPUT_FULL == 0 {
+            return Ok(());
+        }
+    }
+    Err(())
+}
+
+fn wait_output(full: bool) -> Result<(), ()> {
+    for _ in 0..POLL_LIMIT {
+        if (status() & STATUS_OUTPUT_FULL != 0) == full {
+            return Ok(());
+        }
+    }
+    Err(())
+}
+
+fn send_command(command: u8) -> Result<(), ()> {
+    wait_input_empty()?;
+    unsafe { Port::<u8>::new(COMMAND_PORT).write(command) };
+    Ok(())
+}
+
+fn send_data(data: u8) -> Result<(), ()> {
+    wait_input_empty()?;
+    unsafe { Port::<u8>::new(DATA_PORT).write(data) };
+    Ok(())
+}
+
+/// Enables the keyboard interrupt and the scancode translation, and registers the IRQ 1 handler.
+pub fn init() {
+    // discard the scancodes received before initialization
+    while status() & STATUS_OUTPUT_FULL != 0 {
+        unsafe { Port::<u8>::new(DATA_PORT).read() };
+    }
+
+    interrupts::without_interrupts(|| {
+        send_command(READ_CONFIGURATION_COMMAND).unwrap();
+        wait_output(true).unwrap();
+        let configuration = unsafe { Port::<u8>::new(DATA_PORT).read() };
+
+        send_command(WRITE_CONFIGURATION_COMMAND).unwrap();
+        send_data(configuration | CONFIGURATION_KEYBOARD_INTERRUPT | CONFIGURATION_TRANSLATION).unwrap();
+    });
+
+    register_irq(KEYBOARD_IRQ, keyboard_interrupt).unwrap();
+    info!("keyboard initialized");
+}
+
+/// Injects a scancode through the controller, as if it was sent by the keyboard.
+///
+/// Waits for the previous scancode to be read, so interrupts must be enabled.
+pub fn inject_scancode(scancode: u8) -> Result<(), ()> {
+    wait_output(false)?;
+    send_command(WRITE_KEYBOARD_OUTPUT_COMMAND)?;
+    send_data(scancode)
+}
+
+/// Handles the terminal hotkeys, returns whether the event was a hotkey
+fn handle_hotkey(event: &KeyEvent) -> bool {
+    if !event.pressed {
+        return false;
+    }
+    match event.key {
+        Key::Function(number @ 1..=4) if event.modifiers.contains(Modifiers::ALT) => {
+            vga::switch_terminal(number as usize - 1).is_ok()
+        }
+        Key::PageUp if event.modifiers.contains(Modifiers::SHIFT) => {
+            vga::scroll_active_terminal(SCROLL_LINES);
+            true
+        }
+        Key::PageDown if event.modifiers.contains(Modifiers::SHIFT) => {
+            vga::scroll_active_terminal(-SCROLL_LINES);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// IRQ 1 handler, reads the scancode and queues it's key event to the active terminal
+fn keyboard_interrupt() {
+    let scancode = unsafe { Port::<u8>::new(DATA_PORT).read() };
+
+    // interrupts are disabled, so the decoder is never locked when the handler starts
+    if let Some(event) = DECODER.lock().decode(scancode) {
+        if handle_hotkey(&event) {
+            return;
+        }
+        // the event is dropped when the queue is full
+        EVENTS[vga::active_terminal()].push(event).ok();
+    }
+}
+
+/// Pops the oldest key event of a terminal, the events must be consumed by a single reader at a time
+pub fn read_event(terminal: usize) -> Option<KeyEvent> {
+    EVENTS[terminal].pop()
+}
+
+/// Waits for the next key event of a terminal, halting the CPU between interrupts.
+///
+/// The interrupts are enabled while waiting, and restored afterwards.
+/// The events must be consumed by a single reader at a time.
+pub fn wait_event(terminal: usize) -> KeyEvent {
+    let enabled = interrupts::are_enabled();
+    loop {
+        // checking the queue with interrupts disabled makes sure an event can't arrive before the `hlt`
+        interrupts::disable();
+        if let Some(event) = EVENTS[terminal].pop() {
+            if enabled {
+                interrupts::enable();
+            }
+            return event;
+        }
+        interrupts::enable_and_hlt();
+    }
+}
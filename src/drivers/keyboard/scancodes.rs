@@ -0,0 +1,267 @@
+//! Decodes scancode set 1, the set the 8042 controller translates every keyboard to
+
+use bitflags::bitflags;
+
+/// Precedes the scancode of an extended key
+const EXTENDED_PREFIX: u8 = 0xE0;
+/// Precedes the pause key sequence (E1 1D 45 E1 9D C5)
+const PAUSE_PREFIX: u8 = 0xE1;
+const PAUSE_SEQUENCE_LENGTH: u8 = 5;
+/// Set on the break (release) scancode of every key
+const BREAK_BIT: u8 = 0x80;
+
+bitflags! {
+    /// The modifiers state when a key event occurred
+    pub struct Modifiers: u8 {
+        const SHIFT =       1;
+        const CTRL =        1 << 1;
+        const ALT =         1 << 2;
+        const CAPS_LOCK =   1 << 3;
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    /// A key that types a character, saved as it's unshifted ASCII (e.g 'a', '1', '[')
+    Character(u8),
+    /// F1-F12
+    Function(u8),
+    Escape,
+    Backspace,
+    Tab,
+    Enter,
+    LeftShift,
+    RightShift,
+    LeftCtrl,
+    RightCtrl,
+    LeftAlt,
+    RightAlt,
+    CapsLock,
+    NumLock,
+    ScrollLock,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    Unknown,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub key: Key,
+    /// Whether the key was pressed or released
+    pub pressed: bool,
+    pub modifiers: Modifiers,
+}
+
+impl KeyEvent {
+    /// An empty event, used to fill unused queue slots
+    pub const fn empty() -> Self {
+        KeyEvent {
+            key: Key::Unknown,
+            pressed: false,
+            modifiers: Modifiers::empty(),
+        }
+    }
+
+    /// Returns the ASCII character the event types, Ctrl + letter types the matching control character.
+    ///
+    /// Released keys and keys without a character return `None`.
+    pub fn ascii(&self) -> Option<u8> {
+        if !self.pressed {
+            return None;
+        }
+
+        let character = match self.key {
+            Key::Character(character) => character,
+            Key::Enter => return Some(b'\n'),
+            Key::Backspace => return Some(0x08),
+            Key::Tab => return Some(b'\t'),
+            Key::Escape => return Some(0x1B),
+            _ => return None,
+        };
+
+        if self.modifiers.contains(Modifiers::CTRL) && character.is_ascii_lowercase() {
+            // Ctrl + A = 1 ... Ctrl + Z = 26
+            return Some(character - b'a' + 1);
+        }
+
+        let shift = self.modifiers.contains(Modifiers::SHIFT);
+        if character.is_ascii_lowercase() {
+            let caps_lock = self.modifiers.contains(Modifiers::CAPS_LOCK);
+            return Some(if shift != caps_lock { character.to_ascii_uppercase() } else { character });
+        }
+        Some(if shift { shifted(character) } else { character })
+    }
+}
+
+/// Returns the character a key types with shift
+fn shifted(character: u8) -> u8 {
+    match character {
+        b'1' => b'!',
+        b'2' => b'@',
+        b'3' => b'#',
+        b'4' => b'$',
+        b'5' => b'%',
+        b'6' => b'^',
+        b'7' => b'&',
+        b'8' => b'*',
+        b'9' => b'(',
+        b'0' => b')',
+        b'-' => b'_',
+        b'=' => b'+',
+        b'[' => b'{',
+        b']' => b'}',
+        b';' => b':',
+        b'\'' => b'"',
+        b'`' => b'~',
+        b'\\' => b'|',
+        b',' => b'<',
+        b'.' => b'>',
+        b'/' => b'?',
+        character => character,
+    }
+}
+
+/// Maps the make scancode of a regular key to it's key
+fn regular_key(scancode: u8) -> Key {
+    const CHARACTERS: &[u8; 0x3A] = b"\0\x1b1234567890-=\x08\tqwertyuiop[]\n\0asdfghjkl;'`\0\\zxcvbnm,./\0*\0 ";
+    const KEYPAD: &[u8; 13] = b"789-456+1230.";
+
+    match scancode {
+        0x01 => Key::Escape,
+        0x0E => Key::Backspace,
+        0x0F => Key::Tab,
+        0x1C => Key::Enter,
+        0x1D => Key::LeftCtrl,
+        0x2A => Key::LeftShift,
+        0x36 => Key::RightShift,
+        0x38 => Key::LeftAlt,
+        0x3A => Key::CapsLock,
+        0x3B..=0x44 => Key::Function(scancode - 0x3B + 1),
+        0x45 => Key::NumLock,
+        0x46 => Key::ScrollLock,
+        0x47..=0x53 => Key::Character(KEYPAD[(scancode - 0x47) as usize]),
+        0x57 => Key::Function(11),
+        0x58 => Key::Function(12),
+        _ if (scancode as usize) < CHARACTERS.len() && CHARACTERS[scancode as usize] != 0 => {
+            Key::Character(CHARACTERS[scancode as usize])
+        }
+        _ => Key::Unknown,
+    }
+}
+
+/// Maps the make scancode of an extended key (after the 0xE0 prefix) to it's key
+fn extended_key(scancode: u8) -> Key {
+    match scancode {
+        0x1C => Key::Enter,
+        0x1D => Key::RightCtrl,
+        0x35 => Key::Character(b'/'),
+        0x38 => Key::RightAlt,
+        0x47 => Key::Home,
+        0x48 => Key::Up,
+        0x49 => Key::PageUp,
+        0x4B => Key::Left,
+        0x4D => Key::Right,
+        0x4F => Key::End,
+        0x50 => Key::Down,
+        0x51 => Key::PageDown,
+        0x52 => Key::Insert,
+        0x53 => Key::Delete,
+        _ => Key::Unknown,
+    }
+}
+
+/// A state machine that turns scancodes to key events, and tracks the modifier keys
+pub struct Decoder {
+    extended: bool,
+    /// The bytes left to skip of the pause key sequence
+    skip: u8,
+    left_shift: bool,
+    right_shift: bool,
+    left_ctrl: bool,
+    right_ctrl: bool,
+    left_alt: bool,
+    right_alt: bool,
+    caps_lock: bool,
+}
+
+impl Decoder {
+    pub const fn new() -> Self {
+        Decoder {
+            extended: false,
+            skip: 0,
+            left_shift: false,
+            right_shift: false,
+            left_ctrl: false,
+            right_ctrl: false,
+            left_alt: false,
+            right_alt: false,
+            caps_lock: false,
+        }
+    }
+
+    fn modifiers(&self) -> Modifiers {
+        let mut modifiers = Modifiers::empty();
+        modifiers.set(Modifiers::SHIFT, self.left_shift || self.right_shift);
+        modifiers.set(Modifiers::CTRL, self.left_ctrl || self.right_ctrl);
+        modifiers.set(Modifiers::ALT, self.left_alt || self.right_alt);
+        modifiers.set(Modifiers::CAPS_LOCK, self.caps_lock);
+        modifiers
+    }
+
+    /// Feeds the next scancode, returns an event once a whole key sequence was received.
+    pub fn decode(&mut self, scancode: u8) -> Option<KeyEvent> {
+        if self.skip > 0 {
+            self.skip -= 1;
+            return None;
+        }
+        match scancode {
+            EXTENDED_PREFIX => {
+                self.extended = true;
+                return None;
+            }
+            PAUSE_PREFIX => {
+                self.skip = PAUSE_SEQUENCE_LENGTH;
+                return None;
+            }
+            _ => {}
+        }
+
+        let pressed = scancode & BREAK_BIT == 0;
+        let make = scancode & !BREAK_BIT;
+        let key = if self.extended {
+            self.extended = false;
+            // print screen sends a fake shift (E0 2A / E0 AA) that must not change the shift state
+            if make == 0x2A || make == 0x36 {
+                return None;
+            }
+            extended_key(make)
+        } else {
+            regular_key(make)
+        };
+
+        match key {
+            Key::LeftShift => self.left_shift = pressed,
+            Key::RightShift => self.right_shift = pressed,
+            Key::LeftCtrl => self.left_ctrl = pressed,
+            Key::RightCtrl => self.right_ctrl = pressed,
+            Key::LeftAlt => self.left_alt = pressed,
+            Key::RightAlt => self.right_alt = pressed,
+            Key::CapsLock if pressed => self.caps_lock = !self.caps_lock,
+            _ => {}
+        }
+
+        Some(KeyEvent {
+            key,
+            pressed,
+            modifiers: self.modifiers(),
+        })
+    }
+}
@@ -1,4 +1,4 @@
-//! The drivers module goal is to manage IO (keyboard & screen)
+//! The drivers module goal is to manage IO (keyboard, screen & storage)
 
 #[macro_export]
 macro_rules! graphic_print {
@@ -39,5 +39,11 @@ macro_rules! serial_println {
     }
 }
 
+pub mod block;
+pub mod hpet;
+pub mod keyboard;
+pub mod pit;
+pub mod ring_buffer;
+pub mod rtc;
 pub mod serial;
 pub mod vga;
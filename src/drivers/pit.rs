@@ -0,0 +1,42 @@
+//! The Programmable Interval Timer, a fixed frequency timer used to measure the other clocks
+
+use x86_64::instructions::port::Port;
+
+/// The PIT input clock, in Hz
+pub const FREQUENCY: u64 = 1_193_182;
+const MICROSECONDS_PER_SECOND: u64 = 1_000_000;
+/// The longest wait a 16 bit count allows, ~54ms
+pub const MAX_WAIT_MICROSECONDS: u64 = 0xFFFF * MICROSECONDS_PER_SECOND / FREQUENCY;
+
+const CHANNEL_2_DATA: u16 = 0x42;
+const MODE_COMMAND: u16 = 0x43;
+/// Controls the channel 2 gate and reads it's output
+const SPEAKER_CONTROL: u16 = 0x61;
+
+/// Channel 2, low byte then high byte, mode 0 (interrupt on terminal count), binary
+const CHANNEL_2_ONE_SHOT: u8 = 0b1011_0000;
+const GATE_ENABLE: u8 = 1;
+const SPEAKER_ENABLE: u8 = 1 << 1;
+const CHANNEL_2_OUTPUT: u8 = 1 << 5;
+
+/// Busy waits `microseconds` (up to `MAX_WAIT_MICROSECONDS`) using channel 2,
+/// which isn't connected to an IRQ so the wait doesn't depend on interrupts.
+pub fn wait_microseconds(microseconds: u64) {
+    let count = (microseconds.min(MAX_WAIT_MICROSECONDS) * FREQUENCY / MICROSECONDS_PER_SECOND) as u16;
+
+    let mut control = Port::<u8>::new(SPEAKER_CONTROL);
+    let mut data = Port::<u8>::new(CHANNEL_2_DATA);
+    unsafe {
+        // the gate is closed while the count is loaded, and the speaker stays quiet
+        let value = control.read() & !(GATE_ENABLE | SPEAKER_ENABLE);
+        control.write(value);
+
+        Port::<u8>::new(MODE_COMMAND).write(CHANNEL_2_ONE_SHOT);
+        data.write(count as u8);
+        data.write((count >> 8) as u8);
+
+        control.write(value | GATE_ENABLE);
+        while control.read() & CHANNEL_2_OUTPUT == 0 {}
+        control.write(value);
+    }
+}
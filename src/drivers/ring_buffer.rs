@@ -0,0 +1,72 @@
+//! A lock-free ring buffer for passing data from an interrupt handler to the rest of the kernel
+
+use core::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A single producer, single consumer queue of `N - 1` elements.
+///
+/// The producer (an interrupt handler) never waits for the consumer,
+/// which means pushing to a full buffer drops the element instead of deadlocking.
+pub struct RingBuffer<T: Copy, const N: usize> {
+    elements: UnsafeCell<[T; N]>,
+    /// The index of the next element to pop, written only by the consumer
+    head: AtomicUsize,
+    /// The index of the next element to push, written only by the producer
+    tail: AtomicUsize,
+}
+
+// the head & tail indices make sure the producer and the consumer never access the same element
+unsafe impl<T: Copy + Send, const N: usize> Sync for RingBuffer<T, N> {}
+
+impl<T: Copy, const N: usize> RingBuffer<T, N> {
+    /// Creates an empty buffer, `initial` fills the unused elements
+    pub const fn new(initial: T) -> Self {
+        RingBuffer {
+            elements: UnsafeCell::new([initial; N]),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    /// Pushes an element to the end of the buffer, fails if the buffer is full.
+    ///
+    /// Must be called only by the producer.
+    pub fn push(&self, element: T) -> Result<(), ()> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        let next = (tail + 1) % N;
+        if next == self.head.load(Ordering::Acquire) {
+            return Err(());
+        }
+
+        unsafe { (*self.elements.get())[tail] = element };
+        self.tail.store(next, Ordering::Release);
+        Ok(())
+    }
+
+    /// Pops the element at the start of the buffer.
+    ///
+    /// Must be called only by the consumer.
+    pub fn pop(&self) -> Option<T> {
+        let head = self.head.load(Ordering::Relaxed);
+        if head == self.tail.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let element = unsafe { (*self.elements.get())[head] };
+        self.head.store((head + 1) % N, Ordering::Release);
+        Some(element)
+    }
+
+    /// Returns the number of elements that can be pushed, the consumer may pop meanwhile so there may be more.
+    ///
+    /// Must be called only by the producer.
+    pub fn room(&self) -> usize {
+        (self.head.load(Ordering::Acquire) + N - self.tail.load(Ordering::Relaxed) - 1) % N
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire) == self.tail.load(Ordering::Acquire)
+    }
+}
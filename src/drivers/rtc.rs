@@ -0,0 +1,136 @@
+//! The CMOS Real Time Clock, keeps the wall clock time while the computer is off.
+//!
+//! The registers are read through an index port, the values may be BCD and the hours may be in 12 hour format
+//! (see status register B).
+
+use x86_64::instructions::{interrupts, port::Port};
+
+const ADDRESS_PORT: u16 = 0x70;
+const DATA_PORT: u16 = 0x71;
+/// Selecting a register with this bit set keeps the NMI disabled while the register is read
+const NMI_DISABLE: u8 = 1 << 7;
+
+const SECONDS_REGISTER: u8 = 0x00;
+const MINUTES_REGISTER: u8 = 0x02;
+const HOURS_REGISTER: u8 = 0x04;
+const DAY_REGISTER: u8 = 0x07;
+const MONTH_REGISTER: u8 = 0x08;
+const YEAR_REGISTER: u8 = 0x09;
+const STATUS_A_REGISTER: u8 = 0x0A;
+const STATUS_B_REGISTER: u8 = 0x0B;
+/// Not standard, but QEMU & most firmwares keep the century here
+const CENTURY_REGISTER: u8 = 0x32;
+
+/// Set while the RTC updates the time registers
+const UPDATE_IN_PROGRESS: u8 = 1 << 7;
+const HOURS_24: u8 = 1 << 1;
+const BINARY: u8 = 1 << 2;
+/// The PM bit of the hours register in 12 hour format
+const HOURS_PM: u8 = 1 << 7;
+const DEFAULT_CENTURY: u16 = 20;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+/// The days between 0000-03-01 and 1970-01-01 in the proleptic Gregorian calendar
+const UNIX_EPOCH_DAYS: i64 = 719_468;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl DateTime {
+    /// Returns the seconds since 1970-01-01 00:00:00 UTC
+    pub fn unix_seconds(&self) -> u64 {
+        // days from civil (http://howardhinnant.github.io/date_algorithms.html), years start at March
+        let year = self.year as i64 - (self.month <= 2) as i64;
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400;
+        let month = self.month as i64;
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146_097 + day_of_era - UNIX_EPOCH_DAYS;
+
+        days.max(0) as u64 * SECONDS_PER_DAY + self.hour as u64 * 3600 + self.minute as u64 * 60 + self.second as u64
+    }
+}
+
+/// The raw time registers, in the RTC format
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Registers {
+    second: u8,
+    minute: u8,
+    hour: u8,
+    day: u8,
+    month: u8,
+    year: u8,
+    century: u8,
+}
+
+fn read_register(register: u8) -> u8 {
+    unsafe {
+        Port::<u8>::new(ADDRESS_PORT).write(NMI_DISABLE | register);
+        Port::<u8>::new(DATA_PORT).read()
+    }
+}
+
+fn read_registers() -> Registers {
+    while read_register(STATUS_A_REGISTER) & UPDATE_IN_PROGRESS != 0 {
+        core::hint::spin_loop();
+    }
+    Registers {
+        second: read_register(SECONDS_REGISTER),
+        minute: read_register(MINUTES_REGISTER),
+        hour: read_register(HOURS_REGISTER),
+        day: read_register(DAY_REGISTER),
+        month: read_register(MONTH_REGISTER),
+        year: read_register(YEAR_REGISTER),
+        century: read_register(CENTURY_REGISTER),
+    }
+}
+
+fn from_bcd(value: u8) -> u8 {
+    (value >> 4) * 10 + (value & 0xF)
+}
+
+/// Reads the wall clock time
+pub fn read() -> DateTime {
+    // the index port is shared, and an update may start between two reads so they are repeated until they match
+    let (registers, status) = interrupts::without_interrupts(|| {
+        let mut registers = read_registers();
+        loop {
+            let again = read_registers();
+            if again == registers {
+                break (registers, read_register(STATUS_B_REGISTER));
+            }
+            registers = again;
+        }
+    });
+
+    let convert = |value: u8| if status & BINARY == 0 { from_bcd(value) } else { value };
+    let mut hour = convert(registers.hour & !HOURS_PM);
+    if status & HOURS_24 == 0 {
+        // 12AM is midnight
+        hour %= 12;
+        if registers.hour & HOURS_PM != 0 {
+            hour += 12;
+        }
+    }
+    let century = match convert(registers.century) as u16 {
+        century @ 19..=99 => century,
+        _ => DEFAULT_CENTURY,
+    };
+
+    DateTime {
+        year: century * 100 + convert(registers.year) as u16,
+        month: convert(registers.month),
+        day: convert(registers.day),
+        hour,
+        minute: convert(registers.minute),
+        second: convert(registers.second),
+    }
+}
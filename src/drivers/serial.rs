@@ -1,21 +0,0 @@
-//! The uart module goal is to implement`println!` macro with a static serial that will be guarded using a spinlock.
-
-use lazy_static::lazy_static;
-use spin::Mutex;
-use uart_16550::SerialPort;
-
-const SERIAL_IO_PORT: u16 = 0x3F8;
-
-lazy_static! {
-    pub static ref SERIAL: Mutex<SerialPort> = {
-        let mut serial_port = unsafe { SerialPort::new(SERIAL_IO_PORT) };
-        serial_port.init();
-        Mutex::new(serial_port)
-    };
-}
-
-/// internal serial print function, used in this module macros
-pub fn _print(args: core::fmt::Arguments) {
-    use core::fmt::Write;
-    SERIAL.lock().write_fmt(args).unwrap();
-}
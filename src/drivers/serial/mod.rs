@@ -0,0 +1,295 @@
+//! A 16550 UART driver for the four PC serial ports (COM1 - COM4), COM1 is the kernel log.
+//!
+//! Output is queued to a transmit ring that the transmitter empty interrupt drains, and the interrupt handler
+//! queues the received bytes without taking any lock. Before `init` registers the IRQs the output is sent by polling.
+//!
+//! The transmit ring is locked only with interrupts disabled, so a handler that logs never waits for the write it interrupted.
+//! A writer that still can't lock the ring (e.g. a panic while the ring was locked) sends directly to the UART.
+
+pub mod uart;
+
+use alloc::{format, sync::Arc};
+use core::{
+    fmt::{self, Write},
+    sync::atomic::{AtomicBool, Ordering},
+};
+use log::{info, warn};
+use spin::{Mutex, MutexGuard, Once};
+use x86_64::instructions::interrupts;
+
+use crate::{
+    fs::{console::SerialTty, devfs::DEVICES},
+    interrupts::register_irq,
+};
+
+use self::uart::{Uart, FIFO_SIZE};
+use super::ring_buffer::RingBuffer;
+
+pub const PORT_COUNT: usize = 4;
+/// COM1 & COM3 share IRQ 4, COM2 & COM4 share IRQ 3
+pub const COM1_IRQ: u8 = 4;
+pub const COM2_IRQ: u8 = 3;
+const RECEIVE_CAPACITY: usize = 256;
+const TRANSMIT_CAPACITY: usize = 4096;
+/// Lock attempts before a writer gives up on the transmit ring
+const LOCK_ATTEMPTS: usize = 100_000;
+
+pub static PORTS: [SerialPort; PORT_COUNT] = [
+    SerialPort::new(0x3F8, COM1_IRQ),
+    SerialPort::new(0x2F8, COM2_IRQ),
+    SerialPort::new(0x3E8, COM1_IRQ),
+    SerialPort::new(0x2E8, COM2_IRQ),
+];
+
+pub struct SerialPort {
+    uart: Uart,
+    irq: u8,
+    /// Whether a UART answered at the port, checked on first use
+    present: Once<bool>,
+    /// Whether the IRQ drains the transmit ring and fills the receive ring
+    interrupt_driven: AtomicBool,
+    /// Filled only by the interrupt handler
+    received: RingBuffer<u8, RECEIVE_CAPACITY>,
+    /// Pushed & popped only while `transmitter` is locked
+    transmit: RingBuffer<u8, TRANSMIT_CAPACITY>,
+    transmitter: Mutex<()>,
+}
+
+impl SerialPort {
+    const fn new(base: u16, irq: u8) -> Self {
+        SerialPort {
+            uart: Uart::new(base),
+            irq,
+            present: Once::new(),
+            interrupt_driven: AtomicBool::new(false),
+            received: RingBuffer::new(0),
+            transmit: RingBuffer::new(0),
+            transmitter: Mutex::new(()),
+        }
+    }
+
+    /// Initializes the UART on first use, returns whether it exists
+    pub fn is_present(&self) -> bool {
+        *self.present.call_once(|| self.uart.init().is_ok())
+    }
+
+    /// Tries to lock the transmit ring, interrupts must be disabled
+    fn try_lock_transmitter(&self) -> Option<MutexGuard<()>> {
+        (0..LOCK_ATTEMPTS).find_map(|_| {
+            let guard = self.transmitter.try_lock();
+            if guard.is_none() {
+                core::hint::spin_loop();
+            }
+            guard
+        })
+    }
+
+    /// Moves queued bytes to the transmit FIFO if it's empty, the transmitter must be locked
+    fn fill_fifo(&self) {
+        if !self.uart.is_transmitter_empty() {
+            return;
+        }
+        for byte in (0..FIFO_SIZE).map_while(|_| self.transmit.pop()) {
+            self.uart.transmit(byte);
+        }
+    }
+
+    /// Sends the queued bytes by polling, the transmitter must be locked
+    fn drain(&self) {
+        while !self.transmit.is_empty() {
+            self.fill_fifo();
+            core::hint::spin_loop();
+        }
+    }
+
+    /// Calls `operation` with a writer to the port, the writes of a single operation are never interleaved
+    /// with other writers unless the transmit ring couldn't be locked.
+    fn with_writer(&self, operation: impl FnOnce(&mut Writer)) {
+        if !self.is_present() {
+            return;
+        }
+        interrupts::without_interrupts(|| {
+            let guard = self.try_lock_transmitter();
+            let mut writer = Writer {
+                port: self,
+                is_queued: guard.is_some(),
+            };
+            operation(&mut writer);
+
+            if guard.is_some() {
+                if self.interrupt_driven.load(Ordering::Acquire) {
+                    // the transmitter empty interrupt sends the rest
+                    self.fill_fifo();
+                } else {
+                    self.drain();
+                }
+            }
+        });
+    }
+
+    /// Writes bytes without waiting for the transmitter (unless the transmit ring is full)
+    pub fn write(&self, bytes: &[u8]) {
+        self.with_writer(|writer| writer.write_bytes(bytes));
+    }
+
+    pub fn write_fmt(&self, args: fmt::Arguments) {
+        self.with_writer(|writer| {
+            writer.write_fmt(args).ok();
+        });
+    }
+
+    /// Sends all the queued bytes
+    pub fn flush(&self) {
+        if !self.is_present() {
+            return;
+        }
+        interrupts::without_interrupts(|| {
+            if let Some(_guard) = self.try_lock_transmitter() {
+                self.drain();
+            }
+        });
+    }
+
+    /// Makes the port receive the bytes it transmits, used to test the port without a connected terminal.
+    ///
+    /// The queued output is sent first, so it isn't received.
+    pub fn set_loopback(&self, enabled: bool) {
+        self.flush();
+        if self.is_present() {
+            self.uart.set_loopback(enabled);
+        }
+    }
+
+    /// Pops the oldest received byte, the bytes must be consumed by a single reader at a time
+    pub fn read_byte(&self) -> Option<u8> {
+        if self.interrupt_driven.load(Ordering::Acquire) {
+            return self.received.pop();
+        }
+        if !self.is_present() {
+            return None;
+        }
+        self.uart.try_receive()
+    }
+
+    /// Waits for the next received byte, halting the CPU between interrupts.
+    ///
+    /// The interrupts are enabled while waiting, and restored afterwards.
+    /// The bytes must be consumed by a single reader at a time.
+    pub fn wait_byte(&self) -> u8 {
+        let enabled = interrupts::are_enabled();
+        loop {
+            // checking the ring with interrupts disabled makes sure a byte can't arrive before the `hlt`
+            interrupts::disable();
+            if let Some(byte) = self.read_byte() {
+                if enabled {
+                    interrupts::enable();
+                }
+                return byte;
+            }
+            if self.interrupt_driven.load(Ordering::Acquire) {
+                interrupts::enable_and_hlt();
+            } else {
+                interrupts::enable();
+                core::hint::spin_loop();
+            }
+        }
+    }
+
+    /// Receives the available bytes and refills the transmit FIFO
+    fn handle_interrupt(&self) {
+        if !self.interrupt_driven.load(Ordering::Acquire) {
+            return;
+        }
+        while self.uart.is_interrupt_pending() {
+            while let Some(byte) = self.uart.try_receive() {
+                // the byte is dropped when the ring is full
+                self.received.push(byte).ok();
+            }
+            // the transmit ring is locked with interrupts disabled, so only another processor may hold it
+            if let Some(_guard) = self.try_lock_transmitter() {
+                self.fill_fifo();
+            }
+        }
+    }
+}
+
+/// Writes to a port while it's transmit ring is locked, or directly to the UART when it couldn't be locked
+struct Writer<'a> {
+    port: &'a SerialPort,
+    is_queued: bool,
+}
+
+impl Writer<'_> {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            if !self.is_queued {
+                self.port.uart.send_polled(*byte);
+                continue;
+            }
+            // a full ring isn't drained while interrupts are disabled, unless the IRQ is handled by another processor
+            while self.port.transmit.push(*byte).is_err() {
+                self.port.fill_fifo();
+                core::hint::spin_loop();
+            }
+        }
+    }
+}
+
+impl fmt::Write for Writer<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_bytes(s.as_bytes());
+        Ok(())
+    }
+}
+
+fn com1_com3_interrupt() {
+    PORTS[0].handle_interrupt();
+    PORTS[2].handle_interrupt();
+}
+
+fn com2_com4_interrupt() {
+    PORTS[1].handle_interrupt();
+    PORTS[3].handle_interrupt();
+}
+
+/// Makes the present ports interrupt driven, and exposes them as the `ttyS0` - `ttyS3` devices.
+///
+/// Must be called after `interrupts::init_controllers`.
+pub fn init() {
+    for (irq, handler) in [(COM1_IRQ, com1_com3_interrupt as fn()), (COM2_IRQ, com2_com4_interrupt)] {
+        let mut ports = PORTS.iter().filter(|port| port.irq == irq && port.is_present()).peekable();
+        if ports.peek().is_none() {
+            continue;
+        }
+        if register_irq(irq, handler).is_err() {
+            warn!("serial IRQ {} is taken, the ports are polled", irq);
+            continue;
+        }
+        for port in ports {
+            port.interrupt_driven.store(true, Ordering::Release);
+            port.uart.enable_interrupts();
+        }
+    }
+
+    for (index, port) in PORTS.iter().enumerate().filter(|(_, port)| port.is_present()) {
+        DEVICES.register(&format!("ttyS{}", index), Arc::new(SerialTty::new(port)));
+        info!("serial port COM{} initialized", index + 1);
+    }
+}
+
+/// Sends the queued output of every port, called before the machine shuts down
+pub fn flush() {
+    for port in PORTS.iter() {
+        port.flush();
+    }
+}
+
+/// internal serial print function, used in this module macros
+pub fn _print(args: fmt::Arguments) {
+    PORTS[0].write_fmt(args);
+}
+
+/// internal serial byte writer, used by the console device
+pub fn _write_bytes(bytes: &[u8]) {
+    PORTS[0].write(bytes);
+}
@@ -0,0 +1,119 @@
+//! The 16550 UART registers, every serial port has 8 IO ports starting at it's base
+
+use x86_64::instructions::port::Port;
+
+// registers offsets from the port base
+const DATA_REGISTER: u16 = 0;
+const INTERRUPT_ENABLE_REGISTER: u16 = 1;
+/// The divisor latch replaces the data & interrupt enable registers while `LINE_CONTROL_DLAB` is set
+const DIVISOR_LOW_REGISTER: u16 = 0;
+const DIVISOR_HIGH_REGISTER: u16 = 1;
+/// Interrupt identification on read, FIFO control on write
+const INTERRUPT_IDENTIFICATION_REGISTER: u16 = 2;
+const FIFO_CONTROL_REGISTER: u16 = 2;
+const LINE_CONTROL_REGISTER: u16 = 3;
+const MODEM_CONTROL_REGISTER: u16 = 4;
+const LINE_STATUS_REGISTER: u16 = 5;
+const SCRATCH_REGISTER: u16 = 7;
+
+const INTERRUPT_DATA_AVAILABLE: u8 = 1;
+const INTERRUPT_TRANSMITTER_EMPTY: u8 = 1 << 1;
+/// Clear in the interrupt identification while an interrupt is pending
+const INTERRUPT_NOT_PENDING: u8 = 1;
+
+/// Enables and clears the FIFOs, the receive interrupt is raised after 14 bytes (or a timeout)
+const FIFO_ENABLE_CLEAR_14_BYTES: u8 = 0xC7;
+/// 8 data bits, no parity, one stop bit
+const LINE_CONTROL_8N1: u8 = 0x03;
+const LINE_CONTROL_DLAB: u8 = 1 << 7;
+/// 115200 / 3 = 38400 baud
+const BAUD_DIVISOR: u16 = 3;
+/// Data terminal ready, request to send & OUT2, which connects the UART interrupt to the IRQ line
+const MODEM_CONTROL_READY_IRQ: u8 = 0x0B;
+/// The transmitted bytes are received instead of being sent
+const MODEM_CONTROL_LOOPBACK: u8 = 1 << 4;
+
+const LINE_STATUS_DATA_READY: u8 = 1;
+const LINE_STATUS_TRANSMITTER_EMPTY: u8 = 1 << 5;
+
+const SCRATCH_TEST: u8 = 0x5A;
+/// The transmit FIFO size, written at once when the transmitter is empty
+pub const FIFO_SIZE: usize = 16;
+
+pub struct Uart {
+    base: u16,
+}
+
+impl Uart {
+    pub const fn new(base: u16) -> Self {
+        Uart { base }
+    }
+
+    fn read_register(&self, register: u16) -> u8 {
+        unsafe { Port::<u8>::new(self.base + register).read() }
+    }
+
+    fn write_register(&self, register: u16, value: u8) {
+        unsafe { Port::<u8>::new(self.base + register).write(value) }
+    }
+
+    /// Initializes the UART with it's interrupts disabled, fails if there is no UART at the base
+    pub fn init(&self) -> Result<(), ()> {
+        // a missing port reads as 0xFF
+        self.write_register(SCRATCH_REGISTER, SCRATCH_TEST);
+        if self.read_register(SCRATCH_REGISTER) != SCRATCH_TEST {
+            return Err(());
+        }
+
+        self.write_register(INTERRUPT_ENABLE_REGISTER, 0);
+        self.write_register(LINE_CONTROL_REGISTER, LINE_CONTROL_DLAB);
+        self.write_register(DIVISOR_LOW_REGISTER, BAUD_DIVISOR as u8);
+        self.write_register(DIVISOR_HIGH_REGISTER, (BAUD_DIVISOR >> 8) as u8);
+        self.write_register(LINE_CONTROL_REGISTER, LINE_CONTROL_8N1);
+        self.write_register(FIFO_CONTROL_REGISTER, FIFO_ENABLE_CLEAR_14_BYTES);
+        self.write_register(MODEM_CONTROL_REGISTER, MODEM_CONTROL_READY_IRQ);
+        Ok(())
+    }
+
+    /// Raises the port's IRQ when data is received and when the transmitter is empty
+    pub fn enable_interrupts(&self) {
+        self.write_register(INTERRUPT_ENABLE_REGISTER, INTERRUPT_DATA_AVAILABLE | INTERRUPT_TRANSMITTER_EMPTY);
+    }
+
+    /// Connects the transmitter to the receiver, to test the UART without a connected terminal
+    pub fn set_loopback(&self, enabled: bool) {
+        let loopback = if enabled { MODEM_CONTROL_LOOPBACK } else { 0 };
+        self.write_register(MODEM_CONTROL_REGISTER, MODEM_CONTROL_READY_IRQ | loopback);
+    }
+
+    /// Whether the UART raised an interrupt, reading the identification acknowledges a transmitter empty interrupt
+    pub fn is_interrupt_pending(&self) -> bool {
+        self.read_register(INTERRUPT_IDENTIFICATION_REGISTER) & INTERRUPT_NOT_PENDING == 0
+    }
+
+    /// Returns the next received byte
+    pub fn try_receive(&self) -> Option<u8> {
+        if self.read_register(LINE_STATUS_REGISTER) & LINE_STATUS_DATA_READY == 0 {
+            return None;
+        }
+        Some(self.read_register(DATA_REGISTER))
+    }
+
+    /// Whether the transmit FIFO is empty, then `FIFO_SIZE` bytes can be sent without waiting
+    pub fn is_transmitter_empty(&self) -> bool {
+        self.read_register(LINE_STATUS_REGISTER) & LINE_STATUS_TRANSMITTER_EMPTY != 0
+    }
+
+    /// Writes a byte to the transmit FIFO, the FIFO must have room for it
+    pub fn transmit(&self, byte: u8) {
+        self.write_register(DATA_REGISTER, byte);
+    }
+
+    /// Waits until the transmitter is empty, and sends a byte
+    pub fn send_polled(&self, byte: u8) {
+        while !self.is_transmitter_empty() {
+            core::hint::spin_loop();
+        }
+        self.transmit(byte);
+    }
+}
@@ -0,0 +1,107 @@
+//! A parser of the ANSI / VT100 escape sequences subset the console supports:
+//! colors (`ESC[...m`), cursor movement (`ESC[nA` - `ESC[nD`, `ESC[row;columnH`) and erasing (`ESC[nJ`, `ESC[nK`).
+
+const ESCAPE: u8 = 0x1B;
+const MAX_PARAMETERS: usize = 4;
+
+/// What the console does with a parsed byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Print(u8),
+    /// A control character (e.g. '\n', backspace)
+    Control(u8),
+    /// A control sequence, `ESC [ parameters final_byte`
+    Sequence(Sequence),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sequence {
+    parameters: [u16; MAX_PARAMETERS],
+    count: usize,
+    pub final_byte: u8,
+}
+
+impl Sequence {
+    /// Returns the parameter at `index`, missing and zero parameters are `default`
+    pub fn parameter(&self, index: usize, default: u16) -> u16 {
+        match self.parameters[..self.count].get(index) {
+            Some(0) | None => default,
+            Some(parameter) => *parameter,
+        }
+    }
+
+    /// Returns the parameters, an empty sequence has a single zero parameter
+    pub fn parameters(&self) -> &[u16] {
+        &self.parameters[..self.count.max(1)]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    ControlSequence,
+}
+
+pub struct Parser {
+    state: State,
+    sequence: Sequence,
+}
+
+impl Parser {
+    pub const fn new() -> Self {
+        Parser {
+            state: State::Ground,
+            sequence: Sequence {
+                parameters: [0; MAX_PARAMETERS],
+                count: 0,
+                final_byte: 0,
+            },
+        }
+    }
+
+    /// Parses the next byte, returns an action when the byte completes one
+    pub fn advance(&mut self, byte: u8) -> Option<Action> {
+        match (self.state, byte) {
+            (_, ESCAPE) => {
+                self.state = State::Escape;
+                None
+            }
+            (State::Ground, 0x00..=0x1F | 0x7F) => Some(Action::Control(byte)),
+            (State::Ground, _) => Some(Action::Print(byte)),
+            (State::Escape, b'[') => {
+                self.state = State::ControlSequence;
+                self.sequence.parameters = [0; MAX_PARAMETERS];
+                self.sequence.count = 0;
+                None
+            }
+            // other escape sequences are ignored
+            (State::Escape, _) => {
+                self.state = State::Ground;
+                None
+            }
+            (State::ControlSequence, b'0'..=b'9') => {
+                if self.sequence.count == 0 {
+                    self.sequence.count = 1;
+                }
+                // extra parameters are ignored
+                if let Some(parameter) = self.sequence.parameters.get_mut(self.sequence.count - 1) {
+                    *parameter = parameter.saturating_mul(10).saturating_add((byte - b'0') as u16);
+                }
+                None
+            }
+            (State::ControlSequence, b';') => {
+                self.sequence.count = (self.sequence.count.max(1) + 1).min(MAX_PARAMETERS + 1);
+                None
+            }
+            (State::ControlSequence, 0x40..=0x7E) => {
+                self.state = State::Ground;
+                self.sequence.count = self.sequence.count.min(MAX_PARAMETERS);
+                self.sequence.final_byte = byte;
+                Some(Action::Sequence(self.sequence))
+            }
+            // private markers (e.g. '?') and intermediate bytes are ignored
+            (State::ControlSequence, _) => None,
+        }
+    }
+}
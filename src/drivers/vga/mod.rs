@@ -0,0 +1,502 @@
+//! This module writes to the VGA buffer in text mode.
+//!
+//! The writer keeps the screen lines and a scrollback in a ring of lines, scrolling moves the ring's top line
+//! and redraws the screen. The writer understands the control characters and an ANSI escape subset (see `ansi`).
+//!
+//! The screen is multiplexed between `TERMINAL_COUNT` virtual terminals, each with it's own writer. Only the active
+//! terminal draws to the VGA buffer, switching terminals copies the saved lines of the new one into the buffer.
+
+pub mod ansi;
+
+pub const TERMINAL_COUNT: usize = 4;
+const BUFFER_HEIGHT: usize = 25;
+const BUFFER_WIDTH: usize = 80;
+/// The lines kept above the screen
+const SCROLLBACK_LINES: usize = 200;
+const TOTAL_LINES: usize = BUFFER_HEIGHT + SCROLLBACK_LINES;
+const ADDRESS: usize = 0xb8000;
+const SPACE_ASCII: u8 = 0x20;
+const TILDA_ASCII: u8 = 0x7e;
+const NOT_IN_ASCII_RANGE: u8 = 0xfe;
+const TAB_WIDTH: usize = 8;
+
+const BACKSPACE: u8 = 0x08;
+const TAB: u8 = b'\t';
+const CARRIAGE_RETURN: u8 = b'\r';
+
+// CRT controller ports & registers, the cursor is a blinking underline
+const CRTC_ADDRESS_PORT: u16 = 0x3D4;
+const CRTC_DATA_PORT: u16 = 0x3D5;
+const CURSOR_START_REGISTER: u8 = 0x0A;
+const CURSOR_END_REGISTER: u8 = 0x0B;
+const CURSOR_LOCATION_HIGH_REGISTER: u8 = 0x0E;
+const CURSOR_LOCATION_LOW_REGISTER: u8 = 0x0F;
+const CURSOR_DISABLE: u8 = 1 << 5;
+const CURSOR_START_SCANLINE: u8 = 14;
+const CURSOR_END_SCANLINE: u8 = 15;
+
+/// The ANSI colors order (black, red, green, yellow, blue, magenta, cyan, white), dark then bright
+const ANSI_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Brown,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::LightGray,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::Yellow,
+    Color::LightBlue,
+    Color::Pink,
+    Color::LightCyan,
+    Color::White,
+];
+const BRIGHT: u8 = 8;
+
+use core::{
+    fmt::{self, Arguments},
+    ptr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use spin::Mutex;
+use x86_64::instructions::{interrupts, port::Port};
+
+use self::ansi::{Action, Parser, Sequence};
+
+/// The virtual terminals, terminal 0 is the kernel console.
+///
+/// The terminals are switched from the keyboard interrupt, so they should be locked with interrupts disabled (see `with_terminal`).
+pub static TERMINALS: [Mutex<Writer>; TERMINAL_COUNT] = [
+    Mutex::new(Writer::new(0, ColorCode::new(Color::Red, Color::Black))),
+    Mutex::new(Writer::new(1, ColorCode::new(Color::LightGray, Color::Black))),
+    Mutex::new(Writer::new(2, ColorCode::new(Color::LightGreen, Color::Black))),
+    Mutex::new(Writer::new(3, ColorCode::new(Color::Yellow, Color::Black))),
+];
+/// The terminal shown on the screen
+static ACTIVE_TERMINAL: AtomicUsize = AtomicUsize::new(0);
+/// Serializes the terminal switches
+static SWITCH: Mutex<()> = Mutex::new(());
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Color {
+    Black = 0,
+    Blue = 1,
+    Green = 2,
+    Cyan = 3,
+    Red = 4,
+    Magenta = 5,
+    Brown = 6,
+    LightGray = 7,
+    DarkGray = 8,
+    LightBlue = 9,
+    LightGreen = 10,
+    LightCyan = 11,
+    LightRed = 12,
+    Pink = 13,
+    Yellow = 14,
+    White = 15,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+/// ColorCode struct contains the full color byte, containing foreground and background color.
+pub struct ColorCode(u8);
+
+impl ColorCode {
+    pub const fn new(foreground: Color, background: Color) -> ColorCode {
+        ColorCode((background as u8) << 4 | (foreground as u8))
+    }
+
+    fn foreground(&self) -> u8 {
+        self.0 & 0xF
+    }
+
+    fn background(&self) -> u8 {
+        self.0 >> 4
+    }
+
+    fn with_foreground(&self, foreground: u8) -> ColorCode {
+        ColorCode(self.0 & 0xF0 | foreground & 0xF)
+    }
+
+    fn with_background(&self, background: u8) -> ColorCode {
+        ColorCode(self.0 & 0x0F | (background & 0xF) << 4)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(C)]
+struct ScreenChar {
+    ascii_character: u8,
+    color_code: ColorCode,
+}
+
+type Line = [ScreenChar; BUFFER_WIDTH];
+
+/// This struct writes to the vga buffer in text mode
+pub struct Writer {
+    /// The writer's terminal number, only the active terminal draws to the VGA buffer
+    terminal: usize,
+    /// The screen lines and the scrollback, the screen starts at `top`
+    lines: [Line; TOTAL_LINES],
+    top: usize,
+    /// The lines above the screen that were written
+    scrollback: usize,
+    /// How many lines the view is scrolled back, zero shows the screen
+    view_offset: usize,
+    column_position: usize,
+    row_position: usize,
+    // Writer's theme
+    color_code: ColorCode,
+    /// The theme colors, restored by `ESC[0m`
+    default_color: ColorCode,
+    parser: Parser,
+}
+
+impl Writer {
+    pub const fn new(terminal: usize, color_code: ColorCode) -> Self {
+        let blank = ScreenChar {
+            ascii_character: b' ',
+            color_code,
+        };
+        Writer {
+            terminal,
+            lines: [[blank; BUFFER_WIDTH]; TOTAL_LINES],
+            top: 0,
+            scrollback: 0,
+            view_offset: 0,
+            column_position: 0,
+            row_position: 0,
+            color_code,
+            default_color: color_code,
+            parser: Parser::new(),
+        }
+    }
+
+    fn blank(&self) -> ScreenChar {
+        ScreenChar {
+            ascii_character: b' ',
+            color_code: self.color_code,
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        ACTIVE_TERMINAL.load(Ordering::Acquire) == self.terminal
+    }
+
+    /// Returns the ring index of a screen row
+    fn line_index(&self, row: usize) -> usize {
+        (self.top + row) % TOTAL_LINES
+    }
+
+    /// Writes a character to the screen and to the VGA buffer
+    fn set_char(&mut self, row: usize, column: usize, character: ScreenChar) {
+        let index = self.line_index(row);
+        self.lines[index][column] = character;
+        if self.view_offset == 0 && self.is_active() {
+            draw_char(row, column, character);
+        }
+    }
+
+    /// Draws the viewed lines to the VGA buffer
+    fn redraw(&self) {
+        if !self.is_active() {
+            return;
+        }
+        for row in 0..BUFFER_HEIGHT {
+            let index = (self.top + TOTAL_LINES - self.view_offset + row) % TOTAL_LINES;
+            for (column, character) in self.lines[index].iter().enumerate() {
+                draw_char(row, column, *character);
+            }
+        }
+        self.update_cursor();
+    }
+
+    /// Moves the hardware cursor to the writer's position, the cursor is hidden while the view is scrolled back
+    fn update_cursor(&self) {
+        if !self.is_active() {
+            return;
+        }
+        if self.view_offset != 0 {
+            write_crtc(CURSOR_START_REGISTER, CURSOR_DISABLE);
+            return;
+        }
+        let position = self.row_position * BUFFER_WIDTH + self.column_position.min(BUFFER_WIDTH - 1);
+        write_crtc(CURSOR_START_REGISTER, CURSOR_START_SCANLINE);
+        write_crtc(CURSOR_END_REGISTER, CURSOR_END_SCANLINE);
+        write_crtc(CURSOR_LOCATION_HIGH_REGISTER, (position >> 8) as u8);
+        write_crtc(CURSOR_LOCATION_LOW_REGISTER, position as u8);
+    }
+
+    /// Write one byte to the buffer, if new line go to the next row.
+    fn write_byte(&mut self, byte: u8) {
+        match self.parser.advance(byte) {
+            Some(Action::Print(byte)) => self.print(byte),
+            Some(Action::Control(byte)) => self.control(byte),
+            Some(Action::Sequence(sequence)) => self.sequence(&sequence),
+            None => {}
+        }
+    }
+
+    fn print(&mut self, byte: u8) {
+        let byte = match byte {
+            SPACE_ASCII..=TILDA_ASCII => byte,
+            // not part of printable ASCII range
+            _ => NOT_IN_ASCII_RANGE,
+        };
+        // the line wraps when a character is written past it's end, so the cursor can stay at the last column
+        if self.column_position >= BUFFER_WIDTH {
+            self.new_line();
+        }
+
+        let character = ScreenChar {
+            ascii_character: byte,
+            color_code: self.color_code,
+        };
+        self.set_char(self.row_position, self.column_position, character);
+        self.column_position += 1;
+    }
+
+    fn control(&mut self, byte: u8) {
+        match byte {
+            b'\n' => self.new_line(),
+            CARRIAGE_RETURN => self.column_position = 0,
+            // moves back without erasing, the console erases with "\x08 \x08"
+            BACKSPACE => {
+                if self.column_position > 0 {
+                    self.column_position = self.column_position.min(BUFFER_WIDTH) - 1;
+                } else if self.row_position > 0 {
+                    self.row_position -= 1;
+                    self.column_position = BUFFER_WIDTH - 1;
+                }
+            }
+            // moves to the next tab stop without erasing
+            TAB => {
+                if self.column_position < BUFFER_WIDTH {
+                    self.column_position = ((self.column_position / TAB_WIDTH + 1) * TAB_WIDTH).min(BUFFER_WIDTH - 1);
+                }
+            }
+            // other control characters are printed as unknown characters
+            _ => self.print(byte),
+        }
+    }
+
+    /// Runs an escape sequence, unsupported sequences are ignored
+    fn sequence(&mut self, sequence: &Sequence) {
+        let count = sequence.parameter(0, 1) as usize;
+        match sequence.final_byte {
+            b'm' => self.select_graphic_rendition(sequence.parameters()),
+            b'A' => self.row_position = self.row_position.saturating_sub(count),
+            b'B' => self.row_position = (self.row_position + count).min(BUFFER_HEIGHT - 1),
+            b'C' => self.column_position = (self.column_position + count).min(BUFFER_WIDTH - 1),
+            b'D' => self.column_position = self.column_position.min(BUFFER_WIDTH - 1).saturating_sub(count),
+            // the position is 1-based
+            b'H' | b'f' => {
+                self.row_position = (sequence.parameter(0, 1) as usize).min(BUFFER_HEIGHT) - 1;
+                self.column_position = (sequence.parameter(1, 1) as usize).min(BUFFER_WIDTH) - 1;
+            }
+            b'J' => match sequence.parameter(0, 0) {
+                0 => {
+                    self.erase_line(self.row_position, self.column_position, BUFFER_WIDTH);
+                    (self.row_position + 1..BUFFER_HEIGHT).for_each(|row| self.erase_line(row, 0, BUFFER_WIDTH));
+                }
+                1 => {
+                    (0..self.row_position).for_each(|row| self.erase_line(row, 0, BUFFER_WIDTH));
+                    self.erase_line(self.row_position, 0, self.column_position + 1);
+                }
+                2 => self.erase_screen(),
+                3 => {
+                    self.erase_screen();
+                    self.scrollback = 0;
+                }
+                _ => {}
+            },
+            b'K' => match sequence.parameter(0, 0) {
+                0 => self.erase_line(self.row_position, self.column_position, BUFFER_WIDTH),
+                1 => self.erase_line(self.row_position, 0, self.column_position + 1),
+                2 => self.erase_line(self.row_position, 0, BUFFER_WIDTH),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    /// Changes the colors, `ESC[0m` restores the theme and `ESC[1m` brightens the foreground
+    fn select_graphic_rendition(&mut self, parameters: &[u16]) {
+        for parameter in parameters {
+            self.color_code = match *parameter {
+                0 => self.default_color,
+                1 => self.color_code.with_foreground(self.color_code.foreground() | BRIGHT),
+                30..=37 => self.color_code.with_foreground(ANSI_COLORS[*parameter as usize - 30] as u8),
+                39 => self.color_code.with_foreground(self.default_color.foreground()),
+                40..=47 => self.color_code.with_background(ANSI_COLORS[*parameter as usize - 40] as u8),
+                49 => self.color_code.with_background(self.default_color.background()),
+                90..=97 => self.color_code.with_foreground(ANSI_COLORS[*parameter as usize - 90 + BRIGHT as usize] as u8),
+                100..=107 => self.color_code.with_background(ANSI_COLORS[*parameter as usize - 100 + BRIGHT as usize] as u8),
+                _ => self.color_code,
+            };
+        }
+    }
+
+    /// Erases the columns `start..end` of a screen row
+    fn erase_line(&mut self, row: usize, start: usize, end: usize) {
+        for column in start.min(BUFFER_WIDTH)..end.min(BUFFER_WIDTH) {
+            self.set_char(row, column, self.blank());
+        }
+    }
+
+    fn erase_screen(&mut self) {
+        (0..BUFFER_HEIGHT).for_each(|row| self.erase_line(row, 0, BUFFER_WIDTH));
+    }
+
+    /// Takes the writer to the start of the row below the current one, the screen scrolls at the last row.
+    fn new_line(&mut self) {
+        self.column_position = 0;
+        if self.row_position < BUFFER_HEIGHT - 1 {
+            self.row_position += 1;
+            return;
+        }
+
+        // the oldest scrollback line becomes the new bottom line
+        self.top = (self.top + 1) % TOTAL_LINES;
+        self.scrollback = (self.scrollback + 1).min(SCROLLBACK_LINES);
+        let bottom = self.line_index(BUFFER_HEIGHT - 1);
+        self.lines[bottom] = [self.blank(); BUFFER_WIDTH];
+        if self.view_offset == 0 {
+            self.redraw();
+        }
+    }
+
+    /// Writes every ASCII character to the screen
+    fn write_string(&mut self, s: &str) {
+        self.write_bytes(s.as_bytes());
+    }
+
+    /// Writes every byte to the screen, bytes outside the printable ASCII range are replaced.
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        // writing returns the view to the screen
+        if self.view_offset != 0 {
+            self.view_offset = 0;
+            self.redraw();
+        }
+        for &byte in bytes {
+            self.write_byte(byte);
+        }
+        self.update_cursor();
+    }
+
+    /// Set the writer with a new color code.
+    pub fn set_writer_theme(&mut self, foreground: Color, background: Color) {
+        self.color_code = ColorCode::new(foreground, background);
+        self.default_color = self.color_code;
+    }
+
+    /// Erases the screen and moves the cursor to the top left corner, the scrollback is kept
+    pub fn clear(&mut self) {
+        self.view_offset = 0;
+        self.erase_screen();
+        self.row_position = 0;
+        self.column_position = 0;
+        self.update_cursor();
+    }
+
+    /// Scrolls the view `lines` back into the scrollback (negative lines scroll forward)
+    pub fn scroll_view(&mut self, lines: isize) {
+        let offset = (self.view_offset as isize + lines).clamp(0, self.scrollback as isize) as usize;
+        if offset != self.view_offset {
+            self.view_offset = offset;
+            self.redraw();
+        }
+    }
+
+    /// Returns the cursor row & column
+    pub fn cursor_position(&self) -> (usize, usize) {
+        (self.row_position, self.column_position)
+    }
+}
+
+impl fmt::Write for Writer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_string(s);
+        Ok(())
+    }
+}
+
+fn draw_char(row: usize, column: usize, character: ScreenChar) {
+    let address = (ADDRESS as *mut ScreenChar).wrapping_add(row * BUFFER_WIDTH + column);
+    unsafe { ptr::write_volatile(address, character) };
+}
+
+fn write_crtc(register: u8, value: u8) {
+    unsafe {
+        Port::<u8>::new(CRTC_ADDRESS_PORT).write(register);
+        Port::<u8>::new(CRTC_DATA_PORT).write(value);
+    }
+}
+
+/// Returns the character & the color byte at a position of the VGA buffer
+pub fn read_screen(row: usize, column: usize) -> Option<(u8, u8)> {
+    if row >= BUFFER_HEIGHT || column >= BUFFER_WIDTH {
+        return None;
+    }
+    let address = (ADDRESS as *const ScreenChar).wrapping_add(row * BUFFER_WIDTH + column);
+    let character = unsafe { ptr::read_volatile(address) };
+    Some((character.ascii_character, character.color_code.0))
+}
+
+/// Locks a terminal with interrupts disabled, so the keyboard interrupt can't switch terminals while it's locked
+pub fn with_terminal<T>(terminal: usize, operation: impl FnOnce(&mut Writer) -> T) -> T {
+    interrupts::without_interrupts(|| operation(&mut TERMINALS[terminal].lock()))
+}
+
+/// Returns the terminal shown on the screen
+pub fn active_terminal() -> usize {
+    ACTIVE_TERMINAL.load(Ordering::Acquire)
+}
+
+/// Shows a terminal on the screen, copying it's saved lines to the VGA buffer
+pub fn switch_terminal(terminal: usize) -> Result<(), ()> {
+    if terminal >= TERMINAL_COUNT {
+        return Err(());
+    }
+    interrupts::without_interrupts(|| {
+        let _switch = SWITCH.lock();
+        let previous = ACTIVE_TERMINAL.load(Ordering::Acquire);
+        if previous == terminal {
+            return;
+        }
+        {
+            // waits for a write to the previous terminal, so it can't draw over the new one
+            let _previous = TERMINALS[previous].lock();
+            ACTIVE_TERMINAL.store(terminal, Ordering::Release);
+        }
+        TERMINALS[terminal].lock().redraw();
+    });
+    Ok(())
+}
+
+/// Scrolls the active terminal's view `lines` back into the scrollback (negative lines scroll forward)
+pub fn scroll_active_terminal(lines: isize) {
+    with_terminal(active_terminal(), |writer| writer.scroll_view(lines));
+}
+
+/// Erases the active terminal's screen and moves the cursor to the top left corner
+pub fn clear_screen() {
+    with_terminal(active_terminal(), |writer| writer.clear());
+}
+
+/// Private print for macros, prints to the kernel console terminal
+pub fn _print(args: Arguments) {
+    use core::fmt::Write;
+    with_terminal(0, |writer| writer.write_fmt(args).unwrap());
+}
+
+/// Private byte writer for the terminal devices
+pub fn _write_bytes(terminal: usize, bytes: &[u8]) {
+    with_terminal(terminal, |writer| writer.write_bytes(bytes));
+}
@@ -0,0 +1,133 @@
+//! Character devices which are always available to processes
+
+use crate::drivers::{
+    keyboard,
+    serial::{self, SerialPort},
+    vga::{self, TERMINAL_COUNT},
+};
+
+use super::{
+    tty::{LineDiscipline, TtyRequest},
+    File,
+};
+
+const BACKSPACE: u8 = 0x08;
+/// Sent by terminals for the backspace key
+const DELETE: u8 = 0x7F;
+
+#[allow(clippy::declare_interior_mutable_const)]
+const NEW_DISCIPLINE: LineDiscipline = LineDiscipline::new();
+/// The line disciplines of the virtual terminals, the console shares the first one
+static TERMINAL_DISCIPLINES: [LineDiscipline; TERMINAL_COUNT] = [NEW_DISCIPLINE; TERMINAL_COUNT];
+
+/// The kernel console, writes to both the first virtual terminal and the serial port, and reads lines from the keyboard.
+pub struct Console;
+
+impl File for Console {
+    fn read(&self, buffer: &mut [u8]) -> Result<usize, ()> {
+        VirtualTerminal::new(0).read(buffer)
+    }
+
+    fn write(&self, buffer: &[u8]) -> Result<usize, ()> {
+        vga::_write_bytes(0, buffer);
+        serial::_write_bytes(buffer);
+        Ok(buffer.len())
+    }
+
+    fn ioctl(&self, request: TtyRequest) -> Result<usize, ()> {
+        VirtualTerminal::new(0).ioctl(request)
+    }
+}
+
+/// A virtual terminal of the VGA screen, reads the lines typed while it's active.
+pub struct VirtualTerminal {
+    terminal: usize,
+}
+
+impl VirtualTerminal {
+    pub const fn new(terminal: usize) -> Self {
+        VirtualTerminal { terminal }
+    }
+}
+
+impl File for VirtualTerminal {
+    fn read(&self, buffer: &mut [u8]) -> Result<usize, ()> {
+        let next_character = || loop {
+            if let Some(character) = keyboard::wait_event(self.terminal).ascii() {
+                return character;
+            }
+        };
+        TERMINAL_DISCIPLINES[self.terminal].read(buffer, next_character, |bytes| {
+            vga::_write_bytes(self.terminal, bytes);
+        })
+    }
+
+    fn write(&self, buffer: &[u8]) -> Result<usize, ()> {
+        vga::_write_bytes(self.terminal, buffer);
+        Ok(buffer.len())
+    }
+
+    fn ioctl(&self, request: TtyRequest) -> Result<usize, ()> {
+        TERMINAL_DISCIPLINES[self.terminal].ioctl(request)
+    }
+}
+
+/// A terminal connected to a serial port, echoes the typed characters back to the port.
+pub struct SerialTty {
+    port: &'static SerialPort,
+    discipline: LineDiscipline,
+}
+
+impl SerialTty {
+    pub const fn new(port: &'static SerialPort) -> Self {
+        SerialTty {
+            port,
+            discipline: LineDiscipline::new(),
+        }
+    }
+}
+
+impl File for SerialTty {
+    fn read(&self, buffer: &mut [u8]) -> Result<usize, ()> {
+        // terminals send a carriage return for enter, and delete for backspace
+        let next_character = || match self.port.wait_byte() {
+            b'\r' => b'\n',
+            DELETE => BACKSPACE,
+            character => character,
+        };
+        self.discipline.read(buffer, next_character, |bytes| {
+            self.write(bytes).ok();
+        })
+    }
+
+    /// Writes the buffer, a new line also returns the terminal's cursor to the start of the line
+    fn write(&self, buffer: &[u8]) -> Result<usize, ()> {
+        for line in buffer.split_inclusive(|byte| *byte == b'\n') {
+            match line.strip_suffix(b"\n") {
+                Some(line) => {
+                    self.port.write(line);
+                    self.port.write(b"\r\n");
+                }
+                None => self.port.write(line),
+            }
+        }
+        Ok(buffer.len())
+    }
+
+    fn ioctl(&self, request: TtyRequest) -> Result<usize, ()> {
+        self.discipline.ioctl(request)
+    }
+}
+
+/// Discards every write and returns end of file on every read
+pub struct Null;
+
+impl File for Null {
+    fn read(&self, _buffer: &mut [u8]) -> Result<usize, ()> {
+        Ok(0)
+    }
+
+    fn write(&self, buffer: &[u8]) -> Result<usize, ()> {
+        Ok(buffer.len())
+    }
+}
@@ -0,0 +1,85 @@
+//! This module defines the per process file descriptor table
+
+use alloc::{sync::Arc, vec, vec::Vec};
+
+use super::{console::Console, File};
+
+pub use abi::{STDERR, STDIN, STDOUT};
+
+/// The maximum amount of files a process can open at the same time
+pub const MAX_FILE_DESCRIPTORS: usize = 32;
+
+/// Maps file descriptors (indices) to opened kernel files.
+///
+/// Cloning the table shares the opened files between the tables.
+#[derive(Clone)]
+pub struct FileDescriptorTable {
+    files: Vec<Option<Arc<dyn File>>>,
+}
+
+impl FileDescriptorTable {
+    /// Creates a table without any opened file
+    pub const fn empty() -> Self {
+        FileDescriptorTable { files: Vec::new() }
+    }
+
+    /// Creates a table with stdin, stdout and stderr opened to the console
+    pub fn with_standard_streams() -> Self {
+        let console: Arc<dyn File> = Arc::new(Console);
+
+        FileDescriptorTable {
+            files: vec![Some(console.clone()), Some(console.clone()), Some(console)],
+        }
+    }
+
+    /// Creates a table with the standard streams of `parent`, the other files of `parent` aren't inherited
+    pub fn inherit_standard_streams(parent: &FileDescriptorTable) -> Self {
+        FileDescriptorTable {
+            files: (STDIN..=STDERR).map(|fd| parent.get(fd)).collect(),
+        }
+    }
+
+    /// Returns the file opened at `fd`
+    pub fn get(&self, fd: usize) -> Option<Arc<dyn File>> {
+        self.files.get(fd)?.clone()
+    }
+
+    /// Saves the file at the lowest free file descriptor and returns it
+    pub fn insert(&mut self, file: Arc<dyn File>) -> Result<usize, ()> {
+        if let Some(fd) = self.files.iter().position(Option::is_none) {
+            self.files[fd] = Some(file);
+            return Ok(fd);
+        }
+
+        if self.files.len() >= MAX_FILE_DESCRIPTORS {
+            return Err(());
+        }
+        self.files.push(Some(file));
+        Ok(self.files.len() - 1)
+    }
+
+    /// Releases the file descriptor, the file itself is released with its last descriptor
+    pub fn close(&mut self, fd: usize) -> Result<(), ()> {
+        self.files.get_mut(fd).and_then(Option::take).map(|_| ()).ok_or(())
+    }
+
+    /// Duplicates `fd` to the lowest free file descriptor
+    pub fn dup(&mut self, fd: usize) -> Result<usize, ()> {
+        let file = self.get(fd).ok_or(())?;
+        self.insert(file)
+    }
+
+    /// Duplicates `old_fd` to `new_fd`, if `new_fd` is opened it is closed first.
+    pub fn dup2(&mut self, old_fd: usize, new_fd: usize) -> Result<usize, ()> {
+        let file = self.get(old_fd).ok_or(())?;
+
+        if new_fd >= MAX_FILE_DESCRIPTORS {
+            return Err(());
+        }
+        if new_fd >= self.files.len() {
+            self.files.resize(new_fd + 1, None);
+        }
+        self.files[new_fd] = Some(file);
+        Ok(new_fd)
+    }
+}
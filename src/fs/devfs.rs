@@ -0,0 +1,119 @@
+//! A flat filesystem exposing the kernel devices as character device files
+
+use alloc::{format, string::String, sync::Arc, vec::Vec};
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+use crate::drivers::vga::TERMINAL_COUNT;
+
+use super::{
+    console::{Console, Null, VirtualTerminal},
+    vfs::{DirectoryEntry, FileSystem, FileType, InodeId, Stat},
+    File,
+};
+
+const ROOT_INODE: InodeId = 0;
+
+lazy_static! {
+    /// The kernel devices, drivers register their devices here
+    pub static ref DEVICES: Arc<DevFs> = {
+        let devices = DevFs::empty();
+        devices.register("console", Arc::new(Console));
+        devices.register("null", Arc::new(Null));
+        // the virtual terminals are numbered from 1, like Alt + F1 - F4
+        for terminal in 0..TERMINAL_COUNT {
+            devices.register(&format!("tty{}", terminal + 1), Arc::new(VirtualTerminal::new(terminal)));
+        }
+        Arc::new(devices)
+    };
+}
+
+/// The root directory holds all the devices, device `i` is saved at inode `i + 1`.
+pub struct DevFs {
+    devices: Mutex<Vec<(String, Arc<dyn File>)>>,
+}
+
+impl DevFs {
+    pub const fn empty() -> Self {
+        DevFs {
+            devices: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Exposes a new device named `name`
+    pub fn register(&self, name: &str, device: Arc<dyn File>) {
+        self.devices.lock().push((String::from(name), device));
+    }
+
+    fn index(inode: InodeId) -> Result<usize, ()> {
+        (inode as usize).checked_sub(1).ok_or(())
+    }
+}
+
+impl FileSystem for DevFs {
+    fn root(&self) -> InodeId {
+        ROOT_INODE
+    }
+
+    fn lookup(&self, directory: InodeId, name: &str) -> Result<InodeId, ()> {
+        if directory != ROOT_INODE {
+            return Err(());
+        }
+
+        let devices = self.devices.lock();
+        let index = devices.iter().position(|(device_name, _)| device_name == name).ok_or(())?;
+        Ok(index as InodeId + 1)
+    }
+
+    fn create(&self, _directory: InodeId, _name: &str, _file_type: FileType) -> Result<InodeId, ()> {
+        Err(())
+    }
+
+    fn read(&self, _inode: InodeId, _offset: usize, _buffer: &mut [u8]) -> Result<usize, ()> {
+        Err(())
+    }
+
+    fn write(&self, _inode: InodeId, _offset: usize, _buffer: &[u8]) -> Result<usize, ()> {
+        Err(())
+    }
+
+    fn readdir(&self, directory: InodeId, index: usize) -> Result<Option<DirectoryEntry>, ()> {
+        if directory != ROOT_INODE {
+            return Err(());
+        }
+
+        match self.devices.lock().get(index) {
+            Some((name, _)) => Ok(Some(DirectoryEntry::new(index as InodeId + 1, FileType::CharDevice, name).ok_or(())?)),
+            None => Ok(None),
+        }
+    }
+
+    fn unlink(&self, _directory: InodeId, _name: &str) -> Result<(), ()> {
+        Err(())
+    }
+
+    fn stat(&self, inode: InodeId) -> Result<Stat, ()> {
+        if inode == ROOT_INODE {
+            return Ok(Stat {
+                inode,
+                file_type: FileType::Directory,
+                size: 0,
+            });
+        }
+
+        let index = DevFs::index(inode)?;
+        if index >= self.devices.lock().len() {
+            return Err(());
+        }
+        Ok(Stat {
+            inode,
+            file_type: FileType::CharDevice,
+            size: 0,
+        })
+    }
+
+    fn device(&self, inode: InodeId) -> Option<Arc<dyn File>> {
+        let index = DevFs::index(inode).ok()?;
+        self.devices.lock().get(index).map(|(_, device)| device.clone())
+    }
+}
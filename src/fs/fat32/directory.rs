@@ -0,0 +1,253 @@
+//! FAT directory entries, the 8.3 short entries and the long file name (VFAT) entries
+
+use alloc::{string::String, vec::Vec};
+use core::char;
+
+pub const ENTRY_SIZE: usize = 32;
+
+pub const ATTRIBUTE_VOLUME_ID: u8 = 0x08;
+pub const ATTRIBUTE_DIRECTORY: u8 = 0x10;
+pub const ATTRIBUTE_ARCHIVE: u8 = 0x20;
+/// read only | hidden | system | volume id
+pub const ATTRIBUTE_LONG_NAME: u8 = 0x0F;
+
+/// The first name byte of a deleted entry
+pub const FREE_ENTRY: u8 = 0xE5;
+/// The first name byte of the entry after the last used entry
+pub const END_OF_DIRECTORY: u8 = 0x00;
+
+const SHORT_NAME_SIZE: usize = 11;
+const BASE_NAME_SIZE: usize = 8;
+const ALIAS_BASE_SIZE: usize = 6;
+/// Marks the long name entry holding the end of the name (the first entry on disk)
+const LAST_LONG_ENTRY: u8 = 0x40;
+const SEQUENCE_MASK: u8 = 0x1F;
+const CHARACTERS_PER_LONG_ENTRY: usize = 13;
+/// The byte offsets of the 13 UCS-2 characters inside a long name entry
+const LONG_NAME_CHARACTER_OFFSETS: [usize; CHARACTERS_PER_LONG_ENTRY] = [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+const LONG_NAME_PADDING: u16 = 0xFFFF;
+// short name case flags (saved by Windows NT and Linux)
+const LOWERCASE_BASE: u8 = 0x08;
+const LOWERCASE_EXTENSION: u8 = 0x10;
+
+/// An 8.3 directory entry, describes the file location, size and attributes
+#[derive(Clone, Copy)]
+pub struct ShortEntry {
+    pub name: [u8; SHORT_NAME_SIZE],
+    pub attributes: u8,
+    pub case: u8,
+    pub first_cluster: u32,
+    pub size: u32,
+}
+
+impl ShortEntry {
+    pub fn new(name: [u8; SHORT_NAME_SIZE], attributes: u8, first_cluster: u32) -> Self {
+        ShortEntry {
+            name,
+            attributes,
+            case: 0,
+            first_cluster,
+            size: 0,
+        }
+    }
+
+    /// Creates the "." or ".." entry of a new directory
+    pub fn dot(dots: usize, first_cluster: u32) -> Self {
+        let mut name = [b' '; SHORT_NAME_SIZE];
+        name[..dots].fill(b'.');
+        ShortEntry::new(name, ATTRIBUTE_DIRECTORY, first_cluster)
+    }
+
+    pub fn parse(bytes: &[u8]) -> Self {
+        let mut name = [0; SHORT_NAME_SIZE];
+        name.copy_from_slice(&bytes[..SHORT_NAME_SIZE]);
+        let cluster_high = u16::from_le_bytes([bytes[20], bytes[21]]) as u32;
+        let cluster_low = u16::from_le_bytes([bytes[26], bytes[27]]) as u32;
+
+        ShortEntry {
+            name,
+            attributes: bytes[11],
+            case: bytes[12],
+            first_cluster: cluster_high << 16 | cluster_low,
+            size: u32::from_le_bytes([bytes[28], bytes[29], bytes[30], bytes[31]]),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[..SHORT_NAME_SIZE].copy_from_slice(&self.name);
+        bytes[11] = self.attributes;
+        bytes[12] = self.case;
+        bytes[20..22].copy_from_slice(&((self.first_cluster >> 16) as u16).to_le_bytes());
+        bytes[26..28].copy_from_slice(&(self.first_cluster as u16).to_le_bytes());
+        bytes[28..32].copy_from_slice(&self.size.to_le_bytes());
+        bytes
+    }
+
+    pub fn is_directory(&self) -> bool {
+        self.attributes & ATTRIBUTE_DIRECTORY != 0
+    }
+
+    /// Returns whether this is the "." or the ".." entry
+    pub fn is_dot(&self) -> bool {
+        self.name[0] == b'.'
+    }
+
+    /// Returns the "NAME.EXT" form of the short name
+    pub fn display_name(&self) -> String {
+        let base = trim_padding(&self.name[..BASE_NAME_SIZE]);
+        let extension = trim_padding(&self.name[BASE_NAME_SIZE..]);
+
+        let mut name = String::new();
+        push_ascii(&mut name, base, self.case & LOWERCASE_BASE != 0);
+        if !extension.is_empty() {
+            name.push('.');
+            push_ascii(&mut name, extension, self.case & LOWERCASE_EXTENSION != 0);
+        }
+        name
+    }
+
+    /// The checksum every long name entry of this entry saves
+    pub fn checksum(&self) -> u8 {
+        self.name
+            .iter()
+            .fold(0u8, |sum, byte| sum.rotate_right(1).wrapping_add(*byte))
+    }
+}
+
+/// Collects the long name entries that precede a short entry
+pub struct LongName {
+    characters: Vec<u16>,
+    checksum: u8,
+    /// The disk offsets of the long name entries
+    pub offsets: Vec<u64>,
+}
+
+impl LongName {
+    /// Adds a long name entry found at `offset`, the first entry on disk starts a new name.
+    pub fn push(long_name: &mut Option<LongName>, bytes: &[u8], offset: u64) {
+        let sequence = (bytes[0] & SEQUENCE_MASK) as usize;
+        if sequence == 0 {
+            *long_name = None;
+            return;
+        }
+
+        if bytes[0] & LAST_LONG_ENTRY != 0 {
+            *long_name = Some(LongName {
+                characters: alloc::vec![LONG_NAME_PADDING; sequence * CHARACTERS_PER_LONG_ENTRY],
+                checksum: bytes[13],
+                offsets: Vec::new(),
+            });
+        }
+
+        let start = (sequence - 1) * CHARACTERS_PER_LONG_ENTRY;
+        match long_name {
+            Some(name) if start < name.characters.len() => {
+                for (index, character_offset) in LONG_NAME_CHARACTER_OFFSETS.iter().enumerate() {
+                    name.characters[start + index] =
+                        u16::from_le_bytes([bytes[*character_offset], bytes[character_offset + 1]]);
+                }
+                name.offsets.push(offset);
+            }
+            // an entry without the first long name entry, or an invalid sequence
+            _ => *long_name = None,
+        }
+    }
+
+    /// Returns the long name if it belongs to the short entry
+    pub fn name_of(&self, entry: &ShortEntry) -> Option<String> {
+        if self.checksum != entry.checksum() {
+            return None;
+        }
+
+        let characters = self
+            .characters
+            .iter()
+            .copied()
+            .take_while(|character| *character != 0 && *character != LONG_NAME_PADDING);
+        Some(
+            char::decode_utf16(characters)
+                .map(|character| character.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect(),
+        )
+    }
+}
+
+/// Encodes the long name entries of `name`, in their disk order.
+pub fn long_name_entries(name: &str, checksum: u8) -> Vec<[u8; ENTRY_SIZE]> {
+    let mut characters: Vec<u16> = name.encode_utf16().collect();
+    // the name is terminated with a null character only if it doesn't fill the last entry
+    if characters.len() % CHARACTERS_PER_LONG_ENTRY != 0 {
+        characters.push(0);
+    }
+    while characters.len() % CHARACTERS_PER_LONG_ENTRY != 0 {
+        characters.push(LONG_NAME_PADDING);
+    }
+
+    let count = characters.len() / CHARACTERS_PER_LONG_ENTRY;
+    let mut entries = Vec::new();
+    for sequence in (1..=count).rev() {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0] = sequence as u8 | if sequence == count { LAST_LONG_ENTRY } else { 0 };
+        bytes[11] = ATTRIBUTE_LONG_NAME;
+        bytes[13] = checksum;
+
+        let part = &characters[(sequence - 1) * CHARACTERS_PER_LONG_ENTRY..sequence * CHARACTERS_PER_LONG_ENTRY];
+        for (character, offset) in part.iter().zip(LONG_NAME_CHARACTER_OFFSETS.iter()) {
+            bytes[*offset..offset + 2].copy_from_slice(&character.to_le_bytes());
+        }
+        entries.push(bytes);
+    }
+    entries
+}
+
+/// Generates the `index`th short alias of a long name, e.g "A long name.txt" -> "ALONGN~1TXT"
+pub fn short_alias(name: &str, index: usize) -> [u8; SHORT_NAME_SIZE] {
+    let (base, extension) = match name.rsplit_once('.') {
+        Some((base, extension)) if !base.is_empty() => (base, extension),
+        _ => (name, ""),
+    };
+
+    let mut alias = [b' '; SHORT_NAME_SIZE];
+    let suffix = alloc::format!("~{}", index);
+    let base_length = BASE_NAME_SIZE - suffix.len();
+
+    let mut length = 0;
+    for byte in base.bytes().filter_map(short_name_character).take(base_length.min(ALIAS_BASE_SIZE)) {
+        alias[length] = byte;
+        length += 1;
+    }
+    if length == 0 {
+        alias[0] = b'_';
+        length = 1;
+    }
+    alias[length..length + suffix.len()].copy_from_slice(suffix.as_bytes());
+
+    for (position, byte) in extension.bytes().filter_map(short_name_character).take(3).enumerate() {
+        alias[BASE_NAME_SIZE + position] = byte;
+    }
+    alias
+}
+
+/// Converts a character to a valid short name character, invalid characters are removed.
+fn short_name_character(byte: u8) -> Option<u8> {
+    match byte {
+        b'a'..=b'z' => Some(byte.to_ascii_uppercase()),
+        b'A'..=b'Z' | b'0'..=b'9' => Some(byte),
+        b'$' | b'%' | b'\'' | b'-' | b'_' | b'@' | b'~' | b'`' | b'!' | b'(' | b')' | b'{' | b'}' | b'^' | b'#'
+        | b'&' => Some(byte),
+        _ => None,
+    }
+}
+
+fn trim_padding(bytes: &[u8]) -> &[u8] {
+    let length = bytes.iter().rposition(|byte| *byte != b' ').map_or(0, |position| position + 1);
+    &bytes[..length]
+}
+
+fn push_ascii(string: &mut String, bytes: &[u8], lowercase: bool) {
+    for byte in bytes {
+        let character = if lowercase { byte.to_ascii_lowercase() } else { *byte };
+        string.push(character as char);
+    }
+}
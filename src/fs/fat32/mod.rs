@@ -0,0 +1,577 @@
+//! A FAT32 filesystem driver on top of a block device, supports long file names (VFAT).
+//!
+//! FAT doesn't have inodes, the inode id of a file is the disk offset of it's short directory entry.
+
+mod directory;
+
+use alloc::{string::String, sync::Arc, vec, vec::Vec};
+use core::{
+    cmp,
+    sync::atomic::{AtomicU32, Ordering},
+};
+use spin::Mutex;
+
+use crate::drivers::block::BlockDevice;
+
+use self::directory::{
+    long_name_entries, short_alias, LongName, ShortEntry, ATTRIBUTE_ARCHIVE, ATTRIBUTE_DIRECTORY,
+    ATTRIBUTE_LONG_NAME, ATTRIBUTE_VOLUME_ID, END_OF_DIRECTORY, ENTRY_SIZE, FREE_ENTRY,
+};
+use super::vfs::{DirectoryEntry, FileSystem, FileType, InodeId, Stat};
+
+/// The root directory doesn't have a directory entry, offset 0 is the boot sector.
+const ROOT_INODE: InodeId = 0;
+const BOOT_SIGNATURE: [u8; 2] = [0x55, 0xAA];
+const FAT32_SIGNATURE: &[u8] = b"FAT32   ";
+const FAT_ENTRY_SIZE: u64 = 4;
+const CLUSTER_MASK: u32 = 0x0FFF_FFFF;
+/// Clusters numbers above this value mark the end of a chain
+const END_OF_CHAIN: u32 = 0x0FFF_FFF8;
+const FREE_CLUSTER: u32 = 0;
+const FIRST_CLUSTER: u32 = 2;
+/// The maximum alias index ("NAME~N") before giving up on a name
+const MAX_ALIAS_INDEX: usize = 999_999;
+
+/// The volume layout, parsed from the BIOS Parameter Block
+struct Layout {
+    /// The first FAT byte offset
+    fat_offset: u64,
+    fat_size: u64,
+    fat_count: u64,
+    /// The byte offset of cluster 2
+    data_offset: u64,
+    cluster_size: usize,
+    cluster_count: u32,
+    root_cluster: u32,
+}
+
+impl Layout {
+    fn parse(boot_sector: &[u8], sector_size: usize) -> Result<Self, ()> {
+        let read_u16 = |offset: usize| u16::from_le_bytes([boot_sector[offset], boot_sector[offset + 1]]) as u64;
+        let read_u32 = |offset: usize| {
+            u32::from_le_bytes([
+                boot_sector[offset],
+                boot_sector[offset + 1],
+                boot_sector[offset + 2],
+                boot_sector[offset + 3],
+            ]) as u64
+        };
+
+        let bytes_per_sector = read_u16(11);
+        let sectors_per_cluster = boot_sector[13] as u64;
+        let reserved_sectors = read_u16(14);
+        let fat_count = boot_sector[16] as u64;
+        let root_entries = read_u16(17);
+        let total_sectors = if read_u16(19) != 0 { read_u16(19) } else { read_u32(32) };
+        let sectors_per_fat = read_u32(36);
+        let root_cluster = read_u32(44);
+
+        if boot_sector[510..512] != BOOT_SIGNATURE
+            || &boot_sector[82..90] != FAT32_SIGNATURE
+            || bytes_per_sector != sector_size as u64
+            || !sectors_per_cluster.is_power_of_two()
+            || fat_count == 0
+            || root_entries != 0
+            || sectors_per_fat == 0
+        {
+            return Err(());
+        }
+
+        let data_sector = reserved_sectors + fat_count * sectors_per_fat;
+        let cluster_count = total_sectors.checked_sub(data_sector).ok_or(())? / sectors_per_cluster;
+
+        Ok(Layout {
+            fat_offset: reserved_sectors * bytes_per_sector,
+            fat_size: sectors_per_fat * bytes_per_sector,
+            fat_count,
+            data_offset: data_sector * bytes_per_sector,
+            cluster_size: (sectors_per_cluster * bytes_per_sector) as usize,
+            cluster_count: cluster_count as u32,
+            root_cluster: root_cluster as u32,
+        })
+    }
+}
+
+/// A directory entry with it's (long) name and it's location
+struct FatEntry {
+    name: String,
+    short: ShortEntry,
+    /// The disk offset of the short entry
+    offset: u64,
+    /// The disk offsets of the long name entries
+    long_name_offsets: Vec<u64>,
+}
+
+pub struct Fat32 {
+    device: Arc<dyn BlockDevice>,
+    layout: Layout,
+    /// Serializes the filesystem operations
+    lock: Mutex<()>,
+    /// Where the search for a free cluster starts
+    next_free_cluster: AtomicU32,
+}
+
+impl Fat32 {
+    /// Reads the volume layout of the device, fails if it isn't formatted as FAT32.
+    pub fn new(device: Arc<dyn BlockDevice>) -> Result<Self, ()> {
+        let mut boot_sector = vec![0u8; device.sector_size()];
+        device.read_sectors(0, &mut boot_sector)?;
+        let layout = Layout::parse(&boot_sector, device.sector_size())?;
+
+        Ok(Fat32 {
+            device,
+            layout,
+            lock: Mutex::new(()),
+            next_free_cluster: AtomicU32::new(FIRST_CLUSTER),
+        })
+    }
+
+    /// Reads bytes from any disk offset
+    fn read_bytes(&self, offset: u64, buffer: &mut [u8]) -> Result<(), ()> {
+        let sector_size = self.device.sector_size();
+        let mut sector = vec![0u8; sector_size];
+
+        let mut done = 0;
+        while done < buffer.len() {
+            let position = offset + done as u64;
+            let sector_offset = (position % sector_size as u64) as usize;
+            let chunk = cmp::min(buffer.len() - done, sector_size - sector_offset);
+
+            self.device.read_sectors(position / sector_size as u64, &mut sector)?;
+            buffer[done..done + chunk].copy_from_slice(&sector[sector_offset..sector_offset + chunk]);
+            done += chunk;
+        }
+        Ok(())
+    }
+
+    /// Writes bytes to any disk offset, partially written sectors are read first.
+    fn write_bytes(&self, offset: u64, buffer: &[u8]) -> Result<(), ()> {
+        let sector_size = self.device.sector_size();
+        let mut sector = vec![0u8; sector_size];
+
+        let mut done = 0;
+        while done < buffer.len() {
+            let position = offset + done as u64;
+            let sector_offset = (position % sector_size as u64) as usize;
+            let chunk = cmp::min(buffer.len() - done, sector_size - sector_offset);
+
+            if chunk != sector_size {
+                self.device.read_sectors(position / sector_size as u64, &mut sector)?;
+            }
+            sector[sector_offset..sector_offset + chunk].copy_from_slice(&buffer[done..done + chunk]);
+            self.device.write_sectors(position / sector_size as u64, &sector)?;
+            done += chunk;
+        }
+        Ok(())
+    }
+
+    fn cluster_offset(&self, cluster: u32) -> u64 {
+        self.layout.data_offset + (cluster - FIRST_CLUSTER) as u64 * self.layout.cluster_size as u64
+    }
+
+    fn is_valid_cluster(&self, cluster: u32) -> bool {
+        FIRST_CLUSTER <= cluster && cluster < self.layout.cluster_count + FIRST_CLUSTER
+    }
+
+    /// Reads the FAT entry of `cluster`, which is the next cluster of the chain
+    fn read_fat(&self, cluster: u32) -> Result<u32, ()> {
+        let mut entry = [0u8; FAT_ENTRY_SIZE as usize];
+        self.read_bytes(self.layout.fat_offset + cluster as u64 * FAT_ENTRY_SIZE, &mut entry)?;
+        Ok(u32::from_le_bytes(entry) & CLUSTER_MASK)
+    }
+
+    /// Updates the FAT entry of `cluster` in every FAT copy, the 4 reserved bits are kept.
+    fn write_fat(&self, cluster: u32, value: u32) -> Result<(), ()> {
+        for fat in 0..self.layout.fat_count {
+            let offset = self.layout.fat_offset + fat * self.layout.fat_size + cluster as u64 * FAT_ENTRY_SIZE;
+            let mut entry = [0u8; FAT_ENTRY_SIZE as usize];
+            self.read_bytes(offset, &mut entry)?;
+
+            let entry = (u32::from_le_bytes(entry) & !CLUSTER_MASK) | (value & CLUSTER_MASK);
+            self.write_bytes(offset, &entry.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Returns the clusters of the chain starting at `first_cluster`
+    fn cluster_chain(&self, first_cluster: u32) -> Result<Vec<u32>, ()> {
+        let mut chain = Vec::new();
+        let mut cluster = first_cluster;
+
+        while self.is_valid_cluster(cluster) {
+            // a chain longer then the volume is a loop
+            if chain.len() > self.layout.cluster_count as usize {
+                return Err(());
+            }
+            chain.push(cluster);
+            cluster = self.read_fat(cluster)?;
+        }
+        Ok(chain)
+    }
+
+    /// Finds a free cluster, marks it as the end of a chain and zeroes it.
+    fn allocate_cluster(&self) -> Result<u32, ()> {
+        let start = self.next_free_cluster.load(Ordering::Relaxed);
+
+        for index in 0..self.layout.cluster_count {
+            let cluster = FIRST_CLUSTER + (start - FIRST_CLUSTER + index) % self.layout.cluster_count;
+            if self.read_fat(cluster)? != FREE_CLUSTER {
+                continue;
+            }
+
+            self.write_fat(cluster, CLUSTER_MASK)?;
+            self.write_bytes(self.cluster_offset(cluster), &vec![0u8; self.layout.cluster_size])?;
+            self.next_free_cluster.store(cluster + 1, Ordering::Relaxed);
+            return Ok(cluster);
+        }
+        Err(())
+    }
+
+    /// Marks every cluster of the chain as free
+    fn free_chain(&self, first_cluster: u32) -> Result<(), ()> {
+        for cluster in self.cluster_chain(first_cluster)? {
+            self.write_fat(cluster, FREE_CLUSTER)?;
+        }
+        self.next_free_cluster.store(FIRST_CLUSTER, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Grows or shrinks the entry's chain to `count` clusters, returns the new chain.
+    ///
+    /// The entry is updated but not written back to the disk.
+    fn resize_chain(&self, entry: &mut ShortEntry, count: usize) -> Result<Vec<u32>, ()> {
+        let mut chain = self.cluster_chain(entry.first_cluster)?;
+
+        while chain.len() < count {
+            let cluster = self.allocate_cluster()?;
+            match chain.last() {
+                Some(last) => self.write_fat(*last, cluster)?,
+                None => entry.first_cluster = cluster,
+            }
+            chain.push(cluster);
+        }
+
+        if chain.len() > count {
+            self.free_chain(chain[count])?;
+            match count {
+                0 => entry.first_cluster = FREE_CLUSTER,
+                _ => self.write_fat(chain[count - 1], CLUSTER_MASK)?,
+            }
+            chain.truncate(count);
+        }
+        Ok(chain)
+    }
+
+    fn read_entry(&self, inode: InodeId) -> Result<ShortEntry, ()> {
+        if inode == ROOT_INODE {
+            return Err(());
+        }
+
+        let mut bytes = [0u8; ENTRY_SIZE];
+        self.read_bytes(inode, &mut bytes)?;
+        Ok(ShortEntry::parse(&bytes))
+    }
+
+    fn write_entry(&self, inode: InodeId, entry: &ShortEntry) -> Result<(), ()> {
+        self.write_bytes(inode, &entry.to_bytes())
+    }
+
+    /// Returns the first cluster of a directory
+    fn directory_cluster(&self, inode: InodeId) -> Result<u32, ()> {
+        if inode == ROOT_INODE {
+            return Ok(self.layout.root_cluster);
+        }
+
+        let entry = self.read_entry(inode)?;
+        if !entry.is_directory() {
+            return Err(());
+        }
+        // ".." entries of the root's children point to cluster 0
+        match entry.first_cluster {
+            FREE_CLUSTER => Ok(self.layout.root_cluster),
+            cluster => Ok(cluster),
+        }
+    }
+
+    /// Returns the disk offsets of every entry slot in the directory
+    fn directory_slots(&self, first_cluster: u32) -> Result<Vec<u64>, ()> {
+        let entries_per_cluster = self.layout.cluster_size / ENTRY_SIZE;
+
+        Ok(self
+            .cluster_chain(first_cluster)?
+            .into_iter()
+            .flat_map(|cluster| {
+                let offset = self.cluster_offset(cluster);
+                (0..entries_per_cluster).map(move |index| offset + (index * ENTRY_SIZE) as u64)
+            })
+            .collect())
+    }
+
+    /// Reads the used entries of a directory, without the "." and ".." entries.
+    fn read_directory(&self, first_cluster: u32) -> Result<Vec<FatEntry>, ()> {
+        let mut entries = Vec::new();
+        let mut long_name: Option<LongName> = None;
+        let mut cluster = vec![0u8; self.layout.cluster_size];
+
+        for chain_cluster in self.cluster_chain(first_cluster)? {
+            let cluster_offset = self.cluster_offset(chain_cluster);
+            self.read_bytes(cluster_offset, &mut cluster)?;
+
+            for (index, bytes) in cluster.chunks(ENTRY_SIZE).enumerate() {
+                let offset = cluster_offset + (index * ENTRY_SIZE) as u64;
+
+                match bytes[0] {
+                    END_OF_DIRECTORY => return Ok(entries),
+                    FREE_ENTRY => long_name = None,
+                    _ if bytes[11] & ATTRIBUTE_LONG_NAME == ATTRIBUTE_LONG_NAME => {
+                        LongName::push(&mut long_name, bytes, offset)
+                    }
+                    _ => {
+                        let short = ShortEntry::parse(bytes);
+                        let long_name = long_name.take();
+
+                        if short.is_dot() || short.attributes & ATTRIBUTE_VOLUME_ID != 0 {
+                            continue;
+                        }
+                        let (name, long_name_offsets) = match long_name {
+                            Some(long_name) => match long_name.name_of(&short) {
+                                Some(name) => (name, long_name.offsets),
+                                None => (short.display_name(), Vec::new()),
+                            },
+                            None => (short.display_name(), Vec::new()),
+                        };
+                        entries.push(FatEntry {
+                            name,
+                            short,
+                            offset,
+                            long_name_offsets,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Finds `name` inside a directory, names are case insensitive
+    fn find_entry(&self, directory: InodeId, name: &str) -> Result<FatEntry, ()> {
+        self.read_directory(self.directory_cluster(directory)?)?
+            .into_iter()
+            .find(|entry| entry.name.eq_ignore_ascii_case(name))
+            .ok_or(())
+    }
+
+    /// Finds `count` consecutive free slots in a directory, the directory is extended if needed.
+    fn free_slots(&self, first_cluster: u32, count: usize) -> Result<Vec<u64>, ()> {
+        let mut run = Vec::new();
+        let mut entry = [0u8; 1];
+
+        for offset in self.directory_slots(first_cluster)? {
+            self.read_bytes(offset, &mut entry)?;
+            match entry[0] {
+                END_OF_DIRECTORY | FREE_ENTRY => run.push(offset),
+                _ => run.clear(),
+            }
+            if run.len() == count {
+                return Ok(run);
+            }
+        }
+
+        // the new cluster is zeroed, so every slot in it is free
+        let chain = self.cluster_chain(first_cluster)?;
+        let last = *chain.last().ok_or(())?;
+        let cluster = self.allocate_cluster()?;
+        self.write_fat(last, cluster)?;
+
+        let offset = self.cluster_offset(cluster);
+        let entries_per_cluster = self.layout.cluster_size / ENTRY_SIZE;
+        run.extend((0..entries_per_cluster).map(|index| offset + (index * ENTRY_SIZE) as u64));
+        run.truncate(count);
+
+        if run.len() < count {
+            return Err(());
+        }
+        Ok(run)
+    }
+
+    fn file_type(entry: &ShortEntry) -> FileType {
+        if entry.is_directory() {
+            FileType::Directory
+        } else {
+            FileType::Regular
+        }
+    }
+}
+
+impl FileSystem for Fat32 {
+    fn root(&self) -> InodeId {
+        ROOT_INODE
+    }
+
+    fn lookup(&self, directory: InodeId, name: &str) -> Result<InodeId, ()> {
+        let _lock = self.lock.lock();
+        self.find_entry(directory, name).map(|entry| entry.offset)
+    }
+
+    fn create(&self, directory: InodeId, name: &str, file_type: FileType) -> Result<InodeId, ()> {
+        let _lock = self.lock.lock();
+        let directory_cluster = self.directory_cluster(directory)?;
+        let entries = self.read_directory(directory_cluster)?;
+
+        if entries.iter().any(|entry| entry.name.eq_ignore_ascii_case(name)) {
+            return Err(());
+        }
+        let alias = (1..=MAX_ALIAS_INDEX)
+            .map(|index| short_alias(name, index))
+            .find(|alias| entries.iter().all(|entry| entry.short.name != *alias))
+            .ok_or(())?;
+
+        let short = match file_type {
+            FileType::Regular => ShortEntry::new(alias, ATTRIBUTE_ARCHIVE, FREE_CLUSTER),
+            FileType::Directory => {
+                let cluster = self.allocate_cluster()?;
+                let parent = if directory == ROOT_INODE { FREE_CLUSTER } else { directory_cluster };
+                let offset = self.cluster_offset(cluster);
+                self.write_bytes(offset, &ShortEntry::dot(1, cluster).to_bytes())?;
+                self.write_bytes(offset + ENTRY_SIZE as u64, &ShortEntry::dot(2, parent).to_bytes())?;
+
+                ShortEntry::new(alias, ATTRIBUTE_DIRECTORY, cluster)
+            }
+            FileType::CharDevice => return Err(()),
+        };
+
+        let long_entries = long_name_entries(name, short.checksum());
+        let slots = self.free_slots(directory_cluster, long_entries.len() + 1)?;
+        for (slot, bytes) in slots.iter().zip(long_entries.iter()) {
+            self.write_bytes(*slot, bytes)?;
+        }
+
+        let inode = *slots.last().ok_or(())?;
+        self.write_entry(inode, &short)?;
+        Ok(inode)
+    }
+
+    fn read(&self, inode: InodeId, offset: usize, buffer: &mut [u8]) -> Result<usize, ()> {
+        let _lock = self.lock.lock();
+        let entry = self.read_entry(inode)?;
+        if entry.is_directory() {
+            return Err(());
+        }
+        if offset >= entry.size as usize {
+            return Ok(0);
+        }
+
+        let count = cmp::min(buffer.len(), entry.size as usize - offset);
+        let chain = self.cluster_chain(entry.first_cluster)?;
+        let cluster_size = self.layout.cluster_size;
+
+        let mut done = 0;
+        while done < count {
+            let position = offset + done;
+            let cluster = *chain.get(position / cluster_size).ok_or(())?;
+            let cluster_offset = position % cluster_size;
+            let chunk = cmp::min(count - done, cluster_size - cluster_offset);
+
+            self.read_bytes(
+                self.cluster_offset(cluster) + cluster_offset as u64,
+                &mut buffer[done..done + chunk],
+            )?;
+            done += chunk;
+        }
+        Ok(count)
+    }
+
+    fn write(&self, inode: InodeId, offset: usize, buffer: &[u8]) -> Result<usize, ()> {
+        let _lock = self.lock.lock();
+        let mut entry = self.read_entry(inode)?;
+        if entry.is_directory() {
+            return Err(());
+        }
+
+        let end = offset.checked_add(buffer.len()).ok_or(())?;
+        if end > u32::MAX as usize {
+            return Err(());
+        }
+        let cluster_size = self.layout.cluster_size;
+        let clusters = self.cluster_chain(entry.first_cluster)?.len();
+        let chain = self.resize_chain(&mut entry, cmp::max((end + cluster_size - 1) / cluster_size, clusters))?;
+
+        let mut done = 0;
+        while done < buffer.len() {
+            let position = offset + done;
+            let cluster = chain[position / cluster_size];
+            let cluster_offset = position % cluster_size;
+            let chunk = cmp::min(buffer.len() - done, cluster_size - cluster_offset);
+
+            self.write_bytes(
+                self.cluster_offset(cluster) + cluster_offset as u64,
+                &buffer[done..done + chunk],
+            )?;
+            done += chunk;
+        }
+
+        entry.size = cmp::max(entry.size, end as u32);
+        self.write_entry(inode, &entry)?;
+        Ok(buffer.len())
+    }
+
+    fn readdir(&self, directory: InodeId, index: usize) -> Result<Option<DirectoryEntry>, ()> {
+        let _lock = self.lock.lock();
+        let entries = self.read_directory(self.directory_cluster(directory)?)?;
+
+        match entries.get(index) {
+            Some(entry) => Ok(Some(
+                DirectoryEntry::new(entry.offset, Fat32::file_type(&entry.short), &entry.name).ok_or(())?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    fn unlink(&self, directory: InodeId, name: &str) -> Result<(), ()> {
+        let _lock = self.lock.lock();
+        let entry = self.find_entry(directory, name)?;
+
+        if entry.short.is_directory() && !self.read_directory(entry.short.first_cluster)?.is_empty() {
+            return Err(());
+        }
+        if entry.short.first_cluster != FREE_CLUSTER {
+            self.free_chain(entry.short.first_cluster)?;
+        }
+
+        for offset in entry.long_name_offsets.iter().chain(core::iter::once(&entry.offset)) {
+            self.write_bytes(*offset, &[FREE_ENTRY])?;
+        }
+        Ok(())
+    }
+
+    fn stat(&self, inode: InodeId) -> Result<Stat, ()> {
+        if inode == ROOT_INODE {
+            return Ok(Stat {
+                inode,
+                file_type: FileType::Directory,
+                size: 0,
+            });
+        }
+
+        let _lock = self.lock.lock();
+        let entry = self.read_entry(inode)?;
+        Ok(Stat {
+            inode,
+            file_type: Fat32::file_type(&entry),
+            size: entry.size as u64,
+        })
+    }
+
+    fn truncate(&self, inode: InodeId, size: usize) -> Result<(), ()> {
+        let _lock = self.lock.lock();
+        let mut entry = self.read_entry(inode)?;
+        if entry.is_directory() || size > u32::MAX as usize {
+            return Err(());
+        }
+
+        let cluster_size = self.layout.cluster_size;
+        self.resize_chain(&mut entry, (size + cluster_size - 1) / cluster_size)?;
+        entry.size = size as u32;
+        self.write_entry(inode, &entry)
+    }
+}
@@ -0,0 +1,107 @@
+//! Unpacks the initial ram filesystem, a cpio archive (new ASCII format) embedded in the kernel image.
+//!
+//! The archive is packed by the build script from the `initramfs` directory and the userland programs (to "/bin").
+
+use alloc::string::String;
+use core::str;
+use log::{debug, info};
+
+use super::{
+    vfs::{self, FileType},
+    File, OpenFlags,
+};
+
+/// The archive built by `build.rs`
+pub static ARCHIVE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/initramfs.cpio"));
+
+const MAGIC: &[u8] = b"070701";
+const HEADER_SIZE: usize = 110;
+const FIELD_SIZE: usize = 8;
+const TRAILER: &str = "TRAILER!!!";
+
+// header fields indices (each field is 8 hexadecimal digits after the magic)
+const MODE_FIELD: usize = 1;
+const FILE_SIZE_FIELD: usize = 6;
+const NAME_SIZE_FIELD: usize = 11;
+
+const FILE_TYPE_MASK: u32 = 0o170000;
+const DIRECTORY_MODE: u32 = 0o040000;
+const REGULAR_MODE: u32 = 0o100000;
+
+/// Extracts every directory and regular file of the archive to the root filesystem.
+///
+/// The archive must list every directory before the files inside it.
+pub fn unpack(archive: &[u8]) -> Result<(), ()> {
+    let mut offset = 0;
+
+    loop {
+        let header = archive.get(offset..offset + HEADER_SIZE).ok_or(())?;
+        if &header[..MAGIC.len()] != MAGIC {
+            return Err(());
+        }
+
+        let mode = header_field(header, MODE_FIELD)?;
+        let file_size = header_field(header, FILE_SIZE_FIELD)? as usize;
+        let name_size = header_field(header, NAME_SIZE_FIELD)? as usize;
+
+        // the name size includes the terminating null byte
+        let name_start = offset + HEADER_SIZE;
+        let name = archive.get(name_start..name_start + name_size.checked_sub(1).ok_or(())?).ok_or(())?;
+        let name = str::from_utf8(name).map_err(|_| ())?;
+
+        let data_start = align(name_start + name_size);
+        let data = archive.get(data_start..data_start + file_size).ok_or(())?;
+        offset = align(data_start + file_size);
+
+        if name == TRAILER {
+            break;
+        }
+        if name == "." {
+            continue;
+        }
+
+        let mut path = String::from("/");
+        path.push_str(name.trim_start_matches("./"));
+
+        match mode & FILE_TYPE_MASK {
+            DIRECTORY_MODE => extract_directory(&path)?,
+            REGULAR_MODE => extract_file(&path, data)?,
+            _ => debug!("initramfs: skipping {}, unsupported file type", path),
+        }
+    }
+
+    info!("initramfs unpacked");
+    Ok(())
+}
+
+fn extract_directory(path: &str) -> Result<(), ()> {
+    debug!("initramfs: creating directory {}", path);
+    match vfs::stat(path) {
+        Ok(stat) if stat.file_type == FileType::Directory => Ok(()),
+        Ok(_) => Err(()),
+        Err(()) => vfs::mkdir(path),
+    }
+}
+
+fn extract_file(path: &str, data: &[u8]) -> Result<(), ()> {
+    debug!("initramfs: extracting {} ({:#x} bytes)", path, data.len());
+    let file = vfs::open(path, OpenFlags::WRITE | OpenFlags::CREATE)?;
+
+    let mut written = 0;
+    while written < data.len() {
+        written += file.write(&data[written..])?;
+    }
+    Ok(())
+}
+
+/// Parses a header field, fields are saved as 8 hexadecimal digits
+fn header_field(header: &[u8], index: usize) -> Result<u32, ()> {
+    let start = MAGIC.len() + index * FIELD_SIZE;
+    let field = str::from_utf8(&header[start..start + FIELD_SIZE]).map_err(|_| ())?;
+    u32::from_str_radix(field, 16).map_err(|_| ())
+}
+
+/// Headers, names and data are aligned to 4 bytes
+fn align(offset: usize) -> usize {
+    (offset + 3) & !3
+}
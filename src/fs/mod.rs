@@ -0,0 +1,96 @@
+//! The fs module goal is to expose kernel objects (devices & files) to processes through file descriptors
+
+pub mod console;
+pub mod descriptors;
+pub mod devfs;
+pub mod fat32;
+pub mod initramfs;
+pub mod pipe;
+pub mod tmpfs;
+pub mod tty;
+pub mod vfs;
+
+use abi::open_flags;
+use alloc::{format, sync::Arc};
+use bitflags::bitflags;
+use log::info;
+
+use crate::{drivers::block::BLOCK_DEVICES, ipc::{channel::ChannelEnd, shm::SharedMemory}};
+
+use self::{devfs::DEVICES, fat32::Fat32, tmpfs::TmpFs, tty::TtyRequest, vfs::DirectoryEntry};
+
+/// A kernel object that a process can read from and write to.
+///
+/// Files are reference counted (`Arc<dyn File>`),
+/// which means a file is released only when its last file descriptor is closed.
+pub trait File: Send + Sync {
+    /// Reads bytes from the file into `buffer`.
+    ///
+    /// Returns the number of bytes read, zero means end of file.
+    fn read(&self, buffer: &mut [u8]) -> Result<usize, ()>;
+
+    /// Writes the bytes of `buffer` to the file.
+    ///
+    /// Returns the number of bytes written.
+    fn write(&self, buffer: &[u8]) -> Result<usize, ()>;
+
+    /// Reads the next entries of a directory into `entries`.
+    ///
+    /// Returns the number of entries read, zero means there are no more entries.
+    fn read_directory(&self, _entries: &mut [DirectoryEntry]) -> Result<usize, ()> {
+        Err(())
+    }
+
+    /// Handles a terminal request, only terminals support requests.
+    ///
+    /// Returns the request's result.
+    fn ioctl(&self, _request: TtyRequest) -> Result<usize, ()> {
+        Err(())
+    }
+
+    /// Returns the file as a channel end, only channel ends pass messages with files (see `ipc::channel`).
+    fn as_channel(&self) -> Option<&ChannelEnd> {
+        None
+    }
+
+    /// Returns the file as a shared memory segment, only segments are mapped by SHM_MAP (see `ipc::shm`).
+    fn as_shared_memory(&self) -> Option<&SharedMemory> {
+        None
+    }
+}
+
+bitflags! {
+    /// The access mode of an opened file
+    pub struct OpenFlags: u64 {
+        const READ =    open_flags::READ;
+        const WRITE =   open_flags::WRITE;
+        /// Creates a regular file if the path doesn't exist
+        const CREATE =  open_flags::CREATE;
+        /// Truncates a regular file to zero bytes
+        const TRUNCATE = open_flags::TRUNCATE;
+        /// Every write of a regular file is done at it's end
+        const APPEND =  open_flags::APPEND;
+    }
+}
+
+/// Mounts a tmpfs at "/" with the devices at "/dev", and unpacks the initramfs to it.
+///
+/// Every FAT32 formatted block device is mounted at "/mnt/<device name>".
+pub fn init() {
+    vfs::mount("/", Arc::new(TmpFs::new())).unwrap();
+    vfs::mkdir("/dev").unwrap();
+    vfs::mount("/dev", DEVICES.clone()).unwrap();
+    vfs::mkdir("/mnt").unwrap();
+
+    for (name, device) in BLOCK_DEVICES.lock().iter() {
+        if let Ok(fs) = Fat32::new(device.clone()) {
+            let path = format!("/mnt/{}", name);
+            vfs::mkdir(&path).unwrap();
+            vfs::mount(&path, Arc::new(fs)).unwrap();
+            info!("mounted FAT32 volume {} at {}", name, path);
+        }
+    }
+    info!("filesystems mounted");
+
+    initramfs::unpack(initramfs::ARCHIVE).unwrap();
+}
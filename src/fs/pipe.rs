@@ -0,0 +1,139 @@
+//! Anonymous pipes, a kernel ring buffer with a read end and a write end.
+//!
+//! Reading an empty pipe waits for data, and returns end of file once the write end is closed.
+//! Writing a full pipe waits for the reader, and fails once the read end is closed.
+//! An end is closed when it's last file descriptor is closed (the ends are reference counted files).
+//!
+//! A process that waits sleeps on the pipe's wait queues until the other end reads, writes or closes (see `sync`).
+
+use alloc::{collections::VecDeque, sync::Arc};
+use core::cmp;
+
+use crate::sync::{relax, SpinLock, WaitQueue};
+
+use super::File;
+
+/// The amount of bytes a pipe holds before writers wait, a write of up to the capacity is written at once
+pub const PIPE_CAPACITY: usize = 4096;
+
+struct PipeState {
+    buffer: VecDeque<u8>,
+    reader_open: bool,
+    writer_open: bool,
+}
+
+struct Pipe {
+    state: SpinLock<PipeState>,
+    /// The readers that wait for data
+    readable: WaitQueue,
+    /// The writers that wait for room
+    writable: WaitQueue,
+}
+
+/// The read end of a pipe
+pub struct PipeReader {
+    pipe: Arc<Pipe>,
+}
+
+/// The write end of a pipe
+pub struct PipeWriter {
+    pipe: Arc<Pipe>,
+}
+
+/// Creates a pipe, returns it's read end and write end
+pub fn pipe() -> (Arc<dyn File>, Arc<dyn File>) {
+    let pipe = Arc::new(Pipe {
+        state: SpinLock::new(PipeState {
+            buffer: VecDeque::with_capacity(PIPE_CAPACITY),
+            reader_open: true,
+            writer_open: true,
+        }),
+        readable: WaitQueue::new(),
+        writable: WaitQueue::new(),
+    });
+
+    (Arc::new(PipeReader { pipe: pipe.clone() }), Arc::new(PipeWriter { pipe }))
+}
+
+impl File for PipeReader {
+    /// Waits until the pipe has data, returns zero bytes if the write end is closed
+    fn read(&self, buffer: &mut [u8]) -> Result<usize, ()> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            {
+                let mut state = self.pipe.state.lock();
+                if !state.buffer.is_empty() {
+                    let count = cmp::min(buffer.len(), state.buffer.len());
+                    for (byte, value) in buffer.iter_mut().zip(state.buffer.drain(..count)) {
+                        *byte = value;
+                    }
+                    drop(state);
+                    self.pipe.writable.wake_all();
+                    return Ok(count);
+                }
+                if !state.writer_open {
+                    return Ok(0);
+                }
+                // registered while the pipe is locked, so the next write wakes the reader
+                if self.pipe.readable.register().is_ok() {
+                    return Err(());
+                }
+            }
+            relax();
+        }
+    }
+
+    fn write(&self, _buffer: &[u8]) -> Result<usize, ()> {
+        Err(())
+    }
+}
+
+impl Drop for PipeReader {
+    fn drop(&mut self) {
+        self.pipe.state.lock().reader_open = false;
+        self.pipe.writable.wake_all();
+    }
+}
+
+impl File for PipeWriter {
+    fn read(&self, _buffer: &mut [u8]) -> Result<usize, ()> {
+        Err(())
+    }
+
+    /// Waits until the pipe has room for the buffer and writes it at once, a buffer larger than the capacity
+    /// is written partially once the pipe is empty. Fails if the read end is closed.
+    fn write(&self, buffer: &[u8]) -> Result<usize, ()> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        // a syscall that sleeps is issued again, so nothing is written before the write waits
+        let count = cmp::min(buffer.len(), PIPE_CAPACITY);
+        loop {
+            {
+                let mut state = self.pipe.state.lock();
+                if !state.reader_open {
+                    return Err(());
+                }
+                if PIPE_CAPACITY - state.buffer.len() >= count {
+                    state.buffer.extend(&buffer[..count]);
+                    drop(state);
+                    self.pipe.readable.wake_all();
+                    return Ok(count);
+                }
+                if self.pipe.writable.register().is_ok() {
+                    return Err(());
+                }
+            }
+            relax();
+        }
+    }
+}
+
+impl Drop for PipeWriter {
+    fn drop(&mut self) {
+        self.pipe.state.lock().writer_open = false;
+        self.pipe.readable.wake_all();
+    }
+}
@@ -0,0 +1,241 @@
+//! An in-memory filesystem, the content of every file is saved in kernel page frames
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::{
+    cmp,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use spin::Mutex;
+
+use crate::memory::{as_mut_ref, get_linear_addr, kfree, kmalloc, types::PAGE_SIZE};
+
+use super::vfs::{DirectoryEntry, FileSystem, FileType, InodeId, Stat};
+
+const ROOT_INODE: InodeId = 0;
+
+enum Node {
+    /// A regular file, it's content is saved in the page frames in order
+    File { frames: Vec<u64>, size: usize },
+    /// A directory, a list of names and their inodes
+    Directory { entries: Vec<(String, InodeId)> },
+}
+
+impl Node {
+    fn file_type(&self) -> FileType {
+        match self {
+            Node::File { .. } => FileType::Regular,
+            Node::Directory { .. } => FileType::Directory,
+        }
+    }
+
+    /// Releases the page frames of a regular file
+    fn release_frames(&mut self) {
+        if let Node::File { frames, size } = self {
+            for frame in frames.drain(..) {
+                kfree(frame, PAGE_SIZE, PAGE_SIZE);
+            }
+            *size = 0;
+        }
+    }
+}
+
+pub struct TmpFs {
+    nodes: Mutex<BTreeMap<InodeId, Node>>,
+    next_inode: AtomicU64,
+}
+
+impl TmpFs {
+    /// Creates a filesystem with an empty root directory
+    pub fn new() -> Self {
+        let mut nodes = BTreeMap::new();
+        nodes.insert(ROOT_INODE, Node::Directory { entries: Vec::new() });
+
+        TmpFs {
+            nodes: Mutex::new(nodes),
+            next_inode: AtomicU64::new(ROOT_INODE + 1),
+        }
+    }
+
+    /// `FileSystem::lookup` for callers that already hold the nodes lock
+    fn lookup_locked(nodes: &BTreeMap<InodeId, Node>, directory: InodeId, name: &str) -> Result<InodeId, ()> {
+        match nodes.get(&directory).ok_or(())? {
+            Node::Directory { entries } => entries
+                .iter()
+                .find(|(entry_name, _)| entry_name == name)
+                .map(|(_, inode)| *inode)
+                .ok_or(()),
+            Node::File { .. } => Err(()),
+        }
+    }
+}
+
+impl Drop for TmpFs {
+    fn drop(&mut self) {
+        for node in self.nodes.lock().values_mut() {
+            node.release_frames();
+        }
+    }
+}
+
+impl FileSystem for TmpFs {
+    fn root(&self) -> InodeId {
+        ROOT_INODE
+    }
+
+    fn lookup(&self, directory: InodeId, name: &str) -> Result<InodeId, ()> {
+        TmpFs::lookup_locked(&self.nodes.lock(), directory, name)
+    }
+
+    fn create(&self, directory: InodeId, name: &str, file_type: FileType) -> Result<InodeId, ()> {
+        let node = match file_type {
+            FileType::Regular => Node::File { frames: Vec::new(), size: 0 },
+            FileType::Directory => Node::Directory { entries: Vec::new() },
+            FileType::CharDevice => return Err(()),
+        };
+
+        let mut nodes = self.nodes.lock();
+        let entries = match nodes.get_mut(&directory).ok_or(())? {
+            Node::Directory { entries } => entries,
+            Node::File { .. } => return Err(()),
+        };
+        if entries.iter().any(|(entry_name, _)| entry_name == name) {
+            return Err(());
+        }
+
+        let inode = self.next_inode.fetch_add(1, Ordering::Relaxed);
+        entries.push((String::from(name), inode));
+        nodes.insert(inode, node);
+        Ok(inode)
+    }
+
+    fn read(&self, inode: InodeId, offset: usize, buffer: &mut [u8]) -> Result<usize, ()> {
+        let nodes = self.nodes.lock();
+        let (frames, size) = match nodes.get(&inode).ok_or(())? {
+            Node::File { frames, size } => (frames, *size),
+            Node::Directory { .. } => return Err(()),
+        };
+        if offset >= size {
+            return Ok(0);
+        }
+
+        let count = cmp::min(buffer.len(), size - offset);
+        let mut copied = 0;
+        while copied < count {
+            let position = offset + copied;
+            let page_offset = position % PAGE_SIZE;
+            let chunk = cmp::min(count - copied, PAGE_SIZE - page_offset);
+
+            let page = frame_content(frames[position / PAGE_SIZE]);
+            buffer[copied..copied + chunk].copy_from_slice(&page[page_offset..page_offset + chunk]);
+            copied += chunk;
+        }
+        Ok(count)
+    }
+
+    fn write(&self, inode: InodeId, offset: usize, buffer: &[u8]) -> Result<usize, ()> {
+        let mut nodes = self.nodes.lock();
+        let (frames, size) = match nodes.get_mut(&inode).ok_or(())? {
+            Node::File { frames, size } => (frames, size),
+            Node::Directory { .. } => return Err(()),
+        };
+
+        let end = offset.checked_add(buffer.len()).ok_or(())?;
+        while frames.len() * PAGE_SIZE < end {
+            let frame = kmalloc(PAGE_SIZE, PAGE_SIZE)?;
+            frame_content(frame).fill(0);
+            frames.push(frame);
+        }
+
+        let mut copied = 0;
+        while copied < buffer.len() {
+            let position = offset + copied;
+            let page_offset = position % PAGE_SIZE;
+            let chunk = cmp::min(buffer.len() - copied, PAGE_SIZE - page_offset);
+
+            let page = frame_content(frames[position / PAGE_SIZE]);
+            page[page_offset..page_offset + chunk].copy_from_slice(&buffer[copied..copied + chunk]);
+            copied += chunk;
+        }
+        *size = cmp::max(*size, end);
+        Ok(buffer.len())
+    }
+
+    fn readdir(&self, directory: InodeId, index: usize) -> Result<Option<DirectoryEntry>, ()> {
+        let nodes = self.nodes.lock();
+        let entries = match nodes.get(&directory).ok_or(())? {
+            Node::Directory { entries } => entries,
+            Node::File { .. } => return Err(()),
+        };
+
+        match entries.get(index) {
+            Some((name, inode)) => {
+                let file_type = nodes.get(inode).ok_or(())?.file_type();
+                Ok(Some(DirectoryEntry::new(*inode, file_type, name).ok_or(())?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn unlink(&self, directory: InodeId, name: &str) -> Result<(), ()> {
+        let mut nodes = self.nodes.lock();
+        let inode = TmpFs::lookup_locked(&nodes, directory, name)?;
+
+        if let Node::Directory { entries } = nodes.get(&inode).ok_or(())? {
+            if !entries.is_empty() {
+                return Err(());
+            }
+        }
+
+        if let Some(Node::Directory { entries }) = nodes.get_mut(&directory) {
+            entries.retain(|(_, entry_inode)| *entry_inode != inode);
+        }
+        if let Some(mut node) = nodes.remove(&inode) {
+            node.release_frames();
+        }
+        Ok(())
+    }
+
+    fn stat(&self, inode: InodeId) -> Result<Stat, ()> {
+        let nodes = self.nodes.lock();
+        let node = nodes.get(&inode).ok_or(())?;
+
+        let size = match node {
+            Node::File { size, .. } => *size,
+            Node::Directory { entries } => entries.len(),
+        };
+        Ok(Stat {
+            inode,
+            file_type: node.file_type(),
+            size: size as u64,
+        })
+    }
+
+    fn truncate(&self, inode: InodeId, new_size: usize) -> Result<(), ()> {
+        let mut nodes = self.nodes.lock();
+        let (frames, size) = match nodes.get_mut(&inode).ok_or(())? {
+            Node::File { frames, size } => (frames, size),
+            Node::Directory { .. } => return Err(()),
+        };
+
+        while frames.len() * PAGE_SIZE < new_size {
+            let frame = kmalloc(PAGE_SIZE, PAGE_SIZE)?;
+            frame_content(frame).fill(0);
+            frames.push(frame);
+        }
+        while frames.len() * PAGE_SIZE >= new_size + PAGE_SIZE {
+            kfree(frames.pop().ok_or(())?, PAGE_SIZE, PAGE_SIZE);
+        }
+
+        // the bytes after the end must be zeroes if the file grows again
+        if new_size % PAGE_SIZE != 0 {
+            frame_content(frames[new_size / PAGE_SIZE])[new_size % PAGE_SIZE..].fill(0);
+        }
+        *size = new_size;
+        Ok(())
+    }
+}
+
+/// Returns the content of a page frame through the physical memory mapping
+fn frame_content<'a>(frame: u64) -> &'a mut [u8; PAGE_SIZE] {
+    unsafe { as_mut_ref::<[u8; PAGE_SIZE]>(get_linear_addr(frame)) }
+}
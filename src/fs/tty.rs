@@ -0,0 +1,261 @@
+//! The TTY line discipline, it sits between a terminal's input (the keyboard or a serial port) and it's readers.
+//!
+//! In canonical mode the input is edited a line at a time (erase, kill line & word erase) and a read returns a single line,
+//! otherwise every typed byte is read as is. The interrupt & suspend characters signal the terminal's foreground process group,
+//! a reader that is signaled stops reading so the signal is handled (the read is restarted afterwards).
+//!
+//! The input is processed while the terminal is read, so the settings are applied from the next typed character.
+
+use abi::tty::mode;
+use alloc::vec::Vec;
+use bitflags::bitflags;
+use core::{
+    cmp,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use log::info;
+use spin::Mutex;
+
+use crate::processes::{
+    self,
+    signals::{SIGINT, SIGTSTP},
+};
+
+pub use abi::tty::Termios;
+
+const NO_FOREGROUND: usize = usize::MAX;
+const NO_SESSION: usize = usize::MAX;
+
+bitflags! {
+    /// The modes of a terminal (`Termios::mode`), a terminal without any mode is in raw mode
+    pub struct TtyMode: u64 {
+        /// Reads return whole lines, which are edited with the erase, kill & word erase characters
+        const CANONICAL =   mode::CANONICAL;
+        /// The typed characters are written back to the terminal
+        const ECHO =        mode::ECHO;
+        /// The interrupt & suspend characters signal the foreground process group
+        const SIGNALS =     mode::SIGNALS;
+    }
+}
+
+/// A terminal request, the IOCTL syscall passes it to the file
+pub enum TtyRequest<'a> {
+    GetSettings(&'a mut Termios),
+    SetSettings(&'a Termios),
+    /// Returns the foreground process group
+    GetForeground,
+    /// Sets the foreground process group of the caller's session, `None` makes the job control characters only discard the line.
+    /// Fails if the terminal belongs to another session (see `LineDiscipline::set_foreground`)
+    SetForeground { session: usize, group: Option<usize> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Signal {
+    Interrupt,
+    Suspend,
+}
+
+struct Input {
+    /// The bytes that can be read
+    ready: Vec<u8>,
+    /// The line edited in canonical mode
+    line: Vec<u8>,
+    /// Set by the end of file character on an empty line, the next read returns zero bytes
+    end_of_file: bool,
+}
+
+/// The line discipline of a single terminal
+pub struct LineDiscipline {
+    settings: Mutex<Termios>,
+    input: Mutex<Input>,
+    /// The process group the job control characters signal
+    foreground: AtomicUsize,
+    /// The session the terminal belongs to, only it's process groups are the foreground
+    session: AtomicUsize,
+}
+
+impl LineDiscipline {
+    pub const fn new() -> Self {
+        LineDiscipline {
+            settings: Mutex::new(Termios::canonical()),
+            input: Mutex::new(Input {
+                ready: Vec::new(),
+                line: Vec::new(),
+                end_of_file: false,
+            }),
+            foreground: AtomicUsize::new(NO_FOREGROUND),
+            session: AtomicUsize::new(NO_SESSION),
+        }
+    }
+
+    pub fn settings(&self) -> Termios {
+        *self.settings.lock()
+    }
+
+    /// Changes the settings, fails on unknown mode bits
+    pub fn set_settings(&self, settings: Termios) -> Result<(), ()> {
+        TtyMode::from_bits(settings.mode).ok_or(())?;
+        *self.settings.lock() = settings;
+        Ok(())
+    }
+
+    pub fn foreground(&self) -> Option<usize> {
+        match self.foreground.load(Ordering::Acquire) {
+            NO_FOREGROUND => None,
+            pgid => Some(pgid),
+        }
+    }
+
+    /// Sets the foreground process group of `session`, the terminal belongs to the first session that sets it.
+    /// Fails if the terminal belongs to another session.
+    pub fn set_foreground(&self, session: usize, pgid: Option<usize>) -> Result<(), ()> {
+        match self.session.compare_exchange(NO_SESSION, session, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => {}
+            Err(owner) if owner == session => {}
+            Err(_) => return Err(()),
+        }
+        self.foreground.store(pgid.unwrap_or(NO_FOREGROUND), Ordering::Release);
+        Ok(())
+    }
+
+    /// Handles a terminal request, returns the request's result
+    pub fn ioctl(&self, request: TtyRequest) -> Result<usize, ()> {
+        match request {
+            TtyRequest::GetSettings(settings) => {
+                *settings = self.settings();
+                Ok(0)
+            }
+            TtyRequest::SetSettings(settings) => self.set_settings(*settings).map(|()| 0),
+            TtyRequest::GetForeground => self.foreground().ok_or(()),
+            TtyRequest::SetForeground { session, group } => self.set_foreground(session, group).map(|()| 0),
+        }
+    }
+
+    /// Reads the processed input, waits for a line in canonical mode and for a single byte otherwise.
+    /// Fails if the typed characters signal the reader, the signal is delivered before the read is issued again.
+    ///
+    /// # Arguments
+    ///  - `next_character`, waits for the next typed character
+    ///  - `echo`, displays the typed characters
+    pub fn read(&self, buffer: &mut [u8], mut next_character: impl FnMut() -> u8, echo: impl Fn(&[u8])) -> Result<usize, ()> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let signal = {
+                let mut input = self.input.lock();
+                if !input.ready.is_empty() {
+                    let count = cmp::min(buffer.len(), input.ready.len());
+                    buffer[..count].copy_from_slice(&input.ready[..count]);
+                    input.ready.drain(..count);
+                    return Ok(count);
+                }
+                if input.end_of_file {
+                    input.end_of_file = false;
+                    return Ok(0);
+                }
+                let settings = self.settings();
+                LineDiscipline::receive(&mut input, &settings, next_character(), &echo)
+            };
+            // signaling may kill the reader, so the input is unlocked first
+            if let Some(signal) = signal {
+                self.signal_foreground(signal);
+                if processes::has_pending_signals() {
+                    return Err(());
+                }
+            }
+        }
+    }
+
+    /// Processes a typed character, returns the signal it generates
+    fn receive(input: &mut Input, settings: &Termios, character: u8, echo: &impl Fn(&[u8])) -> Option<Signal> {
+        let mode = TtyMode::from_bits_truncate(settings.mode);
+        let echoes = mode.contains(TtyMode::ECHO);
+        let echo = |bytes: &[u8]| {
+            if echoes {
+                echo(bytes)
+            }
+        };
+
+        if mode.contains(TtyMode::SIGNALS) && (character == settings.interrupt || character == settings.suspend) {
+            input.line.clear();
+            if character == settings.interrupt {
+                echo(b"^C\n");
+                return Some(Signal::Interrupt);
+            }
+            echo(b"^Z\n");
+            return Some(Signal::Suspend);
+        }
+
+        if !mode.contains(TtyMode::CANONICAL) {
+            // a line edited before leaving canonical mode is read first
+            let Input { ready, line, .. } = input;
+            ready.append(line);
+            ready.push(character);
+            echo(&[character]);
+            return None;
+        }
+
+        match character {
+            b'\n' => {
+                input.line.push(character);
+                input.ready.append(&mut input.line);
+                echo(b"\n");
+            }
+            _ if character == settings.end_of_file => {
+                input.end_of_file = input.line.is_empty();
+                input.ready.append(&mut input.line);
+            }
+            _ if character == settings.erase => LineDiscipline::erase(&mut input.line, 1, echo),
+            _ if character == settings.kill => {
+                let length = input.line.len();
+                LineDiscipline::erase(&mut input.line, length, echo);
+            }
+            _ if character == settings.word_erase => {
+                let spaces = input.line.iter().rev().take_while(|byte| byte.is_ascii_whitespace()).count();
+                let word = input.line[..input.line.len() - spaces]
+                    .iter()
+                    .rev()
+                    .take_while(|byte| !byte.is_ascii_whitespace())
+                    .count();
+                LineDiscipline::erase(&mut input.line, spaces + word, echo);
+            }
+            b' '..=b'~' | b'\t' => {
+                input.line.push(character);
+                echo(&[character]);
+            }
+            // other control characters are ignored
+            _ => {}
+        }
+        None
+    }
+
+    /// Erases the last `count` characters of the line
+    fn erase(line: &mut Vec<u8>, count: usize, echo: impl Fn(&[u8])) {
+        for _ in 0..count {
+            if line.pop().is_some() {
+                echo(b"\x08 \x08");
+            }
+        }
+    }
+
+    /// Signals the foreground process group, an interrupt sends SIGINT and a suspend sends SIGTSTP.
+    fn signal_foreground(&self, signal: Signal) {
+        let pgid = match self.foreground() {
+            Some(pgid) => pgid,
+            None => return,
+        };
+        let signal = match signal {
+            Signal::Interrupt => SIGINT,
+            Signal::Suspend => SIGTSTP,
+        };
+        info!("sending signal {} to the foreground process group {:#x}", signal, pgid);
+        processes::send_group_signal(pgid, signal).ok();
+    }
+}
+
+impl Default for LineDiscipline {
+    fn default() -> Self {
+        LineDiscipline::new()
+    }
+}
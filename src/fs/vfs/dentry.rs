@@ -0,0 +1,89 @@
+//! Defines the inode and directory entry (dentry) objects of the VFS
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+
+use super::{FileSystem, InodeId, Stat};
+
+/// A filesystem object, identified by it's filesystem and it's inode id
+#[derive(Clone)]
+pub struct Inode {
+    pub fs: Arc<dyn FileSystem>,
+    pub id: InodeId,
+}
+
+impl Inode {
+    pub fn new(fs: Arc<dyn FileSystem>, id: InodeId) -> Self {
+        Inode { fs, id }
+    }
+
+    /// Returns another inode from the same filesystem
+    pub fn with_id(&self, id: InodeId) -> Self {
+        Inode::new(self.fs.clone(), id)
+    }
+
+    /// Returns whether both inodes are the same filesystem object
+    pub fn is(&self, other: &Inode) -> bool {
+        // compare only the data pointers, the vtable pointers may differ between codegen units
+        Arc::as_ptr(&self.fs) as *const u8 == Arc::as_ptr(&other.fs) as *const u8 && self.id == other.id
+    }
+
+    pub fn stat(&self) -> Result<Stat, ()> {
+        self.fs.stat(self.id)
+    }
+}
+
+/// A resolved path component, links an inode to it's name and to it's parent directory.
+///
+/// The parent link is what allows `..` to cross mount points back to the mounting filesystem.
+pub struct Dentry {
+    pub name: String,
+    pub inode: Inode,
+    /// `None` only for the root directory
+    pub parent: Option<Arc<Dentry>>,
+}
+
+impl Dentry {
+    /// Creates the dentry of the root directory
+    pub fn root(inode: Inode) -> Arc<Self> {
+        Arc::new(Dentry {
+            name: String::from("/"),
+            inode,
+            parent: None,
+        })
+    }
+
+    /// Creates a dentry for an inode inside the `parent` directory
+    pub fn child(parent: &Arc<Dentry>, name: &str, inode: Inode) -> Arc<Self> {
+        Arc::new(Dentry {
+            name: String::from(name),
+            inode,
+            parent: Some(parent.clone()),
+        })
+    }
+
+    /// Returns the parent directory, the parent of the root directory is the root itself
+    pub fn parent(self: &Arc<Self>) -> Arc<Dentry> {
+        self.parent.clone().unwrap_or_else(|| self.clone())
+    }
+
+    /// Returns the absolute path of the dentry
+    pub fn path(&self) -> String {
+        let mut names = Vec::new();
+        let mut current = self;
+        while let Some(parent) = &current.parent {
+            names.push(current.name.as_str());
+            current = parent.as_ref();
+        }
+
+        if names.is_empty() {
+            return String::from("/");
+        }
+
+        let mut path = String::new();
+        for name in names.iter().rev() {
+            path.push('/');
+            path.push_str(name);
+        }
+        path
+    }
+}
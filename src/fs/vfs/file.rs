@@ -0,0 +1,72 @@
+//! Defines the file object a process gets when opening a regular file or a directory
+
+use alloc::sync::Arc;
+use spin::Mutex;
+
+use crate::fs::{File, OpenFlags};
+
+use super::{dentry::Dentry, DirectoryEntry};
+
+/// An opened VFS file, saves the position of the next read / write.
+///
+/// The offset of a directory is the index of the next entry to read.
+pub struct OpenFile {
+    dentry: Arc<Dentry>,
+    flags: OpenFlags,
+    offset: Mutex<usize>,
+}
+
+impl OpenFile {
+    pub fn new(dentry: Arc<Dentry>, flags: OpenFlags) -> Self {
+        OpenFile {
+            dentry,
+            flags,
+            offset: Mutex::new(0),
+        }
+    }
+}
+
+impl File for OpenFile {
+    fn read(&self, buffer: &mut [u8]) -> Result<usize, ()> {
+        if !self.flags.contains(OpenFlags::READ) {
+            return Err(());
+        }
+
+        let inode = &self.dentry.inode;
+        let mut offset = self.offset.lock();
+        let count = inode.fs.read(inode.id, *offset, buffer)?;
+        *offset += count;
+        Ok(count)
+    }
+
+    fn write(&self, buffer: &[u8]) -> Result<usize, ()> {
+        if !self.flags.contains(OpenFlags::WRITE) {
+            return Err(());
+        }
+
+        let inode = &self.dentry.inode;
+        let mut offset = self.offset.lock();
+        if self.flags.contains(OpenFlags::APPEND) {
+            *offset = inode.stat()?.size as usize;
+        }
+        let count = inode.fs.write(inode.id, *offset, buffer)?;
+        *offset += count;
+        Ok(count)
+    }
+
+    fn read_directory(&self, entries: &mut [DirectoryEntry]) -> Result<usize, ()> {
+        let inode = &self.dentry.inode;
+        let mut offset = self.offset.lock();
+
+        let mut count = 0;
+        while count < entries.len() {
+            match inode.fs.readdir(inode.id, *offset)? {
+                Some(entry) => entries[count] = entry,
+                None => break,
+            }
+            *offset += 1;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
@@ -0,0 +1,178 @@
+//! The virtual filesystem layer, joins the concrete filesystems into a single directory tree.
+//!
+//! Every concrete filesystem implements the `FileSystem` trait and is attached to the tree with `mount`.
+//! All the paths this module receives must be absolute.
+
+pub mod dentry;
+pub mod file;
+pub mod mount;
+
+use alloc::{string::String, sync::Arc, vec, vec::Vec};
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+use super::{File, OpenFlags};
+
+use self::{dentry::Dentry, file::OpenFile, mount::MountTable};
+
+pub use abi::fs::{DirectoryEntry, FileType, Stat, MAX_NAME_LENGTH};
+
+pub type InodeId = u64;
+
+lazy_static! {
+    pub static ref MOUNT_TABLE: Mutex<MountTable> = Mutex::new(MountTable::empty());
+}
+
+/// The interface every concrete filesystem implements.
+///
+/// Inodes are identified by the filesystem with an `InodeId`, the VFS never interprets it.
+pub trait FileSystem: Send + Sync {
+    /// Returns the inode of the root directory
+    fn root(&self) -> InodeId;
+
+    /// Finds `name` inside `directory`
+    fn lookup(&self, directory: InodeId, name: &str) -> Result<InodeId, ()>;
+
+    /// Creates a new file named `name` inside `directory`
+    fn create(&self, directory: InodeId, name: &str, file_type: FileType) -> Result<InodeId, ()>;
+
+    /// Reads the file content from `offset` into `buffer`, returns the number of bytes read
+    fn read(&self, inode: InodeId, offset: usize, buffer: &mut [u8]) -> Result<usize, ()>;
+
+    /// Writes `buffer` to the file at `offset`, returns the number of bytes written
+    fn write(&self, inode: InodeId, offset: usize, buffer: &[u8]) -> Result<usize, ()>;
+
+    /// Returns the entry at `index` inside `directory`, `None` is returned after the last entry
+    fn readdir(&self, directory: InodeId, index: usize) -> Result<Option<DirectoryEntry>, ()>;
+
+    /// Removes `name` from `directory`, directories must be empty
+    fn unlink(&self, directory: InodeId, name: &str) -> Result<(), ()>;
+
+    /// Returns the inode metadata
+    fn stat(&self, inode: InodeId) -> Result<Stat, ()>;
+
+    /// Changes the size of a regular file, the new bytes are zeroes
+    fn truncate(&self, _inode: InodeId, _size: usize) -> Result<(), ()> {
+        Err(())
+    }
+
+    /// Returns the device of a `FileType::CharDevice` inode
+    fn device(&self, _inode: InodeId) -> Option<Arc<dyn File>> {
+        None
+    }
+}
+
+/// Mounts `fs` at `path`, the first mounted filesystem must be mounted at "/"
+pub fn mount(path: &str, fs: Arc<dyn FileSystem>) -> Result<(), ()> {
+    if path == "/" && MOUNT_TABLE.lock().mount_root(fs.clone()).is_ok() {
+        return Ok(());
+    }
+
+    let mountpoint = resolve(path)?;
+    if mountpoint.inode.stat()?.file_type != FileType::Directory {
+        return Err(());
+    }
+    MOUNT_TABLE.lock().mount(mountpoint.inode.clone(), fs);
+    Ok(())
+}
+
+/// Opens the file at `path`, creates or truncates a regular file if requested.
+pub fn open(path: &str, flags: OpenFlags) -> Result<Arc<dyn File>, ()> {
+    let dentry = match resolve(path) {
+        Ok(dentry) => dentry,
+        Err(()) if flags.contains(OpenFlags::CREATE) => create(path, FileType::Regular)?,
+        Err(()) => return Err(()),
+    };
+
+    let stat = dentry.inode.stat()?;
+    match stat.file_type {
+        FileType::CharDevice => dentry.inode.fs.device(dentry.inode.id).ok_or(()),
+        FileType::Directory if flags.intersects(OpenFlags::WRITE | OpenFlags::TRUNCATE) => Err(()),
+        FileType::Regular if flags.contains(OpenFlags::TRUNCATE) => {
+            dentry.inode.fs.truncate(dentry.inode.id, 0)?;
+            Ok(Arc::new(OpenFile::new(dentry, flags)))
+        }
+        _ => Ok(Arc::new(OpenFile::new(dentry, flags))),
+    }
+}
+
+/// Reads the whole regular file at `path`
+pub fn read_file(path: &str) -> Result<Vec<u8>, ()> {
+    let stat = stat(path)?;
+    if stat.file_type != FileType::Regular {
+        return Err(());
+    }
+
+    let file = open(path, OpenFlags::READ)?;
+    let mut data = vec![0; stat.size as usize];
+    let mut length = 0;
+    while length < data.len() {
+        match file.read(&mut data[length..])? {
+            0 => break,
+            count => length += count,
+        }
+    }
+    data.truncate(length);
+    Ok(data)
+}
+
+/// Changes the size of the regular file at `path`
+pub fn truncate(path: &str, size: usize) -> Result<(), ()> {
+    let dentry = resolve(path)?;
+    dentry.inode.fs.truncate(dentry.inode.id, size)
+}
+
+/// Returns the metadata of the file at `path`
+pub fn stat(path: &str) -> Result<Stat, ()> {
+    resolve(path)?.inode.stat()
+}
+
+/// Creates a directory at `path`
+pub fn mkdir(path: &str) -> Result<(), ()> {
+    create(path, FileType::Directory).map(|_| ())
+}
+
+/// Removes the file or the empty directory at `path`
+pub fn unlink(path: &str) -> Result<(), ()> {
+    let (parent, name) = mount::resolve_parent(&MOUNT_TABLE, path)?;
+    let id = parent.inode.fs.lookup(parent.inode.id, &name)?;
+    if MOUNT_TABLE.lock().is_mountpoint(&parent.inode.with_id(id)) {
+        return Err(());
+    }
+    parent.inode.fs.unlink(parent.inode.id, &name)
+}
+
+/// Returns the canonical path of the directory at `path`
+pub fn canonical_directory(path: &str) -> Result<String, ()> {
+    let dentry = resolve(path)?;
+
+    if dentry.inode.stat()?.file_type != FileType::Directory {
+        return Err(());
+    }
+    Ok(dentry.path())
+}
+
+/// Joins a relative path to the working directory, absolute paths are returned as is.
+pub fn absolute_path(working_directory: &str, path: &str) -> String {
+    if path.starts_with('/') {
+        return String::from(path);
+    }
+
+    let mut absolute = String::from(working_directory);
+    if !absolute.ends_with('/') {
+        absolute.push('/');
+    }
+    absolute.push_str(path);
+    absolute
+}
+
+fn resolve(path: &str) -> Result<Arc<Dentry>, ()> {
+    mount::resolve(&MOUNT_TABLE, path)
+}
+
+fn create(path: &str, file_type: FileType) -> Result<Arc<Dentry>, ()> {
+    let (parent, name) = mount::resolve_parent(&MOUNT_TABLE, path)?;
+    let id = parent.inode.fs.create(parent.inode.id, &name, file_type)?;
+
+    Ok(Dentry::child(&parent, &name, parent.inode.with_id(id)))
+}
@@ -0,0 +1,103 @@
+//! The mount table and the path resolution algorithm
+//!
+//! The table is locked only to follow the mounts, the filesystems are looked up without it,
+//! so a slow filesystem (e.g. FAT32 on a disk) doesn't block the path resolutions of the other filesystems.
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+use spin::Mutex;
+
+use super::{
+    dentry::{Dentry, Inode},
+    FileSystem, MAX_NAME_LENGTH,
+};
+
+/// Saves where every filesystem is attached to the directory tree
+pub struct MountTable {
+    /// The root directory of the first mounted filesystem
+    root: Option<Inode>,
+    /// Pairs of (mount point, root directory of the mounted filesystem)
+    mounts: Vec<(Inode, Inode)>,
+}
+
+impl MountTable {
+    /// Creates a table without any mounted filesystem, every path resolution fails.
+    pub const fn empty() -> Self {
+        MountTable {
+            root: None,
+            mounts: Vec::new(),
+        }
+    }
+
+    /// Makes the root directory of `fs` the root of the tree, fails if a filesystem is already mounted
+    pub fn mount_root(&mut self, fs: Arc<dyn FileSystem>) -> Result<(), ()> {
+        if self.root.is_some() {
+            return Err(());
+        }
+        self.root = Some(Inode::new(fs.clone(), fs.root()));
+        Ok(())
+    }
+
+    /// Attaches the root directory of `fs` to the directory `mountpoint`.
+    ///
+    /// Mounting on top of a mount point hides the previously mounted filesystem.
+    pub fn mount(&mut self, mountpoint: Inode, fs: Arc<dyn FileSystem>) {
+        let fs_root = Inode::new(fs.clone(), fs.root());
+        self.mounts.push((mountpoint, fs_root));
+    }
+
+    /// Returns whether `inode` has a filesystem mounted on it
+    pub fn is_mountpoint(&self, inode: &Inode) -> bool {
+        self.mounts.iter().any(|(mountpoint, _)| mountpoint.is(inode))
+    }
+
+    /// Returns the dentry of the root directory
+    pub fn root(&self) -> Result<Arc<Dentry>, ()> {
+        let root = self.root.clone().ok_or(())?;
+        Ok(Dentry::root(self.follow_mounts(root)))
+    }
+
+    /// Replaces a mount point with the root directory of the filesystem that is mounted on it
+    fn follow_mounts(&self, mut inode: Inode) -> Inode {
+        while let Some((_, fs_root)) = self.mounts.iter().find(|(mountpoint, _)| mountpoint.is(&inode)) {
+            inode = fs_root.clone();
+        }
+        inode
+    }
+}
+
+/// Resolves an absolute path to it's dentry.
+///
+/// `.` stays in the current directory and `..` goes to the parent directory,
+/// the parent of the root directory is the root itself.
+pub fn resolve(table: &Mutex<MountTable>, path: &str) -> Result<Arc<Dentry>, ()> {
+    if !path.starts_with('/') {
+        return Err(());
+    }
+
+    let mut current = table.lock().root()?;
+    for component in path.split('/').filter(|component| !component.is_empty()) {
+        current = match component {
+            "." => current,
+            ".." => current.parent(),
+            name => {
+                let id = current.inode.fs.lookup(current.inode.id, name)?;
+                let inode = table.lock().follow_mounts(current.inode.with_id(id));
+                Dentry::child(&current, name, inode)
+            }
+        };
+    }
+
+    Ok(current)
+}
+
+/// Resolves the parent directory of an absolute path, and returns it with the path's last component.
+pub fn resolve_parent(table: &Mutex<MountTable>, path: &str) -> Result<(Arc<Dentry>, String), ()> {
+    let (parent, name) = path.trim_end_matches('/').rsplit_once('/').ok_or(())?;
+
+    if name.is_empty() || name == "." || name == ".." || name.len() > MAX_NAME_LENGTH {
+        return Err(());
+    }
+
+    let parent = resolve(table, if parent.is_empty() { "/" } else { parent })?;
+    Ok((parent, String::from(name)))
+}
@@ -0,0 +1,196 @@
+//! The local APIC, the interrupt controller of each processor, and it's timer
+
+use core::{
+    ptr,
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+};
+use log::info;
+use x86_64::{instructions::interrupts, structures::idt::InterruptStackFrame};
+
+use crate::{
+    drivers::pit,
+    hardware::rdmsr,
+    memory::map_device_memory,
+    processes::{self, objects::Registers},
+    time, wrmsr,
+};
+
+/// The timer vector, right after the PIC / IOAPIC IRQs
+pub const TIMER_VECTOR: u8 = 0x30;
+/// Sent to a processor when a process is queued to it's run queue
+pub const RESCHEDULE_VECTOR: u8 = 0x31;
+/// Sent to the other processors when a page translation changes
+pub const TLB_SHOOTDOWN_VECTOR: u8 = 0x32;
+/// Raised when an interrupt disappears before the processor acknowledged it, doesn't need an EOI
+pub const SPURIOUS_VECTOR: u8 = 0xFF;
+/// The timer interrupts per second
+pub const TIMER_FREQUENCY: u64 = 100;
+
+const APIC_BASE_MSR: u64 = 0x1B;
+const APIC_BASE_ENABLE: u64 = 1 << 11;
+/// Set only on the processor the firmware booted
+const APIC_BASE_BOOTSTRAP_PROCESSOR: u64 = 1 << 8;
+
+// registers offsets from the APIC base
+const ID_REGISTER: u64 = 0x20;
+const TASK_PRIORITY_REGISTER: u64 = 0x80;
+const EOI_REGISTER: u64 = 0xB0;
+const SPURIOUS_VECTOR_REGISTER: u64 = 0xF0;
+const INTERRUPT_COMMAND_LOW_REGISTER: u64 = 0x300;
+const INTERRUPT_COMMAND_HIGH_REGISTER: u64 = 0x310;
+const TIMER_LVT_REGISTER: u64 = 0x320;
+const TIMER_INITIAL_COUNT_REGISTER: u64 = 0x380;
+const TIMER_CURRENT_COUNT_REGISTER: u64 = 0x390;
+const TIMER_DIVIDE_REGISTER: u64 = 0x3E0;
+
+const SOFTWARE_ENABLE: u32 = 1 << 8;
+const LVT_MASKED: u32 = 1 << 16;
+const TIMER_PERIODIC: u32 = 1 << 17;
+const TIMER_DIVIDE_BY_16: u32 = 0b0011;
+const CALIBRATION_MICROSECONDS: u64 = 10_000;
+
+// interrupt command fields
+const DELIVERY_INIT: u32 = 0b101 << 8;
+const DELIVERY_STARTUP: u32 = 0b110 << 8;
+const DELIVERY_PENDING: u32 = 1 << 12;
+const LEVEL_ASSERT: u32 = 1 << 14;
+const DESTINATION_SHIFT: u32 = 24;
+const MICROSECONDS_PER_SECOND: u64 = 1_000_000;
+
+/// The linear address of the APIC registers, zero while the APIC is disabled
+static BASE: AtomicU64 = AtomicU64::new(0);
+/// The timer counts (after the divider) in a single timer period
+static TIMER_PERIOD_COUNTS: AtomicU32 = AtomicU32::new(0);
+static TIMER_TICKS: AtomicU64 = AtomicU64::new(0);
+
+fn read_register(register: u64) -> u32 {
+    unsafe { ptr::read_volatile((BASE.load(Ordering::Relaxed) + register) as *const u32) }
+}
+
+fn write_register(register: u64, value: u32) {
+    unsafe { ptr::write_volatile((BASE.load(Ordering::Relaxed) + register) as *mut u32, value) }
+}
+
+/// Enables the local APIC of the current processor, and starts it's timer.
+///
+/// # Arguments
+///  - `physical_address`, the APIC registers address from the MADT
+pub fn init(physical_address: u64) -> Result<(), ()> {
+    let apic_base = rdmsr(APIC_BASE_MSR);
+    wrmsr!(APIC_BASE_MSR, apic_base | APIC_BASE_ENABLE);
+    BASE.store(map_device_memory(physical_address)?, Ordering::Relaxed);
+
+    write_register(SPURIOUS_VECTOR_REGISTER, SOFTWARE_ENABLE | SPURIOUS_VECTOR as u32);
+    // accept every interrupt priority
+    write_register(TASK_PRIORITY_REGISTER, 0);
+
+    calibrate_timer();
+    start_timer();
+    info!("local APIC {} initialized", id());
+    Ok(())
+}
+
+/// Enables the local APIC of an application processor, and starts it's timer.
+///
+/// The registers are at the same address on every processor, and the timer was calibrated by `init`.
+pub fn init_application_processor() {
+    let apic_base = rdmsr(APIC_BASE_MSR);
+    wrmsr!(APIC_BASE_MSR, apic_base | APIC_BASE_ENABLE);
+
+    write_register(SPURIOUS_VECTOR_REGISTER, SOFTWARE_ENABLE | SPURIOUS_VECTOR as u32);
+    write_register(TASK_PRIORITY_REGISTER, 0);
+    start_timer();
+}
+
+/// Measures the timer counts in a single period against the PIT
+fn calibrate_timer() {
+    write_register(TIMER_DIVIDE_REGISTER, TIMER_DIVIDE_BY_16);
+    write_register(TIMER_LVT_REGISTER, LVT_MASKED);
+    write_register(TIMER_INITIAL_COUNT_REGISTER, u32::MAX);
+
+    pit::wait_microseconds(CALIBRATION_MICROSECONDS);
+    let elapsed = u32::MAX - read_register(TIMER_CURRENT_COUNT_REGISTER);
+    write_register(TIMER_INITIAL_COUNT_REGISTER, 0);
+
+    let counts_per_second = elapsed as u64 * (MICROSECONDS_PER_SECOND / CALIBRATION_MICROSECONDS);
+    TIMER_PERIOD_COUNTS.store((counts_per_second / TIMER_FREQUENCY) as u32, Ordering::Relaxed);
+    info!("local APIC timer runs at {} Hz", counts_per_second);
+}
+
+/// Starts the periodic timer, the timer must be calibrated first
+fn start_timer() {
+    write_register(TIMER_DIVIDE_REGISTER, TIMER_DIVIDE_BY_16);
+    write_register(TIMER_LVT_REGISTER, TIMER_PERIODIC | TIMER_VECTOR as u32);
+    write_register(TIMER_INITIAL_COUNT_REGISTER, TIMER_PERIOD_COUNTS.load(Ordering::Relaxed));
+}
+
+/// Whether the local APIC was initialized
+pub fn is_enabled() -> bool {
+    BASE.load(Ordering::Relaxed) != 0
+}
+
+/// Returns the APIC id of the current processor
+pub fn id() -> u8 {
+    (read_register(ID_REGISTER) >> 24) as u8
+}
+
+/// Whether the current processor is the bootstrap processor
+pub fn is_bootstrap_processor() -> bool {
+    rdmsr(APIC_BASE_MSR) & APIC_BASE_BOOTSTRAP_PROCESSOR != 0
+}
+
+/// Notifies the local APIC that the current interrupt was handled
+pub fn end_of_interrupt() {
+    write_register(EOI_REGISTER, 0);
+}
+
+/// Writes the interrupt command register, and waits until the command was accepted
+fn send_command(apic_id: u8, command: u32) {
+    interrupts::without_interrupts(|| {
+        // the command is sent when the low register is written
+        write_register(INTERRUPT_COMMAND_HIGH_REGISTER, (apic_id as u32) << DESTINATION_SHIFT);
+        write_register(INTERRUPT_COMMAND_LOW_REGISTER, command);
+        while read_register(INTERRUPT_COMMAND_LOW_REGISTER) & DELIVERY_PENDING != 0 {
+            core::hint::spin_loop();
+        }
+    });
+}
+
+/// Sends an inter-processor interrupt with `vector` to the processor with `apic_id`
+pub fn send_ipi(apic_id: u8, vector: u8) {
+    send_command(apic_id, vector as u32);
+}
+
+/// Resets the processor with `apic_id`, it waits for a startup IPI
+pub fn send_init(apic_id: u8) {
+    send_command(apic_id, DELIVERY_INIT | LEVEL_ASSERT);
+}
+
+/// Starts the processor with `apic_id` in real mode at `page * 0x1000`, the page must be below 1MiB
+pub fn send_startup(apic_id: u8, page: u8) {
+    send_command(apic_id, DELIVERY_STARTUP | page as u32);
+}
+
+/// Returns the timer interrupts of the bootstrap processor since the timer started,
+/// a tick is `1 / TIMER_FREQUENCY` seconds
+pub fn timer_ticks() -> u64 {
+    TIMER_TICKS.load(Ordering::Relaxed)
+}
+
+crate::wrap_interrupt_handler!(timer_handler => timer_interrupt);
+
+/// Counts the tick, wakes the processes whose sleep ended, charges the tick to the current process,
+/// delivers signals and gives the next process a time slice
+extern "sysv64" fn timer_handler(stack_frame: &InterruptStackFrame, registers: &mut Registers) {
+    if is_bootstrap_processor() {
+        TIMER_TICKS.fetch_add(1, Ordering::Relaxed);
+        time::tick();
+    }
+    // the handler may not return
+    end_of_interrupt();
+    processes::account_tick();
+    processes::handle_signals(stack_frame, registers);
+    processes::preempt(stack_frame, registers);
+}
+
+pub extern "x86-interrupt" fn spurious_interrupt(_stack_frame: InterruptStackFrame) {}
@@ -1,13 +1,22 @@
 //! This module constructs a Global Descriptor Table, and a Task State Segment
+//!
+//! Every processor has it's own TSS (the interrupt stacks can't be shared), so every processor has it's own GDT.
+//! The bootstrap processor uses the static tables, the application processors tables are allocated when they start.
 
+use alloc::boxed::Box;
 use lazy_static::lazy_static;
 use log::{debug, info};
 use x86_64::instructions::tables::load_tss;
-use x86_64::registers::segmentation::{Segment, CS, DS};
+use x86_64::registers::segmentation::{Segment, CS, DS, SS};
 use x86_64::structures::gdt::{Descriptor, GlobalDescriptorTable, SegmentSelector};
 use x86_64::structures::tss::TaskStateSegment;
 use x86_64::VirtAddr;
 
+use crate::{
+    memory::{get_linear_addr, kmalloc},
+    smp,
+};
+
 pub const DOUBLE_FAULT_IST_INDEX: usize = 0;
 pub const GENERAL_PROTECTION_FAULT_IST_INDEX: usize = 1;
 pub const PAGE_FAULT_IST_INDEX: usize = 2;
@@ -48,19 +57,40 @@ lazy_static! {
     /// # Global Descriptor Table
     ///
     /// A Table with pointers to all the segments selectors
-    pub static ref GDT: (GlobalDescriptorTable, Selectors) = {
-        let mut gdt = GlobalDescriptorTable::new();
-
-        // will save the reserved stacks and the privileged stacks
-        let tss = gdt.add_entry(Descriptor::tss_segment(&TSS));
-        // save all of these segments to switch between kernel mode and user mode segments
-        let kernel_code = gdt.add_entry(Descriptor::kernel_code_segment());
-        let kernel_data = gdt.add_entry(Descriptor::kernel_data_segment());
-        let user_data = gdt.add_entry(Descriptor::user_data_segment());
-        let user_code = gdt.add_entry(Descriptor::user_code_segment());
-
-        (gdt, Selectors { tss, kernel_code, kernel_data, user_code, user_data })
-     };
+    pub static ref GDT: (GlobalDescriptorTable, Selectors) = create_gdt(&TSS);
+}
+
+/// Creates a GDT with the kernel & userland segments, and the given TSS.
+///
+/// The selectors are the same in every GDT, only the TSS descriptor changes.
+fn create_gdt(tss: &'static TaskStateSegment) -> (GlobalDescriptorTable, Selectors) {
+    let mut gdt = GlobalDescriptorTable::new();
+
+    // will save the reserved stacks and the privileged stacks
+    let tss = gdt.add_entry(Descriptor::tss_segment(tss));
+    // save all of these segments to switch between kernel mode and user mode segments
+    let kernel_code = gdt.add_entry(Descriptor::kernel_code_segment());
+    let kernel_data = gdt.add_entry(Descriptor::kernel_data_segment());
+    let user_data = gdt.add_entry(Descriptor::user_data_segment());
+    let user_code = gdt.add_entry(Descriptor::user_code_segment());
+
+    (gdt, Selectors { tss, kernel_code, kernel_data, user_code, user_data })
+}
+
+/// Creates a TSS for an application processor, it's stacks are allocated from the kernel physical memory
+fn create_tss() -> Result<TaskStateSegment, ()> {
+    let mut tss = TaskStateSegment::new();
+    for index in [DOUBLE_FAULT_IST_INDEX, GENERAL_PROTECTION_FAULT_IST_INDEX, PAGE_FAULT_IST_INDEX] {
+        tss.interrupt_stack_table[index] = allocate_stack()?;
+    }
+    tss.privilege_stack_table[KERNEL_STACK_INDEX] = allocate_stack()?;
+    Ok(tss)
+}
+
+/// Returns the top of a new stack
+fn allocate_stack() -> Result<VirtAddr, ()> {
+    let stack = kmalloc(STACK_SIZE, PAGE_SIZE)?;
+    Ok(VirtAddr::new(get_linear_addr(stack)) + STACK_SIZE)
 }
 
 // `GlobalDescriptorTable` table's is private, that is why we use the Selectors struct
@@ -72,18 +102,32 @@ pub struct Selectors {
     pub user_data: SegmentSelector,
 }
 
+/// Loads the bootstrap processor GDT & TSS, and it's per-CPU data
 pub fn init() {
-    GDT.0.load();
     debug!("GDT Structure: ");
     debug!("kernel cs: {:#x}", GDT.1.kernel_code.0);
     debug!("kernel ds: {:#x}", GDT.1.kernel_data.0);
     debug!("user ds: {:#x}", GDT.1.user_data.0);
     debug!("user cs: {:#x}", GDT.1.user_code.0);
 
+    load(&GDT);
+    smp::BOOTSTRAP_PROCESSOR.load();
+    info!("GDT initialized");
+}
+
+/// Creates and loads a GDT & TSS for the current application processor, must be called after the memory initialization.
+pub fn init_application_processor() -> Result<(), ()> {
+    let tss = Box::leak(Box::new(create_tss()?));
+    load(Box::leak(Box::new(create_gdt(tss))));
+    Ok(())
+}
+
+fn load(gdt: &'static (GlobalDescriptorTable, Selectors)) {
+    gdt.0.load();
     unsafe {
-        CS::set_reg(GDT.1.kernel_code);
-        DS::set_reg(GDT.1.kernel_data);
-        load_tss(GDT.1.tss);
+        CS::set_reg(gdt.1.kernel_code);
+        DS::set_reg(gdt.1.kernel_data);
+        SS::set_reg(gdt.1.kernel_data);
+        load_tss(gdt.1.tss);
     }
-    info!("GDT initialized");
 }
@@ -4,11 +4,19 @@ use lazy_static::lazy_static;
 use log::info;
 use x86_64::structures::idt::InterruptDescriptorTable;
 
-use crate::syscalls::wrapped_syscall_handler;
+use crate::{
+    smp::ipi::{reschedule_interrupt, tlb_shootdown_interrupt},
+    syscalls::wrapped_syscall_handler,
+};
 
 use super::gdt::{
     DOUBLE_FAULT_IST_INDEX, GENERAL_PROTECTION_FAULT_IST_INDEX, PAGE_FAULT_IST_INDEX,
 };
+use super::apic::{
+    spurious_interrupt, timer_interrupt, RESCHEDULE_VECTOR, SPURIOUS_VECTOR, TIMER_VECTOR, TLB_SHOOTDOWN_VECTOR,
+};
+use super::irq::IRQ_STUBS;
+use super::pic::PIC_1_OFFSET;
 use super::service_routines::{double_fault, general_protection_fault, page_fault};
 
 lazy_static! {
@@ -27,6 +35,13 @@ lazy_static! {
             idt.page_fault
                 .set_handler_fn(page_fault)
                 .set_stack_index(PAGE_FAULT_IST_INDEX as u16);
+            for (irq, stub) in IRQ_STUBS.iter().enumerate() {
+                idt[PIC_1_OFFSET as usize + irq].set_handler_fn(*stub);
+            }
+            idt[TIMER_VECTOR as usize].set_handler_fn(core::mem::transmute(timer_interrupt as *mut fn()));
+            idt[RESCHEDULE_VECTOR as usize].set_handler_fn(core::mem::transmute(reschedule_interrupt as *mut fn()));
+            idt[TLB_SHOOTDOWN_VECTOR as usize].set_handler_fn(tlb_shootdown_interrupt);
+            idt[SPURIOUS_VECTOR as usize].set_handler_fn(spurious_interrupt);
             idt[0x80]
                 .set_handler_fn(core::mem::transmute(wrapped_syscall_handler as *mut fn()))
                 .set_privilege_level(x86_64::PrivilegeLevel::Ring3);
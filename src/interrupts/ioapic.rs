@@ -0,0 +1,131 @@
+//! The IOAPICs, route the device interrupts (Global System Interrupts) to the local APICs
+
+use alloc::vec::Vec;
+use core::ptr;
+use lazy_static::lazy_static;
+use log::info;
+use spin::Mutex;
+
+use crate::{
+    acpi::{madt::InterruptOverride, Madt},
+    memory::map_device_memory,
+};
+
+use super::{apic, pic::PIC_1_OFFSET};
+
+/// Selects the register `WINDOW` accesses
+const REGISTER_SELECT: u64 = 0x00;
+const WINDOW: u64 = 0x10;
+
+const VERSION_REGISTER: u32 = 0x01;
+/// Every redirection entry is two 32 bit registers starting here
+const REDIRECTION_TABLE_REGISTER: u32 = 0x10;
+
+const ENTRY_ACTIVE_LOW: u32 = 1 << 13;
+const ENTRY_LEVEL_TRIGGERED: u32 = 1 << 15;
+const ENTRY_MASKED: u32 = 1 << 16;
+const DESTINATION_SHIFT: u32 = 24;
+
+lazy_static! {
+    static ref ROUTING: Mutex<Option<Routing>> = Mutex::new(None);
+}
+
+struct IoApic {
+    /// The linear address of the registers
+    base: u64,
+    gsi_base: u32,
+    entries: u32,
+}
+
+impl IoApic {
+    fn read_register(&self, register: u32) -> u32 {
+        unsafe {
+            ptr::write_volatile((self.base + REGISTER_SELECT) as *mut u32, register);
+            ptr::read_volatile((self.base + WINDOW) as *const u32)
+        }
+    }
+
+    fn write_register(&self, register: u32, value: u32) {
+        unsafe {
+            ptr::write_volatile((self.base + REGISTER_SELECT) as *mut u32, register);
+            ptr::write_volatile((self.base + WINDOW) as *mut u32, value);
+        }
+    }
+
+    fn write_entry(&self, index: u32, low: u32, high: u32) {
+        let register = REDIRECTION_TABLE_REGISTER + index * 2;
+        // the entry is masked while it is updated
+        self.write_register(register, ENTRY_MASKED);
+        self.write_register(register + 1, high);
+        self.write_register(register, low);
+    }
+
+    fn handles(&self, gsi: u32) -> bool {
+        self.gsi_base <= gsi && gsi < self.gsi_base + self.entries
+    }
+}
+
+/// The IOAPICs and the ISA IRQs overrides
+struct Routing {
+    io_apics: Vec<IoApic>,
+    overrides: Vec<InterruptOverride>,
+    /// The APIC id the interrupts are delivered to
+    destination: u8,
+}
+
+/// Maps the IOAPICs of the MADT and masks all of their inputs
+pub fn init(madt: &Madt) -> Result<(), ()> {
+    let mut io_apics = Vec::new();
+    for io_apic in madt.io_apics.iter() {
+        let mut mapped = IoApic {
+            base: map_device_memory(io_apic.address)?,
+            gsi_base: io_apic.gsi_base,
+            entries: 0,
+        };
+        // the maximum redirection entry index is saved in bits 16-23
+        mapped.entries = ((mapped.read_register(VERSION_REGISTER) >> 16) & 0xFF) + 1;
+
+        for index in 0..mapped.entries {
+            mapped.write_entry(index, ENTRY_MASKED, 0);
+        }
+        info!("IOAPIC {} handles GSIs {}-{}", io_apic.id, mapped.gsi_base, mapped.gsi_base + mapped.entries - 1);
+        io_apics.push(mapped);
+    }
+
+    *ROUTING.lock() = Some(Routing {
+        io_apics,
+        overrides: madt.overrides.clone(),
+        destination: apic::id(),
+    });
+    Ok(())
+}
+
+/// Routes an ISA IRQ to it's vector (the same vector the PIC uses) and unmasks it
+pub fn unmask(irq: u8) -> Result<(), ()> {
+    let routing = ROUTING.lock();
+    let routing = routing.as_ref().ok_or(())?;
+
+    // ISA IRQs without an override are connected to the same GSI, active high & edge triggered
+    let route = routing
+        .overrides
+        .iter()
+        .find(|interrupt_override| interrupt_override.irq == irq)
+        .copied()
+        .unwrap_or(InterruptOverride {
+            irq,
+            gsi: irq as u32,
+            active_low: false,
+            level_triggered: false,
+        });
+    let io_apic = routing.io_apics.iter().find(|io_apic| io_apic.handles(route.gsi)).ok_or(())?;
+
+    let mut low = (PIC_1_OFFSET + irq) as u32;
+    if route.active_low {
+        low |= ENTRY_ACTIVE_LOW;
+    }
+    if route.level_triggered {
+        low |= ENTRY_LEVEL_TRIGGERED;
+    }
+    io_apic.write_entry(route.gsi - io_apic.gsi_base, low, (routing.destination as u32) << DESTINATION_SHIFT);
+    Ok(())
+}
@@ -0,0 +1,121 @@
+//! Dispatches the hardware interrupts (IRQs) of the PICs to the handlers drivers register.
+//!
+//! Every IRQ vector points to a stub that calls `dispatch`, which counts the IRQ,
+//! runs it's handler and sends the end of interrupt, so drivers never talk to the interrupt controllers directly.
+//! The IRQs keep their vectors when the IOAPIC replaces the PICs.
+
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use spin::RwLock;
+use x86_64::{
+    instructions::interrupts,
+    structures::idt::{HandlerFunc, InterruptStackFrame},
+};
+
+use super::{
+    apic, ioapic,
+    pic::{self, CASCADE_IRQ},
+};
+
+pub const IRQ_COUNT: usize = 16;
+
+/// A driver's IRQ handler, runs with interrupts disabled and must not wait for locks held outside of it
+pub type IrqHandler = fn();
+
+static HANDLERS: RwLock<[Option<IrqHandler>; IRQ_COUNT]> = RwLock::new([None; IRQ_COUNT]);
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO_COUNTER: AtomicU64 = AtomicU64::new(0);
+static IRQ_COUNTERS: [AtomicU64; IRQ_COUNT] = [ZERO_COUNTER; IRQ_COUNT];
+static SPURIOUS_COUNTER: AtomicU64 = AtomicU64::new(0);
+/// Whether the IRQs are routed through the IOAPIC instead of the PICs
+static USE_APIC: AtomicBool = AtomicBool::new(false);
+
+macro_rules! irq_stubs {
+    ($($irq:literal => $stub:ident),*) => {
+        $(
+            extern "x86-interrupt" fn $stub(_stack_frame: InterruptStackFrame) {
+                dispatch($irq);
+            }
+        )*
+
+        /// The IDT handlers of IRQ 0-15, in order
+        pub const IRQ_STUBS: [HandlerFunc; IRQ_COUNT] = [$($stub),*];
+    };
+}
+
+irq_stubs!(
+    0 => irq_0, 1 => irq_1, 2 => irq_2, 3 => irq_3, 4 => irq_4, 5 => irq_5, 6 => irq_6, 7 => irq_7,
+    8 => irq_8, 9 => irq_9, 10 => irq_10, 11 => irq_11, 12 => irq_12, 13 => irq_13, 14 => irq_14, 15 => irq_15
+);
+
+/// Counts the IRQ, runs it's handler and signals the end of interrupt.
+fn dispatch(irq: u8) {
+    let use_apic = USE_APIC.load(Ordering::Relaxed);
+    if !use_apic && pic::is_spurious(irq) {
+        SPURIOUS_COUNTER.fetch_add(1, Ordering::Relaxed);
+        // the master doesn't know that the slave's IRQ was spurious
+        if irq >= 8 {
+            pic::end_of_interrupt(CASCADE_IRQ);
+        }
+        return;
+    }
+
+    IRQ_COUNTERS[irq as usize].fetch_add(1, Ordering::Relaxed);
+    // copied so the handler can't deadlock with the table, unhandled IRQs are only counted
+    let handler = HANDLERS.read()[irq as usize];
+    if let Some(handler) = handler {
+        handler();
+    }
+
+    if use_apic {
+        apic::end_of_interrupt();
+    } else {
+        pic::end_of_interrupt(irq);
+    }
+}
+
+/// Routes the IRQs through the IOAPIC, the PICs must be disabled first
+pub fn use_apic() {
+    USE_APIC.store(true, Ordering::Relaxed);
+}
+
+/// Sets the handler of `irq` and unmasks it.
+///
+/// # Arguments
+///  - `irq`, the PIC line (0-15), IRQ 2 is the slave cascade and can't be registered
+///  - `handler`, called for every `irq` the PICs raise
+///
+/// Fails if `irq` is invalid or already has a handler.
+pub fn register_irq(irq: u8, handler: IrqHandler) -> Result<(), ()> {
+    if irq as usize >= IRQ_COUNT || irq == CASCADE_IRQ {
+        return Err(());
+    }
+
+    // an IRQ that arrives while the table is locked would spin forever
+    interrupts::without_interrupts(|| {
+        let mut handlers = HANDLERS.write();
+        if handlers[irq as usize].is_some() {
+            return Err(());
+        }
+        handlers[irq as usize] = Some(handler);
+        Ok(())
+    })?;
+
+    if USE_APIC.load(Ordering::Relaxed) {
+        ioapic::unmask(irq)
+    } else {
+        pic::unmask(irq);
+        Ok(())
+    }
+}
+
+/// Returns how many times `irq` was raised, spurious IRQs are not counted
+pub fn irq_count(irq: u8) -> u64 {
+    IRQ_COUNTERS
+        .get(irq as usize)
+        .map_or(0, |counter| counter.load(Ordering::Relaxed))
+}
+
+/// Returns how many spurious PIC IRQs (IRQ 7 or 15 without an interrupt in service) were raised
+pub fn spurious_irq_count() -> u64 {
+    SPURIOUS_COUNTER.load(Ordering::Relaxed)
+}
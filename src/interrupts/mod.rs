@@ -1,11 +1,92 @@
 //! The interrupt module goal is to manage a minimal interrupt functionalities
 
+use log::info;
+
+use crate::acpi;
+
 use self::gdt::GDT;
 
+pub mod apic;
 pub mod gdt;
 pub mod idt;
+pub mod ioapic;
+pub mod irq;
+pub mod pic;
 mod service_routines;
 
+pub use self::irq::{irq_count, register_irq, spurious_irq_count};
+
+/// Wraps `$fn: extern "sysv64" fn(&InterruptStackFrame, &mut Registers)` with an interrupt handler
+/// that saves the interrupted registers, so `$fn` can read & update them or save them to switch processes.
+#[macro_export]
+macro_rules! wrap_interrupt_handler {
+    ($fn:ident => $wrapper:ident) => {
+        #[naked]
+        pub unsafe extern "sysv64" fn $wrapper() {
+            core::arch::asm!(
+                "push r15",
+                "push r14",
+                "push r13",
+                "push r12",
+                "push r11",
+                "push r10",
+                "push r9",
+                "push r8",
+                "push rbp",
+                "push rdi",
+                "push rsi",
+                "push rdx",
+                "push rcx",
+                "push rbx",
+                "push rax",
+                "lea rdi, [rsp + 15 * 8]", // Arg #1: stack frame
+                "mov rsi, rsp", // Arg #2: register list
+                "call {}",
+                "pop rax",
+                "pop rbx",
+                "pop rcx",
+                "pop rdx",
+                "pop rsi",
+                "pop rdi",
+                "pop rbp",
+                "pop r8",
+                "pop r9",
+                "pop r10",
+                "pop r11",
+                "pop r12",
+                "pop r13",
+                "pop r14",
+                "pop r15",
+                "iretq",
+                sym $fn,
+                options(noreturn)
+            );
+        }
+    };
+}
+
+/// Remaps the PICs, and replaces them with the local APIC & IOAPIC when the ACPI MADT describes them.
+///
+/// Must be called after the memory initialization, before registering IRQs.
+pub fn init_controllers() {
+    pic::init();
+
+    let madt = match acpi::madt() {
+        Ok(madt) => madt,
+        Err(()) => {
+            info!("no MADT found, using the PICs");
+            return;
+        }
+    };
+    info!("MADT: {} processors, {} IOAPICs", madt.processors.len(), madt.io_apics.len());
+
+    pic::disable();
+    apic::init(madt.local_apic_address).unwrap();
+    ioapic::init(&madt).unwrap();
+    irq::use_apic();
+    info!("IRQs are routed through the IOAPIC");
+}
+
 /// Returns the userland code and data selectors
 pub fn get_user_selectors() -> (u16, u16) {
     (GDT.1.user_code.0, GDT.1.user_data.0)
@@ -0,0 +1,89 @@
+//! The legacy 8259 Programmable Interrupt Controllers (a master and a slave chained on IRQ 2)
+
+use x86_64::instructions::port::Port;
+
+/// The first vector of the master PIC, the vectors below 32 are reserved for exceptions
+pub const PIC_1_OFFSET: u8 = 32;
+/// The first vector of the slave PIC
+pub const PIC_2_OFFSET: u8 = PIC_1_OFFSET + 8;
+
+const PIC_1_COMMAND: u16 = 0x20;
+const PIC_1_DATA: u16 = 0x21;
+const PIC_2_COMMAND: u16 = 0xA0;
+const PIC_2_DATA: u16 = 0xA1;
+
+/// Starts the initialization sequence, the next 3 data writes are the ICW2-4
+const ICW1_INIT: u8 = 0x11;
+const ICW4_8086_MODE: u8 = 0x01;
+/// The slave is connected to the master's IRQ 2
+pub const CASCADE_IRQ: u8 = 2;
+const END_OF_INTERRUPT: u8 = 0x20;
+/// OCW3, the next command port read returns the In-Service Register
+const READ_IN_SERVICE: u8 = 0x0B;
+/// The lowest priority IRQ of each PIC, raised when an IRQ disappears before it was acknowledged
+const SPURIOUS_IRQS: [u8; 2] = [7, 15];
+
+fn command(port: u16, value: u8) {
+    unsafe { Port::<u8>::new(port).write(value) };
+    io_wait();
+}
+
+/// Gives the PIC time to handle the previous command, by writing to an unused port
+fn io_wait() {
+    unsafe { Port::<u8>::new(0x80).write(0) };
+}
+
+/// Remaps the PICs to the vectors 32-47 and masks every IRQ
+pub fn init() {
+    command(PIC_1_COMMAND, ICW1_INIT);
+    command(PIC_2_COMMAND, ICW1_INIT);
+    command(PIC_1_DATA, PIC_1_OFFSET);
+    command(PIC_2_DATA, PIC_2_OFFSET);
+    command(PIC_1_DATA, 1 << CASCADE_IRQ);
+    command(PIC_2_DATA, CASCADE_IRQ);
+    command(PIC_1_DATA, ICW4_8086_MODE);
+    command(PIC_2_DATA, ICW4_8086_MODE);
+
+    // only the cascade is enabled, drivers unmask their own IRQs
+    command(PIC_1_DATA, !(1 << CASCADE_IRQ));
+    command(PIC_2_DATA, 0xFF);
+}
+
+/// Masks every IRQ, used when the APICs replace the PICs
+pub fn disable() {
+    command(PIC_1_DATA, 0xFF);
+    command(PIC_2_DATA, 0xFF);
+}
+
+/// Allows the PIC to raise `irq` (0-15)
+pub fn unmask(irq: u8) {
+    let port = if irq < 8 { PIC_1_DATA } else { PIC_2_DATA };
+    let mut data = Port::<u8>::new(port);
+    unsafe {
+        let mask = data.read();
+        data.write(mask & !(1 << (irq % 8)));
+    }
+}
+
+/// Returns whether `irq` is a spurious IRQ, which must not be acknowledged by an end of interrupt
+pub fn is_spurious(irq: u8) -> bool {
+    if !SPURIOUS_IRQS.contains(&irq) {
+        return false;
+    }
+
+    let port = if irq < 8 { PIC_1_COMMAND } else { PIC_2_COMMAND };
+    let mut command = Port::<u8>::new(port);
+    let in_service = unsafe {
+        command.write(READ_IN_SERVICE);
+        command.read()
+    };
+    in_service & (1 << (irq % 8)) == 0
+}
+
+/// Notifies the PICs that `irq` was handled, must be sent before the next `irq` is raised.
+pub fn end_of_interrupt(irq: u8) {
+    if irq >= 8 {
+        unsafe { Port::<u8>::new(PIC_2_COMMAND).write(END_OF_INTERRUPT) };
+    }
+    unsafe { Port::<u8>::new(PIC_1_COMMAND).write(END_OF_INTERRUPT) };
+}
@@ -2,7 +2,7 @@
 
 use core::arch::asm;
 
-use crate::log::debug;
+use crate::{log::debug, processes};
 use x86_64::structures::idt::{InterruptStackFrame, PageFaultErrorCode};
 
 /// A page fault occures when:
@@ -12,6 +12,8 @@ use x86_64::structures::idt::{InterruptStackFrame, PageFaultErrorCode};
 /// 3. Protection checks
 /// 4. If a reserved bit is set to 1.
 /// The address pushed to the stack points to the faulty instruction.
+///
+/// A fault in userland terminates the faulting process (SIGSEGV).
 pub extern "x86-interrupt" fn page_fault(
     stack_frame: InterruptStackFrame,
     error_code: PageFaultErrorCode,
@@ -22,6 +24,9 @@ pub extern "x86-interrupt" fn page_fault(
     debug!("Error code: {:#X?}", error_code);
     debug!("Stack frame: {:#X?}", stack_frame);
     debug!("Linear address: {:#X}", linear_addres);
+    if stack_frame.code_segment & 0b11 == 3 {
+        processes::terminate_faulted_process();
+    }
     panic!();
 }
 /// A double fault (#DF) exception can occur
@@ -41,6 +46,8 @@ pub extern "x86-interrupt" fn double_fault(stack_frame: InterruptStackFrame, err
 /// 4. Loading a non-canonical base address into the GDTR or IDTR.
 /// 5. Using WRMSR to write a read-only MSR.
 /// 6. Any long-mode consistency-check violation.
+///
+/// A fault in userland terminates the faulting process (SIGSEGV).
 pub extern "x86-interrupt" fn general_protection_fault(
     stack_frame: InterruptStackFrame,
     error_code: u64,
@@ -48,5 +55,8 @@ pub extern "x86-interrupt" fn general_protection_fault(
     debug!("EXCEPTION: general protection fault");
     debug!("Error code: {:#X?}", error_code);
     debug!("Stack frame: {:#X?}", stack_frame);
+    if stack_frame.code_segment & 0b11 == 3 {
+        processes::terminate_faulted_process();
+    }
     panic!();
 }
@@ -0,0 +1,235 @@
+//! Channels, a pair of connected ends that pass bounded messages both ways.
+//!
+//! A message is up to `MAX_MESSAGE_SIZE` bytes and up to `MAX_MESSAGE_FILES` files (e.g. the end of another channel),
+//! the receiver gets new file descriptors of the sent files. An end is a file, so it's handle is a file descriptor.
+//! Reading & writing an end receives & sends messages without files.
+//!
+//! Receiving from an empty end waits for a message, and fails once the other end is closed and it's messages were received.
+//! Sending to a full end waits for the receiver, and fails once the other end is closed.
+//! A process that waits sleeps on the end's wait queues (see `sync`), and `select` waits on several ends at once.
+
+use alloc::{collections::VecDeque, sync::Arc, vec::Vec};
+use core::mem;
+
+use crate::{
+    fs::File,
+    sync::{relax, SpinLock, WaitQueue, Waiter},
+};
+
+pub use abi::ipc::{MessageBuffers, MAX_MESSAGE_FILES, MAX_MESSAGE_SIZE};
+
+/// The amount of messages an end holds before senders wait
+pub const CHANNEL_CAPACITY: usize = 32;
+
+pub struct Message {
+    pub data: Vec<u8>,
+    pub files: Vec<Arc<dyn File>>,
+}
+
+/// The messages sent to an end
+struct MailboxState {
+    messages: VecDeque<Message>,
+    sender_open: bool,
+    receiver_open: bool,
+}
+
+impl MailboxState {
+    /// Whether a receive doesn't wait
+    fn is_readable(&self) -> bool {
+        !self.messages.is_empty() || !self.sender_open
+    }
+}
+
+struct Mailbox {
+    state: SpinLock<MailboxState>,
+    /// The receivers that wait for messages
+    readable: WaitQueue,
+    /// The senders that wait for room
+    writable: WaitQueue,
+}
+
+impl Mailbox {
+    fn new() -> Arc<Self> {
+        Arc::new(Mailbox {
+            state: SpinLock::new(MailboxState {
+                messages: VecDeque::new(),
+                sender_open: true,
+                receiver_open: true,
+            }),
+            readable: WaitQueue::new(),
+            writable: WaitQueue::new(),
+        })
+    }
+}
+
+/// An end of a channel, it receives the messages of it's mailbox and sends messages to the other end's mailbox
+pub struct ChannelEnd {
+    incoming: Arc<Mailbox>,
+    outgoing: Arc<Mailbox>,
+}
+
+/// Creates a channel, returns it's two ends
+pub fn channel() -> (Arc<dyn File>, Arc<dyn File>) {
+    let (first, second) = (Mailbox::new(), Mailbox::new());
+    (
+        Arc::new(ChannelEnd {
+            incoming: first.clone(),
+            outgoing: second.clone(),
+        }),
+        Arc::new(ChannelEnd {
+            incoming: second,
+            outgoing: first,
+        }),
+    )
+}
+
+impl ChannelEnd {
+    /// Sends a message to the other end, waits until it has room if `blocking`.
+    /// Returns whether the message was sent, a send that doesn't block isn't sent to a full end.
+    ///
+    /// Fails if the message is too large, if it passes the other end (which would never be released) or if the other end is closed.
+    /// A blocking send also fails if the current process sleeps until the other end has room, the caller must fail too
+    /// so it's syscall returns `status::BLOCKED` (see `WaitQueue::register`).
+    pub fn send(&self, message: Message, blocking: bool) -> Result<bool, ()> {
+        if message.data.len() > MAX_MESSAGE_SIZE || message.files.len() > MAX_MESSAGE_FILES {
+            return Err(());
+        }
+        let passes_receiver = message.files.iter().any(|file| {
+            matches!(file.as_channel(), Some(end) if Arc::ptr_eq(&end.incoming, &self.outgoing))
+        });
+        if passes_receiver {
+            return Err(());
+        }
+
+        loop {
+            {
+                let mut state = self.outgoing.state.lock();
+                if !state.receiver_open {
+                    return Err(());
+                }
+                if state.messages.len() < CHANNEL_CAPACITY {
+                    state.messages.push_back(message);
+                    drop(state);
+                    self.outgoing.readable.wake_all();
+                    return Ok(true);
+                }
+                if !blocking {
+                    return Ok(false);
+                }
+                // registered while the mailbox is locked, so the next receive wakes the sender
+                if self.outgoing.writable.register().is_ok() {
+                    return Err(());
+                }
+            }
+            relax();
+        }
+    }
+
+    /// Receives the next message, waits until there is one if `blocking`.
+    /// Returns `None` if there is no message and the receive doesn't block.
+    ///
+    /// Fails if the message is larger than `max_length` bytes or passes more than `max_files` files (it isn't received),
+    /// or if the other end is closed and there are no messages. A blocking receive also fails if the current process sleeps
+    /// until a message is sent, the caller must fail too so it's syscall returns `status::BLOCKED` (see `WaitQueue::register`).
+    pub fn receive(&self, max_length: usize, max_files: usize, blocking: bool) -> Result<Option<Message>, ()> {
+        loop {
+            {
+                let mut state = self.incoming.state.lock();
+                if let Some(message) = state.messages.front() {
+                    if message.data.len() > max_length || message.files.len() > max_files {
+                        return Err(());
+                    }
+                    let message = state.messages.pop_front();
+                    drop(state);
+                    self.incoming.writable.wake_all();
+                    return Ok(message);
+                }
+                if !state.sender_open {
+                    return Err(());
+                }
+                if !blocking {
+                    return Ok(None);
+                }
+                if self.incoming.readable.register().is_ok() {
+                    return Err(());
+                }
+            }
+            relax();
+        }
+    }
+}
+
+impl File for ChannelEnd {
+    /// Receives a message without files, returns it's length
+    fn read(&self, buffer: &mut [u8]) -> Result<usize, ()> {
+        let message = self.receive(buffer.len(), 0, true)?.ok_or(())?;
+        buffer[..message.data.len()].copy_from_slice(&message.data);
+        Ok(message.data.len())
+    }
+
+    /// Sends the buffer as a message without files
+    fn write(&self, buffer: &[u8]) -> Result<usize, ()> {
+        let message = Message {
+            data: buffer.to_vec(),
+            files: Vec::new(),
+        };
+        self.send(message, true)?;
+        Ok(buffer.len())
+    }
+
+    fn as_channel(&self) -> Option<&ChannelEnd> {
+        Some(self)
+    }
+}
+
+impl Drop for ChannelEnd {
+    fn drop(&mut self) {
+        // the messages no one receives are released once the mailbox is unlocked, releasing their files may wake processes
+        let unreceived = {
+            let mut state = self.incoming.state.lock();
+            state.receiver_open = false;
+            mem::take(&mut state.messages)
+        };
+        drop(unreceived);
+        self.incoming.writable.wake_all();
+
+        self.outgoing.state.lock().sender_open = false;
+        self.outgoing.readable.wake_all();
+    }
+}
+
+/// Returns the index of the first end that receives without waiting (it has a message or the other end is closed),
+/// waits until one does if `blocking`. Returns `None` if no end is ready and the select doesn't block.
+///
+/// A blocking select fails if the current process sleeps until one of the ends is ready, the caller must fail too
+/// so it's syscall returns `status::BLOCKED` (see `WaitQueue::register`).
+pub fn select(ends: &[&ChannelEnd], blocking: bool) -> Result<Option<usize>, ()> {
+    loop {
+        if let Some(index) = ends.iter().position(|end| end.incoming.state.lock().is_readable()) {
+            return Ok(Some(index));
+        }
+        if !blocking {
+            return Ok(None);
+        }
+
+        let waiter = match Waiter::current() {
+            Ok(waiter) => waiter,
+            Err(()) => {
+                relax();
+                continue;
+            }
+        };
+        // the process waits on every end in a single wait, registered while the end is locked
+        for (index, end) in ends.iter().enumerate() {
+            let state = end.incoming.state.lock();
+            if state.is_readable() {
+                drop(state);
+                // the current process isn't blocked, so waking it only cancels the wait
+                waiter.wake(None).ok();
+                return Ok(Some(index));
+            }
+            end.incoming.readable.register_waiter(waiter);
+        }
+        return Err(());
+    }
+}
@@ -0,0 +1,5 @@
+//! Inter process communication, processes pass messages through channels (see `channel`)
+//! and share memory through shared memory segments (see `shm`).
+
+pub mod channel;
+pub mod shm;
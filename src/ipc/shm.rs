@@ -0,0 +1,188 @@
+//! Shared memory segments, zeroed frames that several processes map to their address space.
+//!
+//! A segment is a file, so it's handle is a file descriptor that processes pass through channels.
+//! A segment is mapped at the linear address of it's frames, or at an address it's caller chooses
+//! between `SHARED_MEMORY_START` and `SHARED_MEMORY_END`.
+//!
+//! The processes share a single page table, so an address holds a single segment at a time,
+//! and it's pages are unmapped once the last process that maps the segment there unmaps it.
+//! The handles & mappings of a segment count it's references, it's frames are freed once the last one is dropped.
+
+use alloc::{collections::BTreeMap, sync::Arc};
+use core::slice;
+use lazy_static::lazy_static;
+
+use crate::{
+    fs::File,
+    memory::{
+        get_linear_addr, kfree, kmalloc, kmap, kunmap,
+        paging::EntryFlags,
+        types::{VirtualMemoryRegion, PAGE_SIZE},
+    },
+    processes::objects::MAX_MAPPING_PAGES,
+    sync::SpinLock,
+};
+
+/// The lowest address a segment is mapped at by it's caller
+pub const SHARED_MEMORY_START: u64 = 0x4000_0000_0000;
+/// The end of the addresses segments are mapped at by their callers
+pub const SHARED_MEMORY_END: u64 = 0x4800_0000_0000;
+/// The largest segment
+pub const MAX_SEGMENT_PAGES: usize = MAX_MAPPING_PAGES;
+
+/// The segment mapped at an address, and the number of processes that map it there
+struct Placement {
+    frame: u64,
+    pages: usize,
+    mappings: usize,
+}
+
+lazy_static! {
+    /// The placements of the mapped segments, keyed by their first page
+    static ref PLACEMENTS: SpinLock<BTreeMap<u64, Placement>> = SpinLock::new(BTreeMap::new());
+}
+
+/// The frames of a segment, freed once it's dropped
+#[derive(Debug)]
+struct Segment {
+    frame: u64,
+    pages: usize,
+}
+
+impl Drop for Segment {
+    fn drop(&mut self) {
+        kfree(self.frame, self.pages * PAGE_SIZE, PAGE_SIZE);
+    }
+}
+
+/// The handle of a shared memory segment
+pub struct SharedMemory {
+    segment: Arc<Segment>,
+}
+
+impl SharedMemory {
+    /// Allocates a zeroed segment of `length` bytes rounded up to whole pages
+    pub fn new(length: usize) -> Result<Self, ()> {
+        let pages = (length + PAGE_SIZE - 1) / PAGE_SIZE;
+        if pages == 0 || pages > MAX_SEGMENT_PAGES {
+            return Err(());
+        }
+        let frame = kmalloc(pages * PAGE_SIZE, PAGE_SIZE)?;
+        unsafe { slice::from_raw_parts_mut(get_linear_addr(frame) as *mut u8, pages * PAGE_SIZE).fill(0) };
+        Ok(SharedMemory {
+            segment: Arc::new(Segment { frame, pages }),
+        })
+    }
+
+    /// The size of the segment in bytes
+    pub fn size(&self) -> usize {
+        self.segment.pages * PAGE_SIZE
+    }
+
+    /// Places the segment at `address`, or at the linear address of it's frames if there is none.
+    /// Returns the mapping, it's region must be mapped (see `ProcessData::load_address_space`).
+    ///
+    /// Fails if the address isn't page aligned, if the segment doesn't fit between `SHARED_MEMORY_START` and
+    /// `SHARED_MEMORY_END`, or if another segment is placed at one of it's pages.
+    pub fn map(&self, address: Option<u64>) -> Result<Arc<SharedMapping>, ()> {
+        let segment = &self.segment;
+        let size = (segment.pages * PAGE_SIZE) as u64;
+        let page = match address {
+            None => get_linear_addr(segment.frame),
+            Some(address) => {
+                let fits = address.checked_add(size).map_or(false, |end| end <= SHARED_MEMORY_END);
+                if address % PAGE_SIZE as u64 != 0 || address < SHARED_MEMORY_START || !fits {
+                    return Err(());
+                }
+                address
+            }
+        };
+
+        let mut placements = PLACEMENTS.lock();
+        // placements don't overlap, so only the last one that starts before the segment's end may overlap it
+        match placements.range_mut(..page + size).next_back() {
+            Some((&start, placement)) if start + (placement.pages * PAGE_SIZE) as u64 > page => {
+                if start != page || placement.frame != segment.frame || placement.mappings == 0 {
+                    return Err(());
+                }
+                placement.mappings += 1;
+            }
+            _ => {
+                placements.insert(
+                    page,
+                    Placement {
+                        frame: segment.frame,
+                        pages: segment.pages,
+                        mappings: 1,
+                    },
+                );
+            }
+        }
+
+        Ok(Arc::new(SharedMapping {
+            region: VirtualMemoryRegion::new(page, segment.frame, segment.pages),
+            segment: segment.clone(),
+        }))
+    }
+}
+
+impl File for SharedMemory {
+    fn read(&self, _buffer: &mut [u8]) -> Result<usize, ()> {
+        Err(())
+    }
+
+    fn write(&self, _buffer: &[u8]) -> Result<usize, ()> {
+        Err(())
+    }
+
+    fn as_shared_memory(&self) -> Option<&SharedMemory> {
+        Some(self)
+    }
+}
+
+/// A segment mapped by a process, the processes' data hold it while it's mapped.
+///
+/// The last mapping of a placement unmaps it's pages once it's dropped,
+/// which may shoot down the TLB of other processors, so it must not be dropped while the scheduler is locked.
+#[derive(Debug)]
+pub struct SharedMapping {
+    pub region: VirtualMemoryRegion,
+    segment: Arc<Segment>,
+}
+
+impl SharedMapping {
+    /// The address the segment is mapped at
+    pub fn address(&self) -> u64 {
+        self.region.first_page()
+    }
+}
+
+impl Drop for SharedMapping {
+    fn drop(&mut self) {
+        let page = self.address();
+        let is_last = {
+            let mut placements = PLACEMENTS.lock();
+            let placement = placements.get_mut(&page).unwrap();
+            placement.mappings -= 1;
+            placement.mappings == 0
+        };
+        if !is_last {
+            return;
+        }
+
+        // the placement is kept while it's pages are unmapped, so no segment is mapped there meanwhile
+        let is_linear = page == get_linear_addr(self.segment.frame);
+        for (page, frame) in self.region.pages_range.clone().zip(self.region.frames_range.clone()) {
+            unsafe {
+                if is_linear {
+                    // the frames go back to the kernel's linear mapping
+                    kmap(page, frame, EntryFlags::PRESENT | EntryFlags::WRITABLE).ok();
+                } else {
+                    kunmap(page);
+                }
+            }
+        }
+        PLACEMENTS.lock().remove(&page);
+        // the segment's frames are freed after the pages are unmapped, once it's last reference is dropped
+    }
+}
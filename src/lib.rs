@@ -12,16 +12,22 @@
 
 pub extern crate alloc;
 
+pub mod acpi;
 pub mod drivers;
+pub mod fs;
 mod hardware;
 /// note that `pub` keyword makes the modules declaration accessible to external crates
 pub mod interrupts;
+pub mod ipc;
 pub mod log;
 pub mod memory;
 pub mod panic;
 pub mod processes;
+pub mod smp;
+pub mod sync;
 pub mod syscalls;
 pub mod tests;
+pub mod time;
 pub mod userland;
 
 pub use core::panic::PanicInfo;
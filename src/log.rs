@@ -1,11 +1,25 @@
 //! this logger will help us debug / log our kernel
+//!
+//! The records are written to the serial port, and the latest records are kept in a ring buffer (read by `dmesg`).
+
+use core::fmt::{self, Write};
 
 pub use log::{debug, error, info, trace, warn, LevelFilter};
 use log::{Level, Metadata, Record};
+use spin::Mutex;
+use x86_64::instructions::interrupts;
 
 use crate::serial_println;
 
+/// The amount of the latest log bytes the kernel keeps
+pub const LOG_CAPACITY: usize = 0x4000;
+
 static LOGGER: Logger = Logger;
+static LOG_BUFFER: Mutex<LogBuffer> = Mutex::new(LogBuffer {
+    data: [0; LOG_CAPACITY],
+    start: 0,
+    length: 0,
+});
 
 struct Logger;
 
@@ -17,11 +31,36 @@ impl log::Log for Logger {
     fn log(&self, record: &Record) {
         if self.enabled(record.metadata()) {
             serial_println!("[{}] - {}", record.level(), record.args());
+            // records are logged by interrupt handlers as well
+            interrupts::without_interrupts(|| {
+                writeln!(LOG_BUFFER.lock(), "[{}] - {}", record.level(), record.args()).ok();
+            });
         }
     }
     fn flush(&self) {}
 }
 
+/// A ring of the latest logged bytes, the oldest bytes are overwritten
+struct LogBuffer {
+    data: [u8; LOG_CAPACITY],
+    start: usize,
+    length: usize,
+}
+
+impl fmt::Write for LogBuffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            self.data[(self.start + self.length) % LOG_CAPACITY] = byte;
+            if self.length == LOG_CAPACITY {
+                self.start = (self.start + 1) % LOG_CAPACITY;
+            } else {
+                self.length += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Initiate a logger static object
 ///
 /// # Arguments
@@ -30,3 +69,15 @@ pub fn init(filter: LevelFilter) {
     log::set_logger(&LOGGER).unwrap();
     log::set_max_level(filter);
 }
+
+/// Copies the kept log from `offset` to `buffer`, returns the number of copied bytes
+pub fn read_log(offset: usize, buffer: &mut [u8]) -> usize {
+    interrupts::without_interrupts(|| {
+        let log = LOG_BUFFER.lock();
+        let count = log.length.saturating_sub(offset).min(buffer.len());
+        for (index, byte) in buffer[..count].iter_mut().enumerate() {
+            *byte = log.data[(log.start + offset + index) % LOG_CAPACITY];
+        }
+        count
+    })
+}
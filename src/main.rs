@@ -12,11 +12,11 @@ use x86_64::VirtAddr;
 use CrabOS::panic::kernel_panic;
 
 use CrabOS::{
-    graphic_println, hlt_loop,
-    interrupts::{gdt, idt},
+    drivers::{block, keyboard, serial}, fs, graphic_println, hlt_loop,
+    interrupts::{self, gdt, idt},
     log::{self, info, LevelFilter},
     memory::{self, frame_distributer::FrameDistributer, heap, paging},
-    panic::PanicInfo, processes::{spawn_process, execute_process}, userland::user_main, code_addr,
+    panic::PanicInfo, processes::{spawn_process, execute_process}, smp, time, userland::user_main, code_addr,
 };
 
 entry_point!(kmain);
@@ -32,6 +32,13 @@ fn kmain(boot_info: &'static BootInfo) -> ! {
     gdt::init();
     idt::init();
     memory::init(boot_info);
+    interrupts::init_controllers();
+    time::init();
+    smp::init();
+    block::init();
+    keyboard::init();
+    serial::init();
+    fs::init();
     execute_process(spawn_process(code_addr!(user_main)));
     hlt_loop()
 }
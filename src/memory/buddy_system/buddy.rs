@@ -51,6 +51,20 @@ impl Buddy {
         (BUDDY_LIMIT << self.max_order) as usize
     }
 
+    /// Returns the size of the region in bytes
+    pub fn size(&self) -> usize {
+        self.block_max_size()
+    }
+
+    /// Returns the number of free bytes
+    pub fn free_size(&self) -> usize {
+        self.free_blocks
+            .iter()
+            .enumerate()
+            .map(|(order, blocks)| blocks.len() * (self.block_max_size() >> order))
+            .sum()
+    }
+
     /// Gets a block from the free list at a given order or split a block above and return one of the splitted blocks.
     fn get_free_block(&mut self, order: usize) -> Option<u64> {
         self.free_blocks[order]
@@ -28,6 +28,16 @@ impl BuddyManager {
         }
     }
 
+    /// Returns the size of the managed physical memory in bytes
+    pub fn size(&self) -> usize {
+        self.buddies.iter().map(|buddy| buddy.size()).sum()
+    }
+
+    /// Returns the number of free bytes
+    pub fn free_size(&self) -> usize {
+        self.buddies.iter().map(|buddy| buddy.free_size()).sum()
+    }
+
     /// Allocates a given size of physical memory with the appropriate buddy
     pub fn allocate(&mut self, size: usize, alignment: usize) -> Option<u64> {
         for buddy in self.buddies.iter_mut() {
@@ -35,6 +35,12 @@ fn handle_alloc_error(layout: Layout) -> ! {
 #[global_allocator]
 static ALLOCATOR: LockedHeap = LockedHeap::empty();
 
+/// Returns the heap size and the number of allocated bytes
+pub fn usage() -> (usize, usize) {
+    let heap = ALLOCATOR.lock();
+    (heap.size(), heap.used())
+}
+
 /// Create a virtual address space for the heap (must be above the already mapped physical memory)
 pub fn init(frame_distributer: &mut FrameDistributer) {
     for page_addr in (HEAP_BOTTOM..(HEAP_BOTTOM + HEAP_SIZE as u64)).step_by(PAGE_SIZE) {
@@ -3,19 +3,27 @@
 //! 2. virutal memory mapping with mmap
 //! 3. enables dynamic object use
 
-use bootloader::BootInfo;
+use bootloader::{
+    bootinfo::{MemoryMap, MemoryRegionType},
+    BootInfo,
+};
 use lazy_static::lazy_static;
 use log::{debug, info, trace};
-use spin::Mutex;
+use spin::{Mutex, Once};
 
-use crate::memory::{
-    buddy_system::manager::BuddyManager,
-    frame_distributer::FrameDistributer,
-    mapper::Mapper,
-    paging::{get_cr3, EntryFlags, Table},
+use crate::{
+    memory::{
+        buddy_system::manager::BuddyManager,
+        frame_distributer::{FrameAllocator, FrameDistributer},
+        mapper::Mapper,
+        paging::{get_cr3, Entry, EntryFlags, Table},
+    },
+    smp,
 };
 
-use self::types::VirtualMemoryRegion;
+use self::types::{VirtualMemoryRegion, PAGE_SIZE};
+
+pub use abi::memory::MemoryInfo;
 
 pub mod buddy_system;
 pub mod frame_distributer;
@@ -32,6 +40,15 @@ lazy_static! {
     pub static ref KERNEL_MAPPER: Mutex<Mapper<'static>> = Mutex::new(Mapper::empty());
 }
 
+/// Real mode code can only address the first MiB
+const REAL_MODE_MEMORY_LIMIT: u64 = 0x100000;
+
+/// A frame below `REAL_MODE_MEMORY_LIMIT` reserved for real mode code (the application processors trampoline)
+static REAL_MODE_FRAME: Mutex<Option<u64>> = Mutex::new(None);
+
+/// The memory map of the bootloader, the frames of the kernel image are looked up in it
+static MEMORY_MAP: Once<&'static MemoryMap> = Once::new();
+
 #[macro_export]
 macro_rules! aligned_to_page_size {
     ($addr:expr) => {
@@ -53,9 +70,15 @@ pub fn init(boot_info: &'static BootInfo) {
         boot_info.physical_memory_offset
     );
 
+    MEMORY_MAP.call_once(|| &boot_info.memory_map);
     let mut frame_distributer = FrameDistributer::new(&boot_info.memory_map);
     info!("frame distributer initialized");
 
+    // the lowest frames are distributed first, so the frame is reserved before the heap takes them
+    *REAL_MODE_FRAME.lock() = frame_distributer
+        .allocate_frame()
+        .filter(|frame| *frame + PAGE_SIZE as u64 <= REAL_MODE_MEMORY_LIMIT);
+
     KERNEL_MAPPER.lock().init(
         unsafe { as_mut_ref::<Table>(get_cr3() + boot_info.physical_memory_offset) },
         boot_info.physical_memory_offset,
@@ -70,6 +93,18 @@ pub fn init(boot_info: &'static BootInfo) {
     info!("finished initializing memory related structures");
 }
 
+/// Returns the physical memory and the kernel heap usage
+pub fn memory_info() -> MemoryInfo {
+    let (heap_size, heap_used) = heap::usage();
+    let allocator = KERNEL_ALLOCATOR.lock();
+    MemoryInfo {
+        total: allocator.size(),
+        free: allocator.free_size(),
+        heap_size,
+        heap_used,
+    }
+}
+
 /// Allocate a kernel physical memory
 pub fn kmalloc(size: usize, alignment: usize) -> Result<u64, ()> {
     KERNEL_ALLOCATOR.lock().allocate(size, alignment).ok_or(())
@@ -80,15 +115,50 @@ pub fn kfree(address: u64, size: usize, alignment: usize) {
     KERNEL_ALLOCATOR.lock().deallocate(address, size, alignment);
 }
 
-/// Maps a kernel page to a page frame
+/// Takes the frame reserved for real mode code, returns `None` if it was taken
+/// or if there was no usable frame below 1MiB.
+pub fn take_real_mode_frame() -> Option<u64> {
+    REAL_MODE_FRAME.lock().take()
+}
+
+/// Maps a kernel page to a page frame.
+///
+/// Other processors flush the page from their TLB if it was already mapped,
+/// the translation of a non present page is never cached.
 pub unsafe fn kmap(linear_addr: u64, physical_addr: u64, flags: EntryFlags) -> Result<(), ()> {
-    unsafe {
-        KERNEL_MAPPER.lock().map(
-            linear_addr,
-            physical_addr,
-            &mut *KERNEL_ALLOCATOR.lock(),
-            flags,
-        )
+    let was_present = {
+        let mut mapper = KERNEL_MAPPER.lock();
+        let previous = mapper
+            .get_linear_address_entry(linear_addr)
+            .map(|entry| (entry.addr(), entry.flags() - (EntryFlags::ACCESSED | EntryFlags::DIRTY)));
+        if previous == Some((physical_addr, flags)) {
+            return Ok(());
+        }
+
+        unsafe { mapper.map(linear_addr, physical_addr, &mut *KERNEL_ALLOCATOR.lock(), flags)? };
+        previous.is_some()
+    };
+
+    // the mapper lock must be released, other processors may wait for it while the TLB is shot down
+    if was_present {
+        smp::flush_tlb(linear_addr);
+    }
+    Ok(())
+}
+
+/// Unmaps a kernel page, other processors flush it from their TLB if it was mapped
+pub unsafe fn kunmap(linear_addr: u64) {
+    let was_present = match KERNEL_MAPPER.lock().get_linear_address_entry(linear_addr) {
+        Some(entry) => {
+            *entry = Entry::new();
+            true
+        }
+        None => false,
+    };
+
+    // the mapper lock must be released, other processors may wait for it while the TLB is shot down
+    if was_present {
+        smp::flush_tlb(linear_addr);
     }
 }
 
@@ -117,6 +187,7 @@ pub unsafe fn update_pages_access_policy(virtual_memory_region: VirtualMemoryReg
     for page in virtual_memory_region.pages_range {
         trace!("updating page {:#x}", page);
         KERNEL_MAPPER.lock().get_linear_address_entry(page).unwrap().set_flags(flags);
+        smp::flush_tlb(page);
     }
 }
 
@@ -140,6 +211,39 @@ pub fn get_linear_addr(physical_addr: u64) -> u64 {
     physical_addr + get_virutal_memory_base()
 }
 
+/// Returns whether the linear addresses from `start` to `end` are in the frames of the kernel image,
+/// the programs of the kernel (see `userland::programs`) run the kernel's code through the linear mapping
+pub fn is_kernel_image(start: u64, end: u64) -> bool {
+    let offset = get_virutal_memory_base();
+    let (start, end) = match (start.checked_sub(offset), end.checked_sub(offset)) {
+        (Some(start), Some(end)) if start <= end => (start, end),
+        _ => return false,
+    };
+    MEMORY_MAP.get().map_or(false, |memory_map| {
+        memory_map.iter().any(|region| {
+            region.region_type == MemoryRegionType::Kernel
+                && region.range.start_addr() <= start
+                && end <= region.range.end_addr()
+        })
+    })
+}
+
+/// Returns the linear address of a memory mapped device register,
+/// the register page is mapped uncached if it is outside of the physical memory mapping.
+pub fn map_device_memory(physical_addr: u64) -> Result<u64, ()> {
+    let linear_addr = get_linear_addr(physical_addr);
+    if get_physical_addr(linear_addr).is_none() {
+        unsafe {
+            kmap(
+                aligned_to_page_size!(linear_addr),
+                aligned_to_page_size!(physical_addr),
+                EntryFlags::PRESENT | EntryFlags::WRITABLE | EntryFlags::DISABLE_CACHE,
+            )?
+        };
+    }
+    Ok(linear_addr)
+}
+
 /// Converts an address to a const raw pointer
 const fn as_ptr<T>(address: u64) -> *const T {
     address as *const T
@@ -7,10 +7,11 @@ pub enum QemuExitCode {
     Failed = 0x11,
 }
 
-/// Exits qemu by writing the exit code to the debug exit port
+/// Exits qemu by writing the exit code to the debug exit port, the queued serial output is sent first
 pub fn exit_qemu(exit_code: QemuExitCode) {
     use x86_64::instructions::port::Port;
 
+    crate::drivers::serial::flush();
     unsafe {
         let mut port = Port::new(ISA_DEBUG_EXIT_PORT);
         port.write(exit_code as u32);
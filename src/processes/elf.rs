@@ -0,0 +1,214 @@
+//! Loads ELF programs to memory.
+//!
+//! Processes share the kernel's address space, so a program runs at the linear address of the frames it's loaded to.
+//! Only static position independent executables can run at any address, their relative relocations are applied while loading.
+
+use core::slice;
+
+use crate::memory::{
+    get_linear_addr, kfree, kmalloc,
+    types::{VirtualMemoryRegion, PAGE_SIZE},
+};
+
+const MAGIC: &[u8] = b"\x7FELF";
+const CLASS_64: u8 = 2;
+const LITTLE_ENDIAN: u8 = 1;
+/// Position independent executables are shared objects
+const TYPE_SHARED_OBJECT: u16 = 3;
+const MACHINE_X86_64: u16 = 0x3E;
+
+const HEADER_SIZE: usize = 64;
+const PROGRAM_HEADER_SIZE: usize = 56;
+const SEGMENT_LOAD: u32 = 1;
+const SEGMENT_DYNAMIC: u32 = 2;
+
+const DYNAMIC_ENTRY_SIZE: usize = 16;
+const DYNAMIC_NULL: u64 = 0;
+const DYNAMIC_RELA: u64 = 7;
+const DYNAMIC_RELA_SIZE: u64 = 8;
+const DYNAMIC_RELA_ENTRY: u64 = 9;
+
+const RELOCATION_SIZE: usize = 24;
+const RELOCATION_NONE: u64 = 0;
+const RELOCATION_RELATIVE: u64 = 8;
+
+/// Larger programs aren't loaded
+pub const MAX_PROGRAM_SIZE: usize = 0x400000;
+
+/// A program loaded to memory
+pub struct ProgramImage {
+    /// The pages of the program, they are the linear addresses of it's frames
+    pub region: VirtualMemoryRegion,
+    /// The linear address of the program's entry point
+    pub entry: u64,
+}
+
+impl ProgramImage {
+    /// Frees the frames of the program
+    pub fn release(&self) {
+        kfree(self.region.first_frame(), self.region.size * PAGE_SIZE, PAGE_SIZE);
+    }
+}
+
+/// A program header
+struct Segment {
+    kind: u32,
+    offset: usize,
+    address: usize,
+    file_size: usize,
+    memory_size: usize,
+}
+
+impl Segment {
+    fn parse(header: &[u8]) -> Result<Self, ()> {
+        let segment = Segment {
+            kind: read_u32(header, 0)?,
+            offset: read_u64(header, 8)? as usize,
+            address: read_u64(header, 16)? as usize,
+            file_size: read_u64(header, 32)? as usize,
+            memory_size: read_u64(header, 40)? as usize,
+        };
+        if segment.file_size > segment.memory_size
+            || segment.offset.checked_add(segment.file_size).is_none()
+            || segment.address.checked_add(segment.memory_size).is_none()
+        {
+            return Err(());
+        }
+        Ok(segment)
+    }
+
+    fn end(&self) -> usize {
+        self.address + self.memory_size
+    }
+}
+
+/// Loads a static position independent executable to new frames.
+///
+/// The loadable segments are copied to their offset from the first frame,
+/// the rest of the memory (e.g. the bss) is zeroed.
+pub fn load(program: &[u8]) -> Result<ProgramImage, ()> {
+    let header = program.get(..HEADER_SIZE).ok_or(())?;
+    if &header[..MAGIC.len()] != MAGIC
+        || header[4] != CLASS_64
+        || header[5] != LITTLE_ENDIAN
+        || read_u16(header, 16)? != TYPE_SHARED_OBJECT
+        || read_u16(header, 18)? != MACHINE_X86_64
+    {
+        return Err(());
+    }
+    let entry = read_u64(header, 24)? as usize;
+    let headers_offset = read_u64(header, 32)? as usize;
+    let header_size = read_u16(header, 54)? as usize;
+    let header_count = read_u16(header, 56)? as usize;
+    if header_size < PROGRAM_HEADER_SIZE {
+        return Err(());
+    }
+
+    // the offsets are read from the file, so they may overflow
+    let segment = |index: usize| {
+        let start = index.checked_mul(header_size).and_then(|offset| headers_offset.checked_add(offset)).ok_or(())?;
+        Segment::parse(program.get(start..start.checked_add(PROGRAM_HEADER_SIZE).ok_or(())?).ok_or(())?)
+    };
+    let mut size = 0;
+    for index in 0..header_count {
+        let segment = segment(index)?;
+        if segment.kind == SEGMENT_LOAD {
+            size = size.max(segment.end());
+        }
+    }
+    if entry >= size || size > MAX_PROGRAM_SIZE {
+        return Err(());
+    }
+
+    let pages = (size + PAGE_SIZE - 1) / PAGE_SIZE;
+    let frame = kmalloc(pages * PAGE_SIZE, PAGE_SIZE)?;
+    let base = get_linear_addr(frame);
+    let image = ProgramImage {
+        region: VirtualMemoryRegion::new(base, frame, pages),
+        entry: base + entry as u64,
+    };
+
+    // the frames are mapped to their linear addresses with the rest of the physical memory
+    let memory = unsafe { slice::from_raw_parts_mut(base as *mut u8, pages * PAGE_SIZE) };
+    memory.fill(0);
+    let result = (0..header_count).try_for_each(|index| {
+        let segment = segment(index)?;
+        match segment.kind {
+            SEGMENT_LOAD => {
+                let data = program.get(segment.offset..segment.offset + segment.file_size).ok_or(())?;
+                memory[segment.address..segment.address + segment.file_size].copy_from_slice(data);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    });
+    // the dynamic segment is read after it's loaded, it may point to any loaded segment
+    let result = result.and_then(|()| {
+        (0..header_count).try_for_each(|index| {
+            let segment = segment(index)?;
+            match segment.kind {
+                SEGMENT_DYNAMIC => relocate(memory, base, segment.address, segment.memory_size),
+                _ => Ok(()),
+            }
+        })
+    });
+
+    match result {
+        Ok(()) => Ok(image),
+        Err(()) => {
+            image.release();
+            Err(())
+        }
+    }
+}
+
+/// Applies the relocations listed by the dynamic section, only relative relocations are supported
+fn relocate(memory: &mut [u8], base: u64, dynamic: usize, dynamic_size: usize) -> Result<(), ()> {
+    let (mut table, mut table_size, mut entry_size) = (0, 0, RELOCATION_SIZE);
+    for index in 0..dynamic_size / DYNAMIC_ENTRY_SIZE {
+        let entry = dynamic + index * DYNAMIC_ENTRY_SIZE;
+        let value = read_u64(memory, entry + 8)? as usize;
+        match read_u64(memory, entry)? {
+            DYNAMIC_NULL => break,
+            DYNAMIC_RELA => table = value,
+            DYNAMIC_RELA_SIZE => table_size = value,
+            DYNAMIC_RELA_ENTRY => entry_size = value,
+            _ => {}
+        }
+    }
+    if entry_size < RELOCATION_SIZE {
+        return Err(());
+    }
+
+    for index in 0..table_size / entry_size {
+        let relocation = table.checked_add(index * entry_size).ok_or(())?;
+        let fields = memory.get(relocation..relocation.checked_add(RELOCATION_SIZE).ok_or(())?).ok_or(())?;
+        let offset = read_u64(fields, 0)? as usize;
+        let kind = read_u64(fields, 8)? & 0xFFFF_FFFF;
+        let addend = read_u64(fields, 16)?;
+        match kind {
+            RELOCATION_NONE => {}
+            RELOCATION_RELATIVE => memory
+                .get_mut(offset..offset.checked_add(8).ok_or(())?)
+                .ok_or(())?
+                .copy_from_slice(&base.wrapping_add(addend).to_le_bytes()),
+            _ => return Err(()),
+        }
+    }
+    Ok(())
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, ()> {
+    let field = bytes.get(offset..offset.checked_add(2).ok_or(())?).ok_or(())?;
+    Ok(u16::from_le_bytes(field.try_into().map_err(|_| ())?))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, ()> {
+    let field = bytes.get(offset..offset.checked_add(4).ok_or(())?).ok_or(())?;
+    Ok(u32::from_le_bytes(field.try_into().map_err(|_| ())?))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64, ()> {
+    let field = bytes.get(offset..offset.checked_add(8).ok_or(())?).ok_or(())?;
+    Ok(u64::from_le_bytes(field.try_into().map_err(|_| ())?))
+}
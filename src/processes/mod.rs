@@ -1,66 +1,782 @@
 //! this module includes:
 //! 1. definisions of process and thread objects
 //! 2. context switch - change the flow of execusion
-//! 3. a scheduler
+//! 3. a scheduler, every processor runs the processes of it's own run queue (see `smp`)
 
+pub mod elf;
 pub mod objects;
 pub mod scheduler;
+pub mod signals;
 
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::mem;
 use lazy_static::lazy_static;
-use log::{error, info, debug};
+use log::{debug, error, info};
 use scheduler::Scheduler;
-use spin::Mutex;
-use x86_64::structures::idt::InterruptStackFrame;
+use x86_64::{instructions::interrupts, structures::idt::InterruptStackFrame};
 
-use crate::panic::exit_qemu;
+use crate::{
+    drivers::ring_buffer::RingBuffer,
+    fs::{descriptors::FileDescriptorTable, vfs, File},
+    ipc::shm::SharedMemory,
+    memory::mmap,
+    panic::{exit_qemu, QemuExitCode},
+    smp::{self, Cpu},
+    sync::{futex, SpinLock, Waiter},
+    syscalls::wait,
+    time::timer_wheel::{self, TICK_NANOSECONDS},
+};
 
-use self::objects::ProcessData;
+use self::{
+    objects::{release_user_region, ProcessData, ProcessInfo, ProcessState, Registers, Thread, USER_DATA_FLAGS},
+    signals::{Delivery, SignalAction, SIGCHLD, SIGCONT, SIGSEGV},
+};
+
+/// The exit status of a killed process, like a shell reports a process killed by SIGKILL (128 + 9)
+pub const KILLED_EXIT_STATUS: u8 = 137;
+const WOKEN_CAPACITY: usize = 256;
+
+/// The processes the timer interrupt woke with the waits they slept in, queued by `queue_woken_processes`.
+/// Queuing a process and leaving a futex queue use the heap, which the interrupted code may hold.
+static WOKEN: RingBuffer<(usize, Option<u64>), WOKEN_CAPACITY> = RingBuffer::new((0, None));
+static QUEUING_WOKEN: SpinLock<()> = SpinLock::new(());
 
 lazy_static! {
-    pub static ref KERNEL_SCHEDULER: Mutex<Scheduler> = Mutex::new(Scheduler::empty());
+    pub static ref KERNEL_SCHEDULER: SpinLock<Scheduler> = SpinLock::new(Scheduler::empty());
 }
 
+/// Creates a process, it's parent is the current process
 pub fn spawn_process(process_code: u64) -> usize {
-    KERNEL_SCHEDULER.lock().push_process(process_code)
+    KERNEL_SCHEDULER.lock().push_process(process_code, get_current_pid())
 }
 
+/// Creates a process running the ELF program at `path`, it's parent is the current process
+///
+/// # Arguments
+///  - `arguments`, the program's arguments, every argument is followed by a null byte
+pub fn spawn_program(path: &str, arguments: &[u8]) -> Result<usize, ()> {
+    let image = elf::load(&vfs::read_file(path)?)?;
+    KERNEL_SCHEDULER.lock().push_program(image, arguments, get_current_pid())
+}
+
+/// Runs a process from the kernel, returns only if the process can't be executed.
 pub fn execute_process(pid: usize) {
-    // To release the scheduler lock we must end it's lifetime with {}.
-    let process = { KERNEL_SCHEDULER.lock().get_process(pid) };
-    match process {
-        Ok(process) => process.execute(),
-        Err(()) => {
-            error!("cannot execute process with pid {:#x}", pid)
+    if KERNEL_SCHEDULER.lock().start_process(pid, None).is_err() {
+        error!("cannot execute process with pid {:#x}", pid);
+        return;
+    }
+    smp::enqueue(pid);
+    schedule()
+}
+
+/// Queues a child of the current process that never ran, the current process continues running.
+pub fn start_child(pid: usize) -> Result<(), ()> {
+    let parent = get_current_pid().ok_or(())?;
+    if KERNEL_SCHEDULER.lock().start_process(pid, Some(parent)).is_err() {
+        error!("cannot start process with pid {:#x}", pid);
+        return Err(());
+    }
+    smp::enqueue(pid);
+    Ok(())
+}
+
+/// Runs a child of the current process and waits until it exits (see `wait_child`).
+pub fn execute_child(pid: usize, process_context: &InterruptStackFrame, registers: &Registers) -> Result<usize, ()> {
+    start_child(pid)?;
+    wait_child(pid, 0, process_context, registers)
+}
+
+/// Pauses the current process until it's child exits, and runs the next process.
+/// The current process continues with the child's WAIT status, the child's exit status unless the flags say otherwise.
+///
+/// Returns only if the WAIT status is known already (e.g. the child exited), or if `pid` isn't a started child of the current process.
+///
+/// # Arguments
+///  - `flags`, the WAIT flags (see `syscalls::wait`)
+///  - `process_context` & `registers`, the context the current process continues from
+pub fn wait_child(pid: usize, flags: u64, process_context: &InterruptStackFrame, registers: &Registers) -> Result<usize, ()> {
+    let cpu = smp::current();
+    let parent = cpu.current_pid().ok_or(())?;
+    let reports_stops = flags & wait::REPORT_STOPPED != 0;
+
+    let interrupted = {
+        let mut scheduler = KERNEL_SCHEDULER.lock();
+        if let Some(exit_status) = scheduler.take_exit_status(parent, pid)? {
+            return Ok(exit_status as usize);
+        }
+        let stop_signal = match scheduler.get_process(pid) {
+            Ok(child) if child.started && child.internal_data.parent == Some(parent) => child.stop_signal,
+            _ => {
+                error!("cannot wait for process with pid {:#x}", pid);
+                return Err(());
+            }
+        };
+        match stop_signal {
+            Some(signal) if reports_stops => return Ok((wait::STOPPED as usize) | signal),
+            _ if flags & wait::NO_HANG != 0 => return Ok(wait::RUNNING as usize),
+            _ => {}
+        }
+        debug!("pausing process: {:#x}, with context: {:#x?}", parent, process_context);
+        scheduler.pause_process(parent, pid, reports_stops, process_context, registers)?;
+        cpu.set_current_pid(None);
+        // a signal that is pending already interrupts the wait right away (see `Scheduler::interrupt_sleep`)
+        matches!(scheduler.get_process(parent), Ok(process) if process.signals.has_deliverable())
+            && scheduler.interrupt_sleep(parent) == Ok(true)
+    };
+    if interrupted {
+        smp::enqueue(parent);
+    }
+    schedule()
+}
+
+/// Wakes the blocked processes whose deadline passed by `now` (in uptime nanoseconds), called by the timer interrupt.
+/// They're queued by `queue_woken_processes`, a timer that doesn't fit `WOKEN` expires at the next tick.
+pub fn wake_processes(now: u64) {
+    let mut scheduler = KERNEL_SCHEDULER.lock();
+    // the timers expire while the scheduler is locked, so the processes can't block again meanwhile
+    timer_wheel::expire(now, |pid| {
+        if WOKEN.room() == 0 {
+            return false;
+        }
+        if let Ok(sleeping) = scheduler.get_process(pid).map(|process| process.sleeping) {
+            if scheduler.expire_deadline(pid, now).is_ok() {
+                WOKEN.push((pid, sleeping)).ok();
+            }
+        }
+        true
+    });
+}
+
+/// Queues the processes the timer interrupt woke (see `WOKEN`) to the processors, a futex waiter leaves it's queue.
+/// Called by `schedule` and by `preempt` of a process, the processor doesn't interrupt code that may hold the heap.
+fn queue_woken_processes() {
+    // the buffer has a single consumer
+    let _consumer = match QUEUING_WOKEN.try_lock() {
+        Some(guard) => guard,
+        None => return,
+    };
+    while let Some((pid, sleeping)) = WOKEN.pop() {
+        // the futexes are locked before the scheduler, so the timer interrupt doesn't cancel the waiter itself
+        if let Some(wait) = sleeping {
+            futex::cancel(Waiter { pid, wait });
+        }
+        smp::enqueue(pid);
+    }
+}
+
+/// Makes the current process a sleeper (see `sync::WaitQueue::register`), returns it's pid and the id of the wait.
+/// Fails if there is no current process.
+pub fn prepare_sleep() -> Result<(usize, u64), ()> {
+    let pid = get_current_pid().ok_or(())?;
+    let wait = KERNEL_SCHEDULER.lock().prepare_sleep(pid)?;
+    Ok((pid, wait))
+}
+
+/// Returns whether the current process sleeps once it's syscall returns,
+/// a syscall that failed because it waits on a wait queue returns `status::BLOCKED`
+pub fn is_sleeping() -> bool {
+    let pid = match get_current_pid() {
+        Some(pid) => pid,
+        None => return false,
+    };
+    matches!(KERNEL_SCHEDULER.lock().get_process(pid), Ok(process) if process.sleeping.is_some())
+}
+
+/// Blocks the current process until a wait queue wakes it, and runs the next process.
+/// The process issues it's syscall again when it continues.
+///
+/// A process that was woken already issues it's syscall again now, after a pending signal is delivered (see `restart_syscall`).
+/// Returns only if the current process isn't `Active`.
+///
+/// # Arguments
+///  - `process_context` & `registers`, the context of the syscall, the registers must hold it's number & arguments
+pub fn sleep_current_process(process_context: &InterruptStackFrame, registers: &Registers) {
+    let cpu = smp::current();
+    let pid = match cpu.current_pid() {
+        Some(pid) => pid,
+        None => return,
+    };
+
+    {
+        let mut scheduler = KERNEL_SCHEDULER.lock();
+        match scheduler.sleep_process(pid, true, None, process_context, registers) {
+            Ok(true) => cpu.set_current_pid(None),
+            Ok(false) => {
+                drop(scheduler);
+                return restart_syscall(process_context, registers);
+            }
+            Err(()) => return,
         }
     }
+    schedule()
 }
 
-pub fn pause_process(pid: usize, process_context: &InterruptStackFrame) {
-    debug!("pausing process: {:#x}, with context: {:#x?}", pid, process_context);
-    KERNEL_SCHEDULER.try_lock().unwrap().pause_process(pid, process_context)
+/// Blocks the current process until it's woken or until `deadline` (in uptime nanoseconds), and runs the next process.
+/// Unlike `sleep_current_process` the syscall isn't issued again, it returns with the status of the saved registers,
+/// with the status it's waker sets (see `wake_sleeper`) or with `status::FAILURE` if a signal interrupts it.
+///
+/// Returns only if the process isn't blocked, e.g. it was woken already or a signal is pending.
+///
+/// # Arguments
+///  - `process_context` & `registers`, the context the current process continues from
+pub fn block_sleeper(deadline: Option<u64>, process_context: &InterruptStackFrame, registers: &Registers) {
+    let cpu = smp::current();
+    let pid = match cpu.current_pid() {
+        Some(pid) => pid,
+        None => return,
+    };
+
+    {
+        let mut scheduler = KERNEL_SCHEDULER.lock();
+        if scheduler.sleep_process(pid, false, deadline, process_context, registers) != Ok(true) {
+            return;
+        }
+        cpu.set_current_pid(None);
+        // the timer is added while the scheduler is locked, so the process is blocked before it expires
+        if let Some(deadline) = deadline {
+            timer_wheel::add_timer(deadline, pid);
+        }
+    }
+    schedule()
+}
+
+/// Wakes a process that sleeps in `wait`, and queues it to a processor if it was `Blocked`.
+/// Fails if the process doesn't sleep in `wait` (e.g. it was woken already).
+///
+/// # Arguments
+///  - `status`, the status the syscall of a `Blocked` process returns, a process that issues it's syscall again has none
+pub fn wake_sleeper(pid: usize, wait: u64, status: Option<i64>) -> Result<(), ()> {
+    if KERNEL_SCHEDULER.lock().wake_sleeper(pid, wait, status)? {
+        smp::enqueue(pid);
+    }
+    Ok(())
+}
+
+/// Charges a timer tick of CPU time to the process that runs on the current processor, called by the timer interrupt
+pub fn account_tick() {
+    let cpu = smp::current();
+    if let Some(pid) = cpu.current_pid() {
+        let mut scheduler = KERNEL_SCHEDULER.lock();
+        cpu.with_run_queue(|run_queue| scheduler.charge_process(pid, TICK_NANOSECONDS, run_queue)).ok();
+    }
+}
+
+/// Switches to the next process of the current processor, called by the timer and the reschedule interrupts.
+///
+/// Returns if the interrupted code is the kernel (the kernel isn't preemptible),
+/// or if no other process is waiting for the processor.
+pub fn preempt(process_context: &InterruptStackFrame, registers: &Registers) {
+    if process_context.code_segment & 0b11 != 3 {
+        return;
+    }
+    queue_woken_processes();
+    let cpu = smp::current();
+    let pid = match cpu.current_pid() {
+        Some(pid) => pid,
+        None => return,
+    };
+
+    let mut scheduler = KERNEL_SCHEDULER.lock();
+    match scheduler.get_process_info(pid).map(|data| data.state) {
+        // the process was killed by another processor
+        Ok(ProcessState::Terminated) | Err(()) => {
+            scheduler.remove_process(pid).ok();
+            cpu.set_current_pid(None);
+            let released = scheduler.take_released_resources();
+            drop(scheduler);
+            drop(released);
+            schedule()
+        }
+        Ok(_) if !cpu.has_waiting_processes() => return,
+        Ok(_) => {
+            scheduler.preempt_process(pid, process_context, registers).ok();
+            cpu.set_current_pid(None);
+            cpu.push_process(pid);
+        }
+    }
+    drop(scheduler);
+    schedule()
+}
+
+/// Runs the next process of the current processor, steals processes from the other processors
+/// and halts when there are none.
+///
+/// The current kernel stack is abandoned, so no locks may be held.
+pub fn schedule() -> ! {
+    let cpu = smp::current();
+    loop {
+        queue_woken_processes();
+        // a process that is queued while the processor halts wakes it with a reschedule IPI
+        interrupts::disable();
+        let next = {
+            let mut scheduler = KERNEL_SCHEDULER.lock();
+            smp::next_process(cpu, |run_queue| scheduler.pick_next(run_queue))
+        };
+        if let Some(pid) = next {
+            if let Ok(thread) = activate_process(cpu, pid) {
+                unsafe { thread.run() }
+            }
+            continue;
+        }
+        interrupts::enable_and_hlt();
+    }
+}
+
+/// Makes the process `Active` on the processor, and loads it's address space.
+/// A process whose memory can't be mapped is terminated like a faulted process (SIGSEGV).
+fn activate_process(cpu: &Cpu, pid: usize) -> Result<Thread, ()> {
+    let (thread, data) = {
+        let mut scheduler = KERNEL_SCHEDULER.lock();
+        let prepared = scheduler.prepare_process(pid)?;
+        // set while the scheduler is locked, so killing the process sees it runs here
+        cpu.set_current_pid(Some(pid));
+        prepared
+    };
+    if data.load_address_space().is_err() {
+        error!("failed to map the memory of process {:#x}, terminating it", pid);
+        terminate(pid, signals::exit_status(SIGSEGV))?;
+        return Err(());
+    }
+    Ok(thread)
 }
 
 pub fn get_process_info(pid: usize) -> Option<ProcessData> {
-    KERNEL_SCHEDULER.try_lock().unwrap().get_process_info(pid).ok()
-}
-
-pub fn kill_process(pid: usize) -> ! {
-    let process = { KERNEL_SCHEDULER.try_lock().unwrap().terminate_process(pid) };
-    match process {
-        Ok(()) => {
-            info!("succesfully killed process: {:#x}", pid);
-            if pid == 0 {
-                // Shutdown
-                exit_qemu(crate::panic::QemuExitCode::Success);
-            } else {
-                execute_process(pid -1)
+    KERNEL_SCHEDULER.lock().get_process_info(pid).ok()
+}
+
+/// Kills the process and it's descendants, and reports it to it's parent.
+/// Returns only if the current process is still alive.
+pub fn kill_process(pid: usize) -> Result<(), ()> {
+    terminate(pid, KILLED_EXIT_STATUS)?;
+    info!("succesfully killed process: {:#x}", pid);
+    Ok(())
+}
+
+/// Terminates the current process and it's descendants, it's parent continues with `exit_status`.
+/// Returns only if there is no current process.
+pub fn exit_current_process(exit_status: u8) -> Result<(), ()> {
+    let pid = get_current_pid().ok_or(())?;
+    info!("process {:#x} exited with status {}", pid, exit_status);
+    terminate(pid, exit_status)
+}
+
+/// Terminates the process and it's descendants, and reports the exit status to it's parent.
+/// Returns only if the current process is still alive.
+fn terminate(pid: usize, exit_status: u8) -> Result<(), ()> {
+    let current = smp::current();
+    let (parent, released) = {
+        let mut scheduler = KERNEL_SCHEDULER.lock();
+        let parent = scheduler.get_process_info(pid)?.parent;
+        let result = scheduler.terminate_process(pid, |pid| smp::is_running_elsewhere(current, pid));
+        if let Err(()) = result {
+            error!("failed to terminate process: {:#x}", pid);
+            return Err(());
+        }
+        if let Some(process) = parent.and_then(|parent| scheduler.get_process_mut(parent).ok()) {
+            process.signals.send(SIGCHLD).ok();
+        }
+        let parent = parent.filter(|parent| scheduler.child_exited(*parent, pid, exit_status) == Ok(true));
+        (parent, scheduler.take_released_resources())
+    };
+    // closing the files may wake processes, which locks the scheduler, and unmapping shared memory may shoot down TLBs
+    drop(released);
+
+    if pid == 0 {
+        // Shutdown
+        let exit_code = match exit_status {
+            0 | KILLED_EXIT_STATUS => QemuExitCode::Success,
+            _ => QemuExitCode::Failed,
+        };
+        exit_qemu(exit_code);
+    }
+    if let Some(parent) = parent {
+        smp::enqueue(parent);
+    }
+    smp::reschedule_others();
+
+    // the current process may be a descendant
+    let is_alive = match current.current_pid() {
+        Some(pid) => get_process_info(pid).is_some(),
+        None => true,
+    };
+    if !is_alive {
+        current.set_current_pid(None);
+        schedule()
+    }
+    Ok(())
+}
+
+/// Sends a signal to a process. A signal that terminates the process terminates it now with it's descendants,
+/// otherwise it's pending until the process returns to userland (see `handle_signals`).
+/// SIGCONT continues a `Stopped` process, and a process that sleeps on a wait queue is woken to handle the signal.
+///
+/// Returns only if the current process is still alive.
+pub fn send_signal(pid: usize, signal: usize) -> Result<(), ()> {
+    let (terminates, continued, interrupted) = {
+        let mut scheduler = KERNEL_SCHEDULER.lock();
+        let terminates = scheduler.get_process_mut(pid)?.signals.send(signal)?;
+        // the process continues even if SIGCONT is blocked, a handler runs once it does
+        let continued = signal == SIGCONT && scheduler.continue_process(pid).is_ok();
+        (terminates, continued, interrupt_sleeper(&mut scheduler, pid))
+    };
+    if continued {
+        info!("process {:#x} is continued", pid);
+    }
+    let woken = finish_interrupt(pid, interrupted);
+    if continued && !woken {
+        smp::enqueue(pid);
+    }
+    if terminates {
+        info!("process {:#x} is terminated by signal {}", pid, signal);
+        terminate(pid, signals::exit_status(signal))?;
+    }
+    Ok(())
+}
+
+/// Wakes a sleeper or a `Paused` parent that has a signal to deliver, whatever it waits for (see `Scheduler::interrupt_sleep`).
+/// Returns whether it was queued and the wait it slept in, pass them to `finish_interrupt` once the scheduler is unlocked.
+fn interrupt_sleeper(scheduler: &mut Scheduler, pid: usize) -> Option<(bool, Option<u64>)> {
+    let process = scheduler.get_process(pid).ok()?;
+    let wait = process.sleeping;
+    if !process.signals.has_deliverable() {
+        return None;
+    }
+    Some((scheduler.interrupt_sleep(pid).ok()?, wait))
+}
+
+/// Finishes waking an interrupted sleeper (see `interrupt_sleeper`), returns whether it was queued to a processor
+fn finish_interrupt(pid: usize, interrupted: Option<(bool, Option<u64>)>) -> bool {
+    let (blocked, wait) = match interrupted {
+        Some(interrupted) => interrupted,
+        None => return false,
+    };
+    // an interrupted futex waiter leaves it's queue, the futexes are locked before the scheduler
+    if let Some(wait) = wait {
+        futex::cancel(Waiter { pid, wait });
+    }
+    if blocked {
+        smp::enqueue(pid);
+    }
+    blocked
+}
+
+/// Sends a signal to every process of a process group (see `send_signal`), fails if the group is empty.
+///
+/// The current process is signaled last, so the group is signaled before it's terminated.
+pub fn send_group_signal(pgid: usize, signal: usize) -> Result<(), ()> {
+    let mut members = KERNEL_SCHEDULER.lock().process_group(pgid);
+    let current = get_current_pid();
+    members.sort_by_key(|pid| Some(*pid) == current);
+
+    // a member may be terminated with the descendants of another member
+    let mut result = Err(());
+    for pid in members {
+        if send_signal(pid, signal).is_ok() {
+            result = Ok(());
+        }
+    }
+    result
+}
+
+/// Returns whether a signal is delivered to the current process when it returns to userland,
+/// a syscall that waits for input returns early so the signal is handled (see `syscalls::status::RESTART`)
+pub fn has_pending_signals() -> bool {
+    let pid = match get_current_pid() {
+        Some(pid) => pid,
+        None => return false,
+    };
+    matches!(KERNEL_SCHEDULER.lock().get_process(pid), Ok(process) if process.signals.has_deliverable())
+}
+
+/// What the current process does after a signal is delivered
+enum Delivered {
+    /// Continues from the thread, a handler or the restarted syscall
+    Run(Thread),
+    /// Stopped by the signal, the parent is woken if it waits for the process
+    Stop(usize, Option<usize>),
+    /// Terminated by the signal
+    Terminate(usize),
+}
+
+/// Delivers a pending signal of the current process before it returns to userland from a syscall or an interrupt.
+///
+/// The process continues from the signal's handler, it's stopped if the signal's action is to stop,
+/// or it's terminated if the handler can't run (the stack has no room for the signal frame) or the signal's action is to terminate.
+/// Returns if the interrupted code is the kernel or if no signal is delivered.
+///
+/// # Arguments
+///  - `process_context` & `registers`, the context the process returns to, it's saved for the handler
+pub fn handle_signals(process_context: &InterruptStackFrame, registers: &Registers) {
+    deliver_signal(process_context, registers, false)
+}
+
+/// Makes the current process issue it's syscall again after a pending signal is delivered (see `handle_signals`),
+/// the syscall is restarted even if the signal is no longer pending.
+/// Returns only if the current process isn't `Active`.
+///
+/// # Arguments
+///  - `process_context` & `registers`, the context of the syscall, the registers must hold it's number & arguments
+pub fn restart_syscall(process_context: &InterruptStackFrame, registers: &Registers) {
+    deliver_signal(process_context, registers, true)
+}
+
+fn deliver_signal(process_context: &InterruptStackFrame, registers: &Registers, restart: bool) {
+    if process_context.code_segment & 0b11 != 3 {
+        return;
+    }
+    let cpu = smp::current();
+    let pid = match cpu.current_pid() {
+        Some(pid) => pid,
+        None => return,
+    };
+
+    let delivered = {
+        let mut scheduler = KERNEL_SCHEDULER.lock();
+        let process = match scheduler.get_process_mut(pid) {
+            Ok(process) if process.internal_data.state == ProcessState::Active => process,
+            _ => return,
+        };
+        let pending = process.signals.take_pending();
+        if pending.is_none() && !restart {
+            return;
+        }
+        process.save_state(process_context, registers);
+        if restart {
+            process.restart_syscall();
+        }
+        let parent = process.internal_data.parent;
+
+        match pending {
+            None => Delivered::Run(process.prepare_execution()),
+            Some((signal, Delivery::Handle(action))) => match process.enter_signal_handler(signal, action) {
+                Ok(()) => Delivered::Run(process.prepare_execution()),
+                Err(()) => Delivered::Terminate(SIGSEGV),
+            },
+            Some((signal, Delivery::Stop)) => {
+                let woken = match scheduler.stop_process(pid, signal) {
+                    Ok(woken) => woken,
+                    Err(()) => return,
+                };
+                if let Some(process) = parent.and_then(|parent| scheduler.get_process_mut(parent).ok()) {
+                    process.signals.send(SIGCHLD).ok();
+                }
+                // set while the scheduler is locked, so continuing the process doesn't run it here too
+                cpu.set_current_pid(None);
+                Delivered::Stop(signal, woken)
             }
+            Some((signal, _)) => Delivered::Terminate(signal),
         }
-        Err(()) => error!("failed to kill process: {:#x}", pid),
+    };
+
+    match delivered {
+        Delivered::Run(thread) => unsafe { thread.run() },
+        Delivered::Stop(signal, woken) => {
+            info!("process {:#x} is stopped by signal {}", pid, signal);
+            if let Some(parent) = woken {
+                smp::enqueue(parent);
+            }
+            schedule()
+        }
+        Delivered::Terminate(signal) => {
+            info!("process {:#x} is terminated by signal {}", pid, signal);
+            terminate(pid, signals::exit_status(signal)).ok();
+        }
+    }
+}
+
+/// Continues the current process from the context saved in it's signal frame (see `Process::return_from_signal_handler`).
+/// Returns only if the frame isn't in the process' stack.
+pub fn return_from_signal(frame_address: u64) -> Result<(), ()> {
+    let pid = get_current_pid().ok_or(())?;
+    let thread = {
+        let mut scheduler = KERNEL_SCHEDULER.lock();
+        let process = scheduler.get_process_mut(pid)?;
+        process.return_from_signal_handler(frame_address)?;
+        process.prepare_execution()
+    };
+    unsafe { thread.run() }
+}
+
+/// Replaces the current process' action of a signal if an action is given, returns the previous action
+pub fn set_signal_action(signal: usize, action: Option<SignalAction>) -> Result<SignalAction, ()> {
+    let pid = get_current_pid().ok_or(())?;
+    let signals = &mut KERNEL_SCHEDULER.lock().get_process_mut(pid)?.signals;
+    match action {
+        Some(action) => signals.set_action(signal, action),
+        None => signals.action(signal),
     }
-    loop {}
 }
 
-pub fn get_current_pid() -> usize {
-    KERNEL_SCHEDULER.try_lock().unwrap().next_pid() -1
-}
\ No newline at end of file
+/// Changes the signals the current process blocks (see `signals::mask`), returns the previously blocked signals
+pub fn change_blocked_signals(operation: u64, signals: u64) -> Result<u64, ()> {
+    let pid = get_current_pid().ok_or(())?;
+    KERNEL_SCHEDULER.lock().get_process_mut(pid)?.signals.change_blocked(operation, signals)
+}
+
+/// Sends SIGALRM to the current process at the uptime `deadline` (in nanoseconds), `None` cancels the alarm.
+/// Returns the previous alarm.
+pub fn set_alarm(deadline: Option<u64>) -> Result<Option<u64>, ()> {
+    let pid = get_current_pid().ok_or(())?;
+    Ok(KERNEL_SCHEDULER.lock().get_process_mut(pid)?.signals.set_alarm(deadline))
+}
+
+/// Sends SIGALRM to the processes whose alarm expired by `now` (in uptime nanoseconds), called by the timer interrupt.
+///
+/// Like `send_signal` a sleeper is woken to handle it, it's queued by `queue_woken_processes`.
+/// A SIGALRM that terminates the process is left pending until it returns to userland (see `handle_signals`),
+/// the interrupted code may be the process' own syscall. An alarm that doesn't fit `WOKEN` expires at the next tick.
+pub fn expire_alarms(now: u64) {
+    KERNEL_SCHEDULER.lock().expire_alarms(now, WOKEN.room(), |pid, sleeping| {
+        WOKEN.push((pid, sleeping)).ok();
+    });
+}
+
+/// Terminates the current process after it faulted in userland (SIGSEGV), called by the exception handlers.
+///
+/// A fault can't be handled, the faulting context isn't saved. Returns only if there is no current process.
+pub fn terminate_faulted_process() {
+    if let Some(pid) = get_current_pid() {
+        error!("process {:#x} faulted, terminating it", pid);
+        terminate(pid, signals::exit_status(SIGSEGV)).ok();
+    }
+}
+
+/// Moves a process to the process group `pgid`, a new group is created if `pgid` is the process' pid.
+/// The process must be the current process or a child of it (see `Scheduler::set_process_group`).
+pub fn set_process_group(pid: usize, pgid: usize) -> Result<(), ()> {
+    let caller = get_current_pid().ok_or(())?;
+    KERNEL_SCHEDULER.lock().set_process_group(caller, pid, pgid)
+}
+
+pub fn get_process_group(pid: usize) -> Result<usize, ()> {
+    Ok(KERNEL_SCHEDULER.lock().get_process(pid)?.internal_data.pgid)
+}
+
+/// Sets the nice value of the current process or of a child of it (see `Scheduler::set_priority`)
+pub fn set_priority(pid: usize, nice: i64) -> Result<(), ()> {
+    let caller = get_current_pid().ok_or(())?;
+    KERNEL_SCHEDULER.lock().set_priority(caller, pid, nice)
+}
+
+/// Returns whether a process group of the session `sid` has processes
+pub fn is_process_group(pgid: usize, sid: usize) -> bool {
+    let scheduler = KERNEL_SCHEDULER.lock();
+    // a group never spans sessions, so any member tells the group's session
+    let member = scheduler.process_group(pgid).first().copied();
+    member.is_some_and(|pid| scheduler.get_process(pid).is_ok_and(|process| process.internal_data.sid == sid))
+}
+
+/// Returns the session of the current process
+pub fn get_session() -> Result<usize, ()> {
+    let pid = get_current_pid().ok_or(())?;
+    Ok(KERNEL_SCHEDULER.lock().get_process(pid)?.internal_data.sid)
+}
+
+/// Makes the current process the leader of a new session and of a new process group, returns the session id
+pub fn create_session() -> Result<usize, ()> {
+    let pid = get_current_pid().ok_or(())?;
+    KERNEL_SCHEDULER.lock().create_session(pid)
+}
+
+/// Returns the information of every process
+pub fn list_processes() -> Vec<ProcessInfo> {
+    KERNEL_SCHEDULER.lock().processes().map(|process| process.info()).collect()
+}
+
+/// Returns the pid of the process that runs on the current processor
+pub fn get_current_pid() -> Option<usize> {
+    smp::current().current_pid()
+}
+
+/// Returns whether the addresses from `start` to `end` are in the current process' memory (see `ProcessData::owns`)
+pub fn owns_memory(start: u64, end: u64) -> bool {
+    let scheduler = KERNEL_SCHEDULER.lock();
+    get_current_pid().map_or(false, |pid| {
+        matches!(scheduler.get_process(pid), Ok(process) if process.internal_data.owns(start, end))
+    })
+}
+
+/// Returns the file opened at `fd` by the current process
+pub fn get_file(fd: usize) -> Option<Arc<dyn File>> {
+    let pid = get_current_pid()?;
+    KERNEL_SCHEDULER.lock().get_process(pid).ok()?.files.get(fd)
+}
+
+/// Updates the current process file descriptor table with the given operation.
+///
+/// The operation updates a copy of the table while the scheduler is unlocked, so the closed files are released
+/// after the copy replaces the table (closing a file may wake processes, which locks the scheduler).
+pub fn update_files<T>(operation: impl FnOnce(&mut FileDescriptorTable) -> Result<T, ()>) -> Result<T, ()> {
+    let pid = get_current_pid().ok_or(())?;
+    let mut files = KERNEL_SCHEDULER.lock().get_process(pid)?.files.clone();
+    let result = operation(&mut files);
+    let previous = mem::replace(&mut KERNEL_SCHEDULER.lock().get_process_mut(pid)?.files, files);
+    drop(previous);
+    result
+}
+
+/// Returns the current process working directory
+pub fn get_working_directory() -> String {
+    let scheduler = KERNEL_SCHEDULER.lock();
+    get_current_pid()
+        .ok_or(())
+        .and_then(|pid| scheduler.get_process(pid))
+        .map(|process| process.working_directory.clone())
+        .unwrap_or_else(|()| String::from("/"))
+}
+
+/// Changes the current process working directory, the path must be absolute
+pub fn set_working_directory(path: String) -> Result<(), ()> {
+    let pid = get_current_pid().ok_or(())?;
+    KERNEL_SCHEDULER.lock().get_process_mut(pid)?.working_directory = path;
+    Ok(())
+}
+
+/// Moves the current process' program break, returns the new break (see `ProcessData::change_break`)
+pub fn change_break(address: u64) -> Result<u64, ()> {
+    let pid = get_current_pid().ok_or(())?;
+    let (program_break, reserved) = KERNEL_SCHEDULER.lock().get_process_mut(pid)?.internal_data.change_break(address)?;
+    // the scheduler is unlocked, mapping may shoot down the TLB of other processors
+    if let Some(region) = reserved {
+        unsafe { mmap(region, USER_DATA_FLAGS)? };
+    }
+    Ok(program_break)
+}
+
+/// Maps zeroed memory to the current process, returns it's address
+pub fn map_memory(length: usize) -> Result<u64, ()> {
+    let pid = get_current_pid().ok_or(())?;
+    let region = KERNEL_SCHEDULER.lock().get_process_mut(pid)?.internal_data.map_memory(length)?;
+    let address = region.first_page();
+    unsafe { mmap(region, USER_DATA_FLAGS)? };
+    Ok(address)
+}
+
+/// Unmaps memory mapped by `map_memory` from the current process and frees it
+pub fn unmap_memory(address: u64, length: usize) -> Result<(), ()> {
+    let pid = get_current_pid().ok_or(())?;
+    let region = KERNEL_SCHEDULER.lock().get_process_mut(pid)?.internal_data.unmap_memory(address, length)?;
+    // the scheduler is unlocked, the pages go back to the kernel before their frames can be allocated again
+    release_user_region(region);
+    Ok(())
+}
+
+/// Maps a shared memory segment to the current process at `address`, or at an address the kernel chooses if it's zero.
+/// Returns the address of the mapping (see `SharedMemory::map`)
+pub fn map_shared_memory(segment: &SharedMemory, address: u64) -> Result<u64, ()> {
+    let pid = get_current_pid().ok_or(())?;
+    let mapping = segment.map((address != 0).then_some(address))?;
+    let (address, region) = (mapping.address(), mapping.region.clone());
+    KERNEL_SCHEDULER.lock().get_process_mut(pid)?.internal_data.shared_mappings.push(mapping);
+    unsafe { mmap(region, USER_DATA_FLAGS)? };
+    Ok(address)
+}
+
+/// Unmaps the shared memory segment mapped at `address` by `map_shared_memory` from the current process
+pub fn unmap_shared_memory(address: u64) -> Result<(), ()> {
+    let pid = get_current_pid().ok_or(())?;
+    let mapping = KERNEL_SCHEDULER.lock().get_process_mut(pid)?.internal_data.unmap_shared_memory(address)?;
+    // the scheduler is unlocked, the last mapping of the address unmaps it's pages
+    drop(mapping);
+    Ok(())
+}
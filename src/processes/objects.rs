@@ -1,18 +1,50 @@
 //! this module defines thread and object structs
 
-use core::arch::asm;
+use alloc::{string::String, sync::Arc, vec::Vec};
+use core::{arch::asm, mem, slice};
 use log::info;
 use x86_64::structures::idt::InterruptStackFrame;
 
 use crate::{
+    fs::descriptors::FileDescriptorTable,
     interrupts::get_user_selectors,
+    ipc::shm::SharedMapping,
     memory::{
-        get_linear_addr, get_page_frame, kfree, kmalloc, mmap,
+        self, get_linear_addr, get_page_frame, kfree, kmalloc, mmap,
         paging::EntryFlags,
+        update_pages_access_policy,
         types::{VirtualMemoryRegion, PAGE_SIZE},
     },
 };
+
+use super::{
+    elf::ProgramImage,
+    signals::{SignalAction, Signals},
+};
+
+pub use abi::process::{ProcessInfo, ProcessState, NO_PARENT};
+
 const PAGE_INDEX: u64 = 0xFFF;
+/// The arithmetic & direction flags, a signal handler may change them before it returns
+const USER_FLAGS_MASK: u64 = 0xCD5;
+/// The stack below the stack pointer that the interrupted code may use (the System V red zone)
+const RED_ZONE_SIZE: u64 = 128;
+/// The flags of the process' stack, heap & mapped memory
+pub const USER_DATA_FLAGS: EntryFlags = EntryFlags::from_bits_truncate(
+    EntryFlags::PRESENT.bits() | EntryFlags::WRITABLE.bits() | EntryFlags::USER.bits(),
+);
+/// Interrupts are enabled in userland (so the timer can preempt processes), bit 1 is reserved and always set
+const USER_RFLAGS: u64 = 0x202;
+/// The stack of a loaded program, the programs of the kernel (see `userland`) have a single page stack
+const PROGRAM_STACK_PAGES: usize = 4;
+/// The program's arguments are copied to the top of it's stack
+const MAX_ARGUMENTS_SIZE: usize = PAGE_SIZE;
+/// The pages reserved for the program break when the heap is first used, the break moves only within them
+pub const HEAP_PAGES: usize = 256;
+/// The largest region a single MMAP maps
+pub const MAX_MAPPING_PAGES: usize = 0x400;
+/// The length of the syscall instruction (`int 0x80`), a restarted syscall is issued again from it
+const SYSCALL_INSTRUCTION_SIZE: u64 = 2;
 
 #[derive(Default, Clone, Copy)]
 pub struct Thread {
@@ -85,12 +117,15 @@ impl Thread {
                 ss: ds as u64,
                 ds: ds as u64,
                 rsp,
+                rflags: USER_RFLAGS,
                 ..Default::default()
             },
         }
     }
     /// Execute the current thread
     ///
+    /// `gs` isn't loaded, it's base points to the per-CPU data.
+    ///
     /// # Saftey
     ///
     /// The thread registers & selectors must be valid.
@@ -98,7 +133,7 @@ impl Thread {
     pub unsafe fn run(&self) -> ! {
         asm!(
         "mov rsp, {}",
-        "pop rax; mov ds, ax; mov es, ax; mov fs, ax; mov gs, ax",
+        "pop rax; mov ds, ax; mov es, ax; mov fs, ax",
         "pop rax; pop rbx; pop rcx; pop rdx; pop rsi; pop rdi; pop rbp;\
          pop r8; pop r9; pop r10; pop r11; pop r12; pop r13; pop r14; pop r15;",
         "iretq",
@@ -111,18 +146,52 @@ impl Thread {
     /// # Safety
     /// 
     /// This function must be called only from `Process::save_state`, otherwise it may lead to unpredictable behavior.
-    pub unsafe fn save_context(&mut self, new_context: &InterruptStackFrame) {
+    pub unsafe fn save_context(&mut self, new_context: &InterruptStackFrame, registers: &Registers) {
+        self.context.regisetrs = *registers;
         self.context.rip = new_context.instruction_pointer.as_u64();
         self.context.cs = new_context.code_segment;
         self.context.rsp = new_context.stack_pointer.as_u64();
         self.context.ss = new_context.stack_segment;
         self.context.rflags = new_context.cpu_flags;
     }
+
+    /// Sets the value the thread's syscall returns when it continues
+    pub fn set_return_value(&mut self, value: i64) {
+        self.context.regisetrs.rax = value;
+    }
+
+    /// Sets the first two arguments of the thread main (rdi & rsi)
+    pub fn set_arguments(&mut self, first: u64, second: u64) {
+        self.context.regisetrs.rdi = first;
+        self.context.regisetrs.rsi = second;
+    }
 }
 
 #[derive(Clone)]
 pub struct Process {
     pub internal_data: ProcessData,
+    /// The files opened by the process
+    pub files: FileDescriptorTable,
+    /// The absolute path relative paths are resolved from
+    pub working_directory: String,
+    /// The child the process is `Paused` until it exits
+    pub awaited_child: Option<usize>,
+    /// Whether the process is also woken when the awaited child stops
+    pub reports_stops: bool,
+    /// The exit statuses of the children that exited before the process waited for them
+    pub exited_children: Vec<(usize, u8)>,
+    /// Whether the process was queued to run, a process is started once
+    pub started: bool,
+    pub signals: Signals,
+    /// The signal that stopped the process while it's `Stopped`
+    pub stop_signal: Option<usize>,
+    /// The wait the process sleeps in (e.g. on a wait queue or a futex), it's `Blocked` once it's syscall returns.
+    /// Every wait has a new id, so a waker of an earlier wait doesn't wake the process (see `sync::Waiter`)
+    pub sleeping: Option<u64>,
+    /// The uptime (in nanoseconds) the timer of a `Blocked` process wakes it at, timers of earlier deadlines are ignored
+    pub deadline: Option<u64>,
+    /// Whether a `Blocked` sleeper issues it's syscall again when it continues, otherwise a signal makes it's syscall fail
+    pub restarts: bool,
     thread: Thread,
 }
 
@@ -133,7 +202,7 @@ impl Process {
     /// # Safety
     ///
     /// `process_code` must point to the process entry point or else unpredictable behavior may occur.  
-    pub unsafe fn new(pid: usize, process_code: u64) -> Self {
+    pub unsafe fn new(pid: usize, parent: Option<usize>, process_code: u64) -> Self {
         let (cs, ds) = get_user_selectors();
         let stack_top = kmalloc(PAGE_SIZE, PAGE_SIZE).unwrap();
 
@@ -142,10 +211,32 @@ impl Process {
         Process {
             internal_data: ProcessData {
                 pid,
+                parent,
+                pgid: pid,
+                sid: pid,
                 code_region: VirtualMemoryRegion::new(get_linear_addr(code_page_frame), code_page_frame, 1),
                 stack_region: VirtualMemoryRegion::new(get_linear_addr(stack_top), stack_top, 1),
                 state: ProcessState::Waiting,
+                loaded_program: false,
+                heap_region: None,
+                program_break: 0,
+                mappings: Vec::new(),
+                shared_mappings: Vec::new(),
+                nice: 0,
+                cpu_time: 0,
+                vruntime: 0,
             },
+            files: FileDescriptorTable::with_standard_streams(),
+            working_directory: String::from("/"),
+            awaited_child: None,
+            reports_stops: false,
+            exited_children: Vec::new(),
+            started: false,
+            signals: Signals::new(),
+            stop_signal: None,
+            sleeping: None,
+            deadline: None,
+            restarts: false,
             thread: Thread::new(
                 get_linear_addr(code_page_frame) | (process_code & PAGE_INDEX),
                 cs,
@@ -155,39 +246,178 @@ impl Process {
         }
     }
 
-    /// Loads the process virtual address space and executes the process' main thread
-    pub fn execute(&self) -> ! {
-        unsafe {
-            mmap(
-                self.internal_data.stack_region.clone(),
-                EntryFlags::PRESENT | EntryFlags::WRITABLE | EntryFlags::USER,
-            )
-            .unwrap();
-            mmap(
-                self.internal_data.code_region.clone(),
-                EntryFlags::PRESENT | EntryFlags::USER,
-            )
-            .unwrap();
+    /// Creates a process that runs a loaded program, the process owns the program's frames.
+    ///
+    /// The program's main is called with the number of arguments and an array of pointers to them (like C's argv),
+    /// every argument is followed by a null byte.
+    ///
+    /// # Arguments
+    ///  - `arguments`, the program's arguments, every argument is followed by a null byte
+    pub fn from_program(pid: usize, parent: Option<usize>, image: ProgramImage, arguments: &[u8]) -> Result<Self, ()> {
+        if arguments.len() > MAX_ARGUMENTS_SIZE || !matches!(arguments.last(), None | Some(0)) {
+            image.release();
+            return Err(());
+        }
+        let stack_size = PROGRAM_STACK_PAGES * PAGE_SIZE;
+        let stack_bottom = match kmalloc(stack_size, PAGE_SIZE) {
+            Ok(stack_bottom) => stack_bottom,
+            Err(()) => {
+                image.release();
+                return Err(());
+            }
         };
+        let stack_top = get_linear_addr(stack_bottom) + stack_size as u64;
+        let (stack_pointer, argument_count, argument_vector) = unsafe { push_arguments(stack_top, arguments) };
+
+        let (cs, ds) = get_user_selectors();
+        let mut thread = Thread::new(image.entry, cs, ds, stack_pointer);
+        thread.set_arguments(argument_count, argument_vector);
 
+        Ok(Process {
+            internal_data: ProcessData {
+                pid,
+                parent,
+                pgid: pid,
+                sid: pid,
+                code_region: image.region,
+                stack_region: VirtualMemoryRegion::new(get_linear_addr(stack_bottom), stack_bottom, PROGRAM_STACK_PAGES),
+                state: ProcessState::Waiting,
+                loaded_program: true,
+                heap_region: None,
+                program_break: 0,
+                mappings: Vec::new(),
+                shared_mappings: Vec::new(),
+                nice: 0,
+                cpu_time: 0,
+                vruntime: 0,
+            },
+            files: FileDescriptorTable::with_standard_streams(),
+            working_directory: String::from("/"),
+            awaited_child: None,
+            reports_stops: false,
+            exited_children: Vec::new(),
+            started: false,
+            signals: Signals::new(),
+            stop_signal: None,
+            sleeping: None,
+            deadline: None,
+            restarts: false,
+            thread,
+        })
+    }
+
+    /// Returns a copy of the process' main thread to run with, the address space must be loaded first.
+    ///
+    /// The process itself is not copied, so it's resources (e.g. opened files) are owned only by the scheduler.
+    pub fn prepare_execution(&self) -> Thread {
         info!("executing process: {}", self.internal_data.pid);
-        unsafe { self.thread.run() }
+        self.thread
     }
 
-    /// Release the process' and thread' resources.
-    /// Note that the code page is not released because it can be shared
+    /// Returns the regions of the process' stack, code, heap & mapped memory, released with the process.
+    /// Note that the code page of a kernel function is not released because it can be shared
+    pub fn owned_regions(&self) -> Vec<VirtualMemoryRegion> {
+        let data = &self.internal_data;
+        let code_region = data.loaded_program.then_some(&data.code_region);
+        let regions = [Some(&data.stack_region), code_region, data.heap_region.as_ref()];
+        regions.into_iter().flatten().chain(&data.mappings).cloned().collect()
+    }
+
+    /// Release the process' and thread' resources, including it's heap & mapped memory (see `release_user_region`).
+    ///
+    /// Releasing may shoot down the TLB of other processors, so the scheduler must not be locked.
     pub fn release_resources(&self) {
         info!("releasing process {} resources", self.internal_data.pid);
-        kfree(
-            self.internal_data.stack_region.first_frame(),
-            self.internal_data.stack_region.size * PAGE_SIZE,
-            PAGE_SIZE,
-        );
+        for region in self.owned_regions() {
+            release_user_region(region);
+        }
     }
 
     /// Saves the current state of the process' thread.
-    pub fn save_state(&mut self, thread_context: &InterruptStackFrame) {
-        unsafe { self.thread.save_context(thread_context) };
+    pub fn save_state(&mut self, thread_context: &InterruptStackFrame, registers: &Registers) {
+        unsafe { self.thread.save_context(thread_context, registers) };
+    }
+
+    /// Sets the value the syscall the process was paused in returns
+    pub fn set_return_value(&mut self, value: i64) {
+        self.thread.set_return_value(value);
+    }
+
+    /// Makes the process issue the syscall it was interrupted in again when it continues,
+    /// the saved registers must hold the syscall's number & arguments
+    pub fn restart_syscall(&mut self) {
+        self.thread.context.rip -= SYSCALL_INSTRUCTION_SIZE;
+    }
+
+    /// Makes the process issue the syscall `number` with the arguments `first` & `second` when it continues,
+    /// in place of the syscall it was interrupted in (see `restart_syscall`)
+    pub fn reissue_syscall(&mut self, number: u64, first: u64, second: u64) {
+        self.thread.set_return_value(number as i64);
+        self.thread.set_arguments(first, second);
+        self.restart_syscall();
+    }
+
+    /// Makes the process continue from the handler of `signal`, the saved state is pushed to it's stack in a `SignalFrame`.
+    /// The signals of the action are blocked until the handler returns.
+    ///
+    /// The handler is called with the signal, and returns to the action's restorer with the stack pointer at the frame.
+    pub fn enter_signal_handler(&mut self, signal: usize, action: SignalAction) -> Result<(), ()> {
+        let context = self.thread.context;
+        let frame_size = mem::size_of::<SignalFrame>() as u64;
+        // the handler is entered like after a call, with the stack aligned to 16 bytes before the return address
+        let frame_address = context.rsp.checked_sub(RED_ZONE_SIZE + frame_size).ok_or(())? & !0xF;
+        let return_address = frame_address - 8;
+        if !self.internal_data.stack_contains(return_address, frame_address + frame_size) {
+            return Err(());
+        }
+
+        let frame = SignalFrame {
+            context,
+            blocked: self.signals.block_for_handler(signal, &action),
+            signal: signal as u64,
+        };
+        // the stack is mapped to it's linear address with the rest of the physical memory
+        unsafe {
+            *(frame_address as *mut SignalFrame) = frame;
+            *(return_address as *mut u64) = action.restorer;
+        }
+
+        self.thread.context.rip = action.handler;
+        self.thread.context.rsp = return_address;
+        self.thread.set_arguments(signal as u64, frame_address);
+        Ok(())
+    }
+
+    /// Makes the process continue from the context saved in the signal frame at `frame_address`,
+    /// and restores the signals blocked before the handler ran.
+    pub fn return_from_signal_handler(&mut self, frame_address: u64) -> Result<(), ()> {
+        let frame_end = frame_address.checked_add(mem::size_of::<SignalFrame>() as u64).ok_or(())?;
+        if frame_address % 8 != 0 || !self.internal_data.stack_contains(frame_address, frame_end) {
+            return Err(());
+        }
+        let frame = unsafe { *(frame_address as *const SignalFrame) };
+
+        // the frame is in userland memory, so the process may have changed it
+        let (cs, ds) = get_user_selectors();
+        let mut context = frame.context;
+        context.cs = cs as u64;
+        context.ss = ds as u64;
+        context.ds = ds as u64;
+        context.rflags = (context.rflags & USER_FLAGS_MASK) | USER_RFLAGS;
+        self.thread.context = context;
+        self.signals.set_blocked(frame.blocked);
+        Ok(())
+    }
+
+    /// Returns the process information shared with userland
+    pub fn info(&self) -> ProcessInfo {
+        ProcessInfo {
+            pid: self.internal_data.pid,
+            ppid: self.internal_data.parent.unwrap_or(NO_PARENT),
+            state: self.internal_data.state,
+            pgid: self.internal_data.pgid,
+            sid: self.internal_data.sid,
+        }
     }
 }
 
@@ -195,27 +425,190 @@ impl Process {
 #[derive(Clone, Debug)]
 pub struct ProcessData {
     pub pid: usize,
+    /// The process that created this process
+    pub parent: Option<usize>,
+    /// The process group, job control signals a whole group (e.g. the programs of a shell's pipeline)
+    pub pgid: usize,
+    /// The session, process groups move only within their session
+    pub sid: usize,
     pub code_region: VirtualMemoryRegion,
     pub stack_region: VirtualMemoryRegion,
     pub state: ProcessState,
+    /// Whether the code region is a loaded program, which is writable and released with the process
+    pub loaded_program: bool,
+    /// The pages reserved for the program break, reserved by the first BRK
+    pub heap_region: Option<VirtualMemoryRegion>,
+    /// The end of the heap, the heap starts at the heap region
+    pub program_break: u64,
+    /// The regions mapped by MMAP
+    pub mappings: Vec<VirtualMemoryRegion>,
+    /// The shared memory segments mapped by SHM_MAP, they're dropped once the scheduler is unlocked
+    pub shared_mappings: Vec<Arc<SharedMapping>>,
+    /// From `MIN_NICE` to `MAX_NICE`, the scheduling policy weights the CPU time of a process with a lower value less
+    pub nice: i64,
+    /// The CPU time (in nanoseconds) the process used, counted in timer ticks
+    pub cpu_time: u64,
+    /// The CPU time weighted by the nice value, the scheduling policy orders the waiting processes by it
+    pub vruntime: u64,
 }
 
-/// Process States
-/// 
-/// # Active
-/// 
-/// when the current process is running he is `Active`
-/// 
-/// # Waiting
-/// 
-/// If a process has never been executed then he is `Waiting` for execution
-/// 
-/// # Paused
-/// 
-/// If a process starts executing another process he becomes `Paused`
-#[derive(Clone, Copy, Debug)]
-pub enum ProcessState {
-    Active,
-    Waiting,
-    Paused,
+impl ProcessData {
+    /// Maps the process stack, code, heap & mapped regions to userland pages.
+    /// Fails if a page table can't be allocated, some of the regions may be mapped already.
+    ///
+    /// Mapping may shoot down the TLB of other processors, so the scheduler must not be locked.
+    pub fn load_address_space(&self) -> Result<(), ()> {
+        unsafe {
+            mmap(self.stack_region.clone(), USER_DATA_FLAGS)?;
+            let writable = if self.loaded_program { EntryFlags::WRITABLE } else { EntryFlags::empty() };
+            mmap(self.code_region.clone(), EntryFlags::PRESENT | EntryFlags::USER | writable)?;
+            for region in self.heap_region.iter().chain(&self.mappings) {
+                mmap(region.clone(), USER_DATA_FLAGS)?;
+            }
+            for mapping in self.shared_mappings.iter() {
+                mmap(mapping.region.clone(), USER_DATA_FLAGS)?;
+            }
+        };
+        Ok(())
+    }
+
+    /// Moves the program break to `address`, returns the new break.
+    /// The current break is returned if `address` is zero, the heap is reserved on the first call.
+    ///
+    /// A newly reserved heap region is returned too, it must be mapped (see `load_address_space`).
+    pub fn change_break(&mut self, address: u64) -> Result<(u64, Option<VirtualMemoryRegion>), ()> {
+        let reserved = match self.heap_region {
+            Some(_) => None,
+            None if address == 0 => {
+                let region = allocate_user_region(HEAP_PAGES)?;
+                self.program_break = region.first_page();
+                self.heap_region = Some(region.clone());
+                Some(region)
+            }
+            None => return Err(()),
+        };
+
+        if address != 0 {
+            // the heap region is reserved, a break can't be set before the first BRK returns it
+            let heap = self.heap_region.as_ref().ok_or(())?;
+            let start = heap.first_page();
+            if address < start || address > start + (heap.size * PAGE_SIZE) as u64 {
+                return Err(());
+            }
+            self.program_break = address;
+        }
+        Ok((self.program_break, reserved))
+    }
+
+    /// Allocates zeroed pages for `length` bytes, returns the region which must be mapped (see `load_address_space`)
+    pub fn map_memory(&mut self, length: usize) -> Result<VirtualMemoryRegion, ()> {
+        let pages = (length + PAGE_SIZE - 1) / PAGE_SIZE;
+        if pages == 0 || pages > MAX_MAPPING_PAGES {
+            return Err(());
+        }
+        let region = allocate_user_region(pages)?;
+        self.mappings.push(region.clone());
+        Ok(region)
+    }
+
+    /// Returns whether the addresses from `start` to `end` are in the process' stack
+    pub fn stack_contains(&self, start: u64, end: u64) -> bool {
+        region_contains(&self.stack_region, start, end)
+    }
+
+    /// Returns whether the addresses from `start` to `end` are in a single region of the process' memory
+    /// (the stack, code, heap, mapped & shared memory). The programs of the kernel own the kernel image too,
+    /// it's their code and constants (see `memory::is_kernel_image`)
+    pub fn owns(&self, start: u64, end: u64) -> bool {
+        let shared_regions = self.shared_mappings.iter().map(|mapping| &mapping.region);
+        let mut regions = [&self.stack_region, &self.code_region]
+            .into_iter()
+            .chain(&self.heap_region)
+            .chain(&self.mappings)
+            .chain(shared_regions);
+        regions.any(|region| region_contains(region, start, end))
+            || (!self.loaded_program && memory::is_kernel_image(start, end))
+    }
+
+    /// Removes the region mapped at `address` by `map_memory`, `length` must be the mapped length.
+    ///
+    /// The region is returned, it's pages must be unmapped before it's frames are freed.
+    pub fn unmap_memory(&mut self, address: u64, length: usize) -> Result<VirtualMemoryRegion, ()> {
+        let pages = (length + PAGE_SIZE - 1) / PAGE_SIZE;
+        let index = self
+            .mappings
+            .iter()
+            .position(|region| region.first_page() == address && region.size == pages)
+            .ok_or(())?;
+        Ok(self.mappings.remove(index))
+    }
+
+    /// Removes the shared memory segment mapped at `address` by SHM_MAP.
+    ///
+    /// The mapping is returned, it must be dropped after the scheduler is unlocked (see `SharedMapping`).
+    pub fn unmap_shared_memory(&mut self, address: u64) -> Result<Arc<SharedMapping>, ()> {
+        let index = self.shared_mappings.iter().position(|mapping| mapping.address() == address).ok_or(())?;
+        Ok(self.shared_mappings.remove(index))
+    }
+}
+
+/// Gives the pages of a userland region back to the kernel and frees it's frames.
+///
+/// The pages aren't accessible to userland before the frames can be allocated again,
+/// changing them may shoot down the TLB of other processors, so the scheduler must not be locked.
+pub fn release_user_region(region: VirtualMemoryRegion) {
+    let (frame, size) = (region.first_frame(), region.size);
+    unsafe { update_pages_access_policy(region, EntryFlags::PRESENT | EntryFlags::WRITABLE) };
+    kfree(frame, size * PAGE_SIZE, PAGE_SIZE);
+}
+
+/// Returns whether the addresses from `start` to `end` are in `region`
+fn region_contains(region: &VirtualMemoryRegion, start: u64, end: u64) -> bool {
+    let first = region.first_page();
+    first <= start && start <= end && end <= first + (region.size * PAGE_SIZE) as u64
+}
+
+/// Allocates zeroed frames for a userland region at their linear addresses
+fn allocate_user_region(pages: usize) -> Result<VirtualMemoryRegion, ()> {
+    let frame = kmalloc(pages * PAGE_SIZE, PAGE_SIZE)?;
+    let page = get_linear_addr(frame);
+    // the frames are mapped to their linear addresses with the rest of the physical memory
+    unsafe { slice::from_raw_parts_mut(page as *mut u8, pages * PAGE_SIZE).fill(0) };
+    Ok(VirtualMemoryRegion::new(page, frame, pages))
+}
+
+/// The state of a process that handles a signal, pushed to it's stack under the handler's return address
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct SignalFrame {
+    context: Context,
+    /// The signals blocked before the handler ran
+    blocked: u64,
+    signal: u64,
+}
+
+/// Copies the arguments to the top of the stack followed by an array of pointers to them,
+/// returns the stack pointer, the number of arguments and the address of the array.
+///
+/// The stack pointer is aligned like after a call instruction (to 16 bytes minus the return address).
+///
+/// # Safety
+///
+/// The stack must be mapped to it's linear address, and it must have room for the arguments.
+unsafe fn push_arguments(stack_top: u64, arguments: &[u8]) -> (u64, u64, u64) {
+    let strings = (stack_top - arguments.len() as u64) & !0b111;
+    slice::from_raw_parts_mut(strings as *mut u8, arguments.len()).copy_from_slice(arguments);
+
+    let count = arguments.iter().filter(|byte| **byte == 0).count();
+    // the array ends with a null pointer
+    let vector = (strings - (count as u64 + 1) * 8) & !0b1111;
+    let pointers = slice::from_raw_parts_mut(vector as *mut u64, count + 1);
+    let mut start = strings;
+    for (pointer, argument) in pointers.iter_mut().zip(arguments.split_inclusive(|byte| *byte == 0)) {
+        *pointer = start;
+        start += argument.len() as u64;
+    }
+    pointers[count] = 0;
+
+    (vector - 8, count as u64, vector)
 }
@@ -1,82 +1,609 @@
-//! This module defines a minimal schduler that uses a stack to manage it's processes
+//! This module defines the process table of the schduler, the processes that are ready to run
+//! are queued in the run queues of the processors (see `smp::Cpu`).
+//!
+//! The scheduling policy (see `SchedulingPolicy`) picks the process that runs next from a run queue,
+//! and weights the CPU time the processes use by their nice values.
 
-use alloc::vec::Vec;
+use alloc::{
+    collections::{BTreeMap, VecDeque},
+    sync::Arc,
+    vec::Vec,
+};
+use core::mem;
 use log::debug;
 use x86_64::structures::idt::InterruptStackFrame;
 
-use super::objects::{Process, ProcessData, ProcessState};
+use crate::{
+    fs::descriptors::FileDescriptorTable,
+    ipc::shm::SharedMapping,
+    memory::types::VirtualMemoryRegion,
+    sync::{futex, Waiter},
+    syscalls::{number, status, wait},
+    time::timer_wheel::TICK_NANOSECONDS,
+};
+
+use super::{
+    elf::ProgramImage,
+    objects::{release_user_region, Process, ProcessData, ProcessState, Registers, Thread},
+};
+
+pub use abi::{MAX_NICE, MIN_NICE};
+
+/// The weight of nice 0
+const NICE_0_WEIGHT: u64 = 1024;
+/// The weights of the nice values from `MIN_NICE`, every nice value weights about 25% more CPU time (like Linux's CFS)
+const NICE_WEIGHTS: [u64; (MAX_NICE - MIN_NICE + 1) as usize] = [
+    88761, 71755, 56483, 46273, 36291, 29154, 23254, 18705, 14949, 11916, 9548, 7620, 6100, 4904, 3906, 3121, 2501, 1991,
+    1586, 1277, 1024, 820, 655, 526, 423, 335, 272, 215, 172, 137, 110, 87, 70, 56, 45, 36, 29, 23, 18, 15,
+];
+/// The virtual runtime a process that waited is credited with, so an interactive process runs soon after it's woken
+/// without starving the processes that kept running
+const SLEEPER_CREDIT: u64 = TICK_NANOSECONDS;
+
+/// Decides which waiting process runs next, the scheduler keeps it's state in the processes' data
+pub trait SchedulingPolicy: Sync {
+    /// Charges the `nanoseconds` of CPU time a process used
+    fn charge(&self, process: &mut ProcessData, nanoseconds: u64);
+
+    /// Places a process that waits for a processor relative to `min_vruntime`, the virtual runtime the running processes reached,
+    /// so a process that was created or slept doesn't run until it catches up
+    fn place(&self, process: &mut ProcessData, min_vruntime: u64);
+
+    /// Whether `process` runs before `other`
+    fn runs_before(&self, process: &ProcessData, other: &ProcessData) -> bool;
+}
+
+/// A virtual runtime fair scheduler, the waiting process with the least CPU time weighted by it's nice value runs next.
+/// A process with nice 0 gets about 3 times the CPU time of a process with nice 5 while both are running.
+pub struct FairScheduling;
+
+impl FairScheduling {
+    fn weight(nice: i64) -> u64 {
+        NICE_WEIGHTS[(nice.clamp(MIN_NICE, MAX_NICE) - MIN_NICE) as usize]
+    }
+}
+
+impl SchedulingPolicy for FairScheduling {
+    fn charge(&self, process: &mut ProcessData, nanoseconds: u64) {
+        process.vruntime += nanoseconds * NICE_0_WEIGHT / FairScheduling::weight(process.nice);
+    }
+
+    fn place(&self, process: &mut ProcessData, min_vruntime: u64) {
+        process.vruntime = process.vruntime.max(min_vruntime.saturating_sub(SLEEPER_CREDIT));
+    }
+
+    fn runs_before(&self, process: &ProcessData, other: &ProcessData) -> bool {
+        process.vruntime < other.vruntime
+    }
+}
+
+/// The resources of removed processes, released once they're dropped.
+///
+/// Closing files may wake processes, which locks the scheduler, and releasing memory may shoot down the TLB
+/// of other processors, so they must be dropped after the scheduler is unlocked.
+#[derive(Default)]
+pub struct ReleasedResources {
+    files: Vec<FileDescriptorTable>,
+    shared_mappings: Vec<Arc<SharedMapping>>,
+    /// The stack, code, heap & mapped memory
+    regions: Vec<VirtualMemoryRegion>,
+    /// The waits the processes slept in, a futex waiter leaves it's queue
+    waiters: Vec<Waiter>,
+}
+
+impl Drop for ReleasedResources {
+    fn drop(&mut self) {
+        for waiter in self.waiters.drain(..) {
+            futex::cancel(waiter);
+        }
+        for region in self.regions.drain(..) {
+            release_user_region(region);
+        }
+    }
+}
 
 /// This object manages processes in CrabOS
-/// The current process is poped out of the stack when it exits.
 pub struct Scheduler {
-    processes_stack: Vec<Process>,
+    processes: BTreeMap<usize, Process>,
+    next_pid: usize,
+    /// The id of the next wait a process sleeps in (see `prepare_sleep`)
+    next_wait: u64,
+    /// The file tables of the removed processes, they're dropped once the scheduler is unlocked
+    /// because closing a file may wake processes (e.g. the readers of a pipe)
+    released: ReleasedResources,
+    policy: &'static dyn SchedulingPolicy,
+    /// The lowest virtual runtime a processor's running & queued processes reached (see `advance_min_vruntime`), it only grows.
+    /// The processes that slept aren't counted, they're placed relative to it when they're woken
+    min_vruntime: u64,
 }
 
 impl Scheduler {
     /// Creates an empty scheduler
     pub const fn empty() -> Self {
-       Scheduler { processes_stack: Vec::<Process>::new() }
+        Scheduler {
+            processes: BTreeMap::new(),
+            next_pid: 0,
+            next_wait: 0,
+            released: ReleasedResources::default(),
+            policy: &FairScheduling,
+            min_vruntime: 0,
+        }
+    }
+
+    /// Adds a new process object to the process table
+    pub fn push_process(&mut self, process_code: u64, parent: Option<usize>) -> usize {
+        let process = unsafe { Process::new(self.next_pid, parent, process_code) };
+        self.insert_process(process)
+    }
+
+    /// Adds a process running a loaded program to the process table (see `Process::from_program`)
+    pub fn push_program(&mut self, image: ProgramImage, arguments: &[u8], parent: Option<usize>) -> Result<usize, ()> {
+        let process = Process::from_program(self.next_pid, parent, image, arguments)?;
+        Ok(self.insert_process(process))
     }
 
-    /// Pushes a new process object to the scheduler's stack
-    pub fn push_process(&mut self, process_code: u64) -> usize {
-        let pid = self.next_pid();
-        self.processes_stack.push(unsafe { Process::new(pid, process_code) });
+    /// Inserts a process with the next pid, a child inherits the standard streams, the working directory,
+    /// the process group, the session and the nice value of it's parent
+    fn insert_process(&mut self, mut process: Process) -> usize {
+        let pid = self.next_pid;
+        self.next_pid += 1;
+        if let Some(parent) = process.internal_data.parent.and_then(|parent| self.processes.get(&parent)) {
+            process.files = FileDescriptorTable::inherit_standard_streams(&parent.files);
+            process.working_directory = parent.working_directory.clone();
+            process.internal_data.pgid = parent.internal_data.pgid;
+            process.internal_data.sid = parent.internal_data.sid;
+            process.internal_data.nice = parent.internal_data.nice;
+        }
+        // a new process doesn't run before the running processes until it used the CPU time they did
+        process.internal_data.vruntime = self.min_vruntime;
+        self.processes.insert(pid, process);
         pid
     }
 
-    /// Prepares the top process to start executing.
-    /// 1. pauses the previous process & saves its state
-    /// 2. activate the top process
-    /// 3. returns a clone of the top process to run with
-    /// 
-    /// # Safety 
-    /// 
-    /// This function must be followd by and `Process::execute`
-    pub fn get_process(&mut self, pid: usize) -> Result<Process, ()> {
-        if pid > self.processes_stack.len() {
-           return Err(())
+    /// Marks a process that never ran as started, before it's queued to a processor.
+    /// Fails if the process isn't a child of `parent` or if it was started already.
+    pub fn start_process(&mut self, pid: usize, parent: Option<usize>) -> Result<(), ()> {
+        let process = self.get_process_mut(pid)?;
+        if process.started || process.internal_data.state != ProcessState::Waiting || process.internal_data.parent != parent {
+            return Err(());
         }
+        process.started = true;
+        // the process may be started long after it was created
+        self.place(pid);
+        Ok(())
+    }
 
-        self.processes_stack[pid].internal_data.state = ProcessState::Active;
-        
-        Ok(self.processes_stack[pid].clone())
+    /// Prepares a waiting process to start executing.
+    /// 1. activate the process
+    /// 2. returns a copy of the process' thread to run with, and the process data to load it's address space
+    ///
+    /// # Safety
+    ///
+    /// This function must be followd by a `Thread::run`
+    pub fn prepare_process(&mut self, pid: usize) -> Result<(Thread, ProcessData), ()> {
+        let process = self.get_process_mut(pid)?;
+        if process.internal_data.state != ProcessState::Waiting {
+            return Err(());
+        }
+        process.internal_data.state = ProcessState::Active;
+
+        Ok((process.prepare_execution(), process.internal_data.clone()))
     }
 
-    /// Returns the next process id 
-    pub fn next_pid(&self) -> usize {
-        if self.processes_stack.len() == 0 {
-            return 0
+    /// Returns a reference to a process
+    pub fn get_process(&self, pid: usize) -> Result<&Process, ()> {
+        self.processes.get(&pid).ok_or(())
+    }
+
+    /// Returns a mutable reference to a process
+    pub fn get_process_mut(&mut self, pid: usize) -> Result<&mut Process, ()> {
+        self.processes.get_mut(&pid).ok_or(())
+    }
+
+    /// Returns a mutable reference to a process that runs on a processor, terminated processes are not returned
+    fn get_active_process(&mut self, pid: usize) -> Result<&mut Process, ()> {
+        let process = self.get_process_mut(pid)?;
+        if process.internal_data.state != ProcessState::Active {
+            return Err(());
         }
-        self.processes_stack.len()
+        Ok(process)
+    }
+
+    /// Returns the processes of the table
+    pub fn processes(&self) -> impl Iterator<Item = &Process> {
+        self.processes.values()
+    }
+
+    /// Returns the processes of the table for changes
+    pub fn processes_mut(&mut self) -> impl Iterator<Item = &mut Process> {
+        self.processes.values_mut()
+    }
+
+    /// Returns the next process id
+    pub fn next_pid(&self) -> usize {
+        self.next_pid
     }
 
     /// Returns the process internal data.
     pub fn get_process_info(&self, pid: usize) -> Result<ProcessData, ()> {
-        if pid > self.processes_stack.len() {
-            return Err(())
-         }
-        Ok(self.processes_stack[pid].internal_data.clone())
+        Ok(self.get_process(pid)?.internal_data.clone())
+    }
+
+    /// Returns the processes of a process group
+    pub fn process_group(&self, pgid: usize) -> Vec<usize> {
+        self.processes
+            .values()
+            .filter(|process| process.internal_data.pgid == pgid)
+            .map(|process| process.internal_data.pid)
+            .collect()
     }
 
-    /// Releases the process resources and remove it from the stack
-    pub fn terminate_process(&mut self, pid: usize) -> Result<(), ()> {
-        if pid > self.processes_stack.len() {
-            return Err(())
+    /// Moves a process to the process group `pgid`, a new group is created if `pgid` is the process' pid.
+    ///
+    /// The process must be `caller` or a child of it in the caller's session, a session leader can't move,
+    /// and an existing group must be in the same session.
+    pub fn set_process_group(&mut self, caller: usize, pid: usize, pgid: usize) -> Result<(), ()> {
+        let sid = self.get_process(caller)?.internal_data.sid;
+        let process = &self.get_process(pid)?.internal_data;
+        if (pid != caller && process.parent != Some(caller)) || process.sid != sid || process.sid == pid {
+            return Err(());
         }
-        for child_pid in (pid..=self.next_pid() -1).rev() {
-            debug!("poping child: {:#x}", child_pid);
-            self.processes_stack.last().ok_or(())?.release_resources();
-            self.processes_stack.pop();
+        let group_exists = self
+            .processes
+            .values()
+            .any(|process| process.internal_data.pgid == pgid && process.internal_data.sid == sid);
+        if pgid != pid && !group_exists {
+            return Err(());
         }
+        self.get_process_mut(pid)?.internal_data.pgid = pgid;
         Ok(())
     }
 
-    /// Pauses the a given process and saves it's state. Used when executing a new process.
-    pub fn pause_process(&mut self, pid: usize, process_context: &InterruptStackFrame) {    
-        self.processes_stack[pid].internal_data.state = ProcessState::Paused;
-        self.processes_stack[pid].save_state(process_context);
+    /// Makes a process the leader of a new session and of a new process group, returns the session id.
+    /// A process group leader can't create a session, so a group never spans sessions.
+    pub fn create_session(&mut self, pid: usize) -> Result<usize, ()> {
+        let data = &mut self.get_process_mut(pid)?.internal_data;
+        if data.pgid == pid {
+            return Err(());
+        }
+        data.pgid = pid;
+        data.sid = pid;
+        Ok(pid)
+    }
+
+    /// Returns the process and all of it's descendants
+    fn descendants(&self, pid: usize) -> Vec<usize> {
+        let mut descendants = Vec::from([pid]);
+        let mut index = 0;
+        while index < descendants.len() {
+            let parent = descendants[index];
+            descendants.extend(
+                self.processes
+                    .values()
+                    .filter(|process| process.internal_data.parent == Some(parent))
+                    .map(|process| process.internal_data.pid),
+            );
+            index += 1;
+        }
+        descendants
+    }
+
+    /// Terminates the process and it's descendants.
+    ///
+    /// Processes that are `Active` on other processors are marked `Terminated` (they still use their stack),
+    /// the rest are removed from the table and their resources are released.
+    ///
+    /// # Arguments
+    ///  - `is_running_elsewhere`, whether a process is running on another processor
+    pub fn terminate_process(&mut self, pid: usize, is_running_elsewhere: impl Fn(usize) -> bool) -> Result<(), ()> {
+        self.get_process(pid)?;
+
+        for child_pid in self.descendants(pid).into_iter().rev() {
+            debug!("terminating: {:#x}", child_pid);
+            if is_running_elsewhere(child_pid) {
+                self.get_process_mut(child_pid)?.internal_data.state = ProcessState::Terminated;
+            } else {
+                self.remove_process(child_pid)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes a process from the table, it's resources are released by `take_released_resources`
+    pub fn remove_process(&mut self, pid: usize) -> Result<(), ()> {
+        let mut process = self.processes.remove(&pid).ok_or(())?;
+        debug!("removing process {} resources", pid);
+        let released = &mut self.released;
+        released.regions.append(&mut process.owned_regions());
+        released.files.push(mem::replace(&mut process.files, FileDescriptorTable::empty()));
+        released.shared_mappings.append(&mut process.internal_data.shared_mappings);
+        if let Some(wait) = process.sleeping {
+            released.waiters.push(Waiter { pid, wait });
+        }
+        Ok(())
+    }
+
+    /// Returns the resources of the removed processes, drop them after the scheduler is unlocked
+    pub fn take_released_resources(&mut self) -> ReleasedResources {
+        mem::take(&mut self.released)
     }
-    
-}
 
+    /// Pauses the a given process until `child` exits (or stops if `reports_stops`), and saves it's state.
+    pub fn pause_process(
+        &mut self,
+        pid: usize,
+        child: usize,
+        reports_stops: bool,
+        process_context: &InterruptStackFrame,
+        registers: &Registers,
+    ) -> Result<(), ()> {
+        let process = self.get_active_process(pid)?;
+        process.internal_data.state = ProcessState::Paused;
+        process.awaited_child = Some(child);
+        process.reports_stops = reports_stops;
+        process.save_state(process_context, registers);
+        Ok(())
+    }
+
+    /// Saves the state of a preempted process, it waits until it's processor runs it again.
+    pub fn preempt_process(&mut self, pid: usize, process_context: &InterruptStackFrame, registers: &Registers) -> Result<(), ()> {
+        let process = self.get_active_process(pid)?;
+        process.internal_data.state = ProcessState::Waiting;
+        process.save_state(process_context, registers);
+        Ok(())
+    }
+
+    /// Wakes a `Blocked` process whose deadline passed at `now`, it waits until a processor runs it again.
+    ///
+    /// Fails if the process has no deadline or a later one, e.g. the timer is left from a wait that was woken earlier.
+    pub fn expire_deadline(&mut self, pid: usize, now: u64) -> Result<(), ()> {
+        let process = self.get_process_mut(pid)?;
+        if process.internal_data.state != ProcessState::Blocked || !matches!(process.deadline, Some(deadline) if deadline <= now) {
+            return Err(());
+        }
+        process.internal_data.state = ProcessState::Waiting;
+        process.deadline = None;
+        process.sleeping = None;
+        self.place(pid);
+        Ok(())
+    }
+
+    /// Makes an `Active` process a sleeper, it's `Blocked` once it's syscall returns unless it's woken first (see `sync::WaitQueue`).
+    /// Returns the id of the wait, the process is woken only by the wakers of this wait.
+    pub fn prepare_sleep(&mut self, pid: usize) -> Result<u64, ()> {
+        let wait = self.next_wait;
+        self.get_active_process(pid)?.sleeping = Some(wait);
+        self.next_wait += 1;
+        Ok(wait)
+    }
+
+    /// Blocks a sleeper until it's woken, it's timer wakes it at the `deadline` (in uptime nanoseconds) too.
+    /// A sleeper that `restarts` issues it's syscall again when it continues, otherwise it returns with the saved status.
+    ///
+    /// Returns whether the process is `Blocked`, a process that was already woken or that has a signal to deliver isn't.
+    pub fn sleep_process(
+        &mut self,
+        pid: usize,
+        restarts: bool,
+        deadline: Option<u64>,
+        process_context: &InterruptStackFrame,
+        registers: &Registers,
+    ) -> Result<bool, ()> {
+        let process = self.get_active_process(pid)?;
+        if process.sleeping.is_none() || process.signals.has_deliverable() {
+            process.sleeping = None;
+            return Ok(false);
+        }
+        process.internal_data.state = ProcessState::Blocked;
+        process.deadline = deadline;
+        process.restarts = restarts;
+        process.save_state(process_context, registers);
+        if restarts {
+            process.restart_syscall();
+        }
+        Ok(true)
+    }
+
+    /// Wakes the sleeper of `wait`, fails if the process doesn't sleep in it (e.g. it was woken already).
+    ///
+    /// Returns whether the process was `Blocked`, it waits until a processor runs it again and it's syscall returns `status` if there is one.
+    /// Otherwise it isn't blocked once it's syscall returns.
+    pub fn wake_sleeper(&mut self, pid: usize, wait: u64, status: Option<i64>) -> Result<bool, ()> {
+        let process = self.get_process_mut(pid)?;
+        if process.sleeping != Some(wait) {
+            return Err(());
+        }
+        let blocked = Self::wake(process);
+        if let (true, Some(status)) = (blocked, status) {
+            process.set_return_value(status);
+        }
+        if blocked {
+            self.place(pid);
+        }
+        Ok(blocked)
+    }
+
+    /// Wakes a sleeper to handle a signal, whatever it waits for. Fails if the process isn't sleeping or `Paused`.
+    /// A `Blocked` sleeper that doesn't issue it's syscall again returns `status::FAILURE`,
+    /// and a `Paused` parent issues a WAIT for it's child again once the signal is handled.
+    ///
+    /// Returns whether the process was `Blocked` or `Paused`, it waits until a processor runs it again (see `wake_sleeper`).
+    pub fn interrupt_sleep(&mut self, pid: usize) -> Result<bool, ()> {
+        let woken = Self::interrupt(self.get_process_mut(pid)?)?;
+        if woken {
+            self.place(pid);
+        }
+        Ok(woken)
+    }
+
+    /// Raises SIGALRM for the processes whose alarm expired by `now` (in uptime nanoseconds),
+    /// a sleeper is interrupted to handle it (see `interrupt_sleep`).
+    ///
+    /// `woken` is called with the processes that wait for a processor again and the waits they slept in,
+    /// up to `room` of them, the alarms after the last one are left for the next call.
+    pub fn expire_alarms(&mut self, now: u64, mut room: usize, mut woken: impl FnMut(usize, Option<u64>)) {
+        let (policy, min_vruntime) = (self.policy, self.min_vruntime);
+        for process in self.processes.values_mut() {
+            if room == 0 {
+                return;
+            }
+            if !process.signals.expire_alarm(now) {
+                continue;
+            }
+            let sleeping = process.sleeping;
+            if process.signals.has_deliverable() && Self::interrupt(process) == Ok(true) {
+                policy.place(&mut process.internal_data, min_vruntime);
+                room -= 1;
+                woken(process.internal_data.pid, sleeping);
+            }
+        }
+    }
+
+    /// Wakes a sleeper to handle a signal (see `interrupt_sleep`), the woken process isn't placed
+    fn interrupt(process: &mut Process) -> Result<bool, ()> {
+        if process.internal_data.state == ProcessState::Paused {
+            let child = process.awaited_child.take().ok_or(())?;
+            let flags = if process.reports_stops { wait::REPORT_STOPPED } else { 0 };
+            // an EXECUTE started it's child already, so it's issued again as a WAIT
+            process.reissue_syscall(number::WAIT, child as u64, flags);
+            process.internal_data.state = ProcessState::Waiting;
+            return Ok(true);
+        }
+        if process.sleeping.is_none() {
+            return Err(());
+        }
+        let blocked = Self::wake(process);
+        if blocked && !process.restarts {
+            process.set_return_value(status::FAILURE);
+        }
+        Ok(blocked)
+    }
+
+    /// Clears the wait of a sleeper, returns whether it was `Blocked`
+    fn wake(process: &mut Process) -> bool {
+        process.sleeping = None;
+        if process.internal_data.state != ProcessState::Blocked {
+            return false;
+        }
+        process.internal_data.state = ProcessState::Waiting;
+        process.deadline = None;
+        true
+    }
+
+    /// Stops an `Active` process until it's continued, it's state must be saved.
+    ///
+    /// Returns the parent if it was `Paused` until the process stops, it's woken with the stop status (see `syscalls::wait`).
+    pub fn stop_process(&mut self, pid: usize, signal: usize) -> Result<Option<usize>, ()> {
+        let process = self.get_active_process(pid)?;
+        process.internal_data.state = ProcessState::Stopped;
+        process.stop_signal = Some(signal);
+        let parent = match process.internal_data.parent {
+            Some(parent) => parent,
+            None => return Ok(None),
+        };
+        let woken = self.wake_parent(parent, pid, wait::STOPPED | signal as i64, true)?;
+        Ok(woken.then_some(parent))
+    }
+
+    /// Continues a `Stopped` process, it waits until a processor runs it again.
+    pub fn continue_process(&mut self, pid: usize) -> Result<(), ()> {
+        let process = self.get_process_mut(pid)?;
+        if process.internal_data.state != ProcessState::Stopped {
+            return Err(());
+        }
+        process.internal_data.state = ProcessState::Waiting;
+        process.stop_signal = None;
+        self.place(pid);
+        Ok(())
+    }
+
+    /// Reports the exit of `child` to it's parent, fails if the parent doesn't exist.
+    ///
+    /// Returns whether the parent was `Paused` until the child exits, it's woken with the child's exit status.
+    /// Otherwise the exit status is kept until the parent waits for the child.
+    pub fn child_exited(&mut self, parent: usize, child: usize, exit_status: u8) -> Result<bool, ()> {
+        if self.wake_parent(parent, child, exit_status as i64, false)? {
+            return Ok(true);
+        }
+        self.get_process_mut(parent)?.exited_children.push((child, exit_status));
+        Ok(false)
+    }
+
+    /// Wakes a parent with the WAIT status if it's `Paused` until `child` exits, or until it stops if `stopped`
+    fn wake_parent(&mut self, parent: usize, child: usize, status: i64, stopped: bool) -> Result<bool, ()> {
+        let process = self.get_process_mut(parent)?;
+        let is_awaited = process.awaited_child == Some(child) && (process.reports_stops || !stopped);
+        if process.internal_data.state != ProcessState::Paused || !is_awaited {
+            return Ok(false);
+        }
+        process.internal_data.state = ProcessState::Waiting;
+        process.awaited_child = None;
+        process.set_return_value(status);
+        self.place(parent);
+        Ok(true)
+    }
+
+    /// Sets the nice value of a process, the process must be `caller` or a child of it.
+    /// Fails if `nice` isn't between `MIN_NICE` and `MAX_NICE`.
+    pub fn set_priority(&mut self, caller: usize, pid: usize, nice: i64) -> Result<(), ()> {
+        let data = &mut self.get_process_mut(pid)?.internal_data;
+        if (pid != caller && data.parent != Some(caller)) || !(MIN_NICE..=MAX_NICE).contains(&nice) {
+            return Err(());
+        }
+        data.nice = nice;
+        Ok(())
+    }
+
+    /// Charges the CPU time a process that runs on a processor used, the process and it's processor's run queue advance `min_vruntime`
+    pub fn charge_process(&mut self, pid: usize, nanoseconds: u64, run_queue: &VecDeque<usize>) -> Result<(), ()> {
+        let policy = self.policy;
+        let data = &mut self.get_active_process(pid)?.internal_data;
+        data.cpu_time += nanoseconds;
+        policy.charge(data, nanoseconds);
+        self.advance_min_vruntime(run_queue, Some(pid));
+        Ok(())
+    }
+
+    /// Returns the index of the process in `run_queue` that runs next (see `smp::next_process`).
+    /// A queued process that was removed from the table (e.g. it was killed) is returned first, so it leaves the queue.
+    pub fn pick_next(&mut self, run_queue: &VecDeque<usize>) -> Option<usize> {
+        let policy = self.policy;
+        let mut next: Option<(usize, &ProcessData)> = None;
+        for (index, pid) in run_queue.iter().enumerate() {
+            let data = match self.processes.get(pid) {
+                Some(process) => &process.internal_data,
+                None => return Some(index),
+            };
+            // a tie keeps the process that was queued first
+            if next.map_or(true, |(_, best)| policy.runs_before(data, best)) {
+                next = Some((index, data));
+            }
+        }
+        let next = next.map(|(index, _)| index);
+        self.advance_min_vruntime(run_queue, None);
+        next
+    }
+
+    /// Advances `min_vruntime` to the lowest virtual runtime of a processor's `running` process and the processes queued in `run_queue`
+    fn advance_min_vruntime(&mut self, run_queue: &VecDeque<usize>, running: Option<usize>) {
+        let processes = running.iter().chain(run_queue).filter_map(|pid| self.processes.get(pid));
+        if let Some(lowest) = processes.map(|process| process.internal_data.vruntime).min() {
+            self.min_vruntime = self.min_vruntime.max(lowest);
+        }
+    }
+
+    /// Places a process that waits for a processor after it slept or before it first runs (see `SchedulingPolicy::place`)
+    fn place(&mut self, pid: usize) {
+        let (policy, min_vruntime) = (self.policy, self.min_vruntime);
+        if let Some(process) = self.processes.get_mut(&pid) {
+            policy.place(&mut process.internal_data, min_vruntime);
+        }
+    }
+
+    /// Removes the exit status of a child that exited before it's parent waited for it
+    pub fn take_exit_status(&mut self, parent: usize, child: usize) -> Result<Option<u8>, ()> {
+        let exited_children = &mut self.get_process_mut(parent)?.exited_children;
+        let index = exited_children.iter().position(|(pid, _)| *pid == child);
+        Ok(index.map(|index| exited_children.swap_remove(index).1))
+    }
+}
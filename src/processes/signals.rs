@@ -0,0 +1,223 @@
+//! POSIX-like signals, a signal sent to a process is pending until it's delivered.
+//!
+//! A signal that terminates the process by default is delivered as soon as it's sent (unless it's blocked),
+//! the process and it's descendants are terminated like by KILL. The other signals are delivered when the process
+//! returns to userland from a syscall or an interrupt (see `processes::handle_signals`).
+//!
+//! SIGSTOP & SIGTSTP stop the process until SIGCONT continues it, SIGCONT continues a stopped process
+//! even if it's blocked or handled (see `processes::send_signal`).
+//!
+//! A handler runs on the process' stack above a `SignalFrame` that saves the interrupted context,
+//! it returns to the action's restorer, which calls SIGRETURN with the frame to continue from the saved context.
+
+pub const SIGINT: usize = 2;
+pub const SIGKILL: usize = 9;
+pub const SIGSEGV: usize = 11;
+pub const SIGALRM: usize = 14;
+pub const SIGTERM: usize = 15;
+pub const SIGCHLD: usize = 17;
+pub const SIGCONT: usize = 18;
+pub const SIGSTOP: usize = 19;
+pub const SIGTSTP: usize = 20;
+/// Signals are numbered from 1, a mask has a bit for every signal
+pub const SIGNAL_COUNT: usize = 32;
+
+/// The handler of the default action
+pub const DEFAULT_HANDLER: u64 = 0;
+/// The handler that ignores the signal
+pub const IGNORE_HANDLER: u64 = 1;
+
+/// SIGPROCMASK operations
+pub mod mask {
+    pub const BLOCK: u64 = 0;
+    pub const UNBLOCK: u64 = 1;
+    pub const SET: u64 = 2;
+}
+
+/// What a process does when it receives a signal, exchanged with userland by the SIGACTION syscall
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct SignalAction {
+    /// The address of the handler, `DEFAULT_HANDLER` or `IGNORE_HANDLER`
+    pub handler: u64,
+    /// The signals blocked while the handler runs, the signal itself is always blocked
+    pub mask: u64,
+    /// The address the handler returns to, it must call SIGRETURN with the stack pointer
+    pub restorer: u64,
+}
+
+/// How a signal is delivered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Delivery {
+    Ignore,
+    Terminate,
+    Stop,
+    Handle(SignalAction),
+}
+
+/// The signals state of a process
+#[derive(Clone, Debug)]
+pub struct Signals {
+    pending: u64,
+    blocked: u64,
+    actions: [SignalAction; SIGNAL_COUNT],
+    /// The uptime (in nanoseconds) SIGALRM is sent at
+    alarm: Option<u64>,
+}
+
+impl Signals {
+    /// Every signal takes it's default action and none is blocked
+    pub const fn new() -> Self {
+        Signals {
+            pending: 0,
+            blocked: 0,
+            actions: [SignalAction {
+                handler: DEFAULT_HANDLER,
+                mask: 0,
+                restorer: 0,
+            }; SIGNAL_COUNT],
+            alarm: None,
+        }
+    }
+
+    /// Makes a signal pending, returns whether it terminates the process now.
+    /// A stop signal discards a pending SIGCONT, and SIGCONT discards the pending stop signals.
+    pub fn send(&mut self, signal: usize) -> Result<bool, ()> {
+        let delivery = self.delivery(signal)?;
+        match signal {
+            SIGCONT => self.pending &= !(bit(SIGSTOP) | bit(SIGTSTP)),
+            SIGSTOP | SIGTSTP => self.pending &= !bit(SIGCONT),
+            _ => {}
+        }
+        match delivery {
+            Delivery::Ignore => Ok(false),
+            Delivery::Terminate if !self.is_blocked(signal) => Ok(true),
+            _ => {
+                self.pending |= bit(signal);
+                Ok(false)
+            }
+        }
+    }
+
+    /// Removes the lowest pending signal that isn't blocked, returns it with how it's delivered
+    pub fn take_pending(&mut self) -> Option<(usize, Delivery)> {
+        loop {
+            let deliverable = self.pending & !self.blocked;
+            if deliverable == 0 {
+                return None;
+            }
+            let signal = deliverable.trailing_zeros() as usize;
+            self.pending &= !bit(signal);
+            // the action may have changed since the signal was sent
+            match self.delivery(signal) {
+                Ok(Delivery::Ignore) | Err(()) => continue,
+                Ok(delivery) => return Some((signal, delivery)),
+            }
+        }
+    }
+
+    /// Returns whether a pending signal isn't blocked, so it's delivered when the process returns to userland
+    pub fn has_deliverable(&self) -> bool {
+        self.pending & !self.blocked != 0
+    }
+
+    pub fn action(&self, signal: usize) -> Result<SignalAction, ()> {
+        validate(signal)?;
+        Ok(self.actions[signal])
+    }
+
+    /// Replaces the action of a signal, returns the previous action.
+    /// The actions of SIGKILL & SIGSTOP can't be changed, and a handler must have a restorer.
+    pub fn set_action(&mut self, signal: usize, action: SignalAction) -> Result<SignalAction, ()> {
+        validate(signal)?;
+        let is_handler = action.handler != DEFAULT_HANDLER && action.handler != IGNORE_HANDLER;
+        if signal == SIGKILL || signal == SIGSTOP || (is_handler && action.restorer == 0) {
+            return Err(());
+        }
+        Ok(core::mem::replace(&mut self.actions[signal], action))
+    }
+
+    pub fn blocked(&self) -> u64 {
+        self.blocked
+    }
+
+    /// Sets the blocked signals, SIGKILL & SIGSTOP are never blocked
+    pub fn set_blocked(&mut self, blocked: u64) {
+        self.blocked = blocked & !bit(SIGKILL) & !bit(SIGSTOP) & !1;
+    }
+
+    /// Changes the blocked signals with a `mask` operation, returns the previously blocked signals
+    pub fn change_blocked(&mut self, operation: u64, signals: u64) -> Result<u64, ()> {
+        let previous = self.blocked;
+        match operation {
+            mask::BLOCK => self.set_blocked(previous | signals),
+            mask::UNBLOCK => self.set_blocked(previous & !signals),
+            mask::SET => self.set_blocked(signals),
+            _ => return Err(()),
+        }
+        Ok(previous)
+    }
+
+    /// Blocks the signals of the action's mask & the signal itself while it's handler runs, returns the previously blocked signals
+    pub fn block_for_handler(&mut self, signal: usize, action: &SignalAction) -> u64 {
+        let previous = self.blocked;
+        self.set_blocked(previous | action.mask | bit(signal));
+        previous
+    }
+
+    /// Sets the uptime (in nanoseconds) SIGALRM is sent at, `None` cancels the alarm. Returns the previous alarm.
+    pub fn set_alarm(&mut self, deadline: Option<u64>) -> Option<u64> {
+        core::mem::replace(&mut self.alarm, deadline)
+    }
+
+    /// Makes SIGALRM pending if the alarm expired by `now` (in uptime nanoseconds), returns whether it expired.
+    /// It's pending even if it terminates the process, it's delivered when the process returns to userland.
+    pub fn expire_alarm(&mut self, now: u64) -> bool {
+        if !matches!(self.alarm, Some(deadline) if deadline <= now) {
+            return false;
+        }
+        self.alarm = None;
+        self.pending |= bit(SIGALRM);
+        true
+    }
+
+    fn is_blocked(&self, signal: usize) -> bool {
+        self.blocked & bit(signal) != 0
+    }
+
+    fn delivery(&self, signal: usize) -> Result<Delivery, ()> {
+        let action = self.action(signal)?;
+        Ok(match action.handler {
+            _ if signal == SIGKILL => Delivery::Terminate,
+            _ if signal == SIGSTOP => Delivery::Stop,
+            // a stopped process is continued when SIGCONT is sent
+            DEFAULT_HANDLER if signal == SIGCHLD || signal == SIGCONT => Delivery::Ignore,
+            DEFAULT_HANDLER if signal == SIGTSTP => Delivery::Stop,
+            DEFAULT_HANDLER => Delivery::Terminate,
+            IGNORE_HANDLER => Delivery::Ignore,
+            _ => Delivery::Handle(action),
+        })
+    }
+}
+
+impl Default for Signals {
+    fn default() -> Self {
+        Signals::new()
+    }
+}
+
+/// The exit status of a process terminated by a signal, like a shell reports it
+pub fn exit_status(signal: usize) -> u8 {
+    128 + signal as u8
+}
+
+fn validate(signal: usize) -> Result<(), ()> {
+    if signal == 0 || signal >= SIGNAL_COUNT {
+        return Err(());
+    }
+    Ok(())
+}
+
+fn bit(signal: usize) -> u64 {
+    1 << signal
+}
@@ -0,0 +1,86 @@
+//! Inter-processor interrupts, wake processors to run queued processes and flush stale TLB entries
+
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use spin::Mutex;
+use x86_64::{
+    instructions::tlb,
+    structures::idt::InterruptStackFrame,
+    VirtAddr,
+};
+
+use crate::{
+    interrupts::apic::{self, RESCHEDULE_VECTOR, TLB_SHOOTDOWN_VECTOR},
+    processes::{self, objects::Registers},
+};
+
+use super::{current, with_cpus, Cpu};
+
+/// Only one shootdown at a time, the page & the acknowledgments are shared
+static SHOOTDOWN: Mutex<()> = Mutex::new(());
+static SHOOTDOWN_PAGE: AtomicU64 = AtomicU64::new(0);
+/// The processors that didn't flush the page yet
+static SHOOTDOWN_PENDING: AtomicUsize = AtomicUsize::new(0);
+
+/// Wakes `cpu` so it runs a process that was queued to it, or preempts it's current process
+pub fn send_reschedule(cpu: &Cpu) {
+    if apic::is_enabled() {
+        apic::send_ipi(cpu.apic_id(), RESCHEDULE_VECTOR);
+    }
+}
+
+/// Flushes the page from the TLB of every processor, and waits until they all flushed it.
+///
+/// Must be called without holding the locks the other processors may wait for with interrupts disabled.
+pub fn flush_tlb(page: u64) {
+    tlb::flush(VirtAddr::new(page));
+    if !apic::is_enabled() {
+        return;
+    }
+
+    let cpu = current();
+    with_cpus(|cpus| {
+        if cpus.len() < 2 {
+            return;
+        }
+
+        // a processor that waits for another shootdown must still acknowledge it
+        let _shootdown = loop {
+            if let Some(shootdown) = SHOOTDOWN.try_lock() {
+                break shootdown;
+            }
+            acknowledge_shootdown(cpu);
+            core::hint::spin_loop();
+        };
+
+        SHOOTDOWN_PAGE.store(page, Ordering::SeqCst);
+        SHOOTDOWN_PENDING.store(cpus.len() - 1, Ordering::SeqCst);
+        for other in cpus.iter().filter(|other| other.id != cpu.id) {
+            other.tlb_flush_pending.store(true, Ordering::SeqCst);
+            apic::send_ipi(other.apic_id(), TLB_SHOOTDOWN_VECTOR);
+        }
+        while SHOOTDOWN_PENDING.load(Ordering::SeqCst) != 0 {
+            core::hint::spin_loop();
+        }
+    });
+}
+
+/// Flushes the shot down page if the processor didn't flush it yet
+fn acknowledge_shootdown(cpu: &Cpu) {
+    if cpu.tlb_flush_pending.swap(false, Ordering::SeqCst) {
+        tlb::flush(VirtAddr::new(SHOOTDOWN_PAGE.load(Ordering::SeqCst)));
+        SHOOTDOWN_PENDING.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+pub extern "x86-interrupt" fn tlb_shootdown_interrupt(_stack_frame: InterruptStackFrame) {
+    acknowledge_shootdown(current());
+    apic::end_of_interrupt();
+}
+
+crate::wrap_interrupt_handler!(reschedule_handler => reschedule_interrupt);
+
+/// The processor is awake, switches to the next process if the interrupted code is a process
+extern "sysv64" fn reschedule_handler(stack_frame: &InterruptStackFrame, registers: &mut Registers) {
+    apic::end_of_interrupt();
+    processes::preempt(stack_frame, registers);
+}
@@ -0,0 +1,273 @@
+//! The smp module goal is to run the kernel on every processor (Symmetric Multiprocessing).
+//!
+//! The bootstrap processor starts the application processors with INIT-SIPI-SIPI,
+//! they start in real mode at the trampoline that switches them to long mode and jumps to `application_processor_main`.
+//! Every processor has it's own data (`Cpu`), it's address is saved in the processor's GS base.
+
+pub mod ipi;
+mod trampoline;
+
+use alloc::{boxed::Box, collections::VecDeque, vec::Vec};
+use core::{
+    arch::asm,
+    sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering},
+};
+use log::{info, warn};
+use spin::RwLock;
+use x86_64::{
+    registers::segmentation::{Segment, GS},
+    structures::gdt::SegmentSelector,
+};
+
+use crate::{
+    acpi,
+    drivers::pit,
+    interrupts::{apic, gdt, idt::IDT},
+    memory::{get_linear_addr, kmalloc, types::PAGE_SIZE},
+    processes,
+    sync::SpinLock,
+    wrmsr,
+};
+
+use self::trampoline::Trampoline;
+
+pub use self::ipi::flush_tlb;
+
+const GS_BASE_MSR: u64 = 0xC000_0101;
+const AP_STACK_SIZE: usize = 4 * PAGE_SIZE;
+/// The processor resets after the INIT IPI, and only then accepts a startup IPI
+const INIT_DELAY_MICROSECONDS: u64 = 10_000;
+const STARTUP_DELAY_MICROSECONDS: u64 = 200;
+/// How long the bootstrap processor waits for an application processor to start
+const STARTUP_TIMEOUT_MILLISECONDS: u64 = 100;
+const NO_PROCESS: usize = usize::MAX;
+
+/// The data of the processor the firmware booted, it's loaded by `gdt::init`
+pub static BOOTSTRAP_PROCESSOR: Cpu = Cpu::new(0, 0);
+
+/// The online processors, indexed by their id
+static CPUS: RwLock<Vec<&'static Cpu>> = RwLock::new(Vec::new());
+
+/// Per-CPU data
+#[repr(C)]
+pub struct Cpu {
+    /// Points to the `Cpu` itself, read through `gs:[0]`
+    self_pointer: AtomicU64,
+    /// The processor index, the bootstrap processor is 0
+    pub id: usize,
+    apic_id: AtomicU8,
+    /// The pid of the process the processor runs, or `NO_PROCESS`
+    current_pid: AtomicUsize,
+    /// The processes waiting for this processor
+    run_queue: SpinLock<VecDeque<usize>>,
+    /// Set by a TLB shootdown, cleared when the processor flushed the page
+    tlb_flush_pending: AtomicBool,
+}
+
+impl Cpu {
+    const fn new(id: usize, apic_id: u8) -> Self {
+        Cpu {
+            self_pointer: AtomicU64::new(0),
+            id,
+            apic_id: AtomicU8::new(apic_id),
+            current_pid: AtomicUsize::new(NO_PROCESS),
+            run_queue: SpinLock::new(VecDeque::new()),
+            tlb_flush_pending: AtomicBool::new(false),
+        }
+    }
+
+    /// Saves the data address in the current processor's GS base
+    pub fn load(&'static self) {
+        let address = self as *const Cpu as u64;
+        self.self_pointer.store(address, Ordering::Relaxed);
+        // loading a selector to `gs` may reset the base, the selector is null and never loaded again
+        unsafe { GS::set_reg(SegmentSelector(0)) };
+        wrmsr!(GS_BASE_MSR, address);
+    }
+
+    pub fn apic_id(&self) -> u8 {
+        self.apic_id.load(Ordering::Relaxed)
+    }
+
+    /// Returns the pid of the process the processor runs
+    pub fn current_pid(&self) -> Option<usize> {
+        match self.current_pid.load(Ordering::SeqCst) {
+            NO_PROCESS => None,
+            pid => Some(pid),
+        }
+    }
+
+    pub fn set_current_pid(&self, pid: Option<usize>) {
+        self.current_pid.store(pid.unwrap_or(NO_PROCESS), Ordering::SeqCst);
+    }
+
+    /// Calls `operation` with the locked run queue, interrupt handlers queue processes too (e.g. a preempted process).
+    /// The scheduler must be locked before the run queue.
+    pub fn with_run_queue<T>(&self, operation: impl FnOnce(&mut VecDeque<usize>) -> T) -> T {
+        operation(&mut self.run_queue.lock())
+    }
+
+    /// Whether processes are waiting in the run queue
+    pub fn has_waiting_processes(&self) -> bool {
+        self.with_run_queue(|run_queue| !run_queue.is_empty())
+    }
+
+    /// Adds a process to the end of the run queue
+    pub fn push_process(&self, pid: usize) {
+        self.with_run_queue(|run_queue| run_queue.push_back(pid));
+    }
+
+    /// The processes that run on, or wait for the processor
+    fn workload(&self) -> usize {
+        self.with_run_queue(|run_queue| run_queue.len()) + self.current_pid().is_some() as usize
+    }
+}
+
+/// Returns the data of the current processor
+pub fn current() -> &'static Cpu {
+    let address: u64;
+    unsafe { asm!("mov {}, gs:[0]", out(reg) address, options(nostack, readonly, preserves_flags)) };
+    unsafe { &*(address as *const Cpu) }
+}
+
+/// Calls `operation` with the online processors, before `init` only the bootstrap processor is online
+fn with_cpus<T>(operation: impl FnOnce(&[&'static Cpu]) -> T) -> T {
+    let cpus = CPUS.read();
+    if cpus.is_empty() {
+        return operation(&[&BOOTSTRAP_PROCESSOR]);
+    }
+    operation(&cpus)
+}
+
+/// Returns the number of online processors
+pub fn cpu_count() -> usize {
+    with_cpus(|cpus| cpus.len())
+}
+
+/// Returns the data of an online processor
+pub fn get_cpu(id: usize) -> Option<&'static Cpu> {
+    with_cpus(|cpus| cpus.get(id).copied())
+}
+
+/// Queues a process to the processor with the lowest workload, and wakes it if it's another processor
+pub fn enqueue(pid: usize) {
+    let cpu = with_cpus(|cpus| *cpus.iter().min_by_key(|cpu| cpu.workload()).unwrap());
+    cpu.push_process(pid);
+    if cpu.id != current().id {
+        ipi::send_reschedule(cpu);
+    }
+}
+
+/// Returns the next process `cpu` should run, processes are stolen from the busiest processor
+/// when the run queue is empty.
+///
+/// `pick` returns the index of the process that runs next in a run queue (see `Scheduler::pick_next`),
+/// the scheduler must be locked before the run queues.
+pub fn next_process(cpu: &Cpu, mut pick: impl FnMut(&VecDeque<usize>) -> Option<usize>) -> Option<usize> {
+    let mut take = |run_queue: &mut VecDeque<usize>| pick(run_queue).and_then(|index| run_queue.remove(index));
+    if let Some(pid) = cpu.with_run_queue(&mut take) {
+        return Some(pid);
+    }
+
+    let busiest = with_cpus(|cpus| {
+        cpus.iter()
+            .filter(|other| other.id != cpu.id)
+            .max_by_key(|other| other.with_run_queue(|run_queue| run_queue.len()))
+            .copied()
+    })?;
+    busiest.with_run_queue(take)
+}
+
+/// Whether a processor other than `cpu` runs the process
+pub fn is_running_elsewhere(cpu: &Cpu, pid: usize) -> bool {
+    with_cpus(|cpus| cpus.iter().any(|other| other.id != cpu.id && other.current_pid() == Some(pid)))
+}
+
+/// Sends a reschedule IPI to the other processors, so they switch from terminated processes
+pub fn reschedule_others() {
+    let current = current();
+    with_cpus(|cpus| {
+        for cpu in cpus.iter().filter(|cpu| cpu.id != current.id) {
+            ipi::send_reschedule(cpu);
+        }
+    });
+}
+
+/// Starts the enabled application processors the MADT describes, they wait for processes to run.
+///
+/// Must be called after `interrupts::init_controllers`.
+pub fn init() {
+    if apic::is_enabled() {
+        BOOTSTRAP_PROCESSOR.apic_id.store(apic::id(), Ordering::Relaxed);
+    }
+    CPUS.write().push(&BOOTSTRAP_PROCESSOR);
+
+    let madt = match acpi::madt() {
+        Ok(madt) if apic::is_enabled() => madt,
+        _ => {
+            info!("no local APIC, the application processors are not started");
+            return;
+        }
+    };
+    let trampoline = match Trampoline::install() {
+        Ok(trampoline) => trampoline,
+        Err(()) => {
+            warn!("no real mode memory for the trampoline, the application processors are not started");
+            return;
+        }
+    };
+
+    let bootstrap_apic_id = BOOTSTRAP_PROCESSOR.apic_id();
+    for processor in madt.processors.iter().filter(|processor| processor.enabled && processor.apic_id != bootstrap_apic_id) {
+        // a late processor would use the trampoline arguments of the next processor
+        if start_application_processor(&trampoline, processor.apic_id).is_err() {
+            warn!("processor with APIC {} didn't start", processor.apic_id);
+            break;
+        }
+    }
+    info!("{} processors online", cpu_count());
+}
+
+/// Starts a processor with INIT-SIPI-SIPI, and waits until it's online
+fn start_application_processor(trampoline: &Trampoline, apic_id: u8) -> Result<(), ()> {
+    let id = CPUS.read().len();
+    let cpu: &'static Cpu = Box::leak(Box::new(Cpu::new(id, apic_id)));
+    let stack = kmalloc(AP_STACK_SIZE, PAGE_SIZE)?;
+    trampoline.prepare(
+        get_linear_addr(stack) + AP_STACK_SIZE as u64,
+        application_processor_main as *const () as u64,
+        cpu as *const Cpu as u64,
+    );
+
+    apic::send_init(apic_id);
+    pit::wait_microseconds(INIT_DELAY_MICROSECONDS);
+    // a processor that already started ignores the second startup IPI
+    for _ in 0..2 {
+        apic::send_startup(apic_id, trampoline.page());
+        pit::wait_microseconds(STARTUP_DELAY_MICROSECONDS);
+    }
+
+    for _ in 0..STARTUP_TIMEOUT_MILLISECONDS {
+        if CPUS.read().len() > id {
+            return Ok(());
+        }
+        pit::wait_microseconds(1000);
+    }
+    Err(())
+}
+
+/// The application processors continue from the trampoline here, with interrupts disabled
+///
+/// # Arguments
+///  - `cpu`, the address of the processor's `Cpu`
+extern "C" fn application_processor_main(cpu: u64) -> ! {
+    let cpu = unsafe { &*(cpu as *const Cpu) };
+    cpu.load();
+    gdt::init_application_processor().unwrap();
+    IDT.load();
+    apic::init_application_processor();
+
+    CPUS.write().push(cpu);
+    info!("processor {} (APIC {}) is online", cpu.id, cpu.apic_id());
+    processes::schedule()
+}
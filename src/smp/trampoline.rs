@@ -0,0 +1,170 @@
+//! The application processors start in real mode at a page below 1MiB,
+//! the trampoline switches them to protected mode and then to long mode with the kernel page tables.
+//!
+//! The code is copied to the real mode frame, so it only uses addresses relative to it's start:
+//! in real mode `cs` is the frame segment, in protected mode `ebx` holds the frame address.
+
+use core::{arch::global_asm, ptr};
+
+use crate::memory::{
+    get_linear_addr, kmap,
+    paging::{get_cr3, EntryFlags},
+    take_real_mode_frame,
+    types::PAGE_SIZE,
+};
+
+global_asm!(
+    ".section .rodata.ap_trampoline, \"a\"",
+    ".balign 16",
+    ".global ap_trampoline_start",
+    ".global ap_trampoline_end",
+    ".global ap_trampoline_cr3",
+    ".global ap_trampoline_stack",
+    ".global ap_trampoline_entry",
+    ".global ap_trampoline_argument",
+    ".code16",
+    "ap_trampoline_start:",
+    "    cli",
+    "    cld",
+    "    mov ax, cs",
+    "    mov ds, ax",
+    "    xor ebx, ebx",
+    "    mov bx, ax",
+    "    shl ebx, 4",
+    // the GDT pointer and the far jump need the linear addresses
+    "    lea eax, [ebx + ap_trampoline_gdt - ap_trampoline_start]",
+    "    mov dword ptr [ap_trampoline_gdt_pointer - ap_trampoline_start + 2], eax",
+    "    lea eax, [ebx + ap_trampoline_protected_mode - ap_trampoline_start]",
+    "    mov dword ptr [ap_trampoline_protected_mode_pointer - ap_trampoline_start], eax",
+    "    lgdt [ap_trampoline_gdt_pointer - ap_trampoline_start]",
+    "    mov eax, cr0",
+    "    or eax, 1",
+    "    mov cr0, eax",
+    // jmp far dword ptr [ap_trampoline_protected_mode_pointer]
+    "    .byte 0x66, 0xFF, 0x2E",
+    "    .word ap_trampoline_protected_mode_pointer - ap_trampoline_start",
+    ".code32",
+    "ap_trampoline_protected_mode:",
+    "    mov ax, 0x10",
+    "    mov ds, ax",
+    "    mov es, ax",
+    "    mov ss, ax",
+    // PAE
+    "    mov eax, cr4",
+    "    or eax, 1 << 5",
+    "    mov cr4, eax",
+    "    mov eax, [ebx + ap_trampoline_cr3 - ap_trampoline_start]",
+    "    mov cr3, eax",
+    // EFER.LME & EFER.NXE, the kernel page tables use the no execute bit
+    "    mov ecx, 0xC0000080",
+    "    rdmsr",
+    "    or eax, (1 << 8) | (1 << 11)",
+    "    wrmsr",
+    // paging & write protect
+    "    mov eax, cr0",
+    "    or eax, (1 << 31) | (1 << 16)",
+    "    mov cr0, eax",
+    "    lea eax, [ebx + ap_trampoline_long_mode - ap_trampoline_start]",
+    "    mov [ebx + ap_trampoline_long_mode_pointer - ap_trampoline_start], eax",
+    // jmp far fword ptr [ebx + ap_trampoline_long_mode_pointer]
+    "    .byte 0xFF, 0xAB",
+    "    .long ap_trampoline_long_mode_pointer - ap_trampoline_start",
+    ".code64",
+    "ap_trampoline_long_mode:",
+    "    mov ebx, ebx",
+    "    mov rsp, [rbx + ap_trampoline_stack - ap_trampoline_start]",
+    "    mov rdi, [rbx + ap_trampoline_argument - ap_trampoline_start]",
+    "    mov rax, [rbx + ap_trampoline_entry - ap_trampoline_start]",
+    "    and rsp, -16",
+    "    call rax",
+    "    ud2",
+    ".balign 8",
+    "ap_trampoline_gdt:",
+    "    .quad 0",
+    // 0x08, 32 bit code
+    "    .quad 0x00CF9A000000FFFF",
+    // 0x10, data
+    "    .quad 0x00CF92000000FFFF",
+    // 0x18, 64 bit code
+    "    .quad 0x00AF9A000000FFFF",
+    "ap_trampoline_gdt_pointer:",
+    "    .word 4 * 8 - 1",
+    "    .long 0",
+    "ap_trampoline_protected_mode_pointer:",
+    "    .long 0",
+    "    .word 0x08",
+    "ap_trampoline_long_mode_pointer:",
+    "    .long 0",
+    "    .word 0x18",
+    ".balign 8",
+    "ap_trampoline_cr3:",
+    "    .quad 0",
+    "ap_trampoline_stack:",
+    "    .quad 0",
+    "ap_trampoline_entry:",
+    "    .quad 0",
+    "ap_trampoline_argument:",
+    "    .quad 0",
+    "ap_trampoline_end:",
+    ".previous",
+);
+
+extern "C" {
+    static ap_trampoline_start: u8;
+    static ap_trampoline_end: u8;
+    static ap_trampoline_cr3: u8;
+    static ap_trampoline_stack: u8;
+    static ap_trampoline_entry: u8;
+    static ap_trampoline_argument: u8;
+}
+
+/// Returns the offset of a trampoline symbol from the trampoline start
+fn offset_of(symbol: &u8) -> u64 {
+    symbol as *const u8 as u64 - unsafe { &ap_trampoline_start } as *const u8 as u64
+}
+
+/// The trampoline code copied to the real mode frame
+pub struct Trampoline {
+    frame: u64,
+}
+
+impl Trampoline {
+    /// Copies the trampoline to the real mode frame, and identity maps it
+    /// so the processors continue after they enable paging.
+    pub fn install() -> Result<Self, ()> {
+        let frame = take_real_mode_frame().ok_or(())?;
+        let length = offset_of(unsafe { &ap_trampoline_end }) as usize;
+        if length > PAGE_SIZE {
+            return Err(());
+        }
+
+        unsafe {
+            ptr::copy_nonoverlapping(&ap_trampoline_start as *const u8, get_linear_addr(frame) as *mut u8, length);
+            kmap(frame, frame, EntryFlags::PRESENT | EntryFlags::WRITABLE)?;
+        }
+        Ok(Trampoline { frame })
+    }
+
+    /// The startup IPI page number
+    pub fn page(&self) -> u8 {
+        (self.frame / PAGE_SIZE as u64) as u8
+    }
+
+    /// Sets the arguments of the next processor that starts
+    ///
+    /// # Arguments
+    ///  - `stack`, the top of the processor's stack
+    ///  - `entry`, an `extern "C" fn(argument) -> !`
+    ///  - `argument`, the entry argument
+    pub fn prepare(&self, stack: u64, entry: u64, argument: u64) {
+        self.write(unsafe { &ap_trampoline_cr3 }, get_cr3());
+        self.write(unsafe { &ap_trampoline_stack }, stack);
+        self.write(unsafe { &ap_trampoline_entry }, entry);
+        self.write(unsafe { &ap_trampoline_argument }, argument);
+    }
+
+    fn write(&self, symbol: &u8, value: u64) {
+        let address = get_linear_addr(self.frame) + offset_of(symbol);
+        unsafe { ptr::write_volatile(address as *mut u64, value) };
+    }
+}
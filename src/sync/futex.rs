@@ -0,0 +1,98 @@
+//! Futexes (fast userspace mutexes), processes wait on a 32 bit word of their memory until a wake of the word.
+//!
+//! Userland locks take an uncontended lock with atomic operations, and use FUTEX only to wait for a contended lock
+//! and to wake it's waiters. The waiters of a word are keyed by it's physical address,
+//! so processes that share memory wait on the same word even if they map it at different addresses.
+//!
+//! A waiter that times out, is interrupted by a signal or terminates leaves it's queue (see `cancel`).
+
+use alloc::collections::{BTreeMap, VecDeque};
+use core::{
+    mem,
+    sync::atomic::{AtomicU32, Ordering},
+};
+use lazy_static::lazy_static;
+
+use crate::{memory, syscalls::status};
+
+use super::{SpinLock, Waiter};
+
+lazy_static! {
+    /// The waiters of the words that have waiters, keyed by the physical address of the word
+    static ref FUTEXES: SpinLock<BTreeMap<u64, VecDeque<Waiter>>> = SpinLock::new(BTreeMap::new());
+}
+
+/// Returns the key of the word at `address`, fails if the word isn't aligned or mapped
+fn key(address: u64) -> Result<u64, ()> {
+    if address == 0 || address % mem::align_of::<AtomicU32>() as u64 != 0 {
+        return Err(());
+    }
+    memory::get_physical_addr(address).ok_or(())
+}
+
+/// Registers the current process as a waiter of the word at `address` if the word holds `value`,
+/// it sleeps once it's blocked (see `processes::block_sleeper`) until a wake of the word. Returns the waiter.
+///
+/// Fails if the word doesn't hold `value` (e.g. the lock was released meanwhile), or if there is no current process.
+pub fn wait(address: u64, value: u32) -> Result<Waiter, ()> {
+    let key = key(address)?;
+    let mut futexes = FUTEXES.lock();
+    // the word is checked while the waiters are locked, so a wake after the word changes isn't missed
+    let word = unsafe { &*(address as *const AtomicU32) };
+    if word.load(Ordering::SeqCst) != value {
+        return Err(());
+    }
+    let waiter = Waiter::current()?;
+    futexes.entry(key).or_default().push_back(waiter);
+    Ok(waiter)
+}
+
+/// Removes a waiter that stopped waiting without a wake (e.g. it timed out), a word without waiters left is dropped.
+/// Nothing is removed if the waiter was woken already or isn't a futex waiter.
+///
+/// The waiters are locked before the scheduler, so don't call it while the scheduler is locked.
+pub fn cancel(waiter: Waiter) {
+    let mut futexes = FUTEXES.lock();
+    let key = futexes.iter_mut().find_map(|(key, waiters)| {
+        let index = waiters.iter().position(|queued| *queued == waiter)?;
+        waiters.remove(index);
+        Some(*key)
+    });
+    if let Some(key) = key {
+        if futexes[&key].is_empty() {
+            futexes.remove(&key);
+        }
+    }
+}
+
+/// Returns the number of waiters of the word at `address`, fails if the word isn't aligned or mapped
+pub fn queued(address: u64) -> Result<usize, ()> {
+    let key = key(address)?;
+    Ok(FUTEXES.lock().get(&key).map_or(0, VecDeque::len))
+}
+
+/// Wakes up to `count` waiters of the word at `address`, their FUTEX returns `status::SUCCESS`.
+/// Returns the number of waiters woken, fails if the word isn't aligned or mapped.
+pub fn wake(address: u64, count: usize) -> Result<usize, ()> {
+    let key = key(address)?;
+    let mut woken = 0;
+    while woken < count {
+        let waiter = {
+            let mut futexes = FUTEXES.lock();
+            let waiters = match futexes.get_mut(&key) {
+                Some(waiters) => waiters,
+                None => break,
+            };
+            let waiter = waiters.pop_front();
+            if waiters.is_empty() {
+                futexes.remove(&key);
+            }
+            waiter
+        };
+        // the waiters are unlocked before waking, waking locks the scheduler
+        if matches!(waiter, Some(waiter) if waiter.wake(Some(status::SUCCESS)).is_ok()) {
+            woken += 1;
+        }
+    }
+    Ok(woken)
+}
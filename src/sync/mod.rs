@@ -0,0 +1,36 @@
+//! Synchronization primitives, interrupt safe spinlocks and sleeping primitives integrated with the scheduler.
+//!
+//! The kernel stack of a processor is abandoned when it switches processes, so a process can't sleep in the middle of a syscall.
+//! Instead, a sleeping primitive registers the current process on a `WaitQueue` and fails, the syscall returns `status::BLOCKED`
+//! and the process is `Blocked` with it's syscall rewound. Once it's woken the process issues the syscall again,
+//! so a syscall may only wait before it has side effects, and it checks it's condition again after every wake up.
+//!
+//! A process that waits on a futex (see `futex`) isn't rewound, it's FUTEX returns once it's woken or once it's timeout passes.
+//!
+//! Without a current process (e.g. while the kernel initializes) the primitives halt the processor until the next interrupt.
+//!
+//! Waking a process locks the scheduler, so the sleeping primitives aren't used (or released) while it's locked.
+
+pub mod futex;
+pub mod mutex;
+pub mod semaphore;
+pub mod spinlock;
+pub mod wait_queue;
+
+use x86_64::instructions::interrupts;
+
+pub use self::{
+    mutex::{Condvar, Mutex, MutexGuard},
+    semaphore::Semaphore,
+    spinlock::{SpinLock, SpinLockGuard},
+    wait_queue::{WaitQueue, Waiter},
+};
+
+/// Halts until the next interrupt, the interrupts are restored afterwards
+pub fn relax() {
+    let enabled = interrupts::are_enabled();
+    interrupts::enable_and_hlt();
+    if !enabled {
+        interrupts::disable();
+    }
+}
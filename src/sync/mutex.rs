@@ -0,0 +1,140 @@
+//! A sleeping mutex and a condition variable, a process that waits for them sleeps until it's woken.
+
+use core::{
+    cell::UnsafeCell,
+    ops::{Deref, DerefMut},
+};
+
+use super::{relax, SpinLock, WaitQueue};
+
+pub struct Mutex<T> {
+    locked: SpinLock<bool>,
+    waiters: WaitQueue,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Send for Mutex<T> {}
+unsafe impl<T: Send> Sync for Mutex<T> {}
+
+impl<T> Mutex<T> {
+    pub const fn new(data: T) -> Self {
+        Mutex {
+            locked: SpinLock::new(false),
+            waiters: WaitQueue::new(),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Locks the mutex, waits until it's unlocked.
+    ///
+    /// Fails if the current process sleeps until the mutex is unlocked, the caller must fail too
+    /// so it's syscall returns `status::BLOCKED` (see `WaitQueue::register`).
+    pub fn lock(&self) -> Result<MutexGuard<T>, ()> {
+        loop {
+            {
+                let mut locked = self.locked.lock();
+                if !*locked {
+                    *locked = true;
+                    return Ok(MutexGuard { mutex: self });
+                }
+                if self.waiters.register().is_ok() {
+                    return Err(());
+                }
+            }
+            relax();
+        }
+    }
+
+    /// Locks the mutex if it's unlocked
+    pub fn try_lock(&self) -> Option<MutexGuard<T>> {
+        let mut locked = self.locked.lock();
+        if *locked {
+            return None;
+        }
+        *locked = true;
+        Some(MutexGuard { mutex: self })
+    }
+
+    pub fn is_locked(&self) -> bool {
+        *self.locked.lock()
+    }
+
+    fn unlock(&self) {
+        *self.locked.lock() = false;
+        self.waiters.wake_one();
+    }
+}
+
+impl<T: Default> Default for Mutex<T> {
+    fn default() -> Self {
+        Mutex::new(T::default())
+    }
+}
+
+/// Unlocks the mutex when it's dropped, and wakes a waiting process
+pub struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+}
+
+impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.unlock();
+    }
+}
+
+/// A condition variable, processes wait until the condition of the data a mutex protects changes
+pub struct Condvar {
+    waiters: WaitQueue,
+}
+
+impl Condvar {
+    pub const fn new() -> Self {
+        Condvar { waiters: WaitQueue::new() }
+    }
+
+    /// Unlocks the mutex and waits until the condition variable is notified, returns the locked mutex.
+    ///
+    /// Fails if the current process sleeps until it's notified, the caller must fail too so it's syscall returns `status::BLOCKED`,
+    /// the syscall issued again checks the condition again. Without a current process, the processor relaxes and the mutex is locked again.
+    pub fn wait<'a, T>(&self, guard: MutexGuard<'a, T>) -> Result<MutexGuard<'a, T>, ()> {
+        let mutex = guard.mutex;
+        // registered while the mutex is locked, so a notification after the condition changes isn't missed
+        let registered = self.waiters.register();
+        drop(guard);
+        if registered.is_ok() {
+            return Err(());
+        }
+        relax();
+        mutex.lock()
+    }
+
+    /// Wakes the process that waited the longest
+    pub fn notify_one(&self) {
+        self.waiters.wake_one();
+    }
+
+    /// Wakes every waiting process
+    pub fn notify_all(&self) {
+        self.waiters.wake_all();
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Condvar::new()
+    }
+}
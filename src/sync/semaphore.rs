@@ -0,0 +1,58 @@
+//! A counting semaphore, a process that waits for a permit sleeps until one is released.
+
+use super::{relax, SpinLock, WaitQueue};
+
+pub struct Semaphore {
+    permits: SpinLock<usize>,
+    waiters: WaitQueue,
+}
+
+impl Semaphore {
+    pub const fn new(permits: usize) -> Self {
+        Semaphore {
+            permits: SpinLock::new(permits),
+            waiters: WaitQueue::new(),
+        }
+    }
+
+    /// Takes a permit, waits until one is released.
+    ///
+    /// Fails if the current process sleeps until a permit is released, the caller must fail too
+    /// so it's syscall returns `status::BLOCKED` (see `WaitQueue::register`).
+    pub fn acquire(&self) -> Result<(), ()> {
+        loop {
+            {
+                let mut permits = self.permits.lock();
+                if *permits > 0 {
+                    *permits -= 1;
+                    return Ok(());
+                }
+                if self.waiters.register().is_ok() {
+                    return Err(());
+                }
+            }
+            relax();
+        }
+    }
+
+    /// Takes a permit if there is one, returns whether a permit was taken
+    pub fn try_acquire(&self) -> bool {
+        let mut permits = self.permits.lock();
+        if *permits == 0 {
+            return false;
+        }
+        *permits -= 1;
+        true
+    }
+
+    /// Returns a permit, and wakes a waiting process
+    pub fn release(&self) {
+        *self.permits.lock() += 1;
+        self.waiters.wake_one();
+    }
+
+    /// The available permits
+    pub fn permits(&self) -> usize {
+        *self.permits.lock()
+    }
+}
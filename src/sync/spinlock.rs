@@ -0,0 +1,101 @@
+//! An interrupt safe spinlock, interrupts are disabled while it's held.
+//!
+//! An interrupt handler never spins on a lock the code it interrupted holds, so the handlers lock like any other code.
+//! A processor that waits for the lock handles interrupts meanwhile (if they were enabled),
+//! so the holder can still wait for it (e.g. for a TLB shootdown acknowledgment).
+
+use core::{
+    hint::spin_loop,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+};
+use x86_64::instructions::interrupts;
+
+pub struct SpinLock<T> {
+    inner: spin::Mutex<T>,
+}
+
+impl<T> SpinLock<T> {
+    pub const fn new(data: T) -> Self {
+        SpinLock {
+            inner: spin::Mutex::new(data),
+        }
+    }
+
+    /// Spins until the lock is acquired, the interrupts are disabled until the guard is dropped
+    pub fn lock(&self) -> SpinLockGuard<T> {
+        let enabled = interrupts::are_enabled();
+        loop {
+            if let Some(guard) = self.acquire(enabled) {
+                return guard;
+            }
+            while self.inner.is_locked() {
+                spin_loop();
+            }
+        }
+    }
+
+    /// Acquires the lock if it's free, the interrupts are disabled until the guard is dropped
+    pub fn try_lock(&self) -> Option<SpinLockGuard<T>> {
+        self.acquire(interrupts::are_enabled())
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.inner.is_locked()
+    }
+
+    /// Disables the interrupts and tries to lock, the interrupts are restored if the lock is held
+    fn acquire(&self, enabled: bool) -> Option<SpinLockGuard<T>> {
+        interrupts::disable();
+        match self.inner.try_lock() {
+            Some(guard) => Some(SpinLockGuard {
+                guard: ManuallyDrop::new(guard),
+                enabled,
+            }),
+            None => {
+                if enabled {
+                    interrupts::enable();
+                }
+                None
+            }
+        }
+    }
+}
+
+impl<T: Default> Default for SpinLock<T> {
+    fn default() -> Self {
+        SpinLock::new(T::default())
+    }
+}
+
+/// Unlocks the spinlock when it's dropped, then restores the interrupts.
+///
+/// Guards of nested locks must be dropped in reverse order, so the interrupts are enabled only after the outer lock is unlocked.
+pub struct SpinLockGuard<'a, T> {
+    guard: ManuallyDrop<spin::MutexGuard<'a, T>>,
+    /// Whether the interrupts were enabled before the lock was acquired
+    enabled: bool,
+}
+
+impl<T> Deref for SpinLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for SpinLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T> Drop for SpinLockGuard<'_, T> {
+    fn drop(&mut self) {
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        if self.enabled {
+            interrupts::enable();
+        }
+    }
+}
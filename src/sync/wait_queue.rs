@@ -0,0 +1,84 @@
+//! A queue of the processes that wait for an event, they sleep off the run queues until the event wakes them.
+
+use alloc::collections::VecDeque;
+use core::mem;
+
+use crate::processes;
+
+use super::SpinLock;
+
+/// A process that sleeps in a wait, it's woken only if it still sleeps in the same wait
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Waiter {
+    pub pid: usize,
+    pub wait: u64,
+}
+
+impl Waiter {
+    /// Makes the current process a sleeper, fails if there is no current process (see `processes::prepare_sleep`)
+    pub fn current() -> Result<Self, ()> {
+        let (pid, wait) = processes::prepare_sleep()?;
+        Ok(Waiter { pid, wait })
+    }
+
+    /// Wakes the process if it still sleeps in the wait, a `Blocked` process returns from it's syscall with `status` if there is one.
+    /// Fails if the process was woken already (e.g. by a signal) or terminated.
+    pub fn wake(&self, status: Option<i64>) -> Result<(), ()> {
+        processes::wake_sleeper(self.pid, self.wait, status)
+    }
+}
+
+pub struct WaitQueue {
+    waiters: SpinLock<VecDeque<Waiter>>,
+}
+
+impl WaitQueue {
+    pub const fn new() -> Self {
+        WaitQueue {
+            waiters: SpinLock::new(VecDeque::new()),
+        }
+    }
+
+    /// Registers the current process as a waiter, it sleeps once it's syscall returns `status::BLOCKED`
+    /// and issues the syscall again when it's woken (see `processes::sleep_current_process`).
+    ///
+    /// Call it while the event's condition is locked, so an event that happens after the condition is checked wakes the process.
+    /// Fails if there is no current process, the caller should unlock the condition and `relax` before checking it again.
+    pub fn register(&self) -> Result<(), ()> {
+        self.register_waiter(Waiter::current()?);
+        Ok(())
+    }
+
+    /// Registers a waiter, a process that waits for one of several events registers the same waiter on their queues
+    /// and it's woken by the first of them.
+    pub fn register_waiter(&self, waiter: Waiter) {
+        self.waiters.lock().push_back(waiter);
+    }
+
+    /// Wakes the process that waited the longest, returns whether a process was woken.
+    ///
+    /// Waiters that were already woken (e.g. by a signal) or terminated are skipped, even if they sleep again in another wait.
+    pub fn wake_one(&self) -> bool {
+        loop {
+            let waiter = match self.waiters.lock().pop_front() {
+                Some(waiter) => waiter,
+                None => return false,
+            };
+            if waiter.wake(None).is_ok() {
+                return true;
+            }
+        }
+    }
+
+    /// Wakes every waiting process, returns the number of processes woken
+    pub fn wake_all(&self) -> usize {
+        let waiters = mem::take(&mut *self.waiters.lock());
+        waiters.into_iter().filter(|waiter| waiter.wake(None).is_ok()).count()
+    }
+}
+
+impl Default for WaitQueue {
+    fn default() -> Self {
+        WaitQueue::new()
+    }
+}
@@ -2,60 +2,18 @@
 
 mod services;
 
-use core::arch::asm;
 use log::{debug, error, trace};
 use x86_64::structures::idt::InterruptStackFrame;
 
-use crate::{processes::{objects::Registers, pause_process}, syscalls::services::*};
+use crate::{
+    processes::{handle_signals, objects::Registers, restart_syscall, sleep_current_process},
+    syscalls::services::*,
+};
 
+/// The syscall numbers, statuses & flags are shared with userland by the `abi` crate
+pub use abi::{futex, ioctl, message, number, status, wait};
 
-macro_rules! wrap_syscall_handler {
-    ($fn:ident => $wrapper:ident) => {
-        #[naked]
-        pub unsafe extern "sysv64" fn $wrapper() {
-            asm!(
-                "mov rbp, rsp",
-                "push r15",
-                "push r14",
-                "push r13",
-                "push r12",
-                "push r11",
-                "push r10",
-                "push r9",
-                "push r8",
-                "push rbp",
-                "push rdi",
-                "push rsi",
-                "push rdx",
-                "push rcx",
-                "push rbx",
-                "push rax",
-                "mov rdi, rbp", // Arg #1: stack frame
-                "mov rsi, rsp", // Arg #2: register list
-                "call {}",
-                "pop rax",
-                "pop rbx",
-                "pop rcx",
-                "pop rdx",
-                "pop rsi",
-                "pop rdi",
-                "pop rbp",
-                "pop r8",
-                "pop r9",
-                "pop r10",
-                "pop r11",
-                "pop r12",
-                "pop r13",
-                "pop r14",
-                "pop r15",
-                "iretq",
-                sym $fn,
-                options(noreturn)
-            );
-        }
-    };
-}
-wrap_syscall_handler!(syscall_handler => wrapped_syscall_handler);
+crate::wrap_interrupt_handler!(syscall_handler => wrapped_syscall_handler);
 
 /// Save the user process context and call the syscall dispatcher
 ///
@@ -79,12 +37,32 @@ extern "sysv64" fn syscall_handler(
     
     if number == number::EXECUTE {
         debug!("EXECUTE");
-        pause_process(arg1 as usize - 1, stack_frame);
-        execute(arg1 as usize);
+        registers.rax = execute(arg1 as usize, stack_frame, registers);
+    } else if number == number::WAIT {
+        debug!("WAIT");
+        registers.rax = wait(arg1 as usize, arg2, stack_frame, registers);
+    } else if number == number::NANOSLEEP {
+        debug!("NANOSLEEP");
+        registers.rax = nanosleep(arg1, stack_frame, registers);
+    } else if number == number::FUTEX {
+        debug!("FUTEX");
+        registers.rax = futex(arg1, arg2, arg3, arg4, stack_frame, registers);
     } else {
         registers.rax = dispatcher(number, arg1, arg2, arg3, arg4);
     }
-
+    if registers.rax == status::RESTART {
+        // the syscall is issued again with the same number & arguments once the signal is handled
+        registers.rax = number as i64;
+        restart_syscall(stack_frame, registers);
+        registers.rax = status::FAILURE;
+    } else if registers.rax == status::BLOCKED {
+        // the syscall is issued again with the same number & arguments once the caller is woken
+        registers.rax = number as i64;
+        sleep_current_process(stack_frame, registers);
+        registers.rax = status::FAILURE;
+    }
+    // the process returns to userland with the syscall's status
+    handle_signals(stack_frame, registers);
 }
 
 /// Handle all syscalls that require at max four arguments
@@ -109,30 +87,171 @@ fn dispatcher(number: u64, arg1: u64, arg2: u64, arg3: u64, arg4: u64) -> i64 {
         }
         number::KILL => {
             debug!("KILL");
-            kill(arg1 as usize)
+            kill(arg1 as i64, arg2 as usize)
         }
         number::GET_PID => {
             debug!("GET_PID");
             get_current_pid()
         }
+        number::READ => {
+            debug!("READ");
+            read(arg1 as usize, arg2, arg3)
+        }
+        number::WRITE => {
+            debug!("WRITE");
+            write(arg1 as usize, arg2, arg3)
+        }
+        number::OPEN => {
+            debug!("OPEN");
+            open(arg1, arg2, arg3)
+        }
+        number::CLOSE => {
+            debug!("CLOSE");
+            close(arg1 as usize)
+        }
+        number::DUP => {
+            debug!("DUP");
+            dup(arg1 as usize)
+        }
+        number::DUP2 => {
+            debug!("DUP2");
+            dup2(arg1 as usize, arg2 as usize)
+        }
+        number::STAT => {
+            debug!("STAT");
+            stat(arg1, arg2, arg3)
+        }
+        number::GETDENTS => {
+            debug!("GETDENTS");
+            get_directory_entries(arg1 as usize, arg2, arg3)
+        }
+        number::MKDIR => {
+            debug!("MKDIR");
+            make_directory(arg1, arg2)
+        }
+        number::UNLINK => {
+            debug!("UNLINK");
+            unlink(arg1, arg2)
+        }
+        number::CHDIR => {
+            debug!("CHDIR");
+            change_directory(arg1, arg2)
+        }
+        number::GETCWD => {
+            debug!("GETCWD");
+            get_current_directory(arg1, arg2)
+        }
+        number::CLOCK_GETTIME => {
+            debug!("CLOCK_GETTIME");
+            clock_get_time(arg1, arg2)
+        }
+        number::IOCTL => {
+            debug!("IOCTL");
+            io_control(arg1 as usize, arg2, arg3)
+        }
+        number::EXIT => {
+            debug!("EXIT");
+            exit(arg1 as u8)
+        }
+        number::PROCESSES => {
+            debug!("PROCESSES");
+            list_processes(arg1, arg2)
+        }
+        number::MEMINFO => {
+            debug!("MEMINFO");
+            memory_info(arg1)
+        }
+        number::START => {
+            debug!("START");
+            start(arg1 as usize)
+        }
+        number::PIPE => {
+            debug!("PIPE");
+            create_pipe(arg1)
+        }
+        number::SPAWN => {
+            debug!("SPAWN");
+            spawn(arg1, arg2, arg3, arg4)
+        }
+        number::READ_LOG => {
+            debug!("READ_LOG");
+            read_log(arg1 as usize, arg2, arg3)
+        }
+        number::BRK => {
+            debug!("BRK");
+            change_break(arg1)
+        }
+        number::MMAP => {
+            debug!("MMAP");
+            map_memory(arg1 as usize)
+        }
+        number::MUNMAP => {
+            debug!("MUNMAP");
+            unmap_memory(arg1, arg2 as usize)
+        }
+        number::SIGACTION => {
+            debug!("SIGACTION");
+            signal_action(arg1 as usize, arg2, arg3)
+        }
+        number::SIGRETURN => {
+            debug!("SIGRETURN");
+            signal_return(arg1)
+        }
+        number::SIGPROCMASK => {
+            debug!("SIGPROCMASK");
+            signal_mask(arg1, arg2)
+        }
+        number::ALARM => {
+            debug!("ALARM");
+            alarm(arg1)
+        }
+        number::SETPGID => {
+            debug!("SETPGID");
+            set_process_group(arg1 as usize, arg2 as usize)
+        }
+        number::GETPGID => {
+            debug!("GETPGID");
+            get_process_group(arg1 as usize)
+        }
+        number::SETSID => {
+            debug!("SETSID");
+            create_session()
+        }
+        number::CHANNEL_CREATE => {
+            debug!("CHANNEL_CREATE");
+            create_channel(arg1)
+        }
+        number::SEND => {
+            debug!("SEND");
+            send_message(arg1 as usize, arg2, arg3)
+        }
+        number::RECV => {
+            debug!("RECV");
+            receive_message(arg1 as usize, arg2, arg3)
+        }
+        number::SELECT => {
+            debug!("SELECT");
+            select_channels(arg1, arg2, arg3)
+        }
+        number::SHM_CREATE => {
+            debug!("SHM_CREATE");
+            create_shared_memory(arg1 as usize)
+        }
+        number::SHM_MAP => {
+            debug!("SHM_MAP");
+            map_shared_memory(arg1 as usize, arg2)
+        }
+        number::SHM_UNMAP => {
+            debug!("SHM_UNMAP");
+            unmap_shared_memory(arg1)
+        }
+        number::SETPRIORITY => {
+            debug!("SETPRIORITY");
+            set_priority(arg1 as usize, arg2 as i64)
+        }
         _ => {
-            error!("unimplemented syscall");
-            unimplemented!();
+            error!("unimplemented syscall: {}", number);
+            status::FAILURE
         }
     }
 }
-
-/// Syscalls numbers
-pub mod number {
-    pub const DISPLAY_PROCESS_INFO: u64 = 0;
-    pub const CREATE: u64 = 1;
-    pub const EXECUTE: u64 = 2;
-    pub const KILL: u64 = 3;
-    pub const GET_PID: u64 = 4;
-}
-
-/// Syscalls exit statuses
-pub mod status {
-    pub const SUCCESS: i64 = 0;
-    pub const FAILURE: i64 = -1;
-}
\ No newline at end of file
@@ -1,15 +1,51 @@
 //! native syscalls services
 
+use alloc::{string::String, sync::Arc, vec, vec::Vec};
+use core::{
+    mem::{self, MaybeUninit},
+    slice, str,
+    sync::atomic::AtomicU32,
+};
+
 use log::info;
+use x86_64::structures::idt::InterruptStackFrame;
 
-use crate::{syscalls::status, processes::{get_process_info, spawn_process, execute_process, kill_process, self}};
+use crate::{
+    fs::{
+        descriptors::MAX_FILE_DESCRIPTORS,
+        pipe,
+        tty::{Termios, TtyRequest},
+        vfs::{self, DirectoryEntry, Stat},
+        File, OpenFlags,
+    },
+    ipc::{
+        channel::{self, Message, MessageBuffers, MAX_MESSAGE_FILES, MAX_MESSAGE_SIZE},
+        shm::SharedMemory,
+    },
+    memory::{self, MemoryInfo},
+    processes::{
+        self, block_sleeper, change_blocked_signals, execute_child, exit_current_process, get_file, get_process_info,
+        get_session, get_working_directory, has_pending_signals, is_process_group, is_sleeping,
+        objects::{ProcessInfo, Registers},
+        prepare_sleep, return_from_signal, send_group_signal, send_signal, set_alarm, set_signal_action, set_working_directory,
+        signals::SignalAction,
+        spawn_process, start_child, update_files, wait_child,
+    },
+    sync,
+    syscalls::{futex, ioctl, message, status},
+    time::{self, Timespec, NANOSECONDS_PER_SECOND},
+};
 
 pub fn display_process_info(pid: usize) -> i64 {
     info!("process information: {:#x?}", get_process_info(pid));
     status::SUCCESS
 }
 
+/// Creates a process that runs the function at `process_code`, the function must be in the caller's memory
 pub fn create_process(process_code: u64) -> i64 {
+    if check_user_buffer(process_code, 1).is_err() {
+        return status::FAILURE;
+    }
     let pid = spawn_process(process_code) as i64;
     info!("spawned a process with pid {:x}", pid);
     
@@ -20,15 +56,712 @@ pub fn create_process(process_code: u64) -> i64 {
     }
 }
 
-pub fn execute(pid: usize) -> i64 {
-    execute_process(pid);
-    status::FAILURE
+/// Creates a process running the ELF program at `path`, it's arguments are followed by null bytes
+pub fn spawn(path: u64, length: u64, arguments: u64, arguments_length: u64) -> i64 {
+    let path = match unsafe { user_path(path, length) } {
+        Ok(path) => path,
+        Err(()) => return status::FAILURE,
+    };
+    let arguments = match unsafe { user_buffer(arguments, arguments_length) } {
+        Ok(arguments) => arguments,
+        Err(()) if arguments_length == 0 => &[],
+        Err(()) => return status::FAILURE,
+    };
+
+    let result = processes::spawn_program(&path, arguments);
+    if let Ok(pid) = result {
+        info!("spawned {} with pid {:x}", path, pid);
+    }
+    as_status(result)
+}
+
+/// Runs a child process, the caller continues with the child's exit status when the child exits
+pub fn execute(pid: usize, stack_frame: &InterruptStackFrame, registers: &mut Registers) -> i64 {
+    // the caller context is saved with the status it returns with
+    registers.rax = status::SUCCESS;
+    as_status(execute_child(pid, stack_frame, registers))
+}
+
+/// Runs a child process, the caller continues running alongside it
+pub fn start(pid: usize) -> i64 {
+    as_status(start_child(pid).map(|()| 0))
+}
+
+/// Waits for a started child process, the caller continues with the child's exit status when the child exits.
+/// The `flags` may make it return when the child stops, or return right away (see `syscalls::wait`).
+pub fn wait(pid: usize, flags: u64, stack_frame: &InterruptStackFrame, registers: &mut Registers) -> i64 {
+    registers.rax = status::SUCCESS;
+    as_status(wait_child(pid, flags, stack_frame, registers))
+}
+
+/// Sends a signal to a process, or to the process group `-pid` if `pid` is negative.
+/// Returns only if the caller is still alive.
+pub fn kill(pid: i64, signal: usize) -> i64 {
+    let result = match pid {
+        pid if pid < 0 => send_group_signal(pid.unsigned_abs() as usize, signal),
+        pid => send_signal(pid as usize, signal),
+    };
+    as_status(result.map(|()| 0))
+}
+
+/// Moves a process to the process group `pgid`, `pgid` equal to `pid` creates a new group
+pub fn set_process_group(pid: usize, pgid: usize) -> i64 {
+    as_status(processes::set_process_group(pid, pgid).map(|()| 0))
+}
+
+pub fn get_process_group(pid: usize) -> i64 {
+    as_status(processes::get_process_group(pid))
+}
+
+/// Sets the nice value of the caller or of a child of it, from `MIN_NICE` (the highest priority) to `MAX_NICE`
+pub fn set_priority(pid: usize, nice: i64) -> i64 {
+    as_status(processes::set_priority(pid, nice).map(|()| 0))
+}
+
+/// Makes the caller the leader of a new session, returns the session id
+pub fn create_session() -> i64 {
+    as_status(processes::create_session())
 }
 
-pub fn kill(pid: usize) -> i64 {
-    kill_process(pid);
+/// Terminates the caller, returns only if the caller isn't a process
+pub fn exit(exit_status: u8) -> i64 {
+    as_status(exit_current_process(exit_status).map(|()| 0))
+}
+
+/// Writes the information of up to `count` processes, returns the number of written processes
+pub fn list_processes(processes: u64, count: u64) -> i64 {
+    // the array may hold any bytes (e.g. an invalid state), so it's entries are only written
+    let buffer = match unsafe { user_slice_mut::<MaybeUninit<ProcessInfo>>(processes, count) } {
+        Ok(buffer) => buffer,
+        Err(()) => return status::FAILURE,
+    };
+
+    let listed = processes::list_processes();
+    for (slot, info) in buffer.iter_mut().zip(listed.iter()) {
+        slot.write(*info);
+    }
+    listed.len().min(buffer.len()) as i64
+}
+
+pub fn memory_info(info: u64) -> i64 {
+    match unsafe { user_object_mut::<MemoryInfo>(info) } {
+        Ok(info) => {
+            *info = memory::memory_info();
+            status::SUCCESS
+        }
+        Err(()) => status::FAILURE,
+    }
 }
 
 pub fn get_current_pid() -> i64 {
-    processes::get_current_pid() as i64
-}
\ No newline at end of file
+    processes::get_current_pid().map_or(status::FAILURE, |pid| pid as i64)
+}
+
+pub fn read(fd: usize, buffer: u64, length: u64) -> i64 {
+    let file = match get_file(fd) {
+        Some(file) => file,
+        None => return status::FAILURE,
+    };
+    let buffer = match unsafe { user_buffer_mut(buffer, length) } {
+        Ok(buffer) => buffer,
+        Err(()) => return status::FAILURE,
+    };
+
+    as_waiting_status(file.read(buffer))
+}
+
+pub fn write(fd: usize, buffer: u64, length: u64) -> i64 {
+    let file = match get_file(fd) {
+        Some(file) => file,
+        None => return status::FAILURE,
+    };
+    let buffer = match unsafe { user_buffer(buffer, length) } {
+        Ok(buffer) => buffer,
+        Err(()) => return status::FAILURE,
+    };
+
+    as_waiting_status(file.write(buffer))
+}
+
+pub fn open(path: u64, length: u64, flags: u64) -> i64 {
+    let path = match unsafe { user_path(path, length) } {
+        Ok(path) => path,
+        Err(()) => return status::FAILURE,
+    };
+    let flags = match OpenFlags::from_bits(flags) {
+        Some(flags) => flags,
+        None => return status::FAILURE,
+    };
+
+    as_status(vfs::open(&path, flags).and_then(|file| update_files(|files| files.insert(file))))
+}
+
+pub fn close(fd: usize) -> i64 {
+    as_status(update_files(|files| files.close(fd)).map(|()| 0))
+}
+
+pub fn dup(fd: usize) -> i64 {
+    as_status(update_files(|files| files.dup(fd)))
+}
+
+pub fn dup2(old_fd: usize, new_fd: usize) -> i64 {
+    as_status(update_files(|files| files.dup2(old_fd, new_fd)))
+}
+
+pub fn stat(path: u64, length: u64, stat: u64) -> i64 {
+    let path = match unsafe { user_path(path, length) } {
+        Ok(path) => path,
+        Err(()) => return status::FAILURE,
+    };
+    // like GETDENTS' entries, the stat may hold any bytes, so it's only written
+    let stat = match unsafe { user_object_mut::<MaybeUninit<Stat>>(stat) } {
+        Ok(stat) => stat,
+        Err(()) => return status::FAILURE,
+    };
+
+    as_status(vfs::stat(&path).map(|metadata| {
+        stat.write(metadata);
+        0
+    }))
+}
+
+/// Reads the next entries of the directory opened at `fd` to an array of `count` entries, returns the number of entries read
+pub fn get_directory_entries(fd: usize, entries: u64, count: u64) -> i64 {
+    let file = match get_file(fd) {
+        Some(file) => file,
+        None => return status::FAILURE,
+    };
+    // the array may hold any bytes (e.g. an invalid file type), so it's entries are only written
+    let entries = match unsafe { user_slice_mut::<MaybeUninit<DirectoryEntry>>(entries, count) } {
+        Ok(entries) => entries,
+        Err(()) => return status::FAILURE,
+    };
+
+    let mut read = 0;
+    for slot in entries.iter_mut() {
+        let mut entry = DirectoryEntry::empty();
+        match file.read_directory(slice::from_mut(&mut entry)) {
+            Ok(0) => break,
+            Ok(_) => {
+                slot.write(entry);
+            }
+            Err(()) if read == 0 => return status::FAILURE,
+            Err(()) => break,
+        }
+        read += 1;
+    }
+    read as i64
+}
+
+pub fn make_directory(path: u64, length: u64) -> i64 {
+    as_status(unsafe { user_path(path, length) }.and_then(|path| vfs::mkdir(&path)).map(|()| 0))
+}
+
+pub fn unlink(path: u64, length: u64) -> i64 {
+    as_status(unsafe { user_path(path, length) }.and_then(|path| vfs::unlink(&path)).map(|()| 0))
+}
+
+pub fn change_directory(path: u64, length: u64) -> i64 {
+    let result = unsafe { user_path(path, length) }
+        .and_then(|path| vfs::canonical_directory(&path))
+        .and_then(set_working_directory);
+
+    as_status(result.map(|()| 0))
+}
+
+pub fn get_current_directory(buffer: u64, length: u64) -> i64 {
+    let working_directory = get_working_directory();
+    let buffer = match unsafe { user_buffer_mut(buffer, length) } {
+        Ok(buffer) if buffer.len() >= working_directory.len() => buffer,
+        _ => return status::FAILURE,
+    };
+
+    buffer[..working_directory.len()].copy_from_slice(working_directory.as_bytes());
+    working_directory.len() as i64
+}
+
+pub fn clock_get_time(clock: u64, timespec: u64) -> i64 {
+    let timespec = match unsafe { user_object_mut::<Timespec>(timespec) } {
+        Ok(timespec) => timespec,
+        Err(()) => return status::FAILURE,
+    };
+
+    as_status(time::clock_time(clock).map(|now| {
+        *timespec = now;
+        0
+    }))
+}
+
+/// Blocks the caller for a duration, the caller continues after the first timer tick past it's deadline.
+/// A signal interrupts the sleep, it fails once the signal is handled.
+pub fn nanosleep(duration: u64, stack_frame: &InterruptStackFrame, registers: &mut Registers) -> i64 {
+    let duration = match unsafe { user_object::<Timespec>(duration) }.and_then(|duration| duration.as_nanoseconds().ok_or(())) {
+        Ok(duration) => duration,
+        Err(()) => return status::FAILURE,
+    };
+    if duration == 0 {
+        return status::SUCCESS;
+    }
+
+    let deadline = time::uptime_nanoseconds().saturating_add(duration);
+    if prepare_sleep().is_err() {
+        return status::FAILURE;
+    }
+    // the caller context is saved with the status of the deadline, a signal sets a failure
+    registers.rax = status::SUCCESS;
+    block_sleeper(Some(deadline), stack_frame, registers);
+    // a signal is pending, the caller returns before it's deadline
+    status::FAILURE
+}
+
+/// Waits on the 32 bit word at `address` or wakes it's waiters (see `sync::futex`), the word must be in the caller's memory.
+///
+/// # Arguments
+///  - `operation`, `futex::WAIT` waits while the word holds `value`, `futex::WAKE` wakes up to `value` waiters
+///  - `timeout`, a pointer to the `Timespec` a wait fails after, a null pointer waits without a timeout
+pub fn futex(
+    address: u64,
+    operation: u64,
+    value: u64,
+    timeout: u64,
+    stack_frame: &InterruptStackFrame,
+    registers: &mut Registers,
+) -> i64 {
+    // the words are keyed by physical address, so an unchecked address would wait on or wake any mapped word
+    if check_user_buffer(address, mem::size_of::<AtomicU32>() as u64).is_err() {
+        return status::FAILURE;
+    }
+
+    match operation {
+        futex::WAIT => futex_wait(address, value as u32, timeout, stack_frame, registers),
+        futex::WAKE => as_status(sync::futex::wake(address, value as usize)),
+        _ => status::FAILURE,
+    }
+}
+
+/// A woken waiter returns successfully, it may wake spuriously (e.g. when a signal is sent to it) so it checks the word again
+fn futex_wait(address: u64, value: u32, timeout: u64, stack_frame: &InterruptStackFrame, registers: &mut Registers) -> i64 {
+    let deadline = match timeout {
+        0 => None,
+        timeout => match unsafe { user_object::<Timespec>(timeout) }.and_then(|timeout| timeout.as_nanoseconds().ok_or(())) {
+            Ok(timeout) => Some(time::uptime_nanoseconds().saturating_add(timeout)),
+            Err(()) => return status::FAILURE,
+        },
+    };
+    let waiter = match sync::futex::wait(address, value) {
+        Ok(waiter) => waiter,
+        Err(()) => return status::FAILURE,
+    };
+
+    // the caller context is saved with the status of a timeout or a signal, a wake sets a success
+    registers.rax = status::FAILURE;
+    block_sleeper(deadline, stack_frame, registers);
+    // the caller was woken before it blocked, or a signal is pending and it's still queued
+    sync::futex::cancel(waiter);
+    status::SUCCESS
+}
+
+/// Creates a pipe, writes the file descriptors of it's read end and write end to `fds`
+pub fn create_pipe(fds: u64) -> i64 {
+    let fds = match unsafe { user_object_mut::<[usize; 2]>(fds) } {
+        Ok(fds) => fds,
+        Err(()) => return status::FAILURE,
+    };
+
+    let (reader, writer) = pipe::pipe();
+    let result = update_files(|files| {
+        let read_fd = files.insert(reader)?;
+        match files.insert(writer) {
+            Ok(write_fd) => Ok([read_fd, write_fd]),
+            Err(()) => {
+                files.close(read_fd).ok();
+                Err(())
+            }
+        }
+    });
+
+    as_status(result.map(|opened| {
+        *fds = opened;
+        0
+    }))
+}
+
+/// Creates a channel, writes the file descriptors of it's two ends to `handles`
+pub fn create_channel(handles: u64) -> i64 {
+    let handles = match unsafe { user_object_mut::<[usize; 2]>(handles) } {
+        Ok(handles) => handles,
+        Err(()) => return status::FAILURE,
+    };
+
+    let (first, second) = channel::channel();
+    as_status(insert_files(vec![first, second]).map(|opened| {
+        handles.copy_from_slice(&opened);
+        0
+    }))
+}
+
+/// Sends the data & files of `buffers` (a `MessageBuffers`) through the channel end at `handle`,
+/// the files stay open in the sender too. Returns `status::WOULD_BLOCK` if a `NON_BLOCKING` send finds the other end full.
+pub fn send_message(handle: usize, buffers: u64, flags: u64) -> i64 {
+    let end = match get_file(handle) {
+        Some(end) => end,
+        None => return status::FAILURE,
+    };
+    let buffers = match unsafe { user_object::<MessageBuffers>(buffers) } {
+        Ok(buffers) => buffers,
+        Err(()) => return status::FAILURE,
+    };
+    if buffers.length as usize > MAX_MESSAGE_SIZE || buffers.file_count as usize > MAX_MESSAGE_FILES {
+        return status::FAILURE;
+    }
+    let data = unsafe { user_slice::<u8>(buffers.data, buffers.length) };
+    let fds = unsafe { user_slice::<usize>(buffers.files, buffers.file_count) };
+    let (data, fds) = match (data, fds) {
+        (Ok(data), Ok(fds)) => (data, fds),
+        _ => return status::FAILURE,
+    };
+    let files = match fds.iter().map(|fd| get_file(*fd)).collect::<Option<Vec<_>>>() {
+        Some(files) => files,
+        None => return status::FAILURE,
+    };
+
+    let message = Message {
+        data: data.to_vec(),
+        files,
+    };
+    let blocking = flags & message::NON_BLOCKING == 0;
+    let result = end.as_channel().ok_or(()).and_then(|end| end.send(message, blocking));
+    as_channel_status(result.map(|sent| sent.then_some(0)))
+}
+
+/// Receives a message from the channel end at `handle` to `buffers` (a `MessageBuffers`), returns the length of it's data.
+/// The message's files are opened at new file descriptors, written to the files buffer with their count.
+/// Returns `status::WOULD_BLOCK` if a `NON_BLOCKING` receive finds no message.
+pub fn receive_message(handle: usize, buffers: u64, flags: u64) -> i64 {
+    let end = match get_file(handle) {
+        Some(end) => end,
+        None => return status::FAILURE,
+    };
+    let buffers = match unsafe { user_object_mut::<MessageBuffers>(buffers) } {
+        Ok(buffers) => buffers,
+        Err(()) => return status::FAILURE,
+    };
+    let data = unsafe { user_slice_mut::<u8>(buffers.data, buffers.length) };
+    let fds = unsafe { user_slice_mut::<usize>(buffers.files, buffers.file_count) };
+    let (data, fds) = match (data, fds) {
+        (Ok(data), Ok(fds)) => (data, fds),
+        _ => return status::FAILURE,
+    };
+
+    let blocking = flags & message::NON_BLOCKING == 0;
+    let result = end.as_channel().ok_or(()).and_then(|end| end.receive(data.len(), fds.len(), blocking));
+    let message = match result {
+        Ok(Some(message)) => message,
+        result => return as_channel_status(result.map(|_| None)),
+    };
+    data[..message.data.len()].copy_from_slice(&message.data);
+    // the message was received, it's files are released if they don't fit the file descriptor table
+    let opened = match insert_files(message.files) {
+        Ok(opened) => opened,
+        Err(()) => return status::FAILURE,
+    };
+    fds[..opened.len()].copy_from_slice(&opened);
+    buffers.file_count = opened.len() as u64;
+    message.data.len() as i64
+}
+
+/// Waits until one of the channel ends at the `count` file descriptors of `handles` has a message or it's other end is closed,
+/// returns it's index. Returns `status::WOULD_BLOCK` if no end is ready for a `NON_BLOCKING` select.
+pub fn select_channels(handles: u64, count: u64, flags: u64) -> i64 {
+    let handles = match unsafe { user_slice::<usize>(handles, count) } {
+        Ok(handles) if !handles.is_empty() && handles.len() <= MAX_FILE_DESCRIPTORS => handles,
+        _ => return status::FAILURE,
+    };
+    let files = match handles.iter().map(|fd| get_file(*fd)).collect::<Option<Vec<_>>>() {
+        Some(files) => files,
+        None => return status::FAILURE,
+    };
+    let ends = match files.iter().map(|file| file.as_channel()).collect::<Option<Vec<_>>>() {
+        Some(ends) => ends,
+        None => return status::FAILURE,
+    };
+
+    as_channel_status(channel::select(&ends, flags & message::NON_BLOCKING == 0))
+}
+
+/// Creates a zeroed shared memory segment of `length` bytes, returns it's file descriptor
+pub fn create_shared_memory(length: usize) -> i64 {
+    let segment: Arc<dyn File> = match SharedMemory::new(length) {
+        Ok(segment) => Arc::new(segment),
+        Err(()) => return status::FAILURE,
+    };
+    as_status(insert_files(vec![segment]).map(|opened| opened[0]))
+}
+
+/// Maps the segment at `handle` at `address`, or at an address the kernel chooses if it's zero.
+/// Returns the address of the mapping
+pub fn map_shared_memory(handle: usize, address: u64) -> i64 {
+    let file = match get_file(handle) {
+        Some(file) => file,
+        None => return status::FAILURE,
+    };
+    let result = file.as_shared_memory().ok_or(()).and_then(|segment| processes::map_shared_memory(segment, address));
+    as_status(result.map(|address| address as usize))
+}
+
+/// Unmaps the segment mapped at `address` by SHM_MAP, it's frames are freed once it has no mappings & handles
+pub fn unmap_shared_memory(address: u64) -> i64 {
+    as_status(processes::unmap_shared_memory(address).map(|()| 0))
+}
+
+/// Copies the kernel log from `offset`, returns the number of copied bytes
+pub fn read_log(offset: usize, buffer: u64, length: u64) -> i64 {
+    match unsafe { user_buffer_mut(buffer, length) } {
+        Ok(buffer) => crate::log::read_log(offset, buffer) as i64,
+        Err(()) => status::FAILURE,
+    }
+}
+
+/// Moves the program break to `address`, returns the new break (the current break if `address` is zero)
+pub fn change_break(address: u64) -> i64 {
+    as_status(processes::change_break(address).map(|program_break| program_break as usize))
+}
+
+/// Maps `length` bytes of zeroed memory, returns it's address
+pub fn map_memory(length: usize) -> i64 {
+    as_status(processes::map_memory(length).map(|address| address as usize))
+}
+
+/// Unmaps a region mapped by MMAP, `length` must be the mapped length
+pub fn unmap_memory(address: u64, length: usize) -> i64 {
+    as_status(processes::unmap_memory(address, length).map(|()| 0))
+}
+
+/// Replaces the action of a signal if `action` isn't null, the previous action is written to `old_action` if it isn't null
+pub fn signal_action(signal: usize, action: u64, old_action: u64) -> i64 {
+    let action = match action {
+        0 => None,
+        action => match unsafe { user_object::<SignalAction>(action) } {
+            Ok(action) => Some(*action),
+            Err(()) => return status::FAILURE,
+        },
+    };
+    let old_action = match old_action {
+        0 => None,
+        old_action => match unsafe { user_object_mut::<SignalAction>(old_action) } {
+            Ok(old_action) => Some(old_action),
+            Err(()) => return status::FAILURE,
+        },
+    };
+
+    as_status(set_signal_action(signal, action).map(|previous| {
+        if let Some(old_action) = old_action {
+            *old_action = previous;
+        }
+        0
+    }))
+}
+
+/// Continues the caller from the context saved in it's signal frame, returns only if the frame is invalid
+pub fn signal_return(frame: u64) -> i64 {
+    as_status(return_from_signal(frame).map(|()| 0))
+}
+
+/// Changes the blocked signals (see `signals::mask`), returns the previously blocked signals
+pub fn signal_mask(operation: u64, signals: u64) -> i64 {
+    as_status(change_blocked_signals(operation, signals).map(|previous| previous as usize))
+}
+
+/// Sends SIGALRM to the caller after `seconds`, zero cancels the alarm.
+/// Returns the seconds left until the previous alarm (zero if there was none).
+pub fn alarm(seconds: u64) -> i64 {
+    let now = time::uptime_nanoseconds();
+    let deadline = match seconds {
+        0 => None,
+        seconds => Some(now.saturating_add(seconds.saturating_mul(NANOSECONDS_PER_SECOND))),
+    };
+
+    as_status(set_alarm(deadline).map(|previous| {
+        let left = previous.map_or(0, |previous| previous.saturating_sub(now));
+        ((left + NANOSECONDS_PER_SECOND - 1) / NANOSECONDS_PER_SECOND) as usize
+    }))
+}
+
+/// Sends a request to a terminal (see `syscalls::ioctl`)
+pub fn io_control(fd: usize, request: u64, argument: u64) -> i64 {
+    let file = match get_file(fd) {
+        Some(file) => file,
+        None => return status::FAILURE,
+    };
+    let request = match request {
+        ioctl::GET_SETTINGS => unsafe { user_object_mut::<Termios>(argument) }.map(TtyRequest::GetSettings),
+        ioctl::SET_SETTINGS => unsafe { user_object::<Termios>(argument) }.map(TtyRequest::SetSettings),
+        ioctl::GET_FOREGROUND => Ok(TtyRequest::GetForeground),
+        // the foreground is a group of the caller's session, and the terminal must belong to that session
+        ioctl::SET_FOREGROUND => get_session().and_then(|session| match argument {
+            ioctl::NO_FOREGROUND => Ok(TtyRequest::SetForeground { session, group: None }),
+            pgid if is_process_group(pgid as usize, session) => Ok(TtyRequest::SetForeground {
+                session,
+                group: Some(pgid as usize),
+            }),
+            _ => Err(()),
+        }),
+        _ => Err(()),
+    };
+
+    as_status(request.and_then(|request| file.ioctl(request)))
+}
+
+/// Converts a service result to a syscall status
+fn as_status(result: Result<usize, ()>) -> i64 {
+    match result {
+        Ok(value) => value as i64,
+        Err(()) => status::FAILURE,
+    }
+}
+
+/// Converts the result of a service that may wait to a syscall status,
+/// the service fails when the caller sleeps on a wait queue or when it waited until a signal was sent to the caller
+fn as_waiting_status(result: Result<usize, ()>) -> i64 {
+    match result {
+        Err(()) if is_sleeping() => status::BLOCKED,
+        Err(()) if has_pending_signals() => status::RESTART,
+        result => as_status(result),
+    }
+}
+
+/// Converts the result of a service that may wait to a syscall status (see `as_waiting_status`),
+/// `None` means the service doesn't block and would have waited
+fn as_channel_status(result: Result<Option<usize>, ()>) -> i64 {
+    match result {
+        Ok(None) => status::WOULD_BLOCK,
+        result => as_waiting_status(result.map(|value| value.unwrap_or_default())),
+    }
+}
+
+/// Opens the files at the lowest free file descriptors of the current process, returns the file descriptors.
+/// Fails without opening any of the files if they don't fit the table.
+fn insert_files(files: Vec<Arc<dyn File>>) -> Result<Vec<usize>, ()> {
+    update_files(|table| {
+        let mut opened = Vec::with_capacity(files.len());
+        for file in files {
+            match table.insert(file) {
+                Ok(fd) => opened.push(fd),
+                Err(()) => {
+                    for fd in opened {
+                        table.close(fd).ok();
+                    }
+                    return Err(());
+                }
+            }
+        }
+        Ok(opened)
+    })
+}
+
+/// Checks that a userland buffer is in the current process' memory (see `ProcessData::owns`),
+/// so a process can't make the kernel read or write the kernel's memory. An empty buffer may be anywhere
+fn check_user_buffer(address: u64, length: u64) -> Result<(), ()> {
+    if length == 0 {
+        return Ok(());
+    }
+    let end = address.checked_add(length).ok_or(())?;
+    if address == 0 || !processes::owns_memory(address, end) {
+        return Err(());
+    }
+    Ok(())
+}
+
+/// Converts a userland buffer to a kernel slice, fails if the buffer isn't in the current process' memory
+///
+/// # Safety
+///
+/// The current process' memory must stay mapped while the slice is used
+unsafe fn user_buffer<'a>(address: u64, length: u64) -> Result<&'a [u8], ()> {
+    if length == 0 {
+        return Ok(&[]);
+    }
+    check_user_buffer(address, length)?;
+    Ok(slice::from_raw_parts(address as *const u8, length as usize))
+}
+
+/// Converts a userland buffer to a mutable kernel slice, fails if the buffer isn't in the current process' memory
+///
+/// # Safety
+///
+/// The current process' memory must stay mapped while the slice is used
+unsafe fn user_buffer_mut<'a>(address: u64, length: u64) -> Result<&'a mut [u8], ()> {
+    if length == 0 {
+        return Ok(&mut []);
+    }
+    check_user_buffer(address, length)?;
+    Ok(slice::from_raw_parts_mut(address as *mut u8, length as usize))
+}
+
+/// Converts a userland object pointer to a kernel reference
+///
+/// # Safety
+///
+/// The current process' memory must stay mapped while the object is used
+unsafe fn user_object<'a, T>(address: u64) -> Result<&'a T, ()> {
+    if address as usize % mem::align_of::<T>() != 0 {
+        return Err(());
+    }
+    user_buffer(address, mem::size_of::<T>() as u64)?;
+    Ok(&*(address as *const T))
+}
+
+/// Converts a userland object pointer to a mutable kernel reference
+///
+/// # Safety
+///
+/// The current process' memory must stay mapped while the object is used
+unsafe fn user_object_mut<'a, T>(address: u64) -> Result<&'a mut T, ()> {
+    if address as usize % mem::align_of::<T>() != 0 {
+        return Err(());
+    }
+    user_buffer_mut(address, mem::size_of::<T>() as u64)?;
+    Ok(&mut *(address as *mut T))
+}
+
+/// Converts a userland array of `count` objects to a kernel slice, an empty array may be null
+///
+/// # Safety
+///
+/// The current process' memory must stay mapped while the array is used
+unsafe fn user_slice<'a, T>(address: u64, count: u64) -> Result<&'a [T], ()> {
+    if count == 0 {
+        return Ok(&[]);
+    }
+    if address as usize % mem::align_of::<T>() != 0 {
+        return Err(());
+    }
+    user_buffer(address, count.checked_mul(mem::size_of::<T>() as u64).ok_or(())?)?;
+    Ok(slice::from_raw_parts(address as *const T, count as usize))
+}
+
+/// Converts a userland array of `count` objects to a mutable kernel slice, an empty array may be null
+///
+/// # Safety
+///
+/// The current process' memory must stay mapped while the array is used
+unsafe fn user_slice_mut<'a, T>(address: u64, count: u64) -> Result<&'a mut [T], ()> {
+    if count == 0 {
+        return Ok(&mut []);
+    }
+    if address as usize % mem::align_of::<T>() != 0 {
+        return Err(());
+    }
+    user_buffer_mut(address, count.checked_mul(mem::size_of::<T>() as u64).ok_or(())?)?;
+    Ok(slice::from_raw_parts_mut(address as *mut T, count as usize))
+}
+
+/// Converts a userland path to an absolute path, relative paths are resolved from the working directory.
+///
+/// # Safety
+///
+/// The current process' memory must stay mapped while the path is read
+unsafe fn user_path(address: u64, length: u64) -> Result<String, ()> {
+    let path = str::from_utf8(user_buffer(address, length)?).map_err(|_| ())?;
+    Ok(vfs::absolute_path(&get_working_directory(), path))
+}
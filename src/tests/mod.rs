@@ -1,5 +1,13 @@
+use crate::drivers::pit;
+use crate::memory::{kmalloc, kmap, paging::EntryFlags, types::PAGE_SIZE};
 use crate::serial_println;
 use crate::panic::{QemuExitCode, exit_qemu};
+
+/// An unused page in the lower half, the tests share it with their processes
+pub const TEST_PAGE: u64 = 0x5000_0000_0000;
+const POLL_MICROSECONDS: u64 = 1000;
+const POLL_ATTEMPTS: usize = 5000;
+
 pub trait Testable {
     fn run(&self) -> ();
 }
@@ -26,4 +34,19 @@ pub fn runner(tests: &[&dyn Testable]) {
     exit_qemu(QemuExitCode::Success);
 }
 
+/// Maps `TEST_PAGE` to a new frame writable by userland, processes share the kernel page table so they see it too
+pub fn map_test_page() {
+    let frame = kmalloc(PAGE_SIZE, PAGE_SIZE).unwrap();
+    unsafe { kmap(TEST_PAGE, frame, EntryFlags::PRESENT | EntryFlags::WRITABLE | EntryFlags::USER).unwrap() };
+}
 
+/// Polls `condition` every millisecond for about 5 seconds, returns whether it held
+pub fn wait_until(condition: impl Fn() -> bool) -> bool {
+    for _ in 0..POLL_ATTEMPTS {
+        if condition() {
+            return true;
+        }
+        pit::wait_microseconds(POLL_MICROSECONDS);
+    }
+    false
+}
@@ -0,0 +1,100 @@
+//! The time module goal is to measure time: the kernel uptime and the wall clock time.
+//!
+//! The TSC counts at a constant rate, it's calibrated once against the HPET (or the PIT when there is no HPET)
+//! and then converts to nanoseconds without accessing any device. The wall clock is read from the RTC at boot,
+//! and advances with the uptime.
+
+pub mod timer_wheel;
+
+use core::{
+    arch::x86_64::_rdtsc,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use log::info;
+use x86_64::instructions::interrupts;
+
+use crate::{
+    drivers::{hpet, pit, rtc},
+    processes,
+};
+
+pub use abi::time::{Timespec, CLOCK_MONOTONIC, CLOCK_REALTIME, NANOSECONDS_PER_SECOND};
+
+const MICROSECONDS_PER_SECOND: u64 = 1_000_000;
+const CALIBRATION_MICROSECONDS: u64 = 10_000;
+
+/// The TSC value when the clocks were initialized
+static BOOT_TSC: AtomicU64 = AtomicU64::new(0);
+/// The TSC increments per second, zero before `init`
+static TSC_FREQUENCY: AtomicU64 = AtomicU64::new(0);
+/// The wall clock time when the clocks were initialized
+static BOOT_UNIX_SECONDS: AtomicU64 = AtomicU64::new(0);
+
+/// Calibrates the TSC and reads the wall clock.
+///
+/// Must be called after `memory::init`, the HPET registers are mapped.
+pub fn init() {
+    let frequency = match hpet::init() {
+        Ok(()) => calibrate_tsc(hpet::wait_microseconds),
+        Err(()) => calibrate_tsc(pit::wait_microseconds),
+    };
+    TSC_FREQUENCY.store(frequency, Ordering::SeqCst);
+    BOOT_TSC.store(read_tsc(), Ordering::SeqCst);
+
+    let now = rtc::read();
+    BOOT_UNIX_SECONDS.store(now.unix_seconds(), Ordering::SeqCst);
+    info!("TSC runs at {} Hz, the time is {:?}", frequency, now);
+}
+
+fn read_tsc() -> u64 {
+    unsafe { _rdtsc() }
+}
+
+/// Returns the TSC increments per second, measured with `wait_microseconds`
+fn calibrate_tsc(wait_microseconds: fn(u64)) -> u64 {
+    interrupts::without_interrupts(|| {
+        let start = read_tsc();
+        wait_microseconds(CALIBRATION_MICROSECONDS);
+        let elapsed = read_tsc() - start;
+        elapsed * (MICROSECONDS_PER_SECOND / CALIBRATION_MICROSECONDS)
+    })
+}
+
+/// Returns the nanoseconds since the clocks were initialized, zero before `init`.
+///
+/// The processors TSCs are assumed to be synchronized (QEMU & processors with an invariant TSC),
+/// a processor that lags behind the bootstrap processor never reads a time before the boot.
+pub fn uptime_nanoseconds() -> u64 {
+    let frequency = TSC_FREQUENCY.load(Ordering::SeqCst);
+    if frequency == 0 {
+        return 0;
+    }
+    let elapsed = read_tsc().saturating_sub(BOOT_TSC.load(Ordering::SeqCst));
+    (elapsed as u128 * NANOSECONDS_PER_SECOND as u128 / frequency as u128) as u64
+}
+
+/// Returns the time of a clock
+///
+/// # Arguments
+///  - `clock`, `CLOCK_REALTIME` (since 1970-01-01 UTC) or `CLOCK_MONOTONIC` (since boot)
+pub fn clock_time(clock: u64) -> Result<Timespec, ()> {
+    let uptime = Timespec::from_nanoseconds(uptime_nanoseconds());
+    match clock {
+        CLOCK_MONOTONIC => Ok(uptime),
+        CLOCK_REALTIME => Ok(Timespec {
+            seconds: BOOT_UNIX_SECONDS.load(Ordering::SeqCst) + uptime.seconds,
+            nanoseconds: uptime.nanoseconds,
+        }),
+        _ => Err(()),
+    }
+}
+
+/// Wakes the processes whose sleep deadline passed and raises the expired alarms, called by the bootstrap processor's timer interrupt.
+///
+/// The interrupted code may hold the heap, so nothing is allocated, the woken processes are queued later
+/// (see `processes::queue_woken_processes`).
+pub fn tick() {
+    let now = uptime_nanoseconds();
+    processes::wake_processes(now);
+    processes::expire_alarms(now);
+}
@@ -0,0 +1,92 @@
+//! A hashed timer wheel, the deadlines of the blocked processes.
+//!
+//! Time is divided into ticks, a timer is kept in the slot of it's deadline tick (modulo the slots),
+//! so advancing the wheel only visits the slots of the ticks that passed.
+
+use alloc::vec::Vec;
+
+use crate::sync::SpinLock;
+
+/// A tick is a timer interrupt period, 10ms
+pub const TICK_NANOSECONDS: u64 = super::NANOSECONDS_PER_SECOND / crate::interrupts::apic::TIMER_FREQUENCY;
+const SLOTS: usize = 256;
+
+/// Locked by the timer interrupt too
+static WHEEL: SpinLock<TimerWheel> = SpinLock::new(TimerWheel::new());
+
+#[derive(Clone, Copy, Debug)]
+struct Timer {
+    deadline_tick: u64,
+    pid: usize,
+}
+
+pub struct TimerWheel {
+    slots: [Vec<Timer>; SLOTS],
+    /// The last tick the wheel expired
+    current_tick: u64,
+}
+
+impl TimerWheel {
+    const EMPTY_SLOT: Vec<Timer> = Vec::new();
+
+    pub const fn new() -> Self {
+        TimerWheel {
+            slots: [Self::EMPTY_SLOT; SLOTS],
+            current_tick: 0,
+        }
+    }
+
+    /// Adds a timer that expires at the first tick after `deadline` (in uptime nanoseconds)
+    pub fn insert(&mut self, deadline: u64, pid: usize) {
+        // a deadline that already passed expires at the next tick, the slots of passed ticks aren't visited again
+        let deadline_tick = div_ceil(deadline, TICK_NANOSECONDS).max(self.current_tick + 1);
+        self.slots[deadline_tick as usize % SLOTS].push(Timer { deadline_tick, pid });
+    }
+
+    /// Advances the wheel to `now` (in uptime nanoseconds), `expired` is called with the processes whose timers expired.
+    ///
+    /// A timer `expired` refuses (it returns false) stays in the wheel, and the wheel stops at it's tick,
+    /// so the next advance expires it again. Nothing is allocated, the timer interrupt may interrupt an allocation.
+    pub fn expire(&mut self, now: u64, mut expired: impl FnMut(usize) -> bool) {
+        let now_tick = now / TICK_NANOSECONDS;
+        if now_tick <= self.current_tick {
+            return;
+        }
+
+        // every slot is visited at most once, even if many ticks passed
+        let ticks = (now_tick - self.current_tick).min(SLOTS as u64);
+        for tick in now_tick - ticks + 1..=now_tick {
+            let slot = &mut self.slots[tick as usize % SLOTS];
+            let mut refused = false;
+            slot.retain(|timer| {
+                if refused || timer.deadline_tick > now_tick {
+                    return true;
+                }
+                refused = !expired(timer.pid);
+                refused
+            });
+            if refused {
+                self.current_tick = tick - 1;
+                return;
+            }
+        }
+        self.current_tick = now_tick;
+    }
+}
+
+fn div_ceil(value: u64, divisor: u64) -> u64 {
+    value / divisor + (value % divisor != 0) as u64
+}
+
+/// Wakes the process after `deadline` (in uptime nanoseconds).
+///
+/// Timers of killed processes and of processes woken earlier are left in the wheel, their deadlines don't wake the processes
+/// (see `Scheduler::expire_deadline`).
+pub fn add_timer(deadline: u64, pid: usize) {
+    WHEEL.lock().insert(deadline, pid);
+}
+
+/// Calls `expired` with the processes whose timers expired, called by the timer interrupt (see `TimerWheel::expire`)
+pub fn expire(now: u64, expired: impl FnMut(usize) -> bool) {
+    WHEEL.lock().expire(now, expired)
+}
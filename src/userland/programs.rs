@@ -0,0 +1,40 @@
+//! The programs of the kernel the shell runs by name, every program is the entry point of a userland process.
+//!
+//! The other programs are ELF binaries in "/bin" (see the `user` workspace).
+
+use crate::code_addr;
+
+use super::syscalls::*;
+
+/// Returns the entry point of the program named `name`
+pub fn find(name: &str) -> Option<u64> {
+    let code = match name {
+        "hello" => code_addr!(hello),
+        "count" => code_addr!(count),
+        "false" => code_addr!(fail),
+        _ => return None,
+    };
+    Some(code)
+}
+
+pub fn hello() -> ! {
+    print_fmt(format_args!("hello from process {}\n", get_pid()));
+    exit(0)
+}
+
+/// Counts to five, a number every second
+fn count() -> ! {
+    let second = Timespec {
+        seconds: 1,
+        nanoseconds: 0,
+    };
+    for number in 1..=5 {
+        print_fmt(format_args!("{}\n", number));
+        nanosleep(&second).ok();
+    }
+    exit(0)
+}
+
+fn fail() -> ! {
+    exit(1)
+}
@@ -0,0 +1,634 @@
+//! A shell, reads commands from the standard input and runs builtins, programs of the kernel (see `programs`)
+//! or the ELF programs in "/bin".
+//!
+//! Arguments are separated by spaces, quoted arguments ('...' or "...") may contain spaces.
+//! Commands are connected with pipes (`a | b | c`), and their standard streams are redirected
+//! with `< path`, `> path`, `>> path` (appends) & `2>&1`. Builtins run in the shell itself, with the same redirections.
+//!
+//! The programs of a pipeline are a job, they run in their own process group. A line ending with `&` runs in the background,
+//! otherwise the job is the terminal's foreground until it exits or it's stopped (Ctrl + Z). `jobs` lists the background
+//! and stopped jobs, `fg` & `bg` continue them in the foreground or in the background.
+//!
+//! The shell runs with a single page stack, so every buffer is small and nothing is allocated.
+
+use core::str;
+
+use super::{programs, syscalls::*};
+
+const LINE_CAPACITY: usize = 128;
+/// Every argument of the line is a bit of `Command::words`
+const MAX_ARGUMENTS: usize = 16;
+const MAX_COMMANDS: usize = 4;
+const PATH_CAPACITY: usize = 64;
+const MAX_LISTED_PROCESSES: usize = 16;
+const MAX_JOBS: usize = 4;
+/// The names of a job's programs are truncated to this length
+const JOB_NAME_CAPACITY: usize = 12;
+const BIN_DIRECTORY: &str = "/bin/";
+/// The operators are split from the arguments around them, longer operators are matched first
+const OPERATORS: [&[u8]; 6] = [b"2>&1", b">>", b">", b"<", b"|", b"&"];
+
+/// The arguments of a line, unquoted to a single buffer
+struct Arguments {
+    storage: [u8; LINE_CAPACITY],
+    /// The start & end of every argument in `storage`
+    ranges: [(usize, usize); MAX_ARGUMENTS],
+    /// A bit for every argument that is an unquoted operator
+    operators: u16,
+    count: usize,
+}
+
+impl Arguments {
+    /// Splits a line to arguments, fails on an unterminated quote or too many arguments
+    fn parse(line: &[u8]) -> Result<Self, &'static str> {
+        let mut arguments = Arguments {
+            storage: [0; LINE_CAPACITY],
+            ranges: [(0, 0); MAX_ARGUMENTS],
+            operators: 0,
+            count: 0,
+        };
+        let mut length = 0;
+        let mut index = 0;
+
+        loop {
+            while index < line.len() && line[index].is_ascii_whitespace() {
+                index += 1;
+            }
+            if index == line.len() {
+                return Ok(arguments);
+            }
+            if arguments.count == MAX_ARGUMENTS {
+                return Err("too many arguments");
+            }
+
+            let start = length;
+            if let Some(&operator) = OPERATORS.iter().find(|&&operator| line[index..].starts_with(operator)) {
+                arguments
+                    .storage
+                    .get_mut(length..length + operator.len())
+                    .ok_or("the line is too long")?
+                    .copy_from_slice(operator);
+                length += operator.len();
+                index += operator.len();
+                arguments.operators |= 1 << arguments.count;
+            } else {
+                let mut quote = None;
+                while index < line.len() {
+                    let byte = line[index];
+                    if quote.is_none() && (byte.is_ascii_whitespace() || b"|<>&".contains(&byte)) {
+                        break;
+                    }
+                    index += 1;
+                    match (quote, byte) {
+                        (None, b'\'' | b'"') => quote = Some(byte),
+                        (Some(opening), _) if byte == opening => quote = None,
+                        _ => {
+                            *arguments.storage.get_mut(length).ok_or("the line is too long")? = byte;
+                            length += 1;
+                        }
+                    }
+                }
+                if quote.is_some() {
+                    return Err("unterminated quote");
+                }
+            }
+            arguments.ranges[arguments.count] = (start, length);
+            arguments.count += 1;
+        }
+    }
+
+    fn get(&self, index: usize) -> Option<&str> {
+        let (start, end) = *self.ranges[..self.count].get(index)?;
+        str::from_utf8(&self.storage[start..end]).ok()
+    }
+
+    fn is_operator(&self, index: usize) -> bool {
+        index < self.count && self.operators & (1 << index) != 0
+    }
+
+    fn len(&self) -> usize {
+        self.count
+    }
+}
+
+/// A command of a pipeline, it's words & redirection paths are arguments of the line
+#[derive(Clone, Copy)]
+struct Command<'a> {
+    arguments: &'a Arguments,
+    /// A bit for every argument that is a word of the command, the first word is the command's name
+    words: u16,
+    /// The argument of the path the standard input is redirected from
+    input: Option<usize>,
+    /// The argument of the path the standard output is redirected to, and whether it's appended to
+    output: Option<(usize, bool)>,
+    /// Whether the standard error is redirected to the standard output
+    errors_to_output: bool,
+}
+
+impl<'a> Command<'a> {
+    fn new(arguments: &'a Arguments) -> Self {
+        Command {
+            arguments,
+            words: 0,
+            input: None,
+            output: None,
+            errors_to_output: false,
+        }
+    }
+
+    /// Splits the arguments of a line to the commands of a pipeline,
+    /// returns the number of commands and whether the pipeline runs in the background
+    fn parse_pipeline(
+        arguments: &'a Arguments,
+        commands: &mut [Command<'a>; MAX_COMMANDS],
+    ) -> Result<(usize, bool), &'static str> {
+        let mut count = 1;
+        let mut background = false;
+        let mut index = 0;
+        while index < arguments.len() {
+            let command = &mut commands[count - 1];
+            if !arguments.is_operator(index) {
+                command.words |= 1 << index;
+                index += 1;
+                continue;
+            }
+
+            match arguments.get(index).unwrap_or("") {
+                "|" if command.len() == 0 => return Err("missing command"),
+                "|" if count == MAX_COMMANDS => return Err("too many commands"),
+                "|" => count += 1,
+                "&" if index + 1 < arguments.len() => return Err("& must end the line"),
+                "&" => background = true,
+                "2>&1" => command.errors_to_output = true,
+                operator => {
+                    let path = index + 1;
+                    if path == arguments.len() || arguments.is_operator(path) {
+                        return Err("missing redirection path");
+                    }
+                    match operator {
+                        "<" => command.input = Some(path),
+                        ">" => command.output = Some((path, false)),
+                        _ => command.output = Some((path, true)),
+                    }
+                    index += 1;
+                }
+            }
+            index += 1;
+        }
+
+        if commands[count - 1].len() == 0 {
+            return Err("missing command");
+        }
+        Ok((count, background))
+    }
+
+    fn get(&self, index: usize) -> Option<&'a str> {
+        let argument = (0..self.arguments.len())
+            .filter(|argument| self.words & (1 << argument) != 0)
+            .nth(index)?;
+        self.arguments.get(argument)
+    }
+
+    fn len(&self) -> usize {
+        self.words.count_ones() as usize
+    }
+}
+
+/// The programs of a pipeline, they run in their own process group
+#[derive(Clone, Copy)]
+struct Job {
+    /// The process group, it's id is the pid of the first program
+    pgid: usize,
+    /// The programs that didn't exit, at the index of their command
+    pids: [Option<usize>; MAX_COMMANDS],
+    /// The names of the programs, padded with null bytes
+    names: [[u8; JOB_NAME_CAPACITY]; MAX_COMMANDS],
+    stopped: bool,
+}
+
+impl Job {
+    const fn new() -> Self {
+        Job {
+            pgid: 0,
+            pids: [None; MAX_COMMANDS],
+            names: [[0; JOB_NAME_CAPACITY]; MAX_COMMANDS],
+            stopped: false,
+        }
+    }
+
+    /// Adds the program of the command at `index`, returns the process group it joins
+    fn add(&mut self, index: usize, pid: usize, name: &str) -> usize {
+        if self.is_done() {
+            self.pgid = pid;
+        }
+        let length = name.len().min(JOB_NAME_CAPACITY);
+        self.names[index][..length].copy_from_slice(&name.as_bytes()[..length]);
+        self.pids[index] = Some(pid);
+        self.pgid
+    }
+
+    fn name(&self, index: usize) -> &str {
+        let name = &self.names[index];
+        let length = name.iter().position(|byte| *byte == 0).unwrap_or(JOB_NAME_CAPACITY);
+        str::from_utf8(&name[..length]).unwrap_or("?")
+    }
+
+    /// The name of the job's first program, builtins aren't programs
+    fn first_name(&self) -> &str {
+        let index = self.names.iter().position(|name| name[0] != 0).unwrap_or(0);
+        self.name(index)
+    }
+
+    /// Whether every program exited
+    fn is_done(&self) -> bool {
+        self.pids.iter().all(Option::is_none)
+    }
+}
+
+/// The background & stopped jobs, a job's number is it's index + 1
+type Jobs = [Option<Job>; MAX_JOBS];
+
+/// Reads commands until the standard input ends or `exit` is called
+pub fn run() -> ! {
+    let mut line = [0u8; LINE_CAPACITY];
+    let mut jobs: Jobs = [None; MAX_JOBS];
+    loop {
+        report_jobs(&mut jobs);
+        prompt();
+        let length = match read(STDIN, &mut line) {
+            Ok(0) | Err(()) => exit(0),
+            Ok(length) => length,
+        };
+
+        let arguments = match Arguments::parse(&line[..length]) {
+            Ok(arguments) if arguments.len() == 0 => continue,
+            Ok(arguments) => arguments,
+            Err(message) => {
+                print_fmt(format_args!("crabsh: {}\n", message));
+                continue;
+            }
+        };
+        let mut commands = [Command::new(&arguments); MAX_COMMANDS];
+        match Command::parse_pipeline(&arguments, &mut commands) {
+            Ok((count, background)) => run_pipeline(&commands[..count], background, &mut jobs),
+            Err(message) => print_fmt(format_args!("crabsh: {}\n", message)),
+        }
+    }
+}
+
+fn prompt() {
+    let mut path = [0u8; PATH_CAPACITY];
+    let length = get_current_directory(&mut path).unwrap_or(0);
+    print_fmt(format_args!("crab:{}$ ", str::from_utf8(&path[..length]).unwrap_or("?")));
+}
+
+/// Runs a builtin, returns false if the command isn't a builtin
+fn run_builtin(command: &Command, jobs: &mut Jobs) -> bool {
+    match command.get(0).unwrap_or("") {
+        "cd" => {
+            let path = command.get(1).unwrap_or("/");
+            if change_directory(path).is_err() {
+                print_fmt(format_args!("cd: {}: no such directory\n", path));
+            }
+        }
+        "pwd" => {
+            let mut path = [0u8; PATH_CAPACITY];
+            match get_current_directory(&mut path) {
+                Ok(length) => print_fmt(format_args!("{}\n", str::from_utf8(&path[..length]).unwrap_or("?"))),
+                Err(()) => print("pwd: the path is too long\n"),
+            }
+        }
+        "exit" => exit(command.get(1).and_then(|status| status.parse().ok()).unwrap_or(0)),
+        "echo" => {
+            for index in 1..command.len() {
+                if index > 1 {
+                    print(" ");
+                }
+                print(command.get(index).unwrap_or(""));
+            }
+            print("\n");
+        }
+        "ps" => list_processes_table(),
+        "kill" => {
+            // kill [-signal] pid|%job..., the signal is SIGTERM by default
+            let (signal, first) = match command.get(1).and_then(|flag| flag.strip_prefix('-')) {
+                Some(signal) => (signal.parse().unwrap_or(0), 2),
+                None => (SIGTERM, 1),
+            };
+            for index in first..command.len() {
+                let argument = command.get(index).unwrap_or("");
+                let result = match argument.strip_prefix('%') {
+                    Some(number) => match job_index(Some(number), jobs) {
+                        Some(index) => jobs[index].map_or(Err(()), |job| kill_group(job.pgid, signal)),
+                        None => {
+                            print_fmt(format_args!("kill: {}: no such job\n", argument));
+                            continue;
+                        }
+                    },
+                    None => match argument.parse() {
+                        Ok(pid) => kill(pid, signal),
+                        Err(_) => {
+                            print_fmt(format_args!("kill: {}: not a pid\n", argument));
+                            continue;
+                        }
+                    },
+                };
+                if result.is_err() {
+                    print_fmt(format_args!("kill: {}: cannot send signal {}\n", argument, signal));
+                }
+            }
+        }
+        "jobs" => {
+            for (index, job) in jobs.iter().enumerate() {
+                if let Some(job) = job {
+                    let state = if job.stopped { "Stopped" } else { "Running" };
+                    print_fmt(format_args!("[{}] {} {}\n", index + 1, state, job.first_name()));
+                }
+            }
+        }
+        "fg" => match job_index(command.get(1), jobs) {
+            Some(index) => {
+                if let Some(job) = jobs[index].take() {
+                    print_fmt(format_args!("{}\n", job.first_name()));
+                    kill_group(job.pgid, SIGCONT).ok();
+                    run_foreground(job, jobs);
+                }
+            }
+            None => print("fg: no such job\n"),
+        },
+        "bg" => match job_index(command.get(1), jobs) {
+            Some(index) => {
+                if let Some(job) = &mut jobs[index] {
+                    job.stopped = false;
+                    kill_group(job.pgid, SIGCONT).ok();
+                    print_fmt(format_args!("[{}] {} &\n", index + 1, job.first_name()));
+                }
+            }
+            None => print("bg: no such job\n"),
+        },
+        "meminfo" => match memory_info() {
+            Ok(info) => print_fmt(format_args!(
+                "memory: {} KiB free of {} KiB\nheap:   {} KiB used of {} KiB\n",
+                info.free / 1024,
+                info.total / 1024,
+                info.heap_used / 1024,
+                info.heap_size / 1024,
+            )),
+            Err(()) => print("meminfo: failed\n"),
+        },
+        _ => return false,
+    }
+    true
+}
+
+fn list_processes_table() {
+    let mut processes = [ProcessInfo::empty(); MAX_LISTED_PROCESSES];
+    let count = match list_processes(&mut processes) {
+        Ok(count) => count,
+        Err(()) => {
+            print("ps: failed\n");
+            return;
+        }
+    };
+
+    print("  PID  PPID  PGID STATE\n");
+    for process in &processes[..count] {
+        match process.parent() {
+            Some(parent) => print_fmt(format_args!(
+                "{:>5} {:>5} {:>5} {:?}\n",
+                process.pid, parent, process.pgid, process.state
+            )),
+            None => print_fmt(format_args!("{:>5}     - {:>5} {:?}\n", process.pid, process.pgid, process.state)),
+        }
+    }
+}
+
+/// Returns the index of the job numbered `number` (with or without a `%` prefix), the last job if there is no number
+fn job_index(number: Option<&str>, jobs: &Jobs) -> Option<usize> {
+    let index = match number {
+        Some(number) => number.strip_prefix('%').unwrap_or(number).parse::<usize>().ok()?.checked_sub(1)?,
+        None => (0..MAX_JOBS).rev().find(|index| jobs[*index].is_some())?,
+    };
+    jobs.get(index)?.as_ref()?;
+    Some(index)
+}
+
+/// Adds a job to the first free slot, returns it's number
+fn add_job(jobs: &mut Jobs, job: Job) -> Option<usize> {
+    let index = jobs.iter().position(Option::is_none)?;
+    jobs[index] = Some(job);
+    Some(index + 1)
+}
+
+/// Reports the jobs that exited or stopped in the background, the jobs that exited are removed
+fn report_jobs(jobs: &mut Jobs) {
+    for (index, slot) in jobs.iter_mut().enumerate() {
+        let job = match slot {
+            Some(job) => job,
+            None => continue,
+        };
+        let was_stopped = job.stopped;
+        for pid in job.pids.iter_mut() {
+            match pid.map(|child| wait(child, REPORT_STOPPED | NO_HANG)) {
+                Some(Ok(WaitStatus::Stopped(_))) => job.stopped = true,
+                Some(Ok(WaitStatus::Exited(_)) | Err(())) => *pid = None,
+                _ => {}
+            }
+        }
+        if job.is_done() {
+            print_fmt(format_args!("[{}] Done {}\n", index + 1, job.first_name()));
+            *slot = None;
+        } else if job.stopped && !was_stopped {
+            print_fmt(format_args!("[{}] Stopped {}\n", index + 1, job.first_name()));
+        }
+    }
+}
+
+/// Waits for a job in the foreground until it's programs exit or it stops, returns whether it stopped.
+/// The job's process group is the terminal's foreground while it runs, so the job control characters signal it.
+fn wait_foreground(job: &mut Job) -> bool {
+    job.stopped = false;
+    set_foreground(STDIN, Some(job.pgid)).ok();
+    for index in 0..MAX_COMMANDS {
+        let pid = match job.pids[index] {
+            Some(pid) => pid,
+            None => continue,
+        };
+        match wait(pid, REPORT_STOPPED) {
+            Ok(WaitStatus::Stopped(_)) => {
+                job.stopped = true;
+                break;
+            }
+            Ok(WaitStatus::Exited(status)) => {
+                print_fmt(format_args!("[{}] {} exited with status {}\n", pid, job.name(index), status))
+            }
+            _ => {}
+        }
+        job.pids[index] = None;
+    }
+    set_foreground(STDIN, None).ok();
+    job.stopped
+}
+
+/// Runs a job in the foreground, a stopped job is kept until it's continued (see `fg` & `bg`)
+fn run_foreground(mut job: Job, jobs: &mut Jobs) {
+    while wait_foreground(&mut job) {
+        match add_job(jobs, job) {
+            Some(number) => {
+                print_fmt(format_args!("[{}] Stopped {}\n", number, job.first_name()));
+                return;
+            }
+            None => {
+                print("crabsh: too many jobs, the stopped job continues\n");
+                kill_group(job.pgid, SIGCONT).ok();
+            }
+        }
+    }
+}
+
+/// Runs the commands of a pipeline, every command reads the output of the previous one.
+///
+/// The programs are created with the shell's standard streams redirected for them, and run together as a job.
+/// Builtins run while the programs are created, so the pipes hold their output until the next command runs.
+fn run_pipeline(commands: &[Command], background: bool, jobs: &mut Jobs) {
+    if background && jobs.iter().all(Option::is_some) {
+        print("crabsh: too many jobs\n");
+        return;
+    }
+    // the shell's own standard streams, restored after every command
+    let saved = match (dup(STDIN), dup(STDOUT), dup(STDERR)) {
+        (Ok(input), Ok(output), Ok(errors)) => [input, output, errors],
+        duplicated => {
+            for fd in [duplicated.0, duplicated.1, duplicated.2].into_iter().flatten() {
+                close(fd).ok();
+            }
+            print("crabsh: too many open files\n");
+            return;
+        }
+    };
+
+    let mut job = Job::new();
+    let mut input = None;
+    for (index, command) in commands.iter().enumerate() {
+        let mut output = None;
+        if index + 1 < commands.len() {
+            match pipe() {
+                Ok((read_end, write_end)) => output = Some((read_end, write_end)),
+                Err(()) => {
+                    if let Some(fd) = input {
+                        close(fd).ok();
+                    }
+                    print("crabsh: failed to create a pipe\n");
+                    break;
+                }
+            }
+        }
+
+        // the pipe ends are moved to the standard streams, except the read end the next command reads
+        let result = redirect(command, input, output.map(|(_, write_end)| write_end)).and_then(|()| {
+            if run_builtin(command, jobs) {
+                Ok(None)
+            } else {
+                create_program(command).map(Some)
+            }
+        });
+        restore(&saved);
+        match result {
+            Ok(Some(pid)) => {
+                let pgid = job.add(index, pid, command.get(0).unwrap_or(""));
+                set_process_group(pid, pgid).ok();
+            }
+            Ok(None) => {}
+            Err(message) => print_fmt(format_args!("{}: {}\n", command.get(0).unwrap_or(""), message)),
+        }
+        input = output.map(|(read_end, _)| read_end);
+    }
+    for fd in saved {
+        close(fd).ok();
+    }
+
+    for (index, pid) in job.pids.iter_mut().enumerate() {
+        if let Some(child) = *pid {
+            if start(child).is_err() {
+                print_fmt(format_args!("{}: failed to execute\n", commands[index].get(0).unwrap_or("")));
+                *pid = None;
+            }
+        }
+    }
+    if job.is_done() {
+        return;
+    }
+    if !background {
+        run_foreground(job, jobs);
+    } else if let Some(number) = add_job(jobs, job) {
+        print_fmt(format_args!("[{}] {}\n", number, job.pgid));
+    }
+}
+
+/// Redirects the shell's standard streams for a command, the pipe ends are moved to the standard input & output
+fn redirect(command: &Command, input: Option<usize>, output: Option<usize>) -> Result<(), &'static str> {
+    if let Some(fd) = input {
+        move_fd(fd, STDIN)?;
+    }
+    if let Some(fd) = output {
+        move_fd(fd, STDOUT)?;
+    }
+    if let Some(path) = command.input.and_then(|argument| command.arguments.get(argument)) {
+        let fd = open(path, OpenFlags::READ).map_err(|()| "cannot open the input")?;
+        move_fd(fd, STDIN)?;
+    }
+    if let Some((path, append)) = command.output {
+        let mode = if append { OpenFlags::APPEND } else { OpenFlags::TRUNCATE };
+        let path = command.arguments.get(path).unwrap_or("");
+        let fd = open(path, OpenFlags::WRITE | OpenFlags::CREATE | mode).map_err(|()| "cannot open the output")?;
+        move_fd(fd, STDOUT)?;
+    }
+    if command.errors_to_output {
+        dup2(STDOUT, STDERR).map_err(|()| "failed to redirect the standard error")?;
+    }
+    Ok(())
+}
+
+/// Duplicates `fd` to `target` and closes it
+fn move_fd(fd: usize, target: usize) -> Result<(), &'static str> {
+    let result = dup2(fd, target);
+    close(fd).ok();
+    result.map(|_| ()).map_err(|()| "failed to redirect")
+}
+
+/// Restores the shell's standard streams
+fn restore(saved: &[usize; 3]) {
+    for (fd, saved) in saved.iter().enumerate() {
+        dup2(*saved, fd).ok();
+    }
+}
+
+/// Creates the process of a program, it inherits the current standard streams.
+///
+/// A name without a slash is a program of the kernel or a program in "/bin", otherwise it's the program's path.
+fn create_program(command: &Command) -> Result<usize, &'static str> {
+    let name = command.get(0).unwrap_or("");
+    if let Some(code) = programs::find(name) {
+        return create(code).map_err(|()| "failed to create a process");
+    }
+
+    let mut buffer = [0u8; PATH_CAPACITY];
+    let path = if name.contains('/') {
+        name
+    } else {
+        let path = buffer.get_mut(..BIN_DIRECTORY.len() + name.len()).ok_or("command not found")?;
+        path[..BIN_DIRECTORY.len()].copy_from_slice(BIN_DIRECTORY.as_bytes());
+        path[BIN_DIRECTORY.len()..].copy_from_slice(name.as_bytes());
+        str::from_utf8(path).unwrap_or("")
+    };
+
+    // every argument is followed by a null byte
+    let mut arguments = [0u8; LINE_CAPACITY + MAX_ARGUMENTS];
+    let mut length = 0;
+    for index in 0..command.len() {
+        let argument = command.get(index).unwrap_or("");
+        arguments[length..length + argument.len()].copy_from_slice(argument.as_bytes());
+        length += argument.len() + 1;
+    }
+    spawn(path, &arguments[..length]).map_err(|()| "command not found")
+}
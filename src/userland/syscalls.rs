@@ -1,5 +1,25 @@
 //! This module define a comfortable inteface with the native syscall api of CrabOS.
-use crate::syscalls::number::*;
+use core::{
+    fmt::{self, Write},
+    sync::atomic::AtomicU32,
+};
+
+use crate::syscalls::{futex, ioctl, number::*, wait};
+pub use crate::{
+    fs::{
+        descriptors::{STDERR, STDIN, STDOUT},
+        tty::{Termios, TtyMode},
+        vfs::{DirectoryEntry, FileType, Stat},
+        OpenFlags,
+    },
+    memory::MemoryInfo,
+    processes::{
+        objects::{ProcessInfo, ProcessState},
+        signals::{SIGCONT, SIGINT, SIGKILL, SIGTERM},
+    },
+    syscalls::wait::{NO_HANG, REPORT_STOPPED},
+    time::{Timespec, CLOCK_MONOTONIC, CLOCK_REALTIME},
+};
 
 #[macro_export]
 macro_rules! syscall {
@@ -100,19 +120,439 @@ pub fn create(process_code: u64) -> Result<usize, ()> {
     }
 }
 
-pub fn execute(pid: usize) {
-    unsafe { syscall!(EXECUTE, pid) };
+/// Runs a child process and waits for it, returns the child's exit status
+pub fn execute(pid: usize) -> Result<u8, ()> {
+    let result = unsafe { syscall!(EXECUTE, pid) };
+
+    if result >= 0 {
+        Ok(result as u8)
+    } else {
+        Err(())
+    }
 }
 
-pub fn kill(pid: usize) {
-    unsafe { syscall!(KILL, pid) };
+/// Creates a process running the ELF program at `path`, returns it's pid
+///
+/// # Arguments
+///  - `arguments`, the program's arguments, every argument is followed by a null byte
+pub fn spawn(path: &str, arguments: &[u8]) -> Result<usize, ()> {
+    let result = unsafe { syscall!(SPAWN, path.as_ptr(), path.len(), arguments.as_ptr(), arguments.len()) };
+
+    if result >= 0 {
+        Ok(result as usize)
+    } else {
+        Err(())
+    }
+}
+
+/// Runs a child process alongside the caller, the child's exit status is returned by `wait`
+pub fn start(pid: usize) -> Result<(), ()> {
+    let result = unsafe { syscall!(START, pid) };
+
+    if result >= 0 {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+/// How a waited child changed, returned by `wait`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaitStatus {
+    Exited(u8),
+    /// Stopped by the signal
+    Stopped(usize),
+    /// Still running, returned with `NO_HANG`
+    Running,
+}
+
+/// Waits for a started child process until it exits, the WAIT flags may make it return when the child stops
+/// (`REPORT_STOPPED`) or right away (`NO_HANG`)
+pub fn wait(pid: usize, flags: u64) -> Result<WaitStatus, ()> {
+    let result = unsafe { syscall!(WAIT, pid, flags) };
+
+    match result {
+        wait::RUNNING => Ok(WaitStatus::Running),
+        _ if result >= wait::STOPPED => Ok(WaitStatus::Stopped((result - wait::STOPPED) as usize)),
+        _ if result >= 0 => Ok(WaitStatus::Exited(result as u8)),
+        _ => Err(()),
+    }
+}
+
+/// Creates a pipe, returns the file descriptors of it's read end and write end
+pub fn pipe() -> Result<(usize, usize), ()> {
+    let mut fds = [0usize; 2];
+    let result = unsafe { syscall!(PIPE, fds.as_mut_ptr() as u64) };
+
+    if result >= 0 {
+        Ok((fds[0], fds[1]))
+    } else {
+        Err(())
+    }
+}
+
+/// Sends a signal to a process
+pub fn kill(pid: usize, signal: usize) -> Result<(), ()> {
+    let result = unsafe { syscall!(KILL, pid, signal) };
+
+    if result >= 0 {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+/// Sends a signal to every process of a process group
+pub fn kill_group(pgid: usize, signal: usize) -> Result<(), ()> {
+    let result = unsafe { syscall!(KILL, -(pgid as i64), signal) };
+
+    if result >= 0 {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+/// Moves the caller or a child of it to the process group `pgid`, `pgid` equal to `pid` creates a new group
+pub fn set_process_group(pid: usize, pgid: usize) -> Result<(), ()> {
+    let result = unsafe { syscall!(SETPGID, pid, pgid) };
+
+    if result >= 0 {
+        Ok(())
+    } else {
+        Err(())
+    }
 }
 
 pub fn get_pid() -> usize {
     unsafe { syscall!(GET_PID) as usize }
 }
 
-pub fn exit() -> ! {
-    kill(get_pid());
+pub fn read(fd: usize, buffer: &mut [u8]) -> Result<usize, ()> {
+    let result = unsafe { syscall!(READ, fd, buffer.as_mut_ptr() as u64, buffer.len()) };
+
+    if result >= 0 {
+        Ok(result as usize)
+    } else {
+        Err(())
+    }
+}
+
+pub fn write(fd: usize, buffer: &[u8]) -> Result<usize, ()> {
+    let result = unsafe { syscall!(WRITE, fd, buffer.as_ptr() as u64, buffer.len()) };
+
+    if result >= 0 {
+        Ok(result as usize)
+    } else {
+        Err(())
+    }
+}
+
+pub fn open(path: &str, flags: OpenFlags) -> Result<usize, ()> {
+    let result = unsafe { syscall!(OPEN, path.as_ptr() as u64, path.len(), flags.bits()) };
+
+    if result >= 0 {
+        Ok(result as usize)
+    } else {
+        Err(())
+    }
+}
+
+pub fn close(fd: usize) -> Result<(), ()> {
+    let result = unsafe { syscall!(CLOSE, fd) };
+
+    if result >= 0 {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+pub fn dup(fd: usize) -> Result<usize, ()> {
+    let result = unsafe { syscall!(DUP, fd) };
+
+    if result >= 0 {
+        Ok(result as usize)
+    } else {
+        Err(())
+    }
+}
+
+pub fn dup2(old_fd: usize, new_fd: usize) -> Result<usize, ()> {
+    let result = unsafe { syscall!(DUP2, old_fd, new_fd) };
+
+    if result >= 0 {
+        Ok(result as usize)
+    } else {
+        Err(())
+    }
+}
+
+pub fn stat(path: &str) -> Result<Stat, ()> {
+    let mut stat = Stat::empty();
+    let result = unsafe { syscall!(STAT, path.as_ptr() as u64, path.len(), &mut stat as *mut Stat as u64) };
+
+    if result >= 0 {
+        Ok(stat)
+    } else {
+        Err(())
+    }
+}
+
+pub fn get_directory_entries(fd: usize, entries: &mut [DirectoryEntry]) -> Result<usize, ()> {
+    let result = unsafe { syscall!(GETDENTS, fd, entries.as_mut_ptr() as u64, entries.len()) };
+
+    if result >= 0 {
+        Ok(result as usize)
+    } else {
+        Err(())
+    }
+}
+
+pub fn make_directory(path: &str) -> Result<(), ()> {
+    let result = unsafe { syscall!(MKDIR, path.as_ptr() as u64, path.len()) };
+
+    if result >= 0 {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+pub fn unlink(path: &str) -> Result<(), ()> {
+    let result = unsafe { syscall!(UNLINK, path.as_ptr() as u64, path.len()) };
+
+    if result >= 0 {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+pub fn change_directory(path: &str) -> Result<(), ()> {
+    let result = unsafe { syscall!(CHDIR, path.as_ptr() as u64, path.len()) };
+
+    if result >= 0 {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+/// Writes the working directory to `buffer`, returns the path length
+pub fn get_current_directory(buffer: &mut [u8]) -> Result<usize, ()> {
+    let result = unsafe { syscall!(GETCWD, buffer.as_mut_ptr() as u64, buffer.len()) };
+
+    if result >= 0 {
+        Ok(result as usize)
+    } else {
+        Err(())
+    }
+}
+
+/// Returns the time of `clock` (`CLOCK_REALTIME` or `CLOCK_MONOTONIC`)
+pub fn clock_get_time(clock: u64) -> Result<Timespec, ()> {
+    let mut time = Timespec::default();
+    let result = unsafe { syscall!(CLOCK_GETTIME, clock, &mut time as *mut Timespec as u64) };
+
+    if result >= 0 {
+        Ok(time)
+    } else {
+        Err(())
+    }
+}
+
+/// Blocks the current process for at least `duration`
+pub fn nanosleep(duration: &Timespec) -> Result<(), ()> {
+    let result = unsafe { syscall!(NANOSLEEP, duration as *const Timespec as u64) };
+
+    if result >= 0 {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+/// Waits while `word` holds `value` until a wake of the word, or until the `timeout` passes.
+/// Fails if the word doesn't hold `value`, if the timeout passed or if a signal interrupted the wait.
+pub fn futex_wait(word: &AtomicU32, value: u32, timeout: Option<&Timespec>) -> Result<(), ()> {
+    let timeout = timeout.map_or(0, |timeout| timeout as *const Timespec as u64);
+    let result = unsafe { syscall!(FUTEX, word as *const AtomicU32 as u64, futex::WAIT, value as u64, timeout) };
+
+    if result >= 0 {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+/// Wakes up to `count` waiters of `word`, returns the number of waiters woken
+pub fn futex_wake(word: &AtomicU32, count: usize) -> Result<usize, ()> {
+    let result = unsafe { syscall!(FUTEX, word as *const AtomicU32 as u64, futex::WAKE, count as u64) };
+
+    if result >= 0 {
+        Ok(result as usize)
+    } else {
+        Err(())
+    }
+}
+
+/// Creates a zeroed shared memory segment of `length` bytes, returns it's file descriptor
+pub fn shm_create(length: usize) -> Result<usize, ()> {
+    let result = unsafe { syscall!(SHM_CREATE, length as u64) };
+
+    if result >= 0 {
+        Ok(result as usize)
+    } else {
+        Err(())
+    }
+}
+
+/// Maps the segment opened at `fd` at `address`, or at an address the kernel chooses if there is none.
+/// Returns the address of the mapping
+pub fn shm_map(fd: usize, address: Option<u64>) -> Result<u64, ()> {
+    let result = unsafe { syscall!(SHM_MAP, fd as u64, address.unwrap_or(0)) };
+
+    if result >= 0 {
+        Ok(result as u64)
+    } else {
+        Err(())
+    }
+}
+
+/// Unmaps the segment mapped at `address`
+pub fn shm_unmap(address: u64) -> Result<(), ()> {
+    let result = unsafe { syscall!(SHM_UNMAP, address) };
+
+    if result >= 0 {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+/// Sets the nice value of the current process or of a child of it, a lower value gets more CPU time
+pub fn set_priority(pid: usize, nice: i64) -> Result<(), ()> {
+    let result = unsafe { syscall!(SETPRIORITY, pid as u64, nice as u64) };
+
+    if result >= 0 {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+/// Returns the settings of the terminal opened at `fd`
+pub fn get_terminal_settings(fd: usize) -> Result<Termios, ()> {
+    let mut settings = Termios::default();
+    let result = unsafe { syscall!(IOCTL, fd, ioctl::GET_SETTINGS, &mut settings as *mut Termios as u64) };
+
+    if result >= 0 {
+        Ok(settings)
+    } else {
+        Err(())
+    }
+}
+
+/// Changes the settings of the terminal opened at `fd`, e.g. to raw mode or without echo
+pub fn set_terminal_settings(fd: usize, settings: &Termios) -> Result<(), ()> {
+    let result = unsafe { syscall!(IOCTL, fd, ioctl::SET_SETTINGS, settings as *const Termios as u64) };
+
+    if result >= 0 {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+/// Returns the process group the job control characters of the terminal opened at `fd` signal
+pub fn get_foreground(fd: usize) -> Result<usize, ()> {
+    let result = unsafe { syscall!(IOCTL, fd, ioctl::GET_FOREGROUND) };
+
+    if result >= 0 {
+        Ok(result as usize)
+    } else {
+        Err(())
+    }
+}
+
+/// Sets the process group the job control characters of the terminal opened at `fd` signal, `None` signals no process
+pub fn set_foreground(fd: usize, pgid: Option<usize>) -> Result<(), ()> {
+    let pgid = pgid.map_or(ioctl::NO_FOREGROUND, |pgid| pgid as u64);
+    let result = unsafe { syscall!(IOCTL, fd, ioctl::SET_FOREGROUND, pgid) };
+
+    if result >= 0 {
+        Ok(())
+    } else {
+        Err(())
+    }
+}
+
+/// Writes the information of the processes to `processes`, returns the number of written processes
+pub fn list_processes(processes: &mut [ProcessInfo]) -> Result<usize, ()> {
+    let result = unsafe { syscall!(PROCESSES, processes.as_mut_ptr() as u64, processes.len()) };
+
+    if result >= 0 {
+        Ok(result as usize)
+    } else {
+        Err(())
+    }
+}
+
+pub fn memory_info() -> Result<MemoryInfo, ()> {
+    let mut info = MemoryInfo::default();
+    let result = unsafe { syscall!(MEMINFO, &mut info as *mut MemoryInfo as u64) };
+
+    if result >= 0 {
+        Ok(info)
+    } else {
+        Err(())
+    }
+}
+
+/// Writes a message to the standard output
+pub fn print(message: &str) {
+    write(STDOUT, message.as_bytes()).unwrap();
+}
+
+/// Formats a message to a stack buffer, which is written to the standard output whenever it fills
+struct Output {
+    buffer: [u8; 128],
+    length: usize,
+}
+
+impl Output {
+    fn flush(&mut self) {
+        write(STDOUT, &self.buffer[..self.length]).ok();
+        self.length = 0;
+    }
+}
+
+impl fmt::Write for Output {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            if self.length == self.buffer.len() {
+                self.flush();
+            }
+            self.buffer[self.length] = byte;
+            self.length += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a formatted message to the standard output, e.g. `print_fmt(format_args!("{}", pid))`
+pub fn print_fmt(args: fmt::Arguments) {
+    let mut output = Output {
+        buffer: [0; 128],
+        length: 0,
+    };
+    output.write_fmt(args).ok();
+    output.flush();
+}
+
+/// Terminates the current process, it's parent continues with `exit_status`
+pub fn exit(exit_status: u8) -> ! {
+    unsafe { syscall!(EXIT, exit_status as u64) };
     loop {}
 }
\ No newline at end of file
@@ -0,0 +1,78 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use x86_64::instructions::interrupts as cpu_interrupts;
+
+use CrabOS::{
+    acpi,
+    drivers::{
+        keyboard::{self, scancodes::Key, KEYBOARD_IRQ},
+        pit,
+    },
+    hlt_loop,
+    interrupts::{self, apic, gdt, idt, irq_count},
+    log::{self, info, LevelFilter},
+    memory, test_panic_handler,
+};
+
+const KEY_A: u8 = 0x1E;
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Debug);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+    interrupts::init_controllers();
+    keyboard::init();
+    cpu_interrupts::enable();
+
+    test_main();
+    hlt_loop()
+}
+
+#[test_case]
+fn parse_madt() {
+    let madt = acpi::madt().unwrap();
+    info!("{:#x?}", madt);
+
+    assert!(!madt.processors.is_empty());
+    assert!(madt.processors.iter().any(|processor| processor.apic_id == apic::id()));
+    // QEMU connects the PIT (IRQ 0) to GSI 2
+    assert!(madt.overrides.iter().any(|interrupt_override| interrupt_override.irq == 0 && interrupt_override.gsi == 2));
+}
+
+#[test_case]
+fn local_apic_timer_ticks() {
+    assert!(apic::is_enabled());
+
+    let ticks = apic::timer_ticks();
+    // 5 timer periods
+    for _ in 0..5 {
+        pit::wait_microseconds(1_000_000 / apic::TIMER_FREQUENCY);
+    }
+    let elapsed = apic::timer_ticks() - ticks;
+    info!("{} ticks elapsed", elapsed);
+    assert!((3..=7).contains(&elapsed));
+}
+
+#[test_case]
+fn keyboard_irq_through_ioapic() {
+    let count = irq_count(KEYBOARD_IRQ);
+    keyboard::inject_scancode(KEY_A).unwrap();
+
+    assert!(keyboard::wait_event(0).key == Key::Character(b'a'));
+    assert!(irq_count(KEYBOARD_IRQ) == count + 1);
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
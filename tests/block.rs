@@ -0,0 +1,80 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+use CrabOS::{
+    drivers::block::{self, get_device},
+    hlt_loop,
+    interrupts::{gdt, idt},
+    log::{self, info, LevelFilter},
+    memory, test_panic_handler,
+};
+
+/// The test disk is attached as the primary slave, see scripts/make_disks.sh
+const TEST_DISK: &str = "hdb";
+const KNOWN_SECTOR: u64 = 1;
+const KNOWN_SECTOR_CONTENT: &[u8] = b"CrabOS ATA test sector";
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Debug);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+    block::init();
+
+    test_main();
+    hlt_loop()
+}
+
+#[test_case]
+fn detect_test_disk() {
+    let disk = get_device(TEST_DISK).unwrap();
+    info!("test disk: {:#x} sectors of {:#x} bytes", disk.capacity(), disk.sector_size());
+    assert!(disk.sector_size() == 512);
+    assert!(disk.capacity() == 2048);
+}
+
+#[test_case]
+fn read_known_sector() {
+    let disk = get_device(TEST_DISK).unwrap();
+    let mut sector = [0u8; 512];
+    disk.read_sectors(KNOWN_SECTOR, &mut sector).unwrap();
+
+    assert!(sector.starts_with(KNOWN_SECTOR_CONTENT));
+    assert!(sector[KNOWN_SECTOR_CONTENT.len()..].iter().all(|byte| *byte == 0));
+}
+
+#[test_case]
+fn write_and_read_back() {
+    let disk = get_device(TEST_DISK).unwrap();
+    let mut sectors = [0u8; 1024];
+    for (index, byte) in sectors.iter_mut().enumerate() {
+        *byte = index as u8;
+    }
+    disk.write_sectors(2, &sectors).unwrap();
+
+    let mut read_back = [0u8; 1024];
+    disk.read_sectors(2, &mut read_back).unwrap();
+    assert!(read_back == sectors);
+}
+
+#[test_case]
+fn reject_out_of_range_sectors() {
+    let disk = get_device(TEST_DISK).unwrap();
+    let mut sector = [0u8; 512];
+    assert!(disk.read_sectors(disk.capacity(), &mut sector).is_err());
+    assert!(disk.read_sectors(0, &mut sector[..100]).is_err());
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
@@ -0,0 +1,152 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use alloc::{vec, vec::Vec};
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+use CrabOS::{
+    fs::pipe::pipe,
+    hlt_loop,
+    interrupts::{gdt, idt},
+    ipc::channel::{channel, select, Message, CHANNEL_CAPACITY, MAX_MESSAGE_SIZE},
+    log::{self, LevelFilter},
+    memory, test_panic_handler,
+};
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Info);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+
+    test_main();
+    hlt_loop()
+}
+
+fn message(data: &[u8]) -> Message {
+    Message {
+        data: data.to_vec(),
+        files: Vec::new(),
+    }
+}
+
+#[test_case]
+fn messages_keep_their_boundaries() {
+    let (first, second) = channel();
+    assert!(first.write(b"hello") == Ok(5));
+    assert!(first.write(b"crab") == Ok(4));
+
+    let mut buffer = [0u8; 16];
+    assert!(second.read(&mut buffer) == Ok(5));
+    assert!(&buffer[..5] == b"hello");
+    assert!(second.read(&mut buffer) == Ok(4));
+    assert!(&buffer[..4] == b"crab");
+
+    // both ends send
+    second.write(b"back").unwrap();
+    assert!(first.read(&mut buffer) == Ok(4));
+}
+
+#[test_case]
+fn messages_pass_files() {
+    let (first, second) = channel();
+    let (reader, writer) = pipe();
+    let sent = Message {
+        data: b"a pipe".to_vec(),
+        files: vec![writer],
+    };
+    assert!(first.as_channel().unwrap().send(sent, true) == Ok(true));
+
+    let end = second.as_channel().unwrap();
+    let received = end.receive(MAX_MESSAGE_SIZE, 1, false).unwrap().unwrap();
+    assert!(received.data == b"a pipe");
+    received.files[0].write(b"x").unwrap();
+    let mut buffer = [0u8; 1];
+    assert!(reader.read(&mut buffer) == Ok(1));
+}
+
+#[test_case]
+fn messages_are_received_only_if_they_fit() {
+    let (first, second) = channel();
+    let (_, writer) = pipe();
+    let end = second.as_channel().unwrap();
+    first.write(b"too long").unwrap();
+
+    assert!(end.receive(2, 0, false).is_err());
+    assert!(end.receive(8, 0, false).unwrap().unwrap().data == b"too long");
+
+    // a message with files isn't read
+    let sent = Message {
+        data: Vec::new(),
+        files: vec![writer],
+    };
+    first.as_channel().unwrap().send(sent, true).unwrap();
+    assert!(second.read(&mut [0u8; 8]).is_err());
+    assert!(end.receive(0, 1, false).unwrap().unwrap().files.len() == 1);
+
+    assert!(first.write(&vec![0; MAX_MESSAGE_SIZE + 1]).is_err());
+}
+
+#[test_case]
+fn non_blocking_ends_do_not_wait() {
+    let (first, second) = channel();
+    let receiver = second.as_channel().unwrap();
+    assert!(receiver.receive(MAX_MESSAGE_SIZE, 0, false).unwrap().is_none());
+
+    let end = first.as_channel().unwrap();
+    for _ in 0..CHANNEL_CAPACITY {
+        assert!(end.send(message(b"full"), false) == Ok(true));
+    }
+    assert!(end.send(message(b"full"), false) == Ok(false));
+}
+
+#[test_case]
+fn closing_an_end_ends_the_channel() {
+    let (first, second) = channel();
+    first.write(b"last").unwrap();
+    drop(first);
+
+    // the sent messages are received before the channel ends
+    let mut buffer = [0u8; 8];
+    assert!(second.read(&mut buffer) == Ok(4));
+    assert!(second.read(&mut buffer).is_err());
+    assert!(second.write(b"nobody receives").is_err());
+}
+
+#[test_case]
+fn ends_are_not_passed_to_themselves() {
+    let (first, second) = channel();
+    let sent = Message {
+        data: Vec::new(),
+        files: vec![second.clone()],
+    };
+    assert!(first.as_channel().unwrap().send(sent, true).is_err());
+}
+
+#[test_case]
+fn select_finds_the_ready_end() {
+    let (first, first_peer) = channel();
+    let (second, second_peer) = channel();
+    let ends = [first.as_channel().unwrap(), second.as_channel().unwrap()];
+    assert!(select(&ends, false) == Ok(None));
+
+    second_peer.write(b"ready").unwrap();
+    assert!(select(&ends, false) == Ok(Some(1)));
+    second.read(&mut [0u8; 8]).unwrap();
+
+    // a closed peer is ready too, the receive fails without waiting
+    drop(first_peer);
+    assert!(select(&ends, true) == Ok(Some(0)));
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
@@ -0,0 +1,132 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+use CrabOS::{
+    fs::{self, vfs},
+    hlt_loop,
+    interrupts::{gdt, idt},
+    log::{self, LevelFilter},
+    memory::{self, paging::EntryFlags, types::PAGE_SIZE, KERNEL_MAPPER},
+    processes::{
+        elf,
+        objects::{Process, HEAP_PAGES},
+    },
+    test_panic_handler,
+};
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Info);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+    fs::init();
+
+    test_main();
+    hlt_loop()
+}
+
+#[test_case]
+fn programs_are_packed_to_bin() {
+    for name in ["cat", "echo", "hexdump", "ls"] {
+        let path = alloc::format!("/bin/{}", name);
+        assert!(vfs::stat(&path).is_ok());
+    }
+}
+
+#[test_case]
+fn load_a_program() {
+    let program = vfs::read_file("/bin/echo").unwrap();
+    let image = elf::load(&program).unwrap();
+
+    let start = image.region.first_page();
+    let end = start + (image.region.size * PAGE_SIZE) as u64;
+    assert!(start <= image.entry && image.entry < end);
+    image.release();
+}
+
+#[test_case]
+fn only_elf_programs_are_loaded() {
+    let motd = vfs::read_file("/etc/motd").unwrap();
+    assert!(elf::load(&motd).is_err());
+    assert!(elf::load(&[]).is_err());
+
+    // a truncated program has segments beyond it's end
+    let program = vfs::read_file("/bin/echo").unwrap();
+    assert!(elf::load(&program[..program.len() / 2]).is_err());
+}
+
+#[test_case]
+fn headers_at_overflowing_offsets_are_rejected() {
+    let mut program = vfs::read_file("/bin/echo").unwrap();
+    // the program headers offset (e_phoff)
+    program[32..40].copy_from_slice(&(u64::MAX - 8).to_le_bytes());
+    assert!(elf::load(&program).is_err());
+}
+
+#[test_case]
+fn the_program_break_moves_within_the_heap() {
+    let image = elf::load(&vfs::read_file("/bin/echo").unwrap()).unwrap();
+    let mut process = Process::from_program(1, None, image, b"echo\0").unwrap();
+    let data = &mut process.internal_data;
+
+    // the break can't be set before the heap is reserved
+    assert!(data.change_break(0x1000).is_err());
+    let (start, reserved) = data.change_break(0).unwrap();
+    assert!(reserved.map(|heap| heap.first_page()) == Some(start));
+
+    let end = start + (HEAP_PAGES * PAGE_SIZE) as u64;
+    assert!(matches!(data.change_break(end), Ok((address, None)) if address == end));
+    assert!(data.change_break(end + 1).is_err());
+    assert!(data.change_break(start - 1).is_err());
+    assert!(matches!(data.change_break(0), Ok((address, None)) if address == end));
+    process.release_resources();
+}
+
+#[test_case]
+fn mapped_memory_is_zeroed_and_unmapped_by_their_length() {
+    let image = elf::load(&vfs::read_file("/bin/echo").unwrap()).unwrap();
+    let mut process = Process::from_program(1, None, image, b"").unwrap();
+    let data = &mut process.internal_data;
+    assert!(data.map_memory(0).is_err());
+
+    let region = data.map_memory(PAGE_SIZE + 1).unwrap();
+    assert!(region.size == 2);
+    let bytes = unsafe { core::slice::from_raw_parts(region.first_page() as *const u8, 2 * PAGE_SIZE) };
+    assert!(bytes.iter().all(|byte| *byte == 0));
+
+    assert!(data.unmap_memory(region.first_page(), PAGE_SIZE * 3).is_err());
+    assert!(data.unmap_memory(region.first_page(), PAGE_SIZE + 1).is_ok());
+    assert!(data.mappings.is_empty());
+    memory::kfree(region.first_frame(), region.size * PAGE_SIZE, PAGE_SIZE);
+    process.release_resources();
+}
+
+#[test_case]
+fn released_memory_goes_back_to_the_kernel() {
+    let image = elf::load(&vfs::read_file("/bin/echo").unwrap()).unwrap();
+    let mut process = Process::from_program(1, None, image, b"").unwrap();
+    process.internal_data.change_break(0).unwrap();
+    process.internal_data.map_memory(PAGE_SIZE).unwrap();
+    process.internal_data.load_address_space();
+
+    let regions = process.owned_regions();
+    let is_user = |page: u64| KERNEL_MAPPER.lock().get_linear_address_entry(page).unwrap().flags().contains(EntryFlags::USER);
+    assert!(regions.len() == 4 && regions.iter().all(|region| is_user(region.first_page())));
+    // the frames may be allocated to the kernel once they're freed
+    process.release_resources();
+    assert!(regions.iter().all(|region| !is_user(region.first_page())));
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
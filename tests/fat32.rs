@@ -0,0 +1,120 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use alloc::{vec, vec::Vec};
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+use CrabOS::{
+    drivers::block,
+    fs::{
+        self,
+        vfs::{self, DirectoryEntry, FileType},
+        File, OpenFlags,
+    },
+    hlt_loop,
+    interrupts::{gdt, idt},
+    log::{self, info, LevelFilter},
+    memory, test_panic_handler,
+};
+
+/// The FAT32 volume is attached as the secondary master, see scripts/make_disks.sh
+const VOLUME: &str = "/mnt/hdc";
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Debug);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+    block::init();
+    fs::init();
+
+    test_main();
+    hlt_loop()
+}
+
+fn read_all(path: &str) -> Vec<u8> {
+    let file = vfs::open(path, OpenFlags::READ).unwrap();
+    let mut content = vec![0u8; vfs::stat(path).unwrap().size as usize];
+    assert!(file.read(&mut content) == Ok(content.len()));
+    content
+}
+
+#[test_case]
+fn read_host_files() {
+    assert!(read_all("/mnt/hdc/hello.txt") == b"hello from the host");
+    // short names are case insensitive
+    assert!(vfs::stat("/mnt/hdc/HELLO.TXT").is_ok());
+}
+
+#[test_case]
+fn read_long_file_name() {
+    assert!(vfs::stat("/mnt/hdc/docs").unwrap().file_type == FileType::Directory);
+    assert!(read_all("/mnt/hdc/docs/A long file name.text") == b"a file with a long name");
+}
+
+#[test_case]
+fn list_root_directory() {
+    let root = vfs::open(VOLUME, OpenFlags::READ).unwrap();
+    let mut entries = [DirectoryEntry::empty(); 8];
+    let count = root.read_directory(&mut entries).unwrap();
+
+    for entry in entries[..count].iter() {
+        info!("{}", entry.name());
+    }
+    assert!(entries[..count].iter().any(|entry| entry.name() == "hello.txt"));
+    assert!(entries[..count].iter().any(|entry| entry.name() == "docs"));
+}
+
+#[test_case]
+fn create_files_spanning_clusters() {
+    vfs::mkdir("/mnt/hdc/new directory").unwrap();
+    let path = "/mnt/hdc/new directory/a file spanning clusters.bin";
+    let file = vfs::open(path, OpenFlags::WRITE | OpenFlags::CREATE).unwrap();
+
+    let content: Vec<u8> = (0..0x1800).map(|index| index as u8).collect();
+    assert!(file.write(&content) == Ok(content.len()));
+    assert!(read_all(path) == content);
+    assert!(vfs::stat("/mnt/hdc/new directory/..").unwrap().inode == vfs::stat(VOLUME).unwrap().inode);
+
+    // the image is reused between runs
+    vfs::unlink(path).unwrap();
+    vfs::unlink("/mnt/hdc/new directory").unwrap();
+}
+
+#[test_case]
+fn truncate_files() {
+    let path = "/mnt/hdc/truncated.txt";
+    let file = vfs::open(path, OpenFlags::WRITE | OpenFlags::CREATE).unwrap();
+    file.write(b"some content that will be removed").unwrap();
+
+    vfs::truncate(path, 4).unwrap();
+    assert!(read_all(path) == b"some");
+
+    vfs::open(path, OpenFlags::WRITE | OpenFlags::TRUNCATE).unwrap();
+    assert!(vfs::stat(path).unwrap().size == 0);
+    vfs::unlink(path).unwrap();
+}
+
+#[test_case]
+fn unlink_files_and_directories() {
+    vfs::mkdir("/mnt/hdc/to remove").unwrap();
+    vfs::open("/mnt/hdc/to remove/file", OpenFlags::WRITE | OpenFlags::CREATE).unwrap();
+
+    // directories must be empty
+    assert!(vfs::unlink("/mnt/hdc/to remove").is_err());
+    vfs::unlink("/mnt/hdc/to remove/file").unwrap();
+    vfs::unlink("/mnt/hdc/to remove").unwrap();
+    assert!(vfs::stat("/mnt/hdc/to remove").is_err());
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
@@ -0,0 +1,126 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+use CrabOS::{
+    fs::{
+        self,
+        vfs::{self, DirectoryEntry, FileType},
+        File, OpenFlags,
+    },
+    hlt_loop,
+    interrupts::{gdt, idt},
+    log::{self, info, LevelFilter},
+    memory, test_panic_handler,
+};
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Debug);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+    fs::init();
+
+    test_main();
+    hlt_loop()
+}
+
+#[test_case]
+fn resolve_dot_and_dot_dot() {
+    let root = vfs::stat("/").unwrap();
+    assert!(root.file_type == FileType::Directory);
+
+    let console = vfs::stat("/dev/./../dev/console/.").unwrap();
+    assert!(console.file_type == FileType::CharDevice);
+    assert!(vfs::stat("/missing").is_err());
+}
+
+#[test_case]
+fn canonical_paths() {
+    assert!(vfs::canonical_directory("/././..") == Ok("/".into()));
+    assert!(vfs::canonical_directory("/dev/../dev/.") == Ok("/dev".into()));
+    assert!(vfs::absolute_path("/", "dev") == "/dev");
+    assert!(vfs::absolute_path("/dev", "../console") == "/dev/../console");
+}
+
+#[test_case]
+fn write_to_console_device() {
+    let console = vfs::open("/dev/console", OpenFlags::WRITE).unwrap();
+    assert!(console.write(b"hello from the vfs\n") == Ok(19));
+}
+
+#[test_case]
+fn list_devices_directory() {
+    let root = vfs::open("/dev", OpenFlags::READ).unwrap();
+    let mut entries = [DirectoryEntry::empty(); 4];
+    let count = root.read_directory(&mut entries).unwrap();
+
+    for entry in &entries[..count] {
+        info!("{} -> inode {}", entry.name(), entry.inode);
+    }
+    assert!(entries[..count].iter().any(|entry| entry.name() == "null"));
+    assert!(root.read_directory(&mut entries) == Ok(0));
+}
+
+#[test_case]
+fn initramfs_unpacked() {
+    let motd = vfs::open("/etc/motd", OpenFlags::READ).unwrap();
+    let mut buffer = [0u8; 64];
+    let count = motd.read(&mut buffer).unwrap();
+
+    info!("motd: {:?}", core::str::from_utf8(&buffer[..count]));
+    assert!(buffer.starts_with(b"Welcome to CrabOS"));
+}
+
+#[test_case]
+fn tmpfs_files_span_pages() {
+    vfs::mkdir("/tmp").unwrap();
+    let file = vfs::open("/tmp/big", OpenFlags::READ | OpenFlags::WRITE | OpenFlags::CREATE).unwrap();
+
+    let data = [0x42u8; 0x1800];
+    assert!(file.write(&data) == Ok(data.len()));
+    assert!(vfs::stat("/tmp/big").unwrap().size == data.len() as u64);
+
+    let file = vfs::open("/tmp/big", OpenFlags::READ).unwrap();
+    let mut buffer = [0u8; 0x1800];
+    assert!(file.read(&mut buffer) == Ok(data.len()));
+    assert!(buffer == data);
+
+    assert!(vfs::unlink("/tmp").is_err());
+    vfs::unlink("/tmp/big").unwrap();
+    vfs::unlink("/tmp").unwrap();
+    assert!(vfs::stat("/tmp").is_err());
+}
+
+#[test_case]
+fn appended_writes_go_to_the_end() {
+    let file = vfs::open("/appended", OpenFlags::WRITE | OpenFlags::CREATE).unwrap();
+    file.write(b"first ").unwrap();
+
+    let file = vfs::open("/appended", OpenFlags::WRITE | OpenFlags::APPEND).unwrap();
+    file.write(b"second").unwrap();
+
+    let file = vfs::open("/appended", OpenFlags::READ).unwrap();
+    let mut buffer = [0u8; 16];
+    assert!(file.read(&mut buffer) == Ok(12));
+    assert!(&buffer[..12] == b"first second");
+    vfs::unlink("/appended").unwrap();
+}
+
+#[test_case]
+fn mount_points_cannot_be_unlinked() {
+    assert!(vfs::unlink("/dev").is_err());
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
@@ -0,0 +1,196 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use bootloader::{entry_point, BootInfo};
+use core::{
+    panic::PanicInfo,
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+};
+use x86_64::instructions::interrupts as cpu_interrupts;
+
+use CrabOS::{
+    code_addr,
+    fs, hlt_loop,
+    interrupts::{self, gdt, idt},
+    log::{self, LevelFilter},
+    memory::{self, get_linear_addr, get_physical_addr},
+    processes::{get_process_info, kill_process, objects::ProcessState, spawn_process},
+    smp,
+    sync::futex,
+    test_panic_handler,
+    tests::{map_test_page, wait_until, TEST_PAGE},
+    time,
+    userland::syscalls::{execute, futex_wait, spawn, Timespec},
+};
+
+/// The word the processes wait on, FUTEX only accepts words in the caller's memory and the programs
+/// of the kernel own the kernel image (through the linear mapping, see `word_address`)
+static WORD: AtomicU32 = AtomicU32::new(0);
+/// Contends for libcrab's `Mutex` in several processes, and waits for them on a `Condvar`
+const LOCKSTRESS: &str = "/bin/lockstress";
+const LOCKSTRESS_ARGUMENTS: &[u8] = b"/bin/lockstress\0";
+const TIMEOUT_NANOSECONDS: u64 = 50_000_000;
+
+const PENDING: u32 = 0;
+const WOKEN: u32 = 1;
+const FAILED: u32 = 2;
+/// An exit status isn't more than a byte, so it's never a status
+const PENDING_STATUS: u32 = u32::MAX;
+const SPAWN_FAILED: u32 = u32::MAX - 1;
+
+/// The memory of the test page
+#[repr(C)]
+struct Shared {
+    /// The address of `WORD` in the linear mapping
+    word_address: AtomicU64,
+    result: AtomicU32,
+    lockstress_status: AtomicU32,
+}
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Info);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+    interrupts::init_controllers();
+    time::init();
+    smp::init();
+    fs::init();
+    cpu_interrupts::enable();
+
+    map_test_page();
+    // killing pid 0 shuts down the machine, so it's never executed
+    spawn_process(code_addr!(spin));
+
+    test_main();
+    hlt_loop()
+}
+
+fn shared() -> &'static Shared {
+    unsafe { &*(TEST_PAGE as *const Shared) }
+}
+
+/// Returns the address of `WORD` the processes use
+fn word_address() -> u64 {
+    get_linear_addr(get_physical_addr(&WORD as *const AtomicU32 as u64).unwrap())
+}
+
+/// Returns the word the processes wait on
+fn word() -> &'static AtomicU32 {
+    unsafe { &*(shared().word_address.load(Ordering::SeqCst) as *const AtomicU32) }
+}
+
+fn reset() {
+    let shared = shared();
+    WORD.store(0, Ordering::SeqCst);
+    shared.word_address.store(word_address(), Ordering::SeqCst);
+    shared.result.store(PENDING, Ordering::SeqCst);
+    shared.lockstress_status.store(PENDING_STATUS, Ordering::SeqCst);
+}
+
+fn spin() -> ! {
+    loop {}
+}
+
+/// Runs lockstress as a child, and stores it's exit status
+fn lockstress_runner() -> ! {
+    let status = spawn(LOCKSTRESS, LOCKSTRESS_ARGUMENTS).and_then(execute);
+    shared().lockstress_status.store(status.map_or(SPAWN_FAILED, u32::from), Ordering::SeqCst);
+    spin()
+}
+
+fn waiter() -> ! {
+    let shared = shared();
+    let result = futex_wait(word(), 0, None);
+    shared.result.store(if result.is_ok() { WOKEN } else { FAILED }, Ordering::SeqCst);
+    spin()
+}
+
+fn timed_waiter() -> ! {
+    let shared = shared();
+    let timeout = Timespec::from_nanoseconds(TIMEOUT_NANOSECONDS);
+    let result = futex_wait(word(), 0, Some(&timeout));
+    shared.result.store(if result.is_ok() { WOKEN } else { FAILED }, Ordering::SeqCst);
+    spin()
+}
+
+fn state(pid: usize) -> ProcessState {
+    get_process_info(pid).unwrap().state
+}
+
+#[test_case]
+fn libcrab_locks_hold_under_contention() {
+    reset();
+    let pid = spawn_process(code_addr!(lockstress_runner));
+    smp::enqueue(pid);
+
+    let status = &shared().lockstress_status;
+    assert!(wait_until(|| status.load(Ordering::SeqCst) != PENDING_STATUS));
+    // lockstress exits with 1 if an increment was lost
+    assert!(status.load(Ordering::SeqCst) == 0);
+    kill_process(pid).unwrap();
+}
+
+#[test_case]
+fn waiters_are_keyed_by_physical_address() {
+    reset();
+    let pid = spawn_process(code_addr!(waiter));
+    smp::enqueue(pid);
+    assert!(wait_until(|| state(pid) == ProcessState::Blocked));
+
+    // the process waits on the linear address of the word
+    let alias = &WORD as *const AtomicU32 as u64;
+    assert!(alias != word_address());
+    assert!(futex::wake(alias, 1) == Ok(1));
+    assert!(wait_until(|| shared().result.load(Ordering::SeqCst) == WOKEN));
+    kill_process(pid).unwrap();
+}
+
+#[test_case]
+fn waits_time_out() {
+    reset();
+    let start = time::uptime_nanoseconds();
+    let pid = spawn_process(code_addr!(timed_waiter));
+    smp::enqueue(pid);
+
+    assert!(wait_until(|| shared().result.load(Ordering::SeqCst) != PENDING));
+    assert!(shared().result.load(Ordering::SeqCst) == FAILED);
+    assert!(time::uptime_nanoseconds() - start >= TIMEOUT_NANOSECONDS);
+    // the waiter that timed out left it's queue
+    assert!(futex::queued(word_address()) == Ok(0));
+    assert!(futex::wake(word_address(), 1) == Ok(0));
+    kill_process(pid).unwrap();
+}
+
+#[test_case]
+fn killed_waiters_leave_the_queue() {
+    reset();
+    let pid = spawn_process(code_addr!(waiter));
+    smp::enqueue(pid);
+    assert!(wait_until(|| state(pid) == ProcessState::Blocked));
+
+    assert!(futex::queued(word_address()) == Ok(1));
+    kill_process(pid).unwrap();
+    assert!(futex::queued(word_address()) == Ok(0));
+}
+
+#[test_case]
+fn waits_fail_if_the_word_changed() {
+    reset();
+    WORD.store(1, Ordering::SeqCst);
+    let pid = spawn_process(code_addr!(waiter));
+    smp::enqueue(pid);
+
+    assert!(wait_until(|| shared().result.load(Ordering::SeqCst) != PENDING));
+    assert!(shared().result.load(Ordering::SeqCst) == FAILED);
+    kill_process(pid).unwrap();
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
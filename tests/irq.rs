@@ -0,0 +1,77 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::{
+    arch::asm,
+    panic::PanicInfo,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use CrabOS::{
+    hlt_loop,
+    interrupts::{gdt, idt, irq_count, pic, register_irq, spurious_irq_count},
+    log::{self, LevelFilter},
+    memory, test_panic_handler,
+};
+
+/// An IRQ line without a device in QEMU, raised with a software interrupt
+const TEST_IRQ: u8 = 5;
+static HANDLED: AtomicBool = AtomicBool::new(false);
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Debug);
+    gdt::init();
+    idt::init();
+    pic::init();
+    memory::init(boot_info);
+
+    test_main();
+    hlt_loop()
+}
+
+fn test_handler() {
+    HANDLED.store(true, Ordering::Relaxed);
+}
+
+#[test_case]
+fn registered_handler_is_called() {
+    register_irq(TEST_IRQ, test_handler).unwrap();
+    let count = irq_count(TEST_IRQ);
+
+    // vector 32 + 5
+    unsafe { asm!("int 37") };
+    assert!(HANDLED.load(Ordering::Relaxed));
+    assert!(irq_count(TEST_IRQ) == count + 1);
+}
+
+#[test_case]
+fn invalid_registrations_fail() {
+    // the slave cascade
+    assert!(register_irq(pic::CASCADE_IRQ, test_handler).is_err());
+    assert!(register_irq(16, test_handler).is_err());
+    // IRQ 5 was registered by the previous test
+    assert!(register_irq(TEST_IRQ, test_handler).is_err());
+}
+
+#[test_case]
+fn spurious_irqs_are_counted() {
+    let count = irq_count(7);
+    let spurious = spurious_irq_count();
+
+    // IRQ 7 isn't in service, so the PIC didn't raise it
+    unsafe { asm!("int 39") };
+    assert!(irq_count(7) == count);
+    assert!(spurious_irq_count() == spurious + 1);
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
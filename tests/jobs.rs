@@ -0,0 +1,114 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+use CrabOS::{
+    code_addr, hlt_loop,
+    interrupts::{gdt, idt},
+    log::{self, LevelFilter},
+    memory,
+    processes::{objects::ProcessState, scheduler::Scheduler, signals::SIGTSTP},
+    test_panic_handler,
+    userland::programs::hello,
+};
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Info);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+
+    test_main();
+    hlt_loop()
+}
+
+/// A shell (pid 0) with two children (pids 1 & 2), and a child of the first child (pid 3)
+fn session() -> Scheduler {
+    let mut scheduler = Scheduler::empty();
+    let shell = scheduler.push_process(code_addr!(hello), None);
+    let first = scheduler.push_process(code_addr!(hello), Some(shell));
+    scheduler.push_process(code_addr!(hello), Some(shell));
+    scheduler.push_process(code_addr!(hello), Some(first));
+    scheduler
+}
+
+fn release(mut scheduler: Scheduler) {
+    for pid in 0..scheduler.next_pid() {
+        scheduler.remove_process(pid).unwrap();
+    }
+}
+
+#[test_case]
+fn children_inherit_the_group_and_session() {
+    let scheduler = session();
+    for pid in 0..4 {
+        let data = scheduler.get_process_info(pid).unwrap();
+        assert!(data.pgid == 0 && data.sid == 0);
+    }
+    assert!(scheduler.process_group(0) == [0, 1, 2, 3]);
+    release(scheduler);
+}
+
+#[test_case]
+fn processes_move_to_groups_of_their_session() {
+    let mut scheduler = session();
+    // a new group, then a child joins it
+    scheduler.set_process_group(0, 1, 1).unwrap();
+    scheduler.set_process_group(0, 2, 1).unwrap();
+    assert!(scheduler.process_group(1) == [1, 2]);
+
+    // only the caller & it's children move, to an existing group
+    assert!(scheduler.set_process_group(0, 3, 1).is_err());
+    scheduler.set_process_group(3, 3, 1).unwrap();
+    assert!(scheduler.set_process_group(0, 2, 7).is_err());
+    // the session leader stays in it's group
+    assert!(scheduler.set_process_group(0, 0, 1).is_err());
+    release(scheduler);
+}
+
+#[test_case]
+fn only_non_group_leaders_create_sessions() {
+    let mut scheduler = session();
+    assert!(scheduler.create_session(0).is_err());
+    assert!(scheduler.create_session(1) == Ok(1));
+    let data = scheduler.get_process_info(1).unwrap();
+    assert!(data.pgid == 1 && data.sid == 1);
+
+    // groups & processes of another session don't move
+    assert!(scheduler.set_process_group(0, 2, 1).is_err());
+    assert!(scheduler.set_process_group(0, 1, 0).is_err());
+    release(scheduler);
+}
+
+#[test_case]
+fn stopped_processes_run_once_continued() {
+    let mut scheduler = session();
+    let state = |scheduler: &Scheduler| scheduler.get_process_info(1).unwrap().state;
+
+    // only a running process stops
+    assert!(scheduler.stop_process(1, SIGTSTP).is_err());
+    scheduler.prepare_process(1).unwrap();
+    // the parent isn't paused, so it's not woken
+    assert!(scheduler.stop_process(1, SIGTSTP) == Ok(None));
+    assert!(state(&scheduler) == ProcessState::Stopped);
+    assert!(scheduler.get_process(1).unwrap().stop_signal == Some(SIGTSTP));
+    assert!(scheduler.prepare_process(1).is_err());
+
+    scheduler.continue_process(1).unwrap();
+    assert!(state(&scheduler) == ProcessState::Waiting);
+    assert!(scheduler.continue_process(1).is_err());
+    release(scheduler);
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
@@ -0,0 +1,144 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use x86_64::instructions::interrupts;
+
+use CrabOS::{
+    drivers::{
+        keyboard::{
+            self, inject_scancode,
+            scancodes::{Decoder, Key, Modifiers},
+        },
+        vga,
+    },
+    fs::{console::Console, File},
+    hlt_loop,
+    interrupts::{gdt, idt, pic},
+    log::{self, LevelFilter},
+    memory, test_panic_handler,
+};
+
+const LEFT_SHIFT: u8 = 0x2A;
+const CAPS_LOCK: u8 = 0x3A;
+const LEFT_CTRL: u8 = 0x1D;
+const LEFT_ALT: u8 = 0x38;
+const F1: u8 = 0x3B;
+const F2: u8 = 0x3C;
+const KEY_A: u8 = 0x1E;
+const KEY_C: u8 = 0x2E;
+const KEY_H: u8 = 0x23;
+const KEY_I: u8 = 0x17;
+const KEY_1: u8 = 0x02;
+const ENTER: u8 = 0x1C;
+const BACKSPACE: u8 = 0x0E;
+const BREAK: u8 = 0x80;
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Debug);
+    gdt::init();
+    idt::init();
+    pic::init();
+    memory::init(boot_info);
+    keyboard::init();
+    interrupts::enable();
+
+    test_main();
+    hlt_loop()
+}
+
+/// Presses and releases a key through the keyboard controller
+fn type_key(scancode: u8) {
+    inject_scancode(scancode).unwrap();
+    inject_scancode(scancode | BREAK).unwrap();
+}
+
+#[test_case]
+fn decode_modifiers() {
+    let mut decoder = Decoder::new();
+
+    assert!(decoder.decode(KEY_A).unwrap().ascii() == Some(b'a'));
+    decoder.decode(LEFT_SHIFT).unwrap();
+    assert!(decoder.decode(KEY_A).unwrap().ascii() == Some(b'A'));
+    assert!(decoder.decode(KEY_1).unwrap().ascii() == Some(b'!'));
+    assert!(decoder.decode(KEY_A | BREAK).unwrap().ascii() == None);
+    decoder.decode(LEFT_SHIFT | BREAK).unwrap();
+
+    decoder.decode(CAPS_LOCK).unwrap();
+    let event = decoder.decode(KEY_A).unwrap();
+    assert!(event.modifiers.contains(Modifiers::CAPS_LOCK));
+    assert!(event.ascii() == Some(b'A'));
+    assert!(decoder.decode(KEY_1).unwrap().ascii() == Some(b'1'));
+
+    decoder.decode(LEFT_CTRL).unwrap();
+    assert!(decoder.decode(KEY_C).unwrap().ascii() == Some(3));
+}
+
+#[test_case]
+fn decode_extended_keys() {
+    let mut decoder = Decoder::new();
+
+    assert!(decoder.decode(0xE0).is_none());
+    let event = decoder.decode(0x48).unwrap();
+    assert!(event.key == Key::Up && event.pressed);
+    assert!(event.ascii() == None);
+
+    assert!(decoder.decode(0xE0).is_none());
+    assert!(decoder.decode(0x1D).unwrap().key == Key::RightCtrl);
+    assert!(decoder.decode(KEY_A).unwrap().modifiers.contains(Modifiers::CTRL));
+}
+
+#[test_case]
+fn irq_queues_key_events() {
+    type_key(KEY_A);
+
+    let pressed = keyboard::wait_event(0);
+    assert!(pressed.key == Key::Character(b'a') && pressed.pressed);
+    let released = keyboard::wait_event(0);
+    assert!(released.key == Key::Character(b'a') && !released.pressed);
+}
+
+#[test_case]
+fn console_reads_lines() {
+    for scancode in [KEY_H, KEY_A, BACKSPACE, KEY_I, ENTER, KEY_A, ENTER] {
+        type_key(scancode);
+    }
+
+    let mut line = [0u8; 16];
+    assert!(Console.read(&mut line) == Ok(3));
+    assert!(&line[..3] == b"hi\n");
+    // reads return a single line, even if more lines were typed
+    assert!(Console.read(&mut line[..1]) == Ok(1));
+    assert!(Console.read(&mut line[1..]) == Ok(1));
+    assert!(&line[..2] == b"a\n");
+}
+
+#[test_case]
+fn alt_function_keys_switch_terminals() {
+    inject_scancode(LEFT_ALT).unwrap();
+    type_key(F2);
+    inject_scancode(LEFT_ALT | BREAK).unwrap();
+    assert!(vga::active_terminal() == 1);
+
+    // the typed keys go to the active terminal only
+    type_key(KEY_A);
+    while keyboard::wait_event(1).key != Key::Character(b'a') {}
+    assert!(core::iter::from_fn(|| keyboard::read_event(0)).all(|event| event.key != Key::Character(b'a')));
+
+    inject_scancode(LEFT_ALT).unwrap();
+    type_key(F1);
+    inject_scancode(LEFT_ALT | BREAK).unwrap();
+    assert!(vga::active_terminal() == 0);
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
@@ -0,0 +1,84 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use alloc::vec;
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+use CrabOS::{
+    fs::pipe::{pipe, PIPE_CAPACITY},
+    hlt_loop,
+    interrupts::{gdt, idt},
+    log::{self, LevelFilter},
+    memory, test_panic_handler,
+};
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Info);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+
+    test_main();
+    hlt_loop()
+}
+
+#[test_case]
+fn bytes_are_read_in_order() {
+    let (reader, writer) = pipe();
+    assert!(writer.write(b"hello") == Ok(5));
+
+    let mut buffer = [0u8; 3];
+    assert!(reader.read(&mut buffer) == Ok(3));
+    assert!(&buffer == b"hel");
+    assert!(reader.read(&mut buffer) == Ok(2));
+    assert!(&buffer[..2] == b"lo");
+}
+
+#[test_case]
+fn a_pipe_holds_its_capacity() {
+    let (reader, writer) = pipe();
+    let data = vec![0xAB; PIPE_CAPACITY];
+    assert!(writer.write(&data) == Ok(PIPE_CAPACITY));
+
+    let mut buffer = vec![0; PIPE_CAPACITY + 1];
+    assert!(reader.read(&mut buffer) == Ok(PIPE_CAPACITY));
+    assert!(buffer[..PIPE_CAPACITY] == data[..]);
+}
+
+#[test_case]
+fn reads_end_when_the_writer_closes() {
+    let (reader, writer) = pipe();
+    writer.write(b"last").unwrap();
+    drop(writer);
+
+    // the written bytes are read before the end of file
+    let mut buffer = [0u8; 8];
+    assert!(reader.read(&mut buffer) == Ok(4));
+    assert!(reader.read(&mut buffer) == Ok(0));
+}
+
+#[test_case]
+fn writes_fail_when_the_reader_closes() {
+    let (reader, writer) = pipe();
+    drop(reader);
+    assert!(writer.write(b"nobody reads").is_err());
+}
+
+#[test_case]
+fn ends_are_one_directional() {
+    let (reader, writer) = pipe();
+    assert!(reader.write(b"data").is_err());
+    assert!(writer.read(&mut [0u8; 4]).is_err());
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
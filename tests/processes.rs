@@ -9,14 +9,14 @@ use core::{arch::asm, panic::PanicInfo};
 
 use CrabOS::{
     interrupts::{
-        gdt,
+        self, gdt,
         get_kernel_selectors, idt,
     },
     log,
     memory::{self, as_addr, kmap, get_physical_addr},
     processes::{objects::{Process, Thread}, spawn_process, execute_process},
     test_panic_handler,
-    userland::user_main, code_addr,
+    userland::programs::hello, code_addr,
 };
 
 use ::log::{debug, info, LevelFilter};
@@ -31,14 +31,17 @@ fn main(boot_info: &'static BootInfo) -> ! {
     gdt::init();
     idt::init();
     memory::init(boot_info);
+    // processes run with interrupts enabled, so the PICs must be remapped
+    interrupts::init_controllers();
     
     unsafe { asm!("mov {}, rsp", out(reg) stack_top) };
     debug!("stack virtual address {:#x}, physical address: {:#x} ", stack_top, get_physical_addr(stack_top).unwrap());
-    let _dummy_thread = Thread::new(code_addr!(user_main), cs, ds, stack_top);
+    let _dummy_thread = Thread::new(code_addr!(hello), cs, ds, stack_top);
 
     // unsafe { _dummy_thread.run() }
 
-    spawn_process(code_addr!(user_main));
+    // the first process exiting shuts the machine down
+    spawn_process(code_addr!(hello));
     execute_process(0);
     loop {}
 }
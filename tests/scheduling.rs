@@ -0,0 +1,140 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use alloc::collections::VecDeque;
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+use CrabOS::{
+    code_addr, hlt_loop,
+    interrupts::{gdt, idt},
+    log::{self, LevelFilter},
+    memory,
+    processes::{
+        objects::ProcessState,
+        scheduler::{Scheduler, MAX_NICE, MIN_NICE},
+        signals::SIGSTOP,
+    },
+    test_panic_handler,
+    time::timer_wheel::TICK_NANOSECONDS,
+    userland::programs::hello,
+};
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Info);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+
+    test_main();
+    hlt_loop()
+}
+
+/// Runs a process for `ticks` timer ticks while the processes of `run_queue` wait, and preempts it
+fn run(scheduler: &mut Scheduler, run_queue: &VecDeque<usize>, pid: usize, ticks: u64) {
+    scheduler.prepare_process(pid).unwrap();
+    for _ in 0..ticks {
+        scheduler.charge_process(pid, TICK_NANOSECONDS, run_queue).unwrap();
+    }
+    scheduler.get_process_mut(pid).unwrap().internal_data.state = ProcessState::Waiting;
+}
+
+fn release(mut scheduler: Scheduler) {
+    for pid in 0..scheduler.next_pid() {
+        scheduler.remove_process(pid).unwrap();
+    }
+}
+
+#[test_case]
+fn nice_values_weight_cpu_time() {
+    let mut scheduler = Scheduler::empty();
+    let parent = scheduler.push_process(code_addr!(hello), None);
+    let child = scheduler.push_process(code_addr!(hello), Some(parent));
+    scheduler.set_priority(parent, child, 5).unwrap();
+
+    let run_queue = VecDeque::from([parent, child]);
+    run(&mut scheduler, &run_queue, parent, 10);
+    run(&mut scheduler, &run_queue, child, 10);
+    let (parent, child) = (scheduler.get_process_info(parent).unwrap(), scheduler.get_process_info(child).unwrap());
+    assert!(parent.cpu_time == 10 * TICK_NANOSECONDS && child.cpu_time == parent.cpu_time);
+    // the CPU time of nice 5 weights about 3 times the CPU time of nice 0
+    assert!(parent.vruntime == parent.cpu_time);
+    assert!(child.vruntime > 3 * parent.vruntime && child.vruntime < 4 * parent.vruntime);
+    release(scheduler);
+}
+
+#[test_case]
+fn priorities_are_set_by_the_process_or_its_parent() {
+    let mut scheduler = Scheduler::empty();
+    let parent = scheduler.push_process(code_addr!(hello), None);
+    scheduler.set_priority(parent, parent, MIN_NICE).unwrap();
+    let child = scheduler.push_process(code_addr!(hello), Some(parent));
+    let other = scheduler.push_process(code_addr!(hello), None);
+
+    // a child inherits it's parent's nice value
+    assert!(scheduler.get_process_info(child).unwrap().nice == MIN_NICE);
+    scheduler.set_priority(parent, child, MAX_NICE).unwrap();
+    scheduler.set_priority(child, child, 0).unwrap();
+    assert!(scheduler.set_priority(child, parent, 0).is_err());
+    assert!(scheduler.set_priority(other, child, 0).is_err());
+    assert!(scheduler.set_priority(child, child, MAX_NICE + 1).is_err());
+    assert!(scheduler.set_priority(child, child, MIN_NICE - 1).is_err());
+    release(scheduler);
+}
+
+#[test_case]
+fn the_process_that_ran_the_least_runs_next() {
+    let mut scheduler = Scheduler::empty();
+    let pids: VecDeque<usize> = (0..3).map(|_| scheduler.push_process(code_addr!(hello), None)).collect();
+    run(&mut scheduler, &pids, pids[0], 3);
+    run(&mut scheduler, &pids, pids[1], 1);
+    run(&mut scheduler, &pids, pids[2], 2);
+    assert!(scheduler.pick_next(&pids) == Some(1));
+
+    // a tie keeps the process that was queued first
+    run(&mut scheduler, &pids, pids[1], 1);
+    assert!(scheduler.pick_next(&pids) == Some(1));
+    run(&mut scheduler, &pids, pids[1], 1);
+    assert!(scheduler.pick_next(&pids) == Some(2));
+    release(scheduler);
+}
+
+#[test_case]
+fn waiting_processes_do_not_starve_others() {
+    let mut scheduler = Scheduler::empty();
+    let running = scheduler.push_process(code_addr!(hello), None);
+    let sleeper = scheduler.push_process(code_addr!(hello), None);
+    // the sleeper is stopped, so it isn't queued
+    scheduler.prepare_process(sleeper).unwrap();
+    scheduler.stop_process(sleeper, SIGSTOP).unwrap();
+    run(&mut scheduler, &VecDeque::from([running]), running, 100);
+
+    // a new process starts at the lowest virtual runtime of the running & queued processes
+    let new = scheduler.push_process(code_addr!(hello), None);
+    assert!(scheduler.get_process_info(new).unwrap().vruntime == 100 * TICK_NANOSECONDS);
+
+    // the sleeper is placed when it's woken, it runs first but only it's credit ahead of the others
+    assert!(scheduler.get_process_info(sleeper).unwrap().vruntime == 0);
+    scheduler.continue_process(sleeper).unwrap();
+    assert!(scheduler.get_process_info(sleeper).unwrap().vruntime == 99 * TICK_NANOSECONDS);
+    let run_queue = VecDeque::from([running, new, sleeper]);
+    assert!(scheduler.pick_next(&run_queue) == Some(2));
+    run(&mut scheduler, &run_queue, sleeper, 2);
+    assert!(scheduler.pick_next(&run_queue) == Some(0));
+
+    // a process that was removed leaves the queue first
+    let run_queue = VecDeque::from([running, scheduler.next_pid()]);
+    assert!(scheduler.pick_next(&run_queue) == Some(1));
+    release(scheduler);
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
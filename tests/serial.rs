@@ -0,0 +1,85 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use x86_64::instructions::interrupts as cpu_interrupts;
+
+use CrabOS::{
+    drivers::serial::{self, COM1_IRQ, PORTS},
+    fs::{devfs::DEVICES, vfs::FileSystem},
+    hlt_loop,
+    interrupts::{self, gdt, idt, irq_count},
+    log::{self, info, LevelFilter},
+    memory, test_panic_handler,
+    tests::wait_until,
+};
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Info);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+    interrupts::init_controllers();
+    serial::init();
+    cpu_interrupts::enable();
+
+    test_main();
+    hlt_loop()
+}
+
+#[test_case]
+fn only_com1_is_connected() {
+    // QEMU connects a single serial port
+    assert!(PORTS[0].is_present());
+    assert!(PORTS[1..].iter().all(|port| !port.is_present()));
+
+    let root = DEVICES.root();
+    assert!(DEVICES.lookup(root, "ttyS0").is_ok());
+    assert!(DEVICES.lookup(root, "ttyS1").is_err());
+}
+
+#[test_case]
+fn output_is_interrupt_driven() {
+    let before = irq_count(COM1_IRQ);
+    // more output than the transmit ring holds
+    for line in 0..400 {
+        info!("serial output line {}", line);
+    }
+    assert!(wait_until(|| irq_count(COM1_IRQ) > before));
+}
+
+#[test_case]
+fn logging_with_interrupts_disabled() {
+    // the transmitter empty interrupt can't drain the ring, so the writers drain it themselves
+    cpu_interrupts::without_interrupts(|| {
+        for line in 0..400 {
+            info!("serial output line {} without interrupts", line);
+        }
+    });
+}
+
+#[test_case]
+fn loopback_receives_transmitted_bytes() {
+    let port = &PORTS[0];
+    while port.read_byte().is_some() {}
+
+    port.set_loopback(true);
+    port.write(b"crab");
+    let received: Vec<u8> = (0..4).map(|_| port.wait_byte()).collect();
+    port.set_loopback(false);
+
+    assert!(received == b"crab");
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
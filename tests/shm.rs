@@ -0,0 +1,150 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use bootloader::{entry_point, BootInfo};
+use core::{
+    panic::PanicInfo,
+    ptr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+use x86_64::instructions::interrupts as cpu_interrupts;
+
+use CrabOS::{
+    code_addr,
+    hlt_loop,
+    interrupts::{self, gdt, idt},
+    ipc::shm::{SharedMemory, SHARED_MEMORY_END, SHARED_MEMORY_START},
+    log::{self, LevelFilter},
+    memory::{self, get_physical_addr, memory_info, mmap, types::PAGE_SIZE},
+    processes::{kill_process, objects::USER_DATA_FLAGS, spawn_process},
+    smp, test_panic_handler,
+    tests::{map_test_page, wait_until, TEST_PAGE},
+    time,
+    userland::syscalls::{shm_create, shm_map, shm_unmap},
+};
+
+/// Where the writer process maps it's segment
+const WRITER_ADDRESS: u64 = SHARED_MEMORY_START + 0x10_0000;
+const MAGIC: u64 = 0xC4AB;
+
+const PENDING: u64 = 0;
+const MAPPED: u64 = 1;
+const FAILED: u64 = 2;
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Info);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+    interrupts::init_controllers();
+    time::init();
+    smp::init();
+    cpu_interrupts::enable();
+
+    map_test_page();
+    // killing pid 0 shuts down the machine, so it's never executed
+    spawn_process(code_addr!(spin));
+
+    test_main();
+    hlt_loop()
+}
+
+fn result() -> &'static AtomicU64 {
+    unsafe { &*(TEST_PAGE as *const AtomicU64) }
+}
+
+fn spin() -> ! {
+    loop {}
+}
+
+/// Maps a segment twice, writes through one mapping and reads through the other
+fn writer() -> ! {
+    let mapped = shm_create(PAGE_SIZE).and_then(|fd| {
+        let chosen = shm_map(fd, Some(WRITER_ADDRESS))?;
+        let linear = shm_map(fd, None)?;
+        unsafe { ptr::write_volatile(chosen as *mut u64, MAGIC) };
+        // the segment is mapped at the linear address of it's frames too
+        let aliased = unsafe { ptr::read_volatile(linear as *const u64) } == MAGIC;
+        shm_unmap(linear)?;
+        Ok(aliased && chosen == WRITER_ADDRESS)
+    });
+    result().store(if mapped == Ok(true) { MAPPED } else { FAILED }, Ordering::SeqCst);
+    spin()
+}
+
+#[test_case]
+fn segments_are_mapped_at_several_addresses() {
+    let segment = SharedMemory::new(2 * PAGE_SIZE).unwrap();
+    let linear = segment.map(None).unwrap();
+    let chosen = segment.map(Some(SHARED_MEMORY_START)).unwrap();
+    unsafe {
+        mmap(linear.region.clone(), USER_DATA_FLAGS).unwrap();
+        mmap(chosen.region.clone(), USER_DATA_FLAGS).unwrap();
+    }
+
+    let last_word = (2 * PAGE_SIZE - 8) as u64;
+    unsafe { ptr::write_volatile((chosen.address() + last_word) as *mut u64, MAGIC) };
+    assert!(unsafe { ptr::read_volatile((linear.address() + last_word) as *const u64) } == MAGIC);
+
+    drop(chosen);
+    assert!(get_physical_addr(SHARED_MEMORY_START).is_none());
+    // the linear mapping goes back to the kernel
+    assert!(get_physical_addr(linear.address()).is_some());
+}
+
+#[test_case]
+fn placements_do_not_overlap() {
+    let first = SharedMemory::new(2 * PAGE_SIZE).unwrap();
+    let second = SharedMemory::new(PAGE_SIZE).unwrap();
+    let placed = first.map(Some(SHARED_MEMORY_START)).unwrap();
+
+    assert!(second.map(Some(SHARED_MEMORY_START)).is_err());
+    assert!(second.map(Some(SHARED_MEMORY_START + PAGE_SIZE as u64)).is_err());
+    assert!(second.map(Some(SHARED_MEMORY_START + 2 * PAGE_SIZE as u64)).is_ok());
+    // another process may map the same segment at the same address
+    assert!(first.map(Some(SHARED_MEMORY_START)).is_ok());
+
+    assert!(second.map(Some(SHARED_MEMORY_START + 1)).is_err());
+    assert!(second.map(Some(SHARED_MEMORY_END)).is_err());
+    assert!(first.map(Some(SHARED_MEMORY_END - PAGE_SIZE as u64)).is_err());
+    drop(placed);
+}
+
+#[test_case]
+fn frames_are_freed_with_the_last_reference() {
+    let free = memory_info().free;
+    let segment = SharedMemory::new(4 * PAGE_SIZE).unwrap();
+    assert!(memory_info().free == free - segment.size());
+
+    let mapping = segment.map(Some(SHARED_MEMORY_START)).unwrap();
+    drop(segment);
+    // the mapping holds the segment
+    assert!(memory_info().free < free);
+
+    drop(mapping);
+    assert!(memory_info().free == free);
+}
+
+#[test_case]
+fn processes_unmap_their_segments_when_they_exit() {
+    result().store(PENDING, Ordering::SeqCst);
+    let pid = spawn_process(code_addr!(writer));
+    smp::enqueue(pid);
+
+    assert!(wait_until(|| result().load(Ordering::SeqCst) != PENDING));
+    assert!(result().load(Ordering::SeqCst) == MAPPED);
+    assert!(unsafe { ptr::read_volatile(WRITER_ADDRESS as *const u64) } == MAGIC);
+
+    // the process is removed once it's processor preempts it
+    kill_process(pid).unwrap();
+    assert!(wait_until(|| get_physical_addr(WRITER_ADDRESS).is_none()));
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
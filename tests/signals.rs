@@ -0,0 +1,163 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+use CrabOS::{
+    hlt_loop,
+    interrupts::{gdt, idt},
+    log::{self, LevelFilter},
+    memory,
+    processes::signals::{
+        exit_status, mask, Delivery, SignalAction, Signals, IGNORE_HANDLER, SIGALRM, SIGCHLD, SIGCONT, SIGINT,
+        SIGKILL, SIGSTOP, SIGTERM, SIGTSTP, SIGNAL_COUNT,
+    },
+    test_panic_handler,
+};
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Info);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+
+    test_main();
+    hlt_loop()
+}
+
+const HANDLER: SignalAction = SignalAction {
+    handler: 0x1000,
+    mask: 1 << SIGTERM,
+    restorer: 0x2000,
+};
+
+#[test_case]
+fn default_actions_terminate_or_ignore() {
+    let mut signals = Signals::new();
+    assert!(signals.send(SIGINT) == Ok(true));
+    assert!(signals.send(SIGKILL) == Ok(true));
+    assert!(signals.send(SIGCHLD) == Ok(false));
+    assert!(signals.take_pending().is_none());
+    assert!(exit_status(SIGKILL) == 137);
+}
+
+#[test_case]
+fn invalid_signals_are_rejected() {
+    let mut signals = Signals::new();
+    assert!(signals.send(0).is_err());
+    assert!(signals.send(SIGNAL_COUNT).is_err());
+    assert!(signals.action(SIGNAL_COUNT).is_err());
+}
+
+#[test_case]
+fn handled_signals_are_pending_in_order() {
+    let mut signals = Signals::new();
+    signals.set_action(SIGTERM, HANDLER).unwrap();
+    signals.set_action(SIGINT, HANDLER).unwrap();
+    assert!(signals.send(SIGTERM) == Ok(false));
+    assert!(signals.send(SIGINT) == Ok(false));
+
+    assert!(signals.take_pending() == Some((SIGINT, Delivery::Handle(HANDLER))));
+    assert!(signals.take_pending() == Some((SIGTERM, Delivery::Handle(HANDLER))));
+    assert!(signals.take_pending().is_none());
+}
+
+#[test_case]
+fn sigkill_can_not_be_handled_or_blocked() {
+    let mut signals = Signals::new();
+    assert!(signals.set_action(SIGKILL, HANDLER).is_err());
+    signals.change_blocked(mask::SET, u64::MAX).unwrap();
+    assert!(signals.blocked() & (1 << SIGKILL) == 0);
+    assert!(signals.send(SIGKILL) == Ok(true));
+}
+
+#[test_case]
+fn a_handler_needs_a_restorer() {
+    let mut signals = Signals::new();
+    let action = SignalAction {
+        restorer: 0,
+        ..HANDLER
+    };
+    assert!(signals.set_action(SIGINT, action).is_err());
+
+    let ignore = SignalAction {
+        handler: IGNORE_HANDLER,
+        ..Default::default()
+    };
+    assert!(signals.set_action(SIGINT, ignore) == Ok(SignalAction::default()));
+    assert!(signals.send(SIGINT) == Ok(false));
+    assert!(signals.take_pending().is_none());
+}
+
+#[test_case]
+fn blocked_signals_are_delivered_once_unblocked() {
+    let mut signals = Signals::new();
+    signals.change_blocked(mask::BLOCK, 1 << SIGTERM).unwrap();
+    // a blocked signal doesn't terminate the process while it's blocked
+    assert!(signals.send(SIGTERM) == Ok(false));
+    assert!(signals.take_pending().is_none());
+
+    assert!(signals.change_blocked(mask::UNBLOCK, 1 << SIGTERM) == Ok(1 << SIGTERM));
+    assert!(signals.take_pending() == Some((SIGTERM, Delivery::Terminate)));
+}
+
+#[test_case]
+fn a_handler_blocks_its_signal_and_mask() {
+    let mut signals = Signals::new();
+    let previous = signals.block_for_handler(SIGINT, &HANDLER);
+    assert!(previous == 0);
+    assert!(signals.blocked() == (1 << SIGINT) | (1 << SIGTERM));
+    signals.set_blocked(previous);
+    assert!(signals.blocked() == 0);
+}
+
+#[test_case]
+fn alarms_make_sigalrm_pending() {
+    let mut signals = Signals::new();
+    assert!(signals.set_alarm(Some(100)).is_none());
+    assert!(!signals.expire_alarm(99));
+    assert!(signals.take_pending().is_none());
+
+    assert!(signals.expire_alarm(100));
+    assert!(signals.take_pending() == Some((SIGALRM, Delivery::Terminate)));
+    assert!(signals.set_alarm(None).is_none());
+}
+
+#[test_case]
+fn continuing_discards_pending_stops() {
+    let mut signals = Signals::new();
+    assert!(signals.send(SIGTSTP) == Ok(false));
+    assert!(signals.has_deliverable());
+    assert!(signals.send(SIGCONT) == Ok(false));
+    assert!(!signals.has_deliverable());
+
+    assert!(signals.send(SIGSTOP) == Ok(false));
+    assert!(signals.take_pending() == Some((SIGSTOP, Delivery::Stop)));
+}
+
+#[test_case]
+fn sigstop_can_not_be_handled_or_blocked() {
+    let mut signals = Signals::new();
+    assert!(signals.set_action(SIGSTOP, HANDLER).is_err());
+    signals.set_action(SIGTSTP, HANDLER).unwrap();
+    signals.change_blocked(mask::SET, u64::MAX).unwrap();
+    assert!(signals.blocked() & (1 << SIGSTOP) == 0);
+
+    // a blocked SIGTSTP isn't delivered
+    assert!(signals.send(SIGTSTP) == Ok(false));
+    assert!(signals.send(SIGSTOP) == Ok(false));
+    assert!(signals.take_pending() == Some((SIGSTOP, Delivery::Stop)));
+    assert!(!signals.has_deliverable());
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
@@ -0,0 +1,120 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use bootloader::{entry_point, BootInfo};
+use core::{panic::PanicInfo, ptr};
+use x86_64::instructions::interrupts as cpu_interrupts;
+
+use CrabOS::{
+    acpi, code_addr, hlt_loop,
+    interrupts::{self, apic, gdt, idt},
+    log::{self, info, LevelFilter},
+    memory::{self, get_linear_addr, kfree, kmalloc, kmap, paging::EntryFlags, types::PAGE_SIZE},
+    processes::{get_process_info, kill_process, objects::ProcessState, spawn_process},
+    smp, test_panic_handler,
+    tests::{wait_until, TEST_PAGE},
+};
+
+/// QEMU runs the tests with `-smp 4`
+const PROCESSORS: usize = 4;
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Info);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+    interrupts::init_controllers();
+    smp::init();
+    cpu_interrupts::enable();
+
+    test_main();
+    hlt_loop()
+}
+
+fn spin() -> ! {
+    loop {}
+}
+
+#[test_case]
+fn application_processors_online() {
+    let madt = acpi::madt().unwrap();
+    let enabled = madt.processors.iter().filter(|processor| processor.enabled).count();
+
+    assert!(enabled == PROCESSORS);
+    assert!(smp::cpu_count() == PROCESSORS);
+}
+
+#[test_case]
+fn per_cpu_data() {
+    let current = smp::current();
+    assert!(current.id == 0);
+    assert!(current.apic_id() == apic::id());
+
+    let mut apic_ids = Vec::new();
+    for id in 0..smp::cpu_count() {
+        let cpu = smp::get_cpu(id).unwrap();
+        assert!(cpu.id == id);
+        assert!(!apic_ids.contains(&cpu.apic_id()));
+        apic_ids.push(cpu.apic_id());
+    }
+}
+
+#[test_case]
+fn tlb_shootdown() {
+    let first = kmalloc(PAGE_SIZE, PAGE_SIZE).unwrap();
+    let second = kmalloc(PAGE_SIZE, PAGE_SIZE).unwrap();
+    unsafe {
+        ptr::write_volatile(get_linear_addr(first) as *mut u64, 1);
+        ptr::write_volatile(get_linear_addr(second) as *mut u64, 2);
+
+        kmap(TEST_PAGE, first, EntryFlags::PRESENT | EntryFlags::WRITABLE).unwrap();
+        assert!(ptr::read_volatile(TEST_PAGE as *const u64) == 1);
+        // remapping a present page waits until every processor flushed it
+        kmap(TEST_PAGE, second, EntryFlags::PRESENT | EntryFlags::WRITABLE).unwrap();
+        assert!(ptr::read_volatile(TEST_PAGE as *const u64) == 2);
+    }
+
+    kfree(first, PAGE_SIZE, PAGE_SIZE);
+    kfree(second, PAGE_SIZE, PAGE_SIZE);
+}
+
+#[test_case]
+fn processes_are_balanced_between_processors() {
+    // killing pid 0 shuts down the machine, so it's never executed
+    spawn_process(code_addr!(spin));
+    let pids: Vec<usize> = (1..PROCESSORS).map(|_| spawn_process(code_addr!(spin))).collect();
+
+    // the bootstrap processor runs the test, the idle application processors steal the processes from it's queue
+    for pid in pids.iter() {
+        smp::enqueue(*pid);
+    }
+    assert!(wait_until(|| pids
+        .iter()
+        .all(|pid| get_process_info(*pid).unwrap().state == ProcessState::Active)));
+
+    let mut running: Vec<usize> = (1..smp::cpu_count())
+        .filter_map(|id| smp::get_cpu(id).unwrap().current_pid())
+        .collect();
+    running.sort();
+    info!("application processors run {:?}", running);
+    assert!(running == pids);
+
+    // the processors switch from the killed processes when they get the reschedule IPI
+    for pid in pids.iter() {
+        kill_process(*pid).unwrap();
+    }
+    assert!(wait_until(|| pids.iter().all(|pid| get_process_info(*pid).is_none())));
+    assert!((1..smp::cpu_count()).all(|id| smp::get_cpu(id).unwrap().current_pid().is_none()));
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
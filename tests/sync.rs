@@ -0,0 +1,122 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use x86_64::instructions::interrupts as cpu_interrupts;
+
+use CrabOS::{
+    code_addr, hlt_loop,
+    interrupts::{self, gdt, idt},
+    log::{self, LevelFilter},
+    memory,
+    processes::scheduler::Scheduler,
+    sync::{Mutex, Semaphore, SpinLock, WaitQueue},
+    test_panic_handler,
+    userland::programs::hello,
+};
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Info);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+    interrupts::init_controllers();
+
+    test_main();
+    hlt_loop()
+}
+
+#[test_case]
+fn spinlocks_disable_interrupts_while_held() {
+    let lock = SpinLock::new(0);
+    cpu_interrupts::enable();
+    {
+        let mut guard = lock.lock();
+        *guard += 1;
+        assert!(!cpu_interrupts::are_enabled());
+        assert!(lock.try_lock().is_none());
+    }
+    assert!(cpu_interrupts::are_enabled());
+
+    // disabled interrupts stay disabled
+    cpu_interrupts::disable();
+    assert!(*lock.lock() == 1);
+    assert!(!cpu_interrupts::are_enabled());
+}
+
+#[test_case]
+fn mutexes_are_unlocked_by_their_guard() {
+    let mutex = Mutex::new(0);
+    {
+        let mut guard = mutex.lock().unwrap();
+        *guard += 1;
+        assert!(mutex.is_locked());
+        assert!(mutex.try_lock().is_none());
+    }
+    assert!(!mutex.is_locked());
+    assert!(*mutex.try_lock().unwrap() == 1);
+}
+
+#[test_case]
+fn semaphores_count_permits() {
+    let semaphore = Semaphore::new(2);
+    semaphore.acquire().unwrap();
+    assert!(semaphore.try_acquire());
+    assert!(!semaphore.try_acquire());
+
+    semaphore.release();
+    assert!(semaphore.permits() == 1);
+    semaphore.acquire().unwrap();
+    assert!(semaphore.permits() == 0);
+}
+
+#[test_case]
+fn only_processes_wait_on_queues() {
+    let queue = WaitQueue::new();
+    assert!(queue.register().is_err());
+    assert!(!queue.wake_one());
+    assert!(queue.wake_all() == 0);
+}
+
+#[test_case]
+fn sleepers_are_woken_once() {
+    let mut scheduler = Scheduler::empty();
+    let pid = scheduler.push_process(code_addr!(hello), None);
+
+    // only a running process sleeps
+    assert!(scheduler.prepare_sleep(pid).is_err());
+    scheduler.prepare_process(pid).unwrap();
+    let wait = scheduler.prepare_sleep(pid).unwrap();
+    assert!(scheduler.get_process(pid).unwrap().sleeping == Some(wait));
+
+    // a sleeper woken before it blocks isn't blocked once it's syscall returns
+    assert!(scheduler.wake_sleeper(pid, wait, None) == Ok(false));
+    assert!(scheduler.get_process(pid).unwrap().sleeping.is_none());
+    assert!(scheduler.wake_sleeper(pid, wait, None).is_err());
+    scheduler.remove_process(pid).unwrap();
+}
+
+#[test_case]
+fn earlier_waits_do_not_wake_sleepers() {
+    let mut scheduler = Scheduler::empty();
+    let pid = scheduler.push_process(code_addr!(hello), None);
+    scheduler.prepare_process(pid).unwrap();
+
+    // the waker of an interrupted wait is left queued, it doesn't wake the next wait
+    let interrupted = scheduler.prepare_sleep(pid).unwrap();
+    assert!(scheduler.interrupt_sleep(pid) == Ok(false));
+    let wait = scheduler.prepare_sleep(pid).unwrap();
+    assert!(scheduler.wake_sleeper(pid, interrupted, None).is_err());
+    assert!(scheduler.wake_sleeper(pid, wait, None) == Ok(false));
+    scheduler.remove_process(pid).unwrap();
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
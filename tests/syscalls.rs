@@ -0,0 +1,148 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use bootloader::{entry_point, BootInfo};
+use core::{
+    panic::PanicInfo,
+    sync::atomic::{AtomicI64, AtomicU64, Ordering},
+};
+use x86_64::instructions::interrupts as cpu_interrupts;
+
+use CrabOS::{
+    code_addr,
+    fs::descriptors::{STDIN, STDOUT},
+    hlt_loop,
+    interrupts::{self, gdt, idt},
+    log::{self, LevelFilter},
+    memory,
+    processes::{kill_process, spawn_process},
+    smp, syscall,
+    syscalls::{
+        futex,
+        number::{FUTEX, READ, WRITE},
+        status,
+    },
+    test_panic_handler,
+    tests::{map_test_page, wait_until, TEST_PAGE},
+    time,
+};
+
+const KERNEL_BUFFER_SIZE: usize = 64;
+/// A status no syscall returns
+const PENDING: i64 = i64::MIN;
+/// A syscall number the kernel doesn't implement
+const UNKNOWN_SYSCALL: u64 = 0xC4AB;
+
+/// The memory of the test page
+#[repr(C)]
+struct Shared {
+    /// A kernel heap buffer the process passes to syscalls
+    kernel_buffer: AtomicU64,
+    kernel_read: AtomicI64,
+    kernel_write: AtomicI64,
+    kernel_futex: AtomicI64,
+    stack_write: AtomicI64,
+    /// A zero length WRITE of a null buffer
+    empty_write: AtomicI64,
+    unknown: AtomicI64,
+}
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Info);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+    interrupts::init_controllers();
+    time::init();
+    smp::init();
+    cpu_interrupts::enable();
+
+    map_test_page();
+    // killing pid 0 shuts down the machine, so it's never executed
+    spawn_process(code_addr!(spin));
+
+    test_main();
+    hlt_loop()
+}
+
+fn shared() -> &'static Shared {
+    unsafe { &*(TEST_PAGE as *const Shared) }
+}
+
+fn spin() -> ! {
+    loop {}
+}
+
+/// Passes the kernel buffer to READ, WRITE & FUTEX, a buffer of it's own stack and an empty buffer to WRITE
+fn prober() -> ! {
+    let shared = shared();
+    let kernel_buffer = shared.kernel_buffer.load(Ordering::SeqCst);
+    let stack_buffer = *b"probed\n";
+    unsafe {
+        let status = syscall!(READ, STDIN, kernel_buffer, KERNEL_BUFFER_SIZE);
+        shared.kernel_read.store(status, Ordering::SeqCst);
+        let status = syscall!(WRITE, STDOUT, kernel_buffer, KERNEL_BUFFER_SIZE);
+        shared.kernel_write.store(status, Ordering::SeqCst);
+        let status = syscall!(FUTEX, kernel_buffer, futex::WAKE, 1);
+        shared.kernel_futex.store(status, Ordering::SeqCst);
+        let status = syscall!(WRITE, STDOUT, 0, 0);
+        shared.empty_write.store(status, Ordering::SeqCst);
+        let status = syscall!(WRITE, STDOUT, stack_buffer.as_ptr() as u64, stack_buffer.len());
+        shared.stack_write.store(status, Ordering::SeqCst);
+    }
+    spin()
+}
+
+fn unknown_caller() -> ! {
+    let status = unsafe { syscall!(UNKNOWN_SYSCALL) };
+    shared().unknown.store(status, Ordering::SeqCst);
+    spin()
+}
+
+#[test_case]
+fn kernel_buffers_are_rejected() {
+    let kernel_buffer = Box::new([0xC4u8; KERNEL_BUFFER_SIZE]);
+    let shared = shared();
+    shared.kernel_buffer.store(kernel_buffer.as_ptr() as u64, Ordering::SeqCst);
+    let statuses = [&shared.kernel_read, &shared.kernel_write, &shared.kernel_futex, &shared.empty_write, &shared.stack_write];
+    for status in statuses {
+        status.store(PENDING, Ordering::SeqCst);
+    }
+
+    let pid = spawn_process(code_addr!(prober));
+    smp::enqueue(pid);
+    assert!(wait_until(|| shared.stack_write.load(Ordering::SeqCst) != PENDING));
+    assert!(shared.kernel_read.load(Ordering::SeqCst) == status::FAILURE);
+    assert!(shared.kernel_write.load(Ordering::SeqCst) == status::FAILURE);
+    assert!(shared.kernel_futex.load(Ordering::SeqCst) == status::FAILURE);
+    // the process' own memory is accepted, and so is an empty buffer wherever it is
+    assert!(shared.stack_write.load(Ordering::SeqCst) == 7);
+    assert!(shared.empty_write.load(Ordering::SeqCst) == 0);
+    // the kernel buffer is untouched
+    assert!(kernel_buffer.iter().all(|byte| *byte == 0xC4));
+    kill_process(pid).unwrap();
+}
+
+#[test_case]
+fn unknown_syscalls_fail() {
+    let shared = shared();
+    shared.unknown.store(PENDING, Ordering::SeqCst);
+    let pid = spawn_process(code_addr!(unknown_caller));
+    smp::enqueue(pid);
+
+    assert!(wait_until(|| shared.unknown.load(Ordering::SeqCst) != PENDING));
+    assert!(shared.unknown.load(Ordering::SeqCst) == status::FAILURE);
+    kill_process(pid).unwrap();
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
@@ -0,0 +1,192 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+use x86_64::instructions::interrupts as cpu_interrupts;
+
+use CrabOS::{
+    code_addr,
+    drivers::{pit, rtc},
+    hlt_loop,
+    interrupts::{self, gdt, idt},
+    log::{self, info, LevelFilter},
+    memory,
+    processes::{get_process_info, kill_process, objects::ProcessState, spawn_process},
+    smp, syscall,
+    syscalls::number::{ALARM, NANOSLEEP, PIPE, READ},
+    test_panic_handler,
+    tests::wait_until,
+    time::{
+        self,
+        timer_wheel::{TimerWheel, TICK_NANOSECONDS},
+        Timespec, CLOCK_MONOTONIC, CLOCK_REALTIME,
+    },
+};
+
+const NANOSECONDS_PER_MILLISECOND: u64 = 1_000_000;
+const SLEEP_MILLISECONDS: u64 = 100;
+/// 2023-01-01 00:00:00 UTC
+const RECENT_UNIX_SECONDS: u64 = 1_672_531_200;
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Info);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+    interrupts::init_controllers();
+    time::init();
+    smp::init();
+    cpu_interrupts::enable();
+
+    test_main();
+    hlt_loop()
+}
+
+fn spin() -> ! {
+    loop {}
+}
+
+fn sleeper() -> ! {
+    let duration = Timespec::from_nanoseconds(SLEEP_MILLISECONDS * NANOSECONDS_PER_MILLISECOND);
+    unsafe { syscall!(NANOSLEEP, &duration as *const Timespec as u64) };
+    loop {}
+}
+
+fn alarmed_reader() -> ! {
+    let mut fds = [0usize; 2];
+    let mut byte = 0u8;
+    unsafe {
+        syscall!(PIPE, fds.as_mut_ptr() as u64);
+        syscall!(ALARM, 1);
+        // the write end stays open, so the read sleeps until SIGALRM interrupts it and terminates the process
+        syscall!(READ, fds[0] as u64, &mut byte as *mut u8 as u64, 1);
+    }
+    loop {}
+}
+
+#[test_case]
+fn uptime_advances_with_the_pit() {
+    let start = time::uptime_nanoseconds();
+    pit::wait_microseconds(50_000);
+    let elapsed = (time::uptime_nanoseconds() - start) / NANOSECONDS_PER_MILLISECOND;
+
+    info!("waiting 50ms took {}ms of uptime", elapsed);
+    assert!((45..=60).contains(&elapsed));
+}
+
+#[test_case]
+fn monotonic_clock_never_goes_back() {
+    let mut previous = time::clock_time(CLOCK_MONOTONIC).unwrap();
+    for _ in 0..1000 {
+        let now = time::clock_time(CLOCK_MONOTONIC).unwrap();
+        assert!(now.as_nanoseconds().unwrap() >= previous.as_nanoseconds().unwrap());
+        previous = now;
+    }
+    assert!(time::clock_time(2).is_err());
+}
+
+#[test_case]
+fn wall_clock_is_recent() {
+    let now = rtc::read();
+    info!("RTC time is {:?}", now);
+    assert!(now.year >= 2023);
+    assert!((1..=12).contains(&now.month) && (1..=31).contains(&now.day));
+    assert!(now.hour < 24 && now.minute < 60 && now.second < 60);
+
+    assert!(time::clock_time(CLOCK_REALTIME).unwrap().seconds >= RECENT_UNIX_SECONDS);
+}
+
+#[test_case]
+fn unix_time_conversion() {
+    let epoch = rtc::DateTime { year: 1970, month: 1, day: 1, hour: 0, minute: 0, second: 0 };
+    let leap_day = rtc::DateTime { year: 2000, month: 2, day: 29, hour: 12, minute: 30, second: 15 };
+
+    assert!(epoch.unix_seconds() == 0);
+    assert!(leap_day.unix_seconds() == 951_827_415);
+}
+
+/// Advances `wheel` to `now`, returns the processes whose timers expired
+fn expire(wheel: &mut TimerWheel, now: u64) -> Vec<usize> {
+    let mut expired = Vec::new();
+    wheel.expire(now, |pid| {
+        expired.push(pid);
+        true
+    });
+    expired
+}
+
+#[test_case]
+fn timer_wheel_expires_deadlines() {
+    let mut wheel = TimerWheel::new();
+    wheel.insert(5 * TICK_NANOSECONDS / 2, 1);
+    // a deadline a full rotation ahead shares the slot, but doesn't expire with it
+    wheel.insert(259 * TICK_NANOSECONDS, 2);
+
+    assert!(expire(&mut wheel, 2 * TICK_NANOSECONDS).is_empty());
+    assert!(expire(&mut wheel, 3 * TICK_NANOSECONDS) == [1]);
+    assert!(expire(&mut wheel, 258 * TICK_NANOSECONDS).is_empty());
+    // a deadline that passed expires at the next tick, and skipped ticks aren't missed
+    wheel.insert(0, 3);
+    assert!(expire(&mut wheel, 1000 * TICK_NANOSECONDS) == [2, 3]);
+}
+
+#[test_case]
+fn refused_timers_expire_at_the_next_advance() {
+    let mut wheel = TimerWheel::new();
+    wheel.insert(TICK_NANOSECONDS, 1);
+    wheel.insert(2 * TICK_NANOSECONDS, 2);
+    wheel.insert(2 * TICK_NANOSECONDS, 3);
+
+    // the wheel stops at the tick of the refused timer
+    let mut expired = Vec::new();
+    wheel.expire(3 * TICK_NANOSECONDS, |pid| {
+        if pid == 3 {
+            return false;
+        }
+        expired.push(pid);
+        true
+    });
+    assert!(expired == [1, 2]);
+    assert!(expire(&mut wheel, 3 * TICK_NANOSECONDS) == [3]);
+}
+
+#[test_case]
+fn sleeping_process_is_woken_at_it_s_deadline() {
+    // killing pid 0 shuts down the machine, so it's never executed
+    spawn_process(code_addr!(spin));
+    let pid = spawn_process(code_addr!(sleeper));
+    let start = time::uptime_nanoseconds();
+    smp::enqueue(pid);
+
+    let state = || get_process_info(pid).unwrap().state;
+    assert!(wait_until(|| state() == ProcessState::Blocked));
+    assert!(wait_until(|| state() != ProcessState::Blocked));
+    let elapsed = (time::uptime_nanoseconds() - start) / NANOSECONDS_PER_MILLISECOND;
+
+    info!("the process slept {}ms", elapsed);
+    assert!(elapsed >= SLEEP_MILLISECONDS);
+    kill_process(pid).unwrap();
+}
+
+#[test_case]
+fn alarms_interrupt_sleeping_processes() {
+    let pid = spawn_process(code_addr!(alarmed_reader));
+    smp::enqueue(pid);
+
+    assert!(wait_until(|| matches!(get_process_info(pid), Some(data) if data.state == ProcessState::Blocked)));
+    // the alarm is a second away
+    assert!(wait_until(|| get_process_info(pid).is_none()));
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
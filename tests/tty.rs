@@ -0,0 +1,145 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use bootloader::{entry_point, BootInfo};
+use core::{cell::RefCell, panic::PanicInfo};
+
+use CrabOS::{
+    fs::{
+        devfs::DEVICES,
+        tty::{LineDiscipline, Termios, TtyMode, TtyRequest},
+        vfs::FileSystem,
+        File,
+    },
+    hlt_loop,
+    interrupts::{gdt, idt},
+    log::{self, LevelFilter},
+    memory, test_panic_handler,
+};
+
+entry_point!(main);
+fn main(boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Info);
+    gdt::init();
+    idt::init();
+    memory::init(boot_info);
+
+    test_main();
+    hlt_loop()
+}
+
+/// Reads from the discipline while typing `typed`, returns the read bytes and the echo
+fn read(discipline: &LineDiscipline, typed: &[u8], length: usize) -> (Vec<u8>, Vec<u8>) {
+    let mut typed = typed.iter().copied();
+    let echo = RefCell::new(Vec::new());
+    let mut buffer = [0u8; 64];
+
+    let count = discipline.read(
+        &mut buffer[..length],
+        || typed.next().expect("the read waits for more input"),
+        |bytes| echo.borrow_mut().extend_from_slice(bytes),
+    )
+    .unwrap();
+    (Vec::from(&buffer[..count]), echo.into_inner())
+}
+
+#[test_case]
+fn canonical_line_editing() {
+    let discipline = LineDiscipline::new();
+
+    // backspace, kill line (Ctrl + U) and word erase (Ctrl + W)
+    let (line, echo) = read(&discipline, b"ab\x08c\x15xy z\x17w\n", 64);
+    assert!(line == b"xy w\n");
+    assert!(echo == b"ab\x08 \x08c\x08 \x08\x08 \x08xy z\x08 \x08w\n");
+}
+
+#[test_case]
+fn reads_return_a_single_line() {
+    let discipline = LineDiscipline::new();
+
+    assert!(read(&discipline, b"abc\n", 2).0 == b"ab");
+    // the rest of the line is read without typing
+    assert!(read(&discipline, b"", 64).0 == b"c\n");
+}
+
+#[test_case]
+fn end_of_file() {
+    let discipline = LineDiscipline::new();
+
+    // Ctrl + D ends a line without a new line, and ends the input on an empty line
+    assert!(read(&discipline, b"ab\x04", 64).0 == b"ab");
+    assert!(read(&discipline, b"\x04", 64).0.is_empty());
+}
+
+#[test_case]
+fn echo_off() {
+    let discipline = LineDiscipline::new();
+    let mut settings = discipline.settings();
+    settings.mode = TtyMode::CANONICAL.bits();
+    discipline.set_settings(settings).unwrap();
+
+    let (line, echo) = read(&discipline, b"secret\n", 64);
+    assert!(line == b"secret\n");
+    assert!(echo.is_empty());
+}
+
+#[test_case]
+fn raw_mode() {
+    let discipline = LineDiscipline::new();
+    let mut settings = discipline.settings();
+    settings.mode = TtyMode::empty().bits();
+    discipline.set_settings(settings).unwrap();
+
+    // every byte is read as typed, including the control characters
+    assert!(read(&discipline, b"a", 64).0 == b"a");
+    assert!(read(&discipline, b"\x08", 64).0 == b"\x08");
+    assert!(read(&discipline, b"\x03", 64).0 == b"\x03");
+}
+
+#[test_case]
+fn interrupt_discards_the_line() {
+    let discipline = LineDiscipline::new();
+
+    // without a foreground process group nothing is signaled
+    let (line, echo) = read(&discipline, b"ab\x03cd\n", 64);
+    assert!(line == b"cd\n");
+    assert!(echo == b"ab^C\ncd\n");
+
+    let (line, echo) = read(&discipline, b"ab\x1acd\n", 64);
+    assert!(line == b"cd\n");
+    assert!(echo == b"ab^Z\ncd\n");
+}
+
+#[test_case]
+fn terminal_requests() {
+    let root = DEVICES.root();
+    let inode = DEVICES.lookup(root, "tty2").unwrap();
+    let terminal = DEVICES.device(inode).unwrap();
+
+    let mut settings = Termios::default();
+    terminal.ioctl(TtyRequest::GetSettings(&mut settings)).unwrap();
+    assert!(settings == Termios::canonical());
+
+    settings.mode = u64::MAX;
+    assert!(terminal.ioctl(TtyRequest::SetSettings(&settings)).is_err());
+
+    assert!(terminal.ioctl(TtyRequest::GetForeground).is_err());
+    terminal.ioctl(TtyRequest::SetForeground { session: 1, group: Some(3) }).unwrap();
+    assert!(terminal.ioctl(TtyRequest::GetForeground) == Ok(3));
+    // the terminal belongs to the first session that set it's foreground
+    assert!(terminal.ioctl(TtyRequest::SetForeground { session: 2, group: Some(4) }).is_err());
+    assert!(terminal.ioctl(TtyRequest::GetForeground) == Ok(3));
+    terminal.ioctl(TtyRequest::SetForeground { session: 1, group: None }).unwrap();
+    assert!(terminal.ioctl(TtyRequest::GetForeground).is_err());
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
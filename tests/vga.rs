@@ -0,0 +1,115 @@
+#![no_std]
+#![no_main]
+#![feature(custom_test_frameworks)]
+#![test_runner(CrabOS::tests::runner)]
+#![reexport_test_harness_main = "test_main"]
+
+use bootloader::{entry_point, BootInfo};
+use core::panic::PanicInfo;
+
+use CrabOS::{
+    drivers::vga::{self, Color, TERMINALS},
+    graphic_print, graphic_println, hlt_loop,
+    log::{self, LevelFilter},
+    test_panic_handler,
+};
+
+const HEIGHT: usize = 25;
+const RED_ON_BLACK: u8 = Color::Red as u8;
+
+entry_point!(main);
+fn main(_boot_info: &'static BootInfo) -> ! {
+    log::init(LevelFilter::Info);
+
+    test_main();
+    hlt_loop()
+}
+
+/// Returns the characters of a screen row
+fn row_text(row: usize) -> [u8; 80] {
+    let mut text = [0; 80];
+    for (column, character) in text.iter_mut().enumerate() {
+        *character = vga::read_screen(row, column).unwrap().0;
+    }
+    text
+}
+
+#[test_case]
+fn screen_scrolls_past_the_last_row() {
+    vga::clear_screen();
+    for line in 0..HEIGHT * 2 {
+        graphic_println!("line {}", line);
+    }
+
+    // the last line is above the cursor's empty row
+    assert!(row_text(HEIGHT - 2).starts_with(b"line 49 "));
+    assert!(row_text(0).starts_with(b"line 26 "));
+    assert!(TERMINALS[0].lock().cursor_position() == (HEIGHT - 1, 0));
+}
+
+#[test_case]
+fn scrollback_shows_previous_lines() {
+    vga::clear_screen();
+    for line in 0..HEIGHT * 2 {
+        graphic_println!("line {}", line);
+    }
+
+    TERMINALS[0].lock().scroll_view(HEIGHT as isize);
+    assert!(row_text(0).starts_with(b"line 1 "));
+    // writing returns to the screen
+    graphic_print!(" ");
+    assert!(row_text(0).starts_with(b"line 26 "));
+}
+
+#[test_case]
+fn control_characters() {
+    vga::clear_screen();
+    graphic_print!("abc\x08\x08X\rY\tZ");
+
+    assert!(row_text(0).starts_with(b"YXc     Z "));
+    assert!(TERMINALS[0].lock().cursor_position() == (0, 9));
+}
+
+#[test_case]
+fn ansi_colors_and_cursor_movement() {
+    vga::clear_screen();
+    graphic_print!("\x1b[32;44mA\x1b[0mB\x1b[3;5HC\x1b[1AD");
+
+    assert!(vga::read_screen(0, 0).unwrap() == (b'A', (Color::Blue as u8) << 4 | Color::Green as u8));
+    assert!(vga::read_screen(0, 1).unwrap() == (b'B', RED_ON_BLACK));
+    assert!(vga::read_screen(2, 4).unwrap().0 == b'C');
+    assert!(vga::read_screen(1, 5).unwrap().0 == b'D');
+}
+
+#[test_case]
+fn ansi_erase() {
+    vga::clear_screen();
+    graphic_print!("first\nsecond\x1b[3D\x1b[K");
+    assert!(row_text(1).starts_with(b"sec "));
+
+    graphic_print!("\x1b[2J");
+    assert!(row_text(0).iter().all(|character| *character == b' '));
+}
+
+#[test_case]
+fn switching_terminals_restores_their_screens() {
+    vga::clear_screen();
+    graphic_print!("first terminal");
+    vga::_write_bytes(1, b"\x1b[2Jsecond terminal");
+    // an inactive terminal doesn't draw
+    assert!(row_text(0).starts_with(b"first terminal "));
+
+    vga::switch_terminal(1).unwrap();
+    assert!(row_text(0).starts_with(b"second terminal "));
+    assert!(TERMINALS[1].lock().cursor_position() == (0, 15));
+
+    vga::switch_terminal(0).unwrap();
+    assert!(row_text(0).starts_with(b"first terminal "));
+    assert!(vga::read_screen(0, 0).unwrap().1 == RED_ON_BLACK);
+    assert!(vga::switch_terminal(vga::TERMINAL_COUNT).is_err());
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    test_panic_handler(info)
+}
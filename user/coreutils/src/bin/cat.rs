@@ -0,0 +1,51 @@
+//! Concatenates files to the standard output, without files the standard input is copied
+
+#![no_std]
+#![no_main]
+
+use libcrab::{
+    entry_point, eprintln,
+    syscalls::{close, open, open_flags, read, write, STDIN, STDOUT},
+    Args, Result,
+};
+
+entry_point!(main);
+
+fn main(args: Args) -> u8 {
+    if args.len() <= 1 {
+        return copy(STDIN).map_or(1, |()| 0);
+    }
+
+    let mut status = 0;
+    for path in args.skip_name() {
+        match open(path, open_flags::READ) {
+            Ok(fd) => {
+                if copy(fd).is_err() {
+                    eprintln!("cat: {}: read failed", path);
+                    status = 1;
+                }
+                close(fd).ok();
+            }
+            Err(_) => {
+                eprintln!("cat: {}: no such file", path);
+                status = 1;
+            }
+        }
+    }
+    status
+}
+
+/// Copies the file to the standard output until it ends
+fn copy(fd: usize) -> Result<()> {
+    let mut buffer = [0u8; 512];
+    loop {
+        let length = read(fd, &mut buffer)?;
+        if length == 0 {
+            return Ok(());
+        }
+        let mut written = 0;
+        while written < length {
+            written += write(STDOUT, &buffer[written..length])?;
+        }
+    }
+}
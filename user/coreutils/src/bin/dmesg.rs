@@ -0,0 +1,32 @@
+//! Prints the kernel log
+
+#![no_std]
+#![no_main]
+
+use libcrab::{
+    entry_point, eprintln,
+    syscalls::{read_log, write, STDOUT},
+    Args,
+};
+
+entry_point!(main);
+
+fn main(_args: Args) -> u8 {
+    let mut buffer = [0u8; 512];
+    let mut offset = 0;
+    loop {
+        match read_log(offset, &mut buffer) {
+            Ok(0) => return 0,
+            Ok(length) => {
+                if write(STDOUT, &buffer[..length]).is_err() {
+                    return 1;
+                }
+                offset += length;
+            }
+            Err(_) => {
+                eprintln!("dmesg: cannot read the kernel log");
+                return 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! Prints it's arguments separated by spaces
+
+#![no_std]
+#![no_main]
+
+use libcrab::{entry_point, print, println, Args};
+
+entry_point!(main);
+
+fn main(args: Args) -> u8 {
+    for (index, argument) in args.skip_name().enumerate() {
+        if index > 0 {
+            print!(" ");
+        }
+        print!("{}", argument);
+    }
+    println!();
+    0
+}
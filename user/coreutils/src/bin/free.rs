@@ -0,0 +1,35 @@
+//! Prints the physical memory and the kernel heap usage
+
+#![no_std]
+#![no_main]
+
+use libcrab::{entry_point, eprintln, println, syscalls::memory_info, Args};
+
+entry_point!(main);
+
+fn main(_args: Args) -> u8 {
+    let info = match memory_info() {
+        Ok(info) => info,
+        Err(_) => {
+            eprintln!("free: cannot read the memory usage");
+            return 1;
+        }
+    };
+
+    println!("{:>8} {:>10} {:>10} {:>10}", "", "total KiB", "used KiB", "free KiB");
+    println!(
+        "{:>8} {:>10} {:>10} {:>10}",
+        "memory:",
+        info.total / 1024,
+        (info.total - info.free) / 1024,
+        info.free / 1024
+    );
+    println!(
+        "{:>8} {:>10} {:>10} {:>10}",
+        "heap:",
+        info.heap_size / 1024,
+        info.heap_used / 1024,
+        (info.heap_size - info.heap_used) / 1024
+    );
+    0
+}
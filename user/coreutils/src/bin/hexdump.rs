@@ -0,0 +1,76 @@
+//! Prints files in hexadecimal & ASCII, 16 bytes a line, without files the standard input is printed
+
+#![no_std]
+#![no_main]
+
+use libcrab::{
+    entry_point, eprintln, print, println,
+    syscalls::{close, open, open_flags, read, STDIN},
+    Args, Result,
+};
+
+const LINE_LENGTH: usize = 16;
+
+entry_point!(main);
+
+fn main(args: Args) -> u8 {
+    if args.len() <= 1 {
+        return dump(STDIN).map_or(1, |()| 0);
+    }
+
+    let mut status = 0;
+    for path in args.skip_name() {
+        match open(path, open_flags::READ) {
+            Ok(fd) => {
+                if dump(fd).is_err() {
+                    eprintln!("hexdump: {}: read failed", path);
+                    status = 1;
+                }
+                close(fd).ok();
+            }
+            Err(_) => {
+                eprintln!("hexdump: {}: no such file", path);
+                status = 1;
+            }
+        }
+    }
+    status
+}
+
+/// Prints the file a line at a time, the last line may be shorter
+fn dump(fd: usize) -> Result<()> {
+    let mut line = [0u8; LINE_LENGTH];
+    let mut offset = 0;
+    loop {
+        let mut length = 0;
+        while length < LINE_LENGTH {
+            match read(fd, &mut line[length..])? {
+                0 => break,
+                count => length += count,
+            }
+        }
+        if length == 0 {
+            println!("{:08x}", offset);
+            return Ok(());
+        }
+
+        print!("{:08x} ", offset);
+        for index in 0..LINE_LENGTH {
+            match line[..length].get(index) {
+                Some(byte) => print!(" {:02x}", byte),
+                None => print!("   "),
+            }
+        }
+        print!("  |");
+        for byte in &line[..length] {
+            let character = if byte.is_ascii_graphic() || *byte == b' ' { *byte as char } else { '.' };
+            print!("{}", character);
+        }
+        println!("|");
+        offset += length;
+        if length < LINE_LENGTH {
+            println!("{:08x}", offset);
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Sends a signal to processes, SIGTERM by default. A negative pid signals the process group -pid.
+
+#![no_std]
+#![no_main]
+
+use libcrab::{
+    entry_point, eprintln,
+    signal::{kill, kill_group, SIGALRM, SIGCHLD, SIGCONT, SIGINT, SIGKILL, SIGSEGV, SIGSTOP, SIGTERM, SIGTSTP},
+    Args,
+};
+
+const SIGNAL_NAMES: [(&str, usize); 9] = [
+    ("INT", SIGINT),
+    ("KILL", SIGKILL),
+    ("SEGV", SIGSEGV),
+    ("ALRM", SIGALRM),
+    ("TERM", SIGTERM),
+    ("CHLD", SIGCHLD),
+    ("CONT", SIGCONT),
+    ("STOP", SIGSTOP),
+    ("TSTP", SIGTSTP),
+];
+
+entry_point!(main);
+
+fn main(args: Args) -> u8 {
+    let mut pids = args.skip_name();
+    let signal = match args.get(1).and_then(|flag| flag.strip_prefix('-')) {
+        Some(name) => {
+            pids.next();
+            match parse_signal(name) {
+                Some(signal) => signal,
+                None => {
+                    eprintln!("kill: {}: unknown signal", name);
+                    return 1;
+                }
+            }
+        }
+        None => SIGTERM,
+    };
+    if pids.len() == 0 {
+        eprintln!("usage: kill [-signal] <pid | -pgid>...");
+        return 1;
+    }
+
+    let mut status = 0;
+    for argument in pids {
+        let result = match argument.strip_prefix('-') {
+            Some(pgid) => pgid.parse().map(|pgid| kill_group(pgid, signal)),
+            None => argument.parse().map(|pid| kill(pid, signal)),
+        };
+        match result {
+            Ok(Ok(())) => {}
+            Ok(_) => {
+                eprintln!("kill: {}: no such process", argument);
+                status = 1;
+            }
+            Err(_) => {
+                eprintln!("kill: {}: not a pid", argument);
+                status = 1;
+            }
+        }
+    }
+    status
+}
+
+/// Parses a signal number or name, with or without the "SIG" prefix
+fn parse_signal(name: &str) -> Option<usize> {
+    let name = name.strip_prefix("SIG").unwrap_or(name);
+    name.parse().ok().or_else(|| {
+        SIGNAL_NAMES
+            .iter()
+            .find(|(signal_name, _)| *signal_name == name)
+            .map(|(_, signal)| *signal)
+    })
+}
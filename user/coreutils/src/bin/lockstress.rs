@@ -0,0 +1,129 @@
+//! Checks the locks of libcrab under contention: workers increment a counter in shared memory under a `Mutex`,
+//! and the parent waits on a `Condvar` until every worker is done. Exits with 1 if an increment was lost.
+//!
+//! usage: lockstress [workers] [increments]
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::{string::ToString, vec::Vec};
+use core::{mem, str::FromStr};
+
+use libcrab::{
+    entry_point, eprintln, println,
+    process::{Child, Command},
+    shm::SharedMemory,
+    sync::{Condvar, Mutex},
+    syscalls::{dup2, STDIN},
+    Args,
+};
+
+const PROGRAM: &str = "/bin/lockstress";
+const WORKER: &str = "worker";
+const DEFAULT_WORKERS: usize = 8;
+const DEFAULT_INCREMENTS: u64 = 10_000;
+
+/// The memory of the segment, a zeroed segment holds an unlocked mutex and a condition variable that wasn't notified
+#[repr(C)]
+struct Shared {
+    counter: Mutex<Counter>,
+    finished: Condvar,
+}
+
+struct Counter {
+    value: u64,
+    /// The number of workers that are done
+    finished: usize,
+}
+
+entry_point!(main);
+
+fn main(args: Args) -> u8 {
+    if args.get(1) == Some(WORKER) {
+        return work(args);
+    }
+    let (workers, increments) = match (parse(args.get(1), DEFAULT_WORKERS), parse(args.get(2), DEFAULT_INCREMENTS)) {
+        (Some(workers), Some(increments)) => (workers, increments),
+        _ => {
+            eprintln!("usage: lockstress [workers] [increments]");
+            return 1;
+        }
+    };
+
+    let segment = match SharedMemory::create(mem::size_of::<Shared>()) {
+        Ok(segment) => segment,
+        Err(error) => {
+            eprintln!("lockstress: cannot create the shared memory: {}", error);
+            return 1;
+        }
+    };
+    let mapping = match segment.map() {
+        Ok(mapping) => mapping,
+        Err(error) => {
+            eprintln!("lockstress: cannot map the shared memory: {}", error);
+            return 1;
+        }
+    };
+    let shared = unsafe { &*(mapping.as_ptr() as *const Shared) };
+
+    // the workers inherit the standard streams only, so the segment is passed as their standard input
+    if let Err(error) = dup2(segment.handle(), STDIN) {
+        eprintln!("lockstress: cannot pass the shared memory: {}", error);
+        return 1;
+    }
+    let increments_argument = increments.to_string();
+    let mut children = Vec::new();
+    for _ in 0..workers {
+        match Command::new(PROGRAM).arg(WORKER).arg(&increments_argument).spawn() {
+            Ok(child) => children.push(child),
+            Err(error) => {
+                eprintln!("lockstress: cannot spawn a worker: {}", error);
+                return 1;
+            }
+        }
+    }
+
+    let mut counter = shared.counter.lock();
+    while counter.finished < children.len() {
+        counter = shared.finished.wait(counter);
+    }
+    let value = counter.value;
+    drop(counter);
+    let succeeded = children.into_iter().map(Child::wait).all(|status| matches!(status, Ok(status) if status.success()));
+
+    println!("{} workers incremented the counter to {}", workers, value);
+    if value != workers as u64 * increments || !succeeded {
+        eprintln!("lockstress: expected {}", workers as u64 * increments);
+        return 1;
+    }
+    0
+}
+
+/// Increments the counter of the segment at the standard input, and notifies the parent once it's done
+fn work(args: Args) -> u8 {
+    let increments = match parse(args.get(2), DEFAULT_INCREMENTS) {
+        Some(increments) => increments,
+        None => return 1,
+    };
+    let segment = SharedMemory::from_handle(STDIN, mem::size_of::<Shared>());
+    let mapping = match segment.map() {
+        Ok(mapping) => mapping,
+        Err(_) => return 1,
+    };
+    let shared = unsafe { &*(mapping.as_ptr() as *const Shared) };
+
+    for _ in 0..increments {
+        // the increment isn't atomic, only the mutex keeps increments from being lost
+        shared.counter.lock().value += 1;
+    }
+    shared.counter.lock().finished += 1;
+    shared.finished.notify_one();
+    0
+}
+
+/// Parses an optional argument, returns `default` if there is none
+fn parse<T: FromStr>(argument: Option<&str>, default: T) -> Option<T> {
+    argument.map_or(Some(default), |argument| argument.parse().ok())
+}
@@ -0,0 +1,62 @@
+//! Lists the entries of directories, directories are listed with a trailing slash
+
+#![no_std]
+#![no_main]
+
+use libcrab::{
+    entry_point, eprintln, println,
+    syscalls::{close, get_directory_entries, open, open_flags, stat, DirectoryEntry, FileType},
+    Args,
+};
+
+entry_point!(main);
+
+fn main(args: Args) -> u8 {
+    if args.len() <= 1 {
+        return list(".", false);
+    }
+
+    let titled = args.len() > 2;
+    let mut status = 0;
+    for path in args.skip_name() {
+        status |= list(path, titled);
+    }
+    status
+}
+
+/// Lists a directory, or prints the path of any other file
+fn list(path: &str, titled: bool) -> u8 {
+    match stat(path) {
+        Ok(metadata) if metadata.file_type == FileType::Directory => {}
+        Ok(_) => {
+            println!("{}", path);
+            return 0;
+        }
+        Err(_) => {
+            eprintln!("ls: {}: no such file or directory", path);
+            return 1;
+        }
+    }
+    let fd = match open(path, open_flags::READ) {
+        Ok(fd) => fd,
+        Err(_) => {
+            eprintln!("ls: {}: cannot open", path);
+            return 1;
+        }
+    };
+
+    if titled {
+        println!("{}:", path);
+    }
+    let mut entries = [DirectoryEntry::empty(); 8];
+    while let Ok(count @ 1..) = get_directory_entries(fd, &mut entries) {
+        for entry in &entries[..count] {
+            match entry.file_type {
+                FileType::Directory => println!("{}/", entry.name()),
+                _ => println!("{}", entry.name()),
+            }
+        }
+    }
+    close(fd).ok();
+    0
+}
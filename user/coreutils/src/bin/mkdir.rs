@@ -0,0 +1,24 @@
+//! Creates directories
+
+#![no_std]
+#![no_main]
+
+use libcrab::{entry_point, eprintln, syscalls::make_directory, Args};
+
+entry_point!(main);
+
+fn main(args: Args) -> u8 {
+    if args.len() <= 1 {
+        eprintln!("usage: mkdir <directory>...");
+        return 1;
+    }
+
+    let mut status = 0;
+    for path in args.skip_name() {
+        if make_directory(path).is_err() {
+            eprintln!("mkdir: {}: cannot create the directory", path);
+            status = 1;
+        }
+    }
+    status
+}
@@ -0,0 +1,33 @@
+//! Lists the processes
+
+#![no_std]
+#![no_main]
+
+use libcrab::{
+    entry_point, eprintln, println,
+    syscalls::{list_processes, ProcessInfo},
+    Args,
+};
+
+entry_point!(main);
+
+fn main(_args: Args) -> u8 {
+    let mut processes = [ProcessInfo::empty(); 64];
+    let count = match list_processes(&mut processes) {
+        Ok(count) => count,
+        Err(_) => {
+            eprintln!("ps: cannot list the processes");
+            return 1;
+        }
+    };
+
+    println!("  PID  PPID  PGID   SID STATE");
+    for process in &processes[..count] {
+        let (pgid, sid) = (process.pgid, process.sid);
+        match process.parent() {
+            Some(parent) => println!("{:>5} {:>5} {:>5} {:>5} {:?}", process.pid, parent, pgid, sid, process.state),
+            None => println!("{:>5}     - {:>5} {:>5} {:?}", process.pid, pgid, sid, process.state),
+        }
+    }
+    0
+}
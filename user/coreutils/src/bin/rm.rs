@@ -0,0 +1,24 @@
+//! Removes files and empty directories
+
+#![no_std]
+#![no_main]
+
+use libcrab::{entry_point, eprintln, syscalls::unlink, Args};
+
+entry_point!(main);
+
+fn main(args: Args) -> u8 {
+    if args.len() <= 1 {
+        eprintln!("usage: rm <path>...");
+        return 1;
+    }
+
+    let mut status = 0;
+    for path in args.skip_name() {
+        if unlink(path).is_err() {
+            eprintln!("rm: {}: cannot remove", path);
+            status = 1;
+        }
+    }
+    status
+}
@@ -0,0 +1,30 @@
+//! Prints the time since boot
+
+#![no_std]
+#![no_main]
+
+use libcrab::{
+    entry_point, eprintln, println,
+    syscalls::{clock_get_time, CLOCK_MONOTONIC},
+    Args,
+};
+
+entry_point!(main);
+
+fn main(_args: Args) -> u8 {
+    let uptime = match clock_get_time(CLOCK_MONOTONIC) {
+        Ok(uptime) => uptime.seconds,
+        Err(_) => {
+            eprintln!("uptime: cannot read the clock");
+            return 1;
+        }
+    };
+
+    let (days, hours, minutes, seconds) = (uptime / 86400, uptime / 3600 % 24, uptime / 60 % 60, uptime % 60);
+    if days > 0 {
+        println!("up {} days, {:02}:{:02}:{:02}", days, hours, minutes, seconds);
+    } else {
+        println!("up {:02}:{:02}:{:02}", hours, minutes, seconds);
+    }
+    0
+}
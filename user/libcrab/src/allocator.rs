@@ -0,0 +1,78 @@
+//! The global allocator of userland programs.
+//!
+//! Small allocations are taken from a heap which grows by moving the program break (BRK),
+//! large allocations are mapped to their own pages (MMAP) and unmapped once they are freed.
+
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    ptr,
+};
+use linked_list_allocator::LockedHeap;
+
+use crate::syscalls::{change_break, map_memory, unmap_memory};
+
+const PAGE_SIZE: usize = 0x1000;
+/// Allocations of at least this size are mapped to their own pages
+pub const MMAP_THRESHOLD: usize = 0x10000;
+/// The heap grows by at least this size
+const HEAP_GROWTH: usize = 0x4000;
+
+#[global_allocator]
+static ALLOCATOR: Allocator = Allocator {
+    heap: LockedHeap::empty(),
+};
+
+pub struct Allocator {
+    heap: LockedHeap,
+}
+
+impl Allocator {
+    /// Moves the program break to fit an allocation, the heap is initialized on the first growth
+    fn grow(&self, layout: Layout) -> Result<(), ()> {
+        let mut heap = self.heap.lock();
+        let size = align_up(layout.size() + layout.align(), HEAP_GROWTH);
+        if heap.size() == 0 {
+            let bottom = change_break(0).map_err(|_| ())?;
+            change_break(bottom + size).map_err(|_| ())?;
+            unsafe { heap.init(bottom, size) };
+        } else {
+            change_break(heap.top() + size).map_err(|_| ())?;
+            unsafe { heap.extend(size) };
+        }
+        Ok(())
+    }
+}
+
+unsafe impl GlobalAlloc for Allocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.size() >= MMAP_THRESHOLD && layout.align() <= PAGE_SIZE {
+            return map_memory(layout.size()).map_or(ptr::null_mut(), |address| address as *mut u8);
+        }
+
+        loop {
+            if let Ok(allocation) = self.heap.lock().allocate_first_fit(layout) {
+                return allocation.as_ptr();
+            }
+            if self.grow(layout).is_err() {
+                return ptr::null_mut();
+            }
+        }
+    }
+
+    unsafe fn dealloc(&self, allocation: *mut u8, layout: Layout) {
+        if layout.size() >= MMAP_THRESHOLD && layout.align() <= PAGE_SIZE {
+            unmap_memory(allocation as usize, layout.size()).ok();
+        } else if let Some(allocation) = ptr::NonNull::new(allocation) {
+            self.heap.lock().deallocate(allocation, layout);
+        }
+    }
+}
+
+#[alloc_error_handler]
+fn handle_alloc_error(layout: Layout) -> ! {
+    panic!("cannot allocate {} bytes", layout.size())
+}
+
+fn align_up(value: usize, alignment: usize) -> usize {
+    (value + alignment - 1) / alignment * alignment
+}
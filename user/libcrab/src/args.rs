@@ -0,0 +1,62 @@
+//! The arguments of a program, the first argument is the program's name
+
+use core::{slice, str};
+
+/// An iterator over the arguments of the program
+#[derive(Clone, Copy)]
+pub struct Args {
+    argv: *const *const u8,
+    count: usize,
+    index: usize,
+}
+
+impl Args {
+    /// # Safety
+    ///
+    /// `argv` must point to `argc` pointers to null terminated strings
+    pub unsafe fn new(argc: usize, argv: *const *const u8) -> Self {
+        Args {
+            argv,
+            count: argc,
+            index: 0,
+        }
+    }
+
+    /// Returns the argument at `index`, an argument that isn't valid UTF-8 is empty
+    pub fn get(&self, index: usize) -> Option<&'static str> {
+        if index >= self.count {
+            return None;
+        }
+        unsafe {
+            let argument = *self.argv.add(index);
+            let mut length = 0;
+            while *argument.add(length) != 0 {
+                length += 1;
+            }
+            Some(str::from_utf8(slice::from_raw_parts(argument, length)).unwrap_or(""))
+        }
+    }
+
+    /// Returns the arguments after the program's name
+    pub fn skip_name(mut self) -> Self {
+        self.index = self.index.max(1);
+        self
+    }
+}
+
+impl Iterator for Args {
+    type Item = &'static str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let argument = self.get(self.index)?;
+        self.index += 1;
+        Some(argument)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.count.saturating_sub(self.index);
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Args {}
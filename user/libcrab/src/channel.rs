@@ -0,0 +1,86 @@
+//! Channels, connected pairs of ends that pass messages between processes.
+//!
+//! A message is up to `MAX_MESSAGE_SIZE` bytes and up to `MAX_MESSAGE_FILES` files, e.g. the end of another channel
+//! so a service replies on a channel of it's client. An end is closed when it's dropped.
+
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::{
+    error::{Result, SyscallError},
+    syscalls::{self, message_flags::NON_BLOCKING},
+};
+
+pub use crate::syscalls::{MAX_MESSAGE_FILES, MAX_MESSAGE_SIZE};
+
+/// An end of a channel
+#[derive(Debug)]
+pub struct Channel {
+    handle: usize,
+}
+
+impl Channel {
+    /// Creates a channel, returns it's two ends
+    pub fn pair() -> Result<(Channel, Channel)> {
+        let (first, second) = syscalls::create_channel()?;
+        Ok((Channel::from_handle(first), Channel::from_handle(second)))
+    }
+
+    /// Takes the channel end opened at `handle` (e.g. a received file descriptor)
+    pub fn from_handle(handle: usize) -> Self {
+        Channel { handle }
+    }
+
+    /// The file descriptor of the end, to pass it in a message
+    pub fn handle(&self) -> usize {
+        self.handle
+    }
+
+    /// Returns the file descriptor of the end without closing it
+    pub fn into_handle(self) -> usize {
+        let handle = self.handle;
+        mem::forget(self);
+        handle
+    }
+
+    /// Sends a message with the files opened at `fds`, waits while the other end is full
+    pub fn send(&self, data: &[u8], fds: &[usize]) -> Result<()> {
+        syscalls::send(self.handle, data, fds, 0)
+    }
+
+    /// Sends a message without waiting, returns whether it was sent
+    pub fn try_send(&self, data: &[u8], fds: &[usize]) -> Result<bool> {
+        match syscalls::send(self.handle, data, fds, NON_BLOCKING) {
+            Ok(()) => Ok(true),
+            Err(SyscallError::WouldBlock) => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Receives a message, waits until there is one. Returns the length of it's data and the amount of it's files.
+    /// Fails once the other end is closed and every message was received.
+    pub fn receive(&self, data: &mut [u8], fds: &mut [usize]) -> Result<(usize, usize)> {
+        syscalls::receive(self.handle, data, fds, 0)
+    }
+
+    /// Receives a message without waiting, returns `None` if there is no message
+    pub fn try_receive(&self, data: &mut [u8], fds: &mut [usize]) -> Result<Option<(usize, usize)>> {
+        match syscalls::receive(self.handle, data, fds, NON_BLOCKING) {
+            Ok(received) => Ok(Some(received)),
+            Err(SyscallError::WouldBlock) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+impl Drop for Channel {
+    fn drop(&mut self) {
+        syscalls::close(self.handle).ok();
+    }
+}
+
+/// Waits until one of the channels has a message or it's other end is closed, returns it's index
+pub fn select(channels: &[&Channel]) -> Result<usize> {
+    let handles: Vec<usize> = channels.iter().map(|channel| channel.handle).collect();
+    syscalls::select(&handles, 0)
+}
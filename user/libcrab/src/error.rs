@@ -0,0 +1,36 @@
+//! The errors of syscalls
+
+use abi::status;
+use core::{fmt, result};
+
+/// The error of a failed syscall, the kernel reports a failure or that a non blocking syscall would have waited
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyscallError {
+    /// The syscall failed (`status::FAILURE`)
+    Failed,
+    /// The non blocking syscall would have waited (`status::WOULD_BLOCK`)
+    WouldBlock,
+}
+
+/// The result of a syscall wrapper
+pub type Result<T> = result::Result<T, SyscallError>;
+
+impl SyscallError {
+    /// Converts a syscall status to a result, negative statuses are failures
+    pub fn from_status(status: i64) -> Result<usize> {
+        match status {
+            status::WOULD_BLOCK => Err(SyscallError::WouldBlock),
+            _ if status >= 0 => Ok(status as usize),
+            _ => Err(SyscallError::Failed),
+        }
+    }
+}
+
+impl fmt::Display for SyscallError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SyscallError::Failed => write!(f, "the syscall failed"),
+            SyscallError::WouldBlock => write!(f, "the syscall would block"),
+        }
+    }
+}
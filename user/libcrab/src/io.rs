@@ -0,0 +1,93 @@
+//! Formatted printing to the standard output & error, with `print!`, `println!`, `eprint!` & `eprintln!`
+
+use core::fmt::{self, Write};
+
+use crate::syscalls::{self, STDERR, STDOUT};
+
+/// Formats to a stack buffer, which is written to a file descriptor whenever it fills
+struct Writer {
+    fd: usize,
+    buffer: [u8; 256],
+    length: usize,
+}
+
+impl Writer {
+    fn flush(&mut self) {
+        let mut written = 0;
+        while written < self.length {
+            match syscalls::write(self.fd, &self.buffer[written..self.length]) {
+                Ok(count @ 1..) => written += count,
+                _ => break,
+            }
+        }
+        self.length = 0;
+    }
+}
+
+impl fmt::Write for Writer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            if self.length == self.buffer.len() {
+                self.flush();
+            }
+            self.buffer[self.length] = byte;
+            self.length += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a formatted message to a file descriptor
+pub fn write_fmt(fd: usize, args: fmt::Arguments) {
+    let mut writer = Writer {
+        fd,
+        buffer: [0; 256],
+        length: 0,
+    };
+    writer.write_fmt(args).ok();
+    writer.flush();
+}
+
+#[doc(hidden)]
+pub fn _print(args: fmt::Arguments) {
+    write_fmt(STDOUT, args);
+}
+
+#[doc(hidden)]
+pub fn _eprint(args: fmt::Arguments) {
+    write_fmt(STDERR, args);
+}
+
+#[macro_export]
+macro_rules! print {
+    ($($arg:tt)*) => {
+        $crate::io::_print(format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! println {
+    () => {
+        $crate::print!("\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::print!("{}\n", format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! eprint {
+    ($($arg:tt)*) => {
+        $crate::io::_eprint(format_args!($($arg)*))
+    };
+}
+
+#[macro_export]
+macro_rules! eprintln {
+    () => {
+        $crate::eprint!("\n")
+    };
+    ($($arg:tt)*) => {
+        $crate::eprint!("{}\n", format_args!($($arg)*))
+    };
+}
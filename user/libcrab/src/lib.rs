@@ -0,0 +1,61 @@
+//! The runtime library of CrabOS userland programs (libcrab).
+//!
+//! It wraps the syscalls with typed, `Result` returning functions (see `syscalls` & `SyscallError`),
+//! allocates the heap over BRK & MMAP, prints with `print!` & `println!`, spawns programs (see `process`),
+//! handles signals (see `signal`), passes messages between processes (see `channel`), shares memory between them
+//! (see `shm`) and locks the shared memory with futex based mutexes & condition variables (see `sync`).
+//!
+//! A program declares it's main with `entry_point!`, the runtime passes it the program's arguments
+//! and exits with the status it returns. A panic prints the panic message and exits with `PANIC_EXIT_STATUS`.
+
+#![no_std]
+#![feature(alloc_error_handler)]
+
+extern crate alloc;
+
+pub mod allocator;
+pub mod args;
+pub mod channel;
+pub mod error;
+pub mod io;
+pub mod process;
+pub mod shm;
+pub mod signal;
+pub mod sync;
+pub mod syscalls;
+
+use core::panic::PanicInfo;
+
+pub use args::Args;
+pub use error::{Result, SyscallError};
+
+/// The exit status of a program that panicked
+pub const PANIC_EXIT_STATUS: u8 = 101;
+
+/// Defines the entry point of the program (`_start`), which calls `$main` with the program's arguments.
+///
+/// The main must be a `fn(Args) -> u8`, it returns the exit status of the program.
+#[macro_export]
+macro_rules! entry_point {
+    ($main:path) => {
+        #[no_mangle]
+        pub extern "C" fn _start(argc: usize, argv: *const *const u8) -> ! {
+            let main: fn($crate::Args) -> u8 = $main;
+            $crate::start(argc, argv, main)
+        }
+    };
+}
+
+/// Runs the main of the program and exits with the status it returns, called by `_start`
+#[doc(hidden)]
+pub fn start(argc: usize, argv: *const *const u8, main: fn(Args) -> u8) -> ! {
+    // the kernel passes the arguments like C's argv, every argument is followed by a null byte
+    let args = unsafe { Args::new(argc, argv) };
+    process::exit(main(args))
+}
+
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    eprintln!("{}", info);
+    process::exit(PANIC_EXIT_STATUS)
+}
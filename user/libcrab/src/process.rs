@@ -0,0 +1,99 @@
+//! Spawns programs & waits for them, like `std::process`
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{
+    error::Result,
+    syscalls::{self, spawn, start, wait},
+};
+
+/// Terminates the program, it's parent continues with `exit_status`
+pub fn exit(exit_status: u8) -> ! {
+    syscalls::exit(exit_status)
+}
+
+/// Returns the pid of the program
+pub fn id() -> usize {
+    syscalls::get_pid()
+}
+
+/// A builder of a program's process, the first argument is the program's path
+pub struct Command<'a> {
+    path: &'a str,
+    /// The arguments, every argument is followed by a null byte
+    arguments: Vec<u8>,
+}
+
+impl<'a> Command<'a> {
+    pub fn new(path: &'a str) -> Self {
+        let mut command = Command {
+            path,
+            arguments: Vec::new(),
+        };
+        command.arg(path);
+        command
+    }
+
+    /// Appends an argument, an argument must not contain a null byte
+    pub fn arg(&mut self, argument: &str) -> &mut Self {
+        self.arguments.extend_from_slice(argument.as_bytes());
+        self.arguments.push(0);
+        self
+    }
+
+    pub fn args<'b>(&mut self, arguments: impl IntoIterator<Item = &'b str>) -> &mut Self {
+        for argument in arguments {
+            self.arg(argument);
+        }
+        self
+    }
+
+    /// Runs the program as a child, it inherits the standard streams and the working directory
+    pub fn spawn(&self) -> Result<Child> {
+        let pid = spawn(self.path, &self.arguments)?;
+        start(pid)?;
+        Ok(Child { pid })
+    }
+
+    /// Runs the program as a child and waits until it exits
+    pub fn status(&self) -> Result<ExitStatus> {
+        self.spawn()?.wait()
+    }
+}
+
+/// A running child process
+pub struct Child {
+    pid: usize,
+}
+
+impl Child {
+    pub fn id(&self) -> usize {
+        self.pid
+    }
+
+    /// Waits until the child exits, a child is waited for once
+    pub fn wait(self) -> Result<ExitStatus> {
+        wait(self.pid).map(ExitStatus)
+    }
+}
+
+/// The exit status of a child
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExitStatus(u8);
+
+impl ExitStatus {
+    pub fn success(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn code(&self) -> u8 {
+        self.0
+    }
+}
+
+impl fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "exit status {}", self.0)
+    }
+}
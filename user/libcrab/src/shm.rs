@@ -0,0 +1,89 @@
+//! Shared memory segments, zeroed pages that several processes map.
+//!
+//! A segment's handle is a file descriptor, so it's passed to other processes through channels (see `channel`).
+//! The segment is freed once every handle is closed and every mapping is unmapped.
+
+use core::mem;
+
+use crate::{error::Result, syscalls};
+
+/// The handle of a segment, closed when it's dropped
+#[derive(Debug)]
+pub struct SharedMemory {
+    handle: usize,
+    length: usize,
+}
+
+impl SharedMemory {
+    /// Creates a segment of `length` bytes, the kernel rounds it up to whole pages
+    pub fn create(length: usize) -> Result<Self> {
+        let handle = syscalls::shm_create(length)?;
+        Ok(SharedMemory::from_handle(handle, length))
+    }
+
+    /// Takes the segment of `length` bytes opened at `handle` (e.g. a received file descriptor)
+    pub fn from_handle(handle: usize, length: usize) -> Self {
+        SharedMemory { handle, length }
+    }
+
+    /// The file descriptor of the segment, to pass it in a message
+    pub fn handle(&self) -> usize {
+        self.handle
+    }
+
+    /// Returns the file descriptor of the segment without closing it
+    pub fn into_handle(self) -> usize {
+        let handle = self.handle;
+        mem::forget(self);
+        handle
+    }
+
+    /// Maps the segment at an address the kernel chooses
+    pub fn map(&self) -> Result<Mapping> {
+        self.map_at(None)
+    }
+
+    /// Maps the segment at the page aligned `address`, the kernel chooses the address if there is none.
+    /// The processes that share a segment may map it at different addresses.
+    pub fn map_at(&self, address: Option<usize>) -> Result<Mapping> {
+        let address = syscalls::shm_map(self.handle, address)?;
+        Ok(Mapping {
+            address,
+            length: self.length,
+        })
+    }
+}
+
+impl Drop for SharedMemory {
+    fn drop(&mut self) {
+        syscalls::close(self.handle).ok();
+    }
+}
+
+/// A mapped segment, unmapped when it's dropped
+#[derive(Debug)]
+pub struct Mapping {
+    address: usize,
+    length: usize,
+}
+
+impl Mapping {
+    /// The address of the segment's first byte, other processes may change the memory at any time
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.address as *mut u8
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe { syscalls::shm_unmap(self.address).ok() };
+    }
+}
@@ -0,0 +1,134 @@
+//! Signals, a process handles the signals it receives with it's handlers or with their default action.
+//!
+//! A handler runs on the program's stack, when it returns the program continues from where the signal interrupted it.
+//! SIGSTOP & SIGTSTP stop the program until SIGCONT continues it.
+
+use core::{arch::global_asm, ptr};
+
+use crate::{
+    error::{Result, SyscallError},
+    syscall,
+    syscalls::number,
+};
+
+pub const SIGINT: usize = 2;
+pub const SIGKILL: usize = 9;
+pub const SIGSEGV: usize = 11;
+pub const SIGALRM: usize = 14;
+pub const SIGTERM: usize = 15;
+pub const SIGCHLD: usize = 17;
+pub const SIGCONT: usize = 18;
+pub const SIGSTOP: usize = 19;
+pub const SIGTSTP: usize = 20;
+
+/// The exit status of a program terminated by a signal is 128 + the signal
+pub const SIGNALED_EXIT_STATUS: u8 = 128;
+
+/// The handler of the default action
+pub const DEFAULT_HANDLER: usize = 0;
+/// The handler that ignores the signal
+pub const IGNORE_HANDLER: usize = 1;
+
+/// `block_signals` operations
+pub mod mask {
+    pub const BLOCK: u64 = 0;
+    pub const UNBLOCK: u64 = 1;
+    pub const SET: u64 = 2;
+}
+
+/// What the program does when it receives a signal, matches the kernel's `SignalAction`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct SignalAction {
+    /// The address of the handler, `DEFAULT_HANDLER` or `IGNORE_HANDLER`
+    pub handler: usize,
+    /// The signals blocked while the handler runs, the signal itself is always blocked
+    pub mask: u64,
+    /// The address the handler returns to, it calls SIGRETURN with the signal frame
+    pub restorer: usize,
+}
+
+// a handler returns here with the stack pointer at the signal frame the kernel pushed (32 is SIGRETURN)
+global_asm!(
+    ".pushsection .text",
+    ".global __crab_signal_restorer",
+    "__crab_signal_restorer:",
+    "mov rdi, rsp",
+    "mov eax, 32",
+    "int 0x80",
+    "ud2",
+    ".popsection",
+);
+
+extern "C" {
+    fn __crab_signal_restorer();
+}
+
+/// Handles `signal` with `handler`, returns the previous action
+pub fn signal(signal: usize, handler: extern "C" fn(usize)) -> Result<SignalAction> {
+    set_action(
+        signal,
+        SignalAction {
+            handler: handler as usize,
+            mask: 0,
+            restorer: __crab_signal_restorer as usize,
+        },
+    )
+}
+
+/// Makes `signal` take it's default action, returns the previous action
+pub fn reset(signal: usize) -> Result<SignalAction> {
+    set_action(signal, SignalAction::default())
+}
+
+/// Ignores `signal`, returns the previous action
+pub fn ignore(signal: usize) -> Result<SignalAction> {
+    set_action(
+        signal,
+        SignalAction {
+            handler: IGNORE_HANDLER,
+            ..Default::default()
+        },
+    )
+}
+
+/// Replaces the action of a signal, returns the previous action.
+/// A handler's action must have a restorer, `signal` sets the runtime's.
+pub fn set_action(signal: usize, action: SignalAction) -> Result<SignalAction> {
+    let mut previous = SignalAction::default();
+    SyscallError::from_status(unsafe {
+        syscall!(number::SIGACTION, signal, &action as *const SignalAction, &mut previous as *mut SignalAction)
+    })?;
+    Ok(previous)
+}
+
+/// Returns the action of a signal
+pub fn action(signal: usize) -> Result<SignalAction> {
+    let mut action = SignalAction::default();
+    SyscallError::from_status(unsafe {
+        syscall!(number::SIGACTION, signal, ptr::null::<SignalAction>(), &mut action as *mut SignalAction)
+    })?;
+    Ok(action)
+}
+
+/// Changes the blocked signals with a `mask` operation, a signal's bit is `1 << signal`.
+/// Returns the previously blocked signals.
+pub fn block_signals(operation: u64, signals: u64) -> Result<u64> {
+    SyscallError::from_status(unsafe { syscall!(number::SIGPROCMASK, operation, signals) }).map(|previous| previous as u64)
+}
+
+/// Sends a signal to a process
+pub fn kill(pid: usize, signal: usize) -> Result<()> {
+    SyscallError::from_status(unsafe { syscall!(number::KILL, pid, signal) }).map(|_| ())
+}
+
+/// Sends a signal to every process of a process group
+pub fn kill_group(pgid: usize, signal: usize) -> Result<()> {
+    SyscallError::from_status(unsafe { syscall!(number::KILL, -(pgid as i64), signal) }).map(|_| ())
+}
+
+/// Sends SIGALRM to the program after `seconds`, zero cancels the alarm.
+/// Returns the seconds left until the previous alarm.
+pub fn alarm(seconds: u64) -> Result<u64> {
+    SyscallError::from_status(unsafe { syscall!(number::ALARM, seconds) }).map(|left| left as u64)
+}
@@ -0,0 +1,301 @@
+//! The syscalls of CrabOS, the numbers, flags and shared structures come from the `abi` crate the kernel uses too.
+
+use core::{ptr, sync::atomic::AtomicU32};
+
+pub use abi::{
+    fs::{DirectoryEntry, FileType, Stat, MAX_NAME_LENGTH},
+    futex as futex_operations,
+    ipc::{MAX_MESSAGE_FILES, MAX_MESSAGE_SIZE},
+    memory::MemoryInfo,
+    message as message_flags, number, open_flags,
+    process::{ProcessInfo, ProcessState, NO_PARENT},
+    time::{Timespec, CLOCK_MONOTONIC, CLOCK_REALTIME},
+    wait as wait_flags, MAX_NICE, MIN_NICE, STDERR, STDIN, STDOUT,
+};
+use abi::ipc::MessageBuffers;
+
+use crate::error::{Result, SyscallError};
+
+#[macro_export]
+macro_rules! syscall {
+    ($number:expr) => {
+        $crate::syscall!($number, 0, 0, 0, 0)
+    };
+    ($number:expr, $arg1:expr) => {
+        $crate::syscall!($number, $arg1, 0, 0, 0)
+    };
+    ($number:expr, $arg1:expr, $arg2:expr) => {
+        $crate::syscall!($number, $arg1, $arg2, 0, 0)
+    };
+    ($number:expr, $arg1:expr, $arg2:expr, $arg3:expr) => {
+        $crate::syscall!($number, $arg1, $arg2, $arg3, 0)
+    };
+    ($number:expr, $arg1:expr, $arg2:expr, $arg3:expr, $arg4:expr) => {
+        {
+            let result: i64;
+            core::arch::asm!(
+                "int 0x80",
+                in("rax") $number,
+                in("rdi") $arg1,
+                in("rsi") $arg2,
+                in("rdx") $arg3,
+                in("r8") $arg4,
+                lateout("rax") result
+            );
+            result
+        }
+    };
+}
+
+/// How a waited child changed, returned by `wait_for`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WaitStatus {
+    Exited(u8),
+    /// Stopped by the signal
+    Stopped(usize),
+    /// Still running, returned with `NO_HANG`
+    Running,
+}
+
+pub fn read(fd: usize, buffer: &mut [u8]) -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::READ, fd, buffer.as_mut_ptr(), buffer.len()) })
+}
+
+pub fn write(fd: usize, buffer: &[u8]) -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::WRITE, fd, buffer.as_ptr(), buffer.len()) })
+}
+
+/// Opens the file at `path` with the `open_flags`, returns it's file descriptor
+pub fn open(path: &str, flags: u64) -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::OPEN, path.as_ptr(), path.len(), flags) })
+}
+
+pub fn close(fd: usize) -> Result<()> {
+    SyscallError::from_status(unsafe { syscall!(number::CLOSE, fd) }).map(|_| ())
+}
+
+/// Duplicates a file descriptor to the lowest free file descriptor
+pub fn dup(fd: usize) -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::DUP, fd) })
+}
+
+/// Duplicates a file descriptor to `new_fd`, the file opened at `new_fd` is closed first
+pub fn dup2(old_fd: usize, new_fd: usize) -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::DUP2, old_fd, new_fd) })
+}
+
+/// Creates a pipe, returns the file descriptors of it's read end and write end
+pub fn pipe() -> Result<(usize, usize)> {
+    let mut fds = [0usize; 2];
+    SyscallError::from_status(unsafe { syscall!(number::PIPE, fds.as_mut_ptr()) })?;
+    Ok((fds[0], fds[1]))
+}
+
+/// Creates a channel, returns the file descriptors of it's two ends
+pub fn create_channel() -> Result<(usize, usize)> {
+    let mut handles = [0usize; 2];
+    SyscallError::from_status(unsafe { syscall!(number::CHANNEL_CREATE, handles.as_mut_ptr()) })?;
+    Ok((handles[0], handles[1]))
+}
+
+/// Sends `data` and the files opened at `fds` through the channel end at `handle`, the files stay open
+pub fn send(handle: usize, data: &[u8], fds: &[usize], flags: u64) -> Result<()> {
+    let buffers = MessageBuffers {
+        data: data.as_ptr() as u64,
+        length: data.len() as u64,
+        files: fds.as_ptr() as u64,
+        file_count: fds.len() as u64,
+    };
+    SyscallError::from_status(unsafe { syscall!(number::SEND, handle, &buffers as *const MessageBuffers, flags) }).map(|_| ())
+}
+
+/// Receives a message from the channel end at `handle`, the message must fit the buffers.
+/// Returns the length of it's data and the amount of it's files, which are opened at the file descriptors written to `fds`
+pub fn receive(handle: usize, data: &mut [u8], fds: &mut [usize], flags: u64) -> Result<(usize, usize)> {
+    let mut buffers = MessageBuffers {
+        data: data.as_mut_ptr() as u64,
+        length: data.len() as u64,
+        files: fds.as_mut_ptr() as u64,
+        file_count: fds.len() as u64,
+    };
+    let length = SyscallError::from_status(unsafe { syscall!(number::RECV, handle, &mut buffers as *mut MessageBuffers, flags) })?;
+    Ok((length, buffers.file_count as usize))
+}
+
+/// Waits until one of the channel ends at `handles` has a message or it's other end is closed, returns it's index
+pub fn select(handles: &[usize], flags: u64) -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::SELECT, handles.as_ptr(), handles.len(), flags) })
+}
+
+/// Creates a zeroed shared memory segment of `length` bytes, returns it's file descriptor
+pub fn shm_create(length: usize) -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::SHM_CREATE, length) })
+}
+
+/// Maps the segment opened at `fd` at `address`, or at an address the kernel chooses if there is none.
+/// Returns the page aligned address of the mapping
+pub fn shm_map(fd: usize, address: Option<usize>) -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::SHM_MAP, fd, address.unwrap_or(0)) })
+}
+
+/// Unmaps the segment mapped at `address` by `shm_map`
+///
+/// # Safety
+///
+/// The memory must not be used afterwards
+pub unsafe fn shm_unmap(address: usize) -> Result<()> {
+    SyscallError::from_status(syscall!(number::SHM_UNMAP, address)).map(|_| ())
+}
+
+pub fn stat(path: &str) -> Result<Stat> {
+    let mut stat = Stat::empty();
+    SyscallError::from_status(unsafe { syscall!(number::STAT, path.as_ptr(), path.len(), &mut stat as *mut Stat) })?;
+    Ok(stat)
+}
+
+/// Reads the next entries of the directory opened at `fd`, returns the number of entries read
+pub fn get_directory_entries(fd: usize, entries: &mut [DirectoryEntry]) -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::GETDENTS, fd, entries.as_mut_ptr(), entries.len()) })
+}
+
+pub fn make_directory(path: &str) -> Result<()> {
+    SyscallError::from_status(unsafe { syscall!(number::MKDIR, path.as_ptr(), path.len()) }).map(|_| ())
+}
+
+pub fn unlink(path: &str) -> Result<()> {
+    SyscallError::from_status(unsafe { syscall!(number::UNLINK, path.as_ptr(), path.len()) }).map(|_| ())
+}
+
+pub fn change_directory(path: &str) -> Result<()> {
+    SyscallError::from_status(unsafe { syscall!(number::CHDIR, path.as_ptr(), path.len()) }).map(|_| ())
+}
+
+/// Copies the working directory to `buffer`, returns it's length
+pub fn get_current_directory(buffer: &mut [u8]) -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::GETCWD, buffer.as_mut_ptr(), buffer.len()) })
+}
+
+/// Creates a process running the program at `path`, the process runs once it's started (see `start`)
+///
+/// # Arguments
+///  - `arguments`, the program's arguments, every argument is followed by a null byte
+pub fn spawn(path: &str, arguments: &[u8]) -> Result<usize> {
+    SyscallError::from_status(unsafe {
+        syscall!(number::SPAWN, path.as_ptr(), path.len(), arguments.as_ptr(), arguments.len())
+    })
+}
+
+/// Queues a child process created by the caller to run
+pub fn start(pid: usize) -> Result<()> {
+    SyscallError::from_status(unsafe { syscall!(number::START, pid) }).map(|_| ())
+}
+
+/// Waits until a started child exits, returns it's exit status
+pub fn wait(pid: usize) -> Result<u8> {
+    SyscallError::from_status(unsafe { syscall!(number::WAIT, pid) }).map(|status| status as u8)
+}
+
+/// Waits for a started child with the `wait_flags`, without flags it waits until the child exits
+pub fn wait_for(pid: usize, flags: u64) -> Result<WaitStatus> {
+    let status = unsafe { syscall!(number::WAIT, pid, flags) };
+    match status {
+        wait_flags::RUNNING => Ok(WaitStatus::Running),
+        _ if status >= wait_flags::STOPPED => Ok(WaitStatus::Stopped((status - wait_flags::STOPPED) as usize)),
+        _ => SyscallError::from_status(status).map(|status| WaitStatus::Exited(status as u8)),
+    }
+}
+
+/// Moves the caller or a child of it to the process group `pgid`, `pgid` equal to `pid` creates a new group.
+/// A child must be in the caller's session, and so must an existing group.
+pub fn set_process_group(pid: usize, pgid: usize) -> Result<()> {
+    SyscallError::from_status(unsafe { syscall!(number::SETPGID, pid, pgid) }).map(|_| ())
+}
+
+pub fn get_process_group(pid: usize) -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::GETPGID, pid) })
+}
+
+/// Sets the nice value of the caller or of a child of it, from `MIN_NICE` to `MAX_NICE`.
+/// A process with a lower nice value gets more CPU time than the processes it runs with.
+pub fn set_priority(pid: usize, nice: i64) -> Result<()> {
+    SyscallError::from_status(unsafe { syscall!(number::SETPRIORITY, pid, nice) }).map(|_| ())
+}
+
+/// Makes the caller the leader of a new session and of a new process group, returns the session id.
+/// Fails if the caller leads a process group.
+pub fn create_session() -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::SETSID) })
+}
+
+pub fn get_pid() -> usize {
+    unsafe { syscall!(number::GET_PID) as usize }
+}
+
+/// Writes the information of the processes to `processes`, returns the number of written processes
+pub fn list_processes(processes: &mut [ProcessInfo]) -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::PROCESSES, processes.as_mut_ptr(), processes.len()) })
+}
+
+pub fn memory_info() -> Result<MemoryInfo> {
+    let mut info = MemoryInfo::default();
+    SyscallError::from_status(unsafe { syscall!(number::MEMINFO, &mut info as *mut MemoryInfo) })?;
+    Ok(info)
+}
+
+/// Returns the time of `clock` (`CLOCK_REALTIME` or `CLOCK_MONOTONIC`)
+pub fn clock_get_time(clock: u64) -> Result<Timespec> {
+    let mut time = Timespec::default();
+    SyscallError::from_status(unsafe { syscall!(number::CLOCK_GETTIME, clock, &mut time as *mut Timespec) })?;
+    Ok(time)
+}
+
+/// Sleeps for at least `duration`
+pub fn nanosleep(duration: &Timespec) -> Result<()> {
+    SyscallError::from_status(unsafe { syscall!(number::NANOSLEEP, duration as *const Timespec) }).map(|_| ())
+}
+
+/// Waits while `word` holds `value` until a wake of the word, or until the `timeout` passes.
+/// Fails if the word doesn't hold `value`, if the timeout passed or if a signal interrupted the wait, a waiter may wake spuriously.
+pub fn futex_wait(word: &AtomicU32, value: u32, timeout: Option<&Timespec>) -> Result<()> {
+    let timeout = timeout.map_or(ptr::null(), |timeout| timeout as *const Timespec);
+    SyscallError::from_status(unsafe {
+        syscall!(number::FUTEX, word as *const AtomicU32, futex_operations::WAIT, value as u64, timeout)
+    })
+    .map(|_| ())
+}
+
+/// Wakes up to `count` waiters of `word`, returns the number of waiters woken
+pub fn futex_wake(word: &AtomicU32, count: usize) -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::FUTEX, word as *const AtomicU32, futex_operations::WAKE, count) })
+}
+
+/// Copies the kernel log from `offset` to `buffer`, returns the number of copied bytes
+pub fn read_log(offset: usize, buffer: &mut [u8]) -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::READ_LOG, offset, buffer.as_mut_ptr(), buffer.len()) })
+}
+
+/// Moves the program break to `address`, returns the new break.
+/// The current break is returned if `address` is zero, the heap starts at the break returned first.
+pub fn change_break(address: usize) -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::BRK, address) })
+}
+
+/// Maps `length` bytes of zeroed memory, returns it's page aligned address
+pub fn map_memory(length: usize) -> Result<usize> {
+    SyscallError::from_status(unsafe { syscall!(number::MMAP, length) })
+}
+
+/// Unmaps memory mapped by `map_memory`, `length` must be the mapped length
+///
+/// # Safety
+///
+/// The memory must not be used afterwards
+pub unsafe fn unmap_memory(address: usize, length: usize) -> Result<()> {
+    SyscallError::from_status(syscall!(number::MUNMAP, address, length)).map(|_| ())
+}
+
+/// Terminates the program, it's parent continues with `exit_status`
+pub fn exit(exit_status: u8) -> ! {
+    unsafe { syscall!(number::EXIT, exit_status as u64) };
+    unreachable!("the program continued after exiting")
+}